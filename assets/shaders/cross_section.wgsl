@@ -0,0 +1,78 @@
+// Standard materials cut away in front of the cross section plane.
+// Mirrors `CrossSectionSettings` in src/cross_section_material.rs.
+
+#import bevy_pbr::{
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::alpha_discard,
+}
+
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::prepass_io::{VertexOutput, FragmentOutput}
+#ifdef MOTION_VECTOR_PREPASS
+#import bevy_pbr::pbr_prepass_functions::calculate_motion_vector
+#endif
+#else
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
+}
+#endif
+
+struct CrossSectionSettings {
+    plane: vec4<f32>,
+    cap_color: vec4<f32>,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(100) var<uniform> cross_section: CrossSectionSettings;
+
+fn clip(world_position: vec4<f32>) {
+    if dot(cross_section.plane.xyz, world_position.xyz) > cross_section.plane.w {
+        discard;
+    }
+}
+
+#ifdef PREPASS_PIPELINE
+// Only what the standard prepass writes for opaque meshes, so the depth and motion vectors
+// of the cut away part don't hide what's behind it. Deferred rendering isn't used.
+#ifdef PREPASS_FRAGMENT
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> FragmentOutput {
+    clip(in.world_position);
+    var out: FragmentOutput;
+#ifdef UNCLIPPED_DEPTH_ORTHO_EMULATION
+    out.frag_depth = in.unclipped_depth;
+#endif
+#ifdef NORMAL_PREPASS
+    let normal = normalize(select(-in.world_normal, in.world_normal, is_front));
+    out.normal = vec4<f32>(normal * 0.5 + 0.5, 1.0);
+#endif
+#ifdef MOTION_VECTOR_PREPASS
+    out.motion_vector = calculate_motion_vector(in.world_position, in.previous_world_position);
+#endif
+    return out;
+}
+#else
+@fragment
+fn fragment(in: VertexOutput) {
+    clip(in.world_position);
+}
+#endif
+
+#else
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> FragmentOutput {
+    // Shaded before anything is discarded, since texture sampling needs every pixel of a quad
+    // to still be running
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    clip(in.world_position);
+    // The inside of the mesh, seen through the cut
+    if !is_front {
+        out.color = cross_section.cap_color;
+    }
+    return out;
+}
+#endif
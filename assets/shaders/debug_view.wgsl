@@ -0,0 +1,92 @@
+// Debug views of generated meshes, one mode at a time.
+// Mirrors `DebugViewSettings` in src/debug_view_material.rs, and the modes `DebugView` in
+// src/debug_view.rs numbers.
+
+#import bevy_pbr::forward_io::VertexOutput
+
+const MODE_UV_CHECKER: u32 = 0u;
+const MODE_TEXEL_DENSITY: u32 = 1u;
+const MODE_NORMALS: u32 = 2u;
+const MODE_TANGENTS: u32 = 3u;
+const MODE_VERTEX_COLORS: u32 = 4u;
+const MODE_FACE_ORIENTATION: u32 = 5u;
+const MODE_BACK_FACES: u32 = 6u;
+
+// Missing attributes show up in magenta
+const MISSING: vec4<f32> = vec4<f32>(1.0, 0.0, 1.0, 1.0);
+
+struct DebugViewSettings {
+    mode: u32,
+    texture_size: f32,
+    target_density: f32,
+    clip_plane: vec4<f32>,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> settings: DebugViewSettings;
+@group(#{MATERIAL_BIND_GROUP}) @binding(1) var checker_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(2) var checker_sampler: sampler;
+
+// Blue below the target density, green on it and red above, over two octaves either way
+fn density_heatmap(density: f32) -> vec4<f32> {
+    let t = clamp(log2(density / settings.target_density) / 4.0 + 0.5, 0.0, 1.0);
+    let low = mix(vec3<f32>(0.0, 0.2, 1.0), vec3<f32>(0.0, 1.0, 0.2), t * 2.0);
+    let high = mix(vec3<f32>(0.0, 1.0, 0.2), vec3<f32>(1.0, 0.1, 0.0), t * 2.0 - 1.0);
+    return vec4<f32>(select(low, high, t > 0.5), 1.0);
+}
+
+// Directions from -1 to 1 as colors from 0 to 1, so +X is red, +Y green and +Z blue
+fn direction_color(direction: vec3<f32>) -> vec4<f32> {
+    return vec4<f32>(normalize(direction) * 0.5 + 0.5, 1.0);
+}
+
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> @location(0) vec4<f32> {
+    // Every mode picks its color before back faces are discarded, since sampling and
+    // derivatives need all pixels of a quad to still be running
+    var color = MISSING;
+    if settings.mode == MODE_NORMALS {
+        color = direction_color(in.world_normal);
+    }
+#ifdef VERTEX_TANGENTS
+    if settings.mode == MODE_TANGENTS {
+        color = direction_color(in.world_tangent.xyz);
+    }
+#endif
+#ifdef VERTEX_COLORS
+    if settings.mode == MODE_VERTEX_COLORS {
+        color = in.color;
+    }
+#endif
+#ifdef VERTEX_UVS_A
+    if settings.mode == MODE_UV_CHECKER {
+        color = textureSample(checker_texture, checker_sampler, in.uv);
+    }
+    if settings.mode == MODE_TEXEL_DENSITY {
+        // Texels covering a unit of surface, from how fast UVs and positions change across
+        // the pixel. Areas, so it doesn't matter which way the UVs are turned.
+        let uv_area = abs(determinant(mat2x2<f32>(dpdx(in.uv), dpdy(in.uv))));
+        let world_area = length(cross(dpdx(in.world_position.xyz), dpdy(in.world_position.xyz)));
+        color = density_heatmap(settings.texture_size * sqrt(uv_area / max(world_area, 1e-12)));
+    }
+#endif
+    // Cut away in front of the cross section plane
+    if dot(settings.clip_plane.xyz, in.world_position.xyz) > settings.clip_plane.w {
+        discard;
+    }
+    if settings.mode == MODE_FACE_ORIENTATION {
+        // Shaded a little by the normal, so the shape still reads
+        let shade = 0.6 + 0.4 * abs(normalize(in.world_normal).y);
+        let tint = select(vec3<f32>(1.0, 0.1, 0.1), vec3<f32>(0.1, 0.3, 1.0), is_front);
+        return vec4<f32>(tint * shade, 1.0);
+    }
+    if settings.mode == MODE_BACK_FACES {
+        // Gray in front so the red backs stand out
+        let shade = 0.4 + 0.4 * abs(normalize(in.world_normal).y);
+        let tint = select(vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(1.0), is_front);
+        return vec4<f32>(tint * shade, 1.0);
+    }
+    if !is_front {
+        discard;
+    }
+    return color;
+}
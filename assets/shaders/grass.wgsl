@@ -0,0 +1,116 @@
+// Grass blades swaying in the wind, one instanced draw for every blade sharing the material.
+// Mirrors `GrassWind` in src/grass_material.rs and `Wind::at` in src/wind.rs.
+
+#import bevy_pbr::{
+    mesh_functions,
+    view_transformations::position_world_to_clip,
+}
+#import bevy_render::globals::Globals
+
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::prepass_io::{Vertex, VertexOutput}
+// The prepass and shadow views bind the globals next to the view, not where the main pass does
+@group(0) @binding(1) var<uniform> globals: Globals;
+#else
+#import bevy_pbr::forward_io::{Vertex, VertexOutput}
+#import bevy_pbr::mesh_view_bindings::globals
+#endif
+
+const TAU: f32 = 6.28318530718;
+
+struct GrassWind {
+    direction: vec2<f32>,
+    strength: f32,
+    gust_strength: f32,
+    gust_frequency: f32,
+    gust_length: f32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(100) var<uniform> wind: GrassWind;
+
+// Spreads a blade's tag over 0 to 1, so neighbours don't sway in step.
+fn hash(tag: u32) -> f32 {
+    let state = tag * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return f32((word >> 22u) ^ word) / 4294967295.0;
+}
+
+fn wind_at(position: vec3<f32>, time: f32) -> vec3<f32> {
+    let along = dot(position.xz, wind.direction) / max(wind.gust_length, 1e-6);
+    let phase = (time * wind.gust_frequency - along) * TAU;
+    let gust = 0.6 * (0.5 + 0.5 * sin(phase)) + 0.4 * (0.5 + 0.5 * sin(phase * 2.3 + 1.7));
+    let speed = wind.strength + wind.gust_strength * gust;
+    return vec3(wind.direction.x, 0.0, wind.direction.y) * speed;
+}
+
+// Rotates `offset` from the blade's root about the horizontal axis across the wind.
+fn sway(offset: vec3<f32>, root: vec3<f32>, tag: u32, time: f32) -> vec3<f32> {
+    let blow = wind_at(root, time);
+    let across = cross(vec3(0.0, 1.0, 0.0), blow);
+    if dot(across, across) < 1e-8 {
+        return offset;
+    }
+    let axis = normalize(across);
+    let variation = hash(tag);
+    let stiffness = mix(0.7, 1.3, variation);
+    let flutter = 0.05 * sin(time * 6.0 + variation * TAU);
+    let angle = min(length(blow) * 0.2 / stiffness, 1.0) + flutter;
+    let c = cos(angle);
+    let s = sin(angle);
+    return offset * c + cross(axis, offset) * s + axis * dot(axis, offset) * (1.0 - c);
+}
+
+fn swayed_position(world_from_local: mat4x4<f32>, position: vec3<f32>, tag: u32, time: f32) -> vec4<f32> {
+    let root = (world_from_local * vec4(0.0, 0.0, 0.0, 1.0)).xyz;
+    let world = mesh_functions::mesh_position_local_to_world(world_from_local, vec4(position, 1.0));
+    return vec4(root + sway(world.xyz - root, root, tag, time), 1.0);
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    let world_from_local = mesh_functions::get_world_from_local(vertex.instance_index);
+    let tag = mesh_functions::get_tag(vertex.instance_index);
+    let root = (world_from_local * vec4(0.0, 0.0, 0.0, 1.0)).xyz;
+
+    out.world_position = swayed_position(world_from_local, vertex.position, tag, globals.time);
+    out.position = position_world_to_clip(out.world_position.xyz);
+#ifdef PREPASS_PIPELINE
+#ifdef UNCLIPPED_DEPTH_ORTHO_EMULATION
+    out.unclipped_depth = out.position.z;
+    out.position.z = min(out.position.z, 1.0);
+#endif
+#endif
+
+#ifdef VERTEX_UVS_A
+    out.uv = vertex.uv;
+#endif
+
+    // The depth only prepass has no normals to sway
+#ifdef PREPASS_PIPELINE
+#ifdef NORMAL_PREPASS_OR_DEFERRED_PREPASS
+    let world_normal = mesh_functions::mesh_normal_local_to_world(vertex.normal, vertex.instance_index);
+    out.world_normal = sway(world_normal, root, tag, globals.time);
+#endif
+#else
+#ifdef VERTEX_NORMALS
+    let world_normal = mesh_functions::mesh_normal_local_to_world(vertex.normal, vertex.instance_index);
+    out.world_normal = sway(world_normal, root, tag, globals.time);
+#endif
+#endif
+
+#ifdef MOTION_VECTOR_PREPASS
+    out.previous_world_position = swayed_position(
+        mesh_functions::get_previous_world_from_local(vertex.instance_index),
+        vertex.position,
+        tag,
+        globals.time - globals.delta_time,
+    );
+#endif
+
+#ifdef VERTEX_OUTPUT_INSTANCE_INDEX
+    out.instance_index = vertex.instance_index;
+#endif
+
+    return out;
+}
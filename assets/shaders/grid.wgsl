@@ -0,0 +1,27 @@
+// World-space floor grid whose lines fade out with distance from the camera.
+// Mirrors `GridSettings` in src/grid_material.rs.
+
+#import bevy_pbr::{
+    forward_io::VertexOutput,
+    mesh_view_bindings::view,
+}
+
+struct GridSettings {
+    base_color: vec4<f32>,
+    line_color: vec4<f32>,
+    cell_size: f32,
+    line_width: f32,
+    fade_distance: f32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> settings: GridSettings;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coord = in.world_position.xz / settings.cell_size;
+    // Distance to the nearest line in screen pixels, so lines keep their width at any distance
+    let grid = abs(fract(coord - 0.5) - 0.5) / fwidth(coord);
+    let line = 1.0 - min(min(grid.x, grid.y) / settings.line_width, 1.0);
+    let fade = 1.0 - smoothstep(0.0, settings.fade_distance, distance(in.world_position.xyz, view.world_position));
+    return mix(settings.base_color, settings.line_color, line * fade);
+}
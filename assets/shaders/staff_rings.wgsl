@@ -0,0 +1,40 @@
+// Expands staff rings into vertices, one invocation per ring vertex.
+// Mirrors `staff_ring_vertices` in staff_gen/src/staff.rs.
+
+struct Params {
+    resolution: u32,
+    num_rings: u32,
+    generation: u32,
+    _padding: u32,
+}
+
+const TAU: f32 = 6.28318530717958647692528676655900577;
+
+@group(0) @binding(0) var<storage, read> params: Params;
+// x: radius, y: offset x, z: height, w: offset z
+@group(0) @binding(1) var<storage, read> rings: array<vec4<f32>>;
+// [0]: generation header, then every ring position, then every ring normal
+@group(0) @binding(2) var<storage, read_write> output: array<vec4<f32>>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let per_ring = params.resolution + 1u;
+    let count = per_ring * params.num_rings;
+    let i = id.x;
+
+    if i == 0u {
+        // Lets the CPU tell this generation's output apart from stale buffer contents
+        output[0] = vec4<f32>(bitcast<f32>(params.generation), 0.0, 0.0, 0.0);
+    }
+    if i >= count {
+        return;
+    }
+
+    let ring = rings[i / per_ring];
+    let theta = f32(i % per_ring) * TAU / f32(params.resolution);
+    let s = sin(theta);
+    let c = cos(theta);
+
+    output[1u + i] = vec4<f32>(ring.x * c + ring.y, ring.z, ring.x * s + ring.w, 1.0);
+    output[1u + count + i] = vec4<f32>(c, 0.0, s, 0.0);
+}
@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+use bevy::ecs::system::SystemId;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::locale::Locale;
+use crate::settings::Persistent;
+
+/// Something the user can do from the command palette, as a one-shot system.
+#[derive(Debug, Clone)]
+pub struct Action {
+    pub name: String,
+    pub system: SystemId,
+}
+
+/// Every action plugins have registered through [`RegisterAction`], in registration order.
+#[derive(Resource, Debug, Default)]
+pub struct ActionRegistry {
+    pub actions: Vec<Action>,
+}
+
+impl ActionRegistry {
+    /// Indices of the actions `query` fuzzily matches by their names in the UI's language, best
+    /// match first. An empty query matches every action in registration order.
+    pub fn search(&self, query: &str, locale: &Locale) -> Vec<usize> {
+        let mut matches: Vec<(u32, usize)> = self
+            .actions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, action)| {
+                Some((fuzzy_score(query, locale.action_name(&action.name))?, i))
+            })
+            .collect();
+        matches.sort_by_key(|&(score, i)| (std::cmp::Reverse(score), i));
+        matches.into_iter().map(|(_, i)| i).collect()
+    }
+}
+
+/// Keys that run actions, from key names as Bevy prints them, like `"F6"` or `"KeyJ"`, to
+/// action names as the command palette lists them in English. Kept in `key_bindings.ron`.
+#[derive(Resource, Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub keys: BTreeMap<String, String>,
+}
+
+impl Persistent for KeyBindings {
+    const FILE_NAME: &'static str = "key_bindings.ron";
+}
+
+/// Runs the actions bound to keys pressed this frame.
+pub fn run_bound_actions(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    registry: Res<ActionRegistry>,
+) {
+    for key in keyboard_input.get_just_pressed() {
+        let Some(name) = bindings.keys.get(&format!("{key:?}")) else {
+            continue;
+        };
+        match registry.actions.iter().find(|action| &action.name == name) {
+            Some(action) => commands.run_system(action.system),
+            None => warn!("{key:?} is bound to {name}, which is not an action"),
+        }
+    }
+}
+
+/// Scores `name` against `query` when every character of the query appears in it in order,
+/// ignoring case. Runs of consecutive characters and characters starting a word score higher.
+pub fn fuzzy_score(query: &str, name: &str) -> Option<u32> {
+    let mut score = 0;
+    let mut name_chars = name.chars().enumerate();
+    let mut before: Option<char> = None;
+    let mut last_match: Option<usize> = None;
+    for query_char in query.chars().filter(|c| !c.is_whitespace()) {
+        let (position, word_start) = loop {
+            let (position, name_char) = name_chars.next()?;
+            let word_start = before.is_none_or(|c| !c.is_alphanumeric());
+            before = Some(name_char);
+            if name_char.to_lowercase().eq(query_char.to_lowercase()) {
+                break (position, word_start);
+            }
+        };
+        score += 1;
+        if word_start {
+            score += 3;
+        } else if last_match.is_some_and(|last| last + 1 == position) {
+            score += 2;
+        }
+        last_match = Some(position);
+    }
+    Some(score)
+}
+
+/// Adds actions to the [`ActionRegistry`] from a plugin's `build`.
+pub trait RegisterAction {
+    fn register_action<M>(
+        &mut self,
+        name: impl Into<String>,
+        system: impl IntoSystem<(), (), M> + 'static,
+    ) -> &mut Self;
+}
+
+impl RegisterAction for App {
+    fn register_action<M>(
+        &mut self,
+        name: impl Into<String>,
+        system: impl IntoSystem<(), (), M> + 'static,
+    ) -> &mut Self {
+        let world = self.world_mut();
+        let system = world.register_system(system);
+        world
+            .get_resource_or_init::<ActionRegistry>()
+            .actions
+            .push(Action {
+                name: name.into(),
+                system,
+            });
+        self
+    }
+}
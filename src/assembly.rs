@@ -0,0 +1,113 @@
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use staff_gen::assembly::{PartId, PartShape, StaffAssembly};
+
+use crate::environment::FLOOR_HEIGHT;
+use crate::generation::{GeneratedObject, GeneratorKind, MeshGenMessages, empty_mesh};
+
+/// Which part of its parent's [`StaffAssembly`] an entity shows.
+#[derive(Component, Debug)]
+pub struct AssemblyPartId(pub PartId);
+
+/// Spawns `assembly` standing on the floor at `position`, one child entity per part.
+pub fn spawn_assembly(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    mesh_gen: &mut MeshGenMessages,
+    assembly: StaffAssembly,
+    position: Vec2,
+) -> Entity {
+    let height = assembly.part(0).map_or(0., |root| root.shape.height());
+    let entity = commands
+        .spawn((
+            Name::new("StaffAssembly"),
+            GeneratedObject(GeneratorKind::Assembly),
+            Transform::from_xyz(position.x, height / 2. + FLOOR_HEIGHT / 2., position.y),
+            Visibility::default(),
+        ))
+        .id();
+    spawn_parts(commands, meshes, materials, mesh_gen, entity, &assembly);
+    commands.entity(entity).insert(assembly);
+    entity
+}
+
+fn spawn_parts(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    mesh_gen: &mut MeshGenMessages,
+    parent: Entity,
+    assembly: &StaffAssembly,
+) {
+    let transforms = assembly.part_transforms();
+    for (id, part) in assembly.parts() {
+        let entity = commands.spawn(Name::new(part.name.clone())).id();
+        let mesh = mesh_gen.try_generate(entity, GeneratorKind::Assembly, || {
+            part.shape.try_generate_mesh()
+        });
+        commands.entity(entity).insert((
+            AssemblyPartId(id),
+            Mesh3d(meshes.add(mesh.unwrap_or_else(empty_mesh))),
+            MeshMaterial3d(materials.add(part_material(&part.shape))),
+            transforms[id].unwrap_or_default(),
+            ChildOf(parent),
+        ));
+    }
+}
+
+fn part_material(shape: &PartShape) -> StandardMaterial {
+    let color = Color::from(match shape {
+        PartShape::Shaft(_) => css::SADDLE_BROWN,
+        PartShape::Crystal(_) => css::SKY_BLUE,
+        PartShape::Head(_) => css::SILVER,
+        PartShape::Ring { .. } => css::GOLD,
+        PartShape::Banner { .. } => css::CRIMSON,
+        PartShape::Ferrule(_) => css::DARK_GRAY,
+        PartShape::Rock { .. } => css::DIM_GRAY,
+        PartShape::Embers { .. } => css::DARK_RED,
+    });
+    match shape {
+        PartShape::Ferrule(_) => StandardMaterial {
+            base_color: color,
+            metallic: 1.,
+            perceptual_roughness: 0.35,
+            ..default()
+        },
+        PartShape::Embers { .. } => StandardMaterial {
+            base_color: color,
+            emissive: LinearRgba::from(css::ORANGE_RED) * 4.,
+            ..default()
+        },
+        _ => color.into(),
+    }
+}
+
+/// Respawns the parts of assemblies that were edited after spawning.
+pub fn rebuild_changed_assemblies(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    assemblies: Query<(Entity, Ref<StaffAssembly>, Option<&Children>)>,
+    parts: Query<(), With<AssemblyPartId>>,
+    mut mesh_gen: MeshGenMessages,
+) {
+    for (entity, assembly, children) in &assemblies {
+        if !assembly.is_changed() || assembly.is_added() {
+            continue;
+        }
+        for &child in children.into_iter().flatten() {
+            if parts.contains(child) {
+                commands.entity(child).despawn();
+            }
+        }
+        spawn_parts(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &mut mesh_gen,
+            entity,
+            &assembly,
+        );
+    }
+}
@@ -5,17 +5,34 @@ pub struct SceneAssets {
     pub laura: Handle<Scene>,
 }
 
+#[derive(Resource, Debug, Default)]
+pub struct SoundAssets {
+    pub chime: Handle<AudioSource>,
+    pub crystal_hum: Handle<AudioSource>,
+    pub wind: Handle<AudioSource>,
+}
+
 pub struct AssetLoaderPlugin;
 
 impl Plugin for AssetLoaderPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<SceneAssets>()
+            .init_resource::<SoundAssets>()
             .add_systems(Startup, load_assets);
     }
 }
 
-fn load_assets(mut scene_assets: ResMut<SceneAssets>, asset_server: Res<AssetServer>) {
+fn load_assets(
+    mut scene_assets: ResMut<SceneAssets>,
+    mut sound_assets: ResMut<SoundAssets>,
+    asset_server: Res<AssetServer>,
+) {
     *scene_assets = SceneAssets {
         laura: asset_server.load("laura-hg.glb#Scene0"),
-    }
+    };
+    *sound_assets = SoundAssets {
+        chime: asset_server.load("sounds/chime.wav"),
+        crystal_hum: asset_server.load("sounds/crystal_hum.wav"),
+        wind: asset_server.load("sounds/wind.wav"),
+    };
 }
@@ -0,0 +1,203 @@
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use staff_gen::sockets::{self, Sockets};
+
+use crate::actions::RegisterAction;
+use crate::asset_loader::SoundAssets;
+use crate::camera::MainCamera;
+use crate::generation::MeshGenCompleted;
+use crate::settings::{PersistPlugin, Persistent};
+use crate::staff::{Staff, StaffGem};
+use crate::wind::Wind;
+
+const CHIME_VOLUME: f32 = 0.3;
+/// Seconds after a chime before generating again chimes, so batches like the gallery chime once
+const CHIME_COOLDOWN: f32 = 0.2;
+const HUM_VOLUME: f32 = 0.4;
+/// Loudest the wind gets, at the peak of a gust
+const WIND_VOLUME: f32 = 0.5;
+/// How much the master volume actions change it by
+const VOLUME_STEP: f32 = 0.1;
+
+/// Master volume every sound is scaled by, from 0 to 1. Kept in `audio_settings.ron` across
+/// runs.
+#[derive(Resource, Reflect, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master_volume: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self { master_volume: 0.8 }
+    }
+}
+
+impl Persistent for AudioSettings {
+    const FILE_NAME: &'static str = "audio_settings.ron";
+}
+
+/// Loops at the tip of the viewer's staff while its head holds a crystal.
+#[derive(Component, Debug)]
+struct CrystalHum;
+
+#[derive(Component, Debug)]
+struct WindAmbience;
+
+/// A chime when generation completes, a hum from the viewer's staff crystal heard from where it
+/// is, and wind that swells with the gusts.
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<AudioSettings>()
+            .init_resource::<AudioSettings>()
+            .add_plugins(PersistPlugin::<AudioSettings>::default())
+            .register_action(
+                "Raise master volume",
+                |mut settings: ResMut<AudioSettings>| {
+                    settings.master_volume = (settings.master_volume + VOLUME_STEP).min(1.);
+                },
+            )
+            .register_action(
+                "Lower master volume",
+                |mut settings: ResMut<AudioSettings>| {
+                    settings.master_volume = (settings.master_volume - VOLUME_STEP).max(0.);
+                },
+            )
+            .add_systems(PostStartup, start_wind_ambience)
+            .add_systems(
+                Update,
+                (
+                    listen_from_main_camera,
+                    chime_on_generation,
+                    hum_at_staff_gems,
+                    follow_staff_tip,
+                    apply_master_volume,
+                    swell_wind_ambience,
+                ),
+            );
+    }
+}
+
+fn listen_from_main_camera(
+    mut commands: Commands,
+    cameras: Query<Entity, (Added<MainCamera>, Without<SpatialListener>)>,
+) {
+    for camera in &cameras {
+        commands.entity(camera).insert(SpatialListener::default());
+    }
+}
+
+fn start_wind_ambience(mut commands: Commands, sounds: Res<SoundAssets>) {
+    commands.spawn((
+        Name::new("WindAmbience"),
+        WindAmbience,
+        AudioPlayer(sounds.wind.clone()),
+        PlaybackSettings::LOOP.with_volume(Volume::Linear(0.)),
+    ));
+}
+
+fn chime_on_generation(
+    mut commands: Commands,
+    mut completed: MessageReader<MeshGenCompleted>,
+    sounds: Res<SoundAssets>,
+    time: Res<Time<Real>>,
+    mut last_chime: Local<Option<f32>>,
+) {
+    if completed.read().count() == 0 {
+        return;
+    }
+    let now = time.elapsed_secs();
+    if last_chime.is_some_and(|last_chime| now - last_chime < CHIME_COOLDOWN) {
+        return;
+    }
+    *last_chime = Some(now);
+    commands.spawn((
+        Name::new("GenerationChime"),
+        AudioPlayer(sounds.chime.clone()),
+        PlaybackSettings::DESPAWN.with_volume(Volume::Linear(CHIME_VOLUME)),
+    ));
+}
+
+/// Starts a hum under each new crystal on the viewer's staff. Rebuilding the head despawns the
+/// crystal and its hum with it.
+fn hum_at_staff_gems(
+    mut commands: Commands,
+    sounds: Res<SoundAssets>,
+    gems: Query<(Entity, &ChildOf), Added<StaffGem>>,
+    staffs: Query<(), With<Staff>>,
+) {
+    for (gem, staff) in &gems {
+        if !staffs.contains(staff.parent()) {
+            continue;
+        }
+        commands.spawn((
+            Name::new("CrystalHum"),
+            CrystalHum,
+            AudioPlayer(sounds.crystal_hum.clone()),
+            PlaybackSettings::LOOP
+                .with_volume(Volume::Linear(HUM_VOLUME))
+                .with_spatial(true),
+            Transform::default(),
+            ChildOf(gem),
+        ));
+    }
+}
+
+/// Keeps hums at the [`sockets::TOP`] socket of their staff as it is morphed.
+fn follow_staff_tip(
+    mut hums: Query<(&mut Transform, &ChildOf), With<CrystalHum>>,
+    gems: Query<&ChildOf, With<StaffGem>>,
+    staffs: Query<&Sockets>,
+) {
+    for (mut transform, gem) in &mut hums {
+        let Some(tip) = gems
+            .get(gem.parent())
+            .and_then(|staff| staffs.get(staff.parent()))
+            .ok()
+            .and_then(|sockets| sockets.get(sockets::TOP))
+        else {
+            continue;
+        };
+        transform.set_if_neq(Transform::from_translation(tip.translation));
+    }
+}
+
+/// Scales sounds already playing by the master volume. Sounds started later are scaled by
+/// [`GlobalVolume`].
+fn apply_master_volume(
+    settings: Res<AudioSettings>,
+    mut global_volume: ResMut<GlobalVolume>,
+    mut sinks: Query<(&PlaybackSettings, &mut AudioSink), Without<WindAmbience>>,
+    mut spatial_sinks: Query<(&PlaybackSettings, &mut SpatialAudioSink)>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let master = Volume::Linear(settings.master_volume);
+    global_volume.volume = master;
+    for (playback, mut sink) in &mut sinks {
+        sink.set_volume(playback.volume * master);
+    }
+    for (playback, mut sink) in &mut spatial_sinks {
+        sink.set_volume(playback.volume * master);
+    }
+}
+
+/// Louder with the wind's speed at the origin, peaking with each gust.
+fn swell_wind_ambience(
+    settings: Res<AudioSettings>,
+    wind: Res<Wind>,
+    time: Res<Time>,
+    mut sinks: Query<&mut AudioSink, With<WindAmbience>>,
+) {
+    let peak = wind.strength + wind.gust_strength;
+    let speed = wind.at(Vec3::ZERO, time.elapsed_secs()).length();
+    let gust = if peak > 0. { speed / peak } else { 0. };
+    for mut sink in &mut sinks {
+        sink.set_volume(Volume::Linear(settings.master_volume * WIND_VOLUME * gust));
+    }
+}
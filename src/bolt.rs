@@ -0,0 +1,185 @@
+use std::f32::consts::TAU;
+
+use bevy::color::palettes::css;
+use bevy::light::NotShadowCaster;
+use bevy::prelude::*;
+use rand::Rng;
+use staff_gen::bolt::BoltConfig;
+use staff_gen::sockets::{self, Sockets};
+
+use crate::actions::RegisterAction;
+use crate::camera::MainCamera;
+use crate::pickup::Held;
+use crate::staff::Staff;
+use crate::units::UnitsConfig;
+
+/// How much brighter than its color a bolt glows, so it blooms
+const BOLT_GLOW: f32 = 12.;
+/// In lumens
+const FLASH_INTENSITY: f32 = 80_000.;
+/// In meters
+const FLASH_RANGE: f32 = 6.;
+/// Times per second a bolt flickers
+const FLICKER_RATE: f32 = 30.;
+
+/// What bolts cast from a staff look like, edited from the inspector.
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct BoltSettings {
+    /// Shape of every bolt, in meters. Each bolt is given a seed of its own.
+    pub bolt: BoltConfig,
+    pub color: Color,
+    /// Seconds a bolt lasts
+    pub lifetime: f32,
+    /// Furthest a bolt strays from the direction the staff points, in radians
+    pub spread: f32,
+}
+
+impl Default for BoltSettings {
+    fn default() -> Self {
+        Self {
+            bolt: BoltConfig::default(),
+            color: Color::from(css::LIGHT_CYAN),
+            lifetime: 0.25,
+            spread: 0.5,
+        }
+    }
+}
+
+/// A bolt flickering and fading out, together with the flash it lights its surroundings with.
+#[derive(Component, Debug)]
+struct Bolt {
+    age: f32,
+}
+
+#[derive(Component, Debug)]
+struct BoltFlash;
+
+/// Casts jagged bolts from the top of the held staff, or the viewer's staff, from the command
+/// palette. Each is generated by [`BoltConfig`] with a fresh seed, so no two are alike.
+pub struct BoltPlugin;
+
+impl Plugin for BoltPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<BoltSettings>()
+            .init_resource::<BoltSettings>()
+            .register_action("Cast a bolt", cast_bolt)
+            .add_systems(Update, fade_bolts);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cast_bolt(
+    mut commands: Commands,
+    settings: Res<BoltSettings>,
+    units: Res<UnitsConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    camera: Single<&GlobalTransform, With<MainCamera>>,
+    held: Query<(&GlobalTransform, &Sockets), With<Held>>,
+    staffs: Query<(&GlobalTransform, &Sockets), With<Staff>>,
+) {
+    let Some((transform, sockets)) = held.iter().next().or_else(|| staffs.iter().next()) else {
+        return;
+    };
+    let top = sockets.get(sockets::TOP).unwrap_or_default();
+    let tip = transform.transform_point(top.translation);
+    let mut rand = rand::rng();
+    let tilt_axis = Vec2::from_angle(rand.random_range(0. ..TAU));
+    let tilt = Quat::from_axis_angle(
+        vec3(tilt_axis.x, 0., tilt_axis.y),
+        rand.random_range(0. ..=settings.spread.abs()),
+    );
+    let rotation = Quat::from_rotation_arc(Vec3::Y, *transform.up()) * tilt;
+    // Where the camera looks at the bolt from, in the bolt's own space
+    let view = rotation.inverse() * (tip - camera.translation()).normalize_or_zero();
+    let config = BoltConfig {
+        seed: rand.random(),
+        ..settings.bolt.clone()
+    };
+    let mesh = match config.try_generate_mesh(view) {
+        Ok(mesh) => mesh,
+        Err(error) => {
+            warn!("Can't cast bolt: {error}");
+            return;
+        }
+    };
+    // Each bolt fades on its own
+    let material = materials.add(StandardMaterial {
+        base_color: settings.color,
+        emissive: settings.color.to_linear() * BOLT_GLOW,
+        alpha_mode: AlphaMode::Add,
+        unlit: true,
+        ..default()
+    });
+    let from_meters = units.from_meters();
+    let bolt = commands
+        .spawn((
+            Name::new("Bolt"),
+            Bolt { age: 0. },
+            Mesh3d(meshes.add(mesh)),
+            MeshMaterial3d(material),
+            NotShadowCaster,
+            Transform::from_translation(tip)
+                .with_rotation(rotation)
+                .with_scale(Vec3::splat(from_meters)),
+        ))
+        .id();
+    commands.spawn((
+        Name::new("BoltFlash"),
+        BoltFlash,
+        PointLight {
+            color: settings.color,
+            intensity: FLASH_INTENSITY,
+            range: FLASH_RANGE * from_meters,
+            ..default()
+        },
+        // Halfway along the bolt
+        Transform::from_translation(Vec3::Y * config.length / 2.),
+        ChildOf(bolt),
+    ));
+}
+
+/// Bolts flicker between bright and dim while they fade, and are removed with their mesh and
+/// material once they've lasted their lifetime.
+fn fade_bolts(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<BoltSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut bolts: Query<(
+        Entity,
+        &mut Bolt,
+        &Mesh3d,
+        &MeshMaterial3d<StandardMaterial>,
+        &Children,
+    )>,
+    mut flashes: Query<&mut PointLight, With<BoltFlash>>,
+) {
+    for (entity, mut bolt, mesh, material, children) in &mut bolts {
+        bolt.age += time.delta_secs();
+        let t = bolt.age / settings.lifetime.max(f32::EPSILON);
+        if t >= 1. {
+            meshes.remove(&mesh.0);
+            materials.remove(&material.0);
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let flicker = if ((bolt.age * FLICKER_RATE) as u32).is_multiple_of(2) {
+            1.
+        } else {
+            0.4
+        };
+        let brightness = (1. - t) * flicker;
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color = settings.color.with_alpha(brightness);
+            material.emissive = settings.color.to_linear() * BOLT_GLOW * brightness;
+        }
+        for &child in children {
+            if let Ok(mut flash) = flashes.get_mut(child) {
+                flash.intensity = FLASH_INTENSITY * brightness;
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use bevy::image::TextureFormatPixelInfo;
+use bevy::prelude::*;
+
+use crate::gallery::GalleryStaff;
+use crate::locale::Locale;
+
+const MIB: f32 = 1024. * 1024.;
+
+/// Memory used by meshes and images created at runtime rather than loaded from files.
+/// When over `limit` bytes, the gallery staffs seen least recently are despawned.
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct GenBudget {
+    pub limit: usize,
+    pub mesh_bytes: usize,
+    pub image_bytes: usize,
+}
+
+impl Default for GenBudget {
+    fn default() -> Self {
+        Self {
+            limit: Self::platform_default_limit(),
+            mesh_bytes: 0,
+            image_bytes: 0,
+        }
+    }
+}
+
+impl GenBudget {
+    /// Browsers give a tab far less memory, so the web build evicts much sooner.
+    pub fn platform_default_limit() -> usize {
+        if cfg!(target_arch = "wasm32") {
+            64 << 20
+        } else {
+            512 << 20
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.mesh_bytes + self.image_bytes
+    }
+
+    pub fn is_exceeded(&self) -> bool {
+        self.total() > self.limit
+    }
+}
+
+/// Size of every generated asset of one type and their total, kept up to date from asset
+/// events so a regenerated mesh only costs its own size to account for.
+#[derive(Debug)]
+struct AssetSizes<A: Asset> {
+    sizes: HashMap<AssetId<A>, usize>,
+    total: usize,
+}
+
+impl<A: Asset> Default for AssetSizes<A> {
+    fn default() -> Self {
+        Self {
+            sizes: HashMap::new(),
+            total: 0,
+        }
+    }
+}
+
+impl<A: Asset> AssetSizes<A> {
+    /// Applies `events`, returning the new total if any of them changed it. Assets with a path
+    /// were loaded from a file and aren't counted.
+    fn update(
+        &mut self,
+        events: &mut MessageReader<AssetEvent<A>>,
+        assets: &Assets<A>,
+        asset_server: &AssetServer,
+        size: impl Fn(&A) -> usize,
+    ) -> Option<usize> {
+        let mut changed = false;
+        for event in events.read() {
+            let id = match *event {
+                AssetEvent::Added { id }
+                | AssetEvent::Modified { id }
+                | AssetEvent::Removed { id } => id,
+                _ => continue,
+            };
+            changed = true;
+            if let Some(old) = self.sizes.remove(&id) {
+                self.total -= old;
+            }
+            // Removed assets are gone from `assets` too
+            if let Some(asset) = assets.get(id)
+                && asset_server.get_path(id).is_none()
+            {
+                let new = size(asset);
+                self.sizes.insert(id, new);
+                self.total += new;
+            }
+        }
+        changed.then_some(self.total)
+    }
+}
+
+/// Last time a gallery staff was visible, for least recently used eviction.
+#[derive(Component, Debug)]
+struct LastSeen(f32);
+
+#[derive(Component)]
+struct BudgetLabel;
+
+pub struct BudgetPlugin;
+
+impl Plugin for BudgetPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<GenBudget>()
+            .init_resource::<GenBudget>()
+            .add_systems(Startup, setup_budget_label)
+            .add_systems(
+                Update,
+                (
+                    track_gallery_visibility,
+                    measure_generated_assets,
+                    evict_gallery_staffs,
+                    update_budget_label,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn setup_budget_label(mut commands: Commands) {
+    commands.spawn((
+        Name::new("BudgetLabel"),
+        BudgetLabel,
+        Text::default(),
+        TextFont::from_font_size(12.),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.),
+            right: Val::Px(12.),
+            ..default()
+        },
+    ));
+}
+
+fn mesh_size(mesh: &Mesh) -> usize {
+    mesh.get_vertex_buffer_size() + mesh.get_index_buffer_bytes().map_or(0, <[u8]>::len)
+}
+
+fn image_size(image: &Image) -> usize {
+    // Main world image data may already be freed after upload, so size it from its descriptor
+    let size = image.texture_descriptor.size;
+    let pixel_size = image.texture_descriptor.format.pixel_size().unwrap_or(4);
+    (size.width * size.height * size.depth_or_array_layers) as usize * pixel_size
+}
+
+fn track_gallery_visibility(
+    mut commands: Commands,
+    gallery: Query<(Entity, &ViewVisibility, Option<&mut LastSeen>), With<GalleryStaff>>,
+    time: Res<Time<Real>>,
+) {
+    let now = time.elapsed_secs();
+    for (entity, visibility, last_seen) in gallery {
+        match last_seen {
+            Some(mut last_seen) if visibility.get() => last_seen.0 = now,
+            Some(_) => {}
+            None => {
+                commands.entity(entity).insert(LastSeen(now));
+            }
+        }
+    }
+}
+
+/// Updates the totals of runtime created assets for the meshes and images that were added,
+/// changed or removed. Assets with a path were loaded from a file, everything else was
+/// generated.
+#[allow(clippy::too_many_arguments)]
+fn measure_generated_assets(
+    mut mesh_events: MessageReader<AssetEvent<Mesh>>,
+    mut image_events: MessageReader<AssetEvent<Image>>,
+    meshes: Res<Assets<Mesh>>,
+    images: Res<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+    mut mesh_sizes: Local<AssetSizes<Mesh>>,
+    mut image_sizes: Local<AssetSizes<Image>>,
+    mut budget: ResMut<GenBudget>,
+) {
+    if let Some(total) = mesh_sizes.update(&mut mesh_events, &meshes, &asset_server, mesh_size) {
+        budget.mesh_bytes = total;
+    }
+    if let Some(total) = image_sizes.update(&mut image_events, &images, &asset_server, image_size) {
+        budget.image_bytes = total;
+    }
+}
+
+fn evict_gallery_staffs(
+    mut commands: Commands,
+    mut budget: ResMut<GenBudget>,
+    gallery: Query<(Entity, &Mesh3d, &LastSeen), With<GalleryStaff>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if !budget.is_exceeded() {
+        return;
+    }
+    let mut staffs: Vec<_> = gallery.iter().collect();
+    staffs.sort_by(|(_, _, a), (_, _, b)| a.0.total_cmp(&b.0));
+
+    let mut evicted = 0;
+    for (entity, mesh, _) in staffs {
+        if !budget.is_exceeded() {
+            break;
+        }
+        // Update the total right away rather than waiting for the asset events
+        if let Some(mesh) = meshes.remove(&mesh.0) {
+            budget.mesh_bytes = budget.mesh_bytes.saturating_sub(mesh_size(&mesh));
+        }
+        commands.entity(entity).despawn();
+        evicted += 1;
+    }
+    if evicted > 0 {
+        warn!(
+            "Generated assets over budget, evicted {evicted} gallery staffs ({:.1} / {:.1} MiB)",
+            budget.total() as f32 / MIB,
+            budget.limit as f32 / MIB
+        );
+    }
+}
+
+fn update_budget_label(
+    budget: Res<GenBudget>,
+    locale: Res<Locale>,
+    mut label: Single<&mut Text, With<BudgetLabel>>,
+) {
+    if !budget.is_changed() && !locale.is_changed() {
+        return;
+    }
+    label.0 = locale.format(
+        "budget.label",
+        &[
+            ("used", &format_args!("{:.1}", budget.total() as f32 / MIB)),
+            ("limit", &format_args!("{:.0}", budget.limit as f32 / MIB)),
+        ],
+    );
+}
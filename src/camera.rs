@@ -1,18 +1,45 @@
 /// Mostly Pulled from Bevy's Camera Orbit Example
 use std::{f32::consts::FRAC_PI_2, ops::Range};
 
-use bevy::{input::mouse::AccumulatedMouseMotion, prelude::*};
+use bevy::{
+    camera::{ScalingMode, primitives::Aabb},
+    input::{common_conditions::input_just_pressed, mouse::AccumulatedMouseMotion},
+    math::bounding::{Aabb3d, BoundingVolume},
+    prelude::*,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions::RegisterAction;
+use crate::environment::FLOOR_HEIGHT;
+use crate::generation::{GeneratedObject, is_generated};
+use crate::graphics::GraphicsSettings;
+use crate::locale::Locale;
+use crate::selection::{Selected, selected_or_staff, world_aabb};
+use crate::staff::Staff;
+
+/// The viewer's own camera, as opposed to ones rendering offscreen.
+#[derive(Component, Debug)]
+pub struct MainCamera;
 
 const CAMERA_DISTANCE: f32 = 3.5;
 const CAMERA_TARGET: Vec3 = vec3(0., 1.5, 0.);
 
-#[derive(Debug, Resource)]
-struct CameraSettings {
+#[derive(Debug, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct CameraSettings {
+    /// Point the camera orbits around
+    pub target: Vec3,
     pub orbit_distance: f32,
     pub pitch_speed: f32,
     // Clamp pitch to this range
     pub pitch_range: Range<f32>,
     pub yaw_speed: f32,
+    /// Keep the camera above the floor and outside large generated objects
+    pub collision: bool,
+    /// Radius of the sphere kept clear around the camera. Objects smaller than it are passed
+    /// through.
+    pub collision_radius: f32,
 }
 impl Default for CameraSettings {
     fn default() -> Self {
@@ -21,20 +48,63 @@ impl Default for CameraSettings {
         Self {
             // These values are completely arbitrary, chosen because they seem to produce
             // "sensible" results for this example. Adjust as required.
+            target: CAMERA_TARGET,
             orbit_distance: CAMERA_DISTANCE * 1.5,
             pitch_speed: 0.01,
             pitch_range: -pitch_limit..pitch_limit / 4.,
             yaw_speed: 0.0075,
+            collision: true,
+            collision_radius: 0.2,
         }
     }
 }
+/// A saved camera position, stored with Ctrl and a number key and recalled with the number key.
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CameraBookmark {
+    /// Number key 1 to 9
+    pub slot: u8,
+    pub name: String,
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub target: Vec3,
+    pub orbit_distance: f32,
+}
+
+const BOOKMARK_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(CameraSettings::default())
+        app.register_type::<CameraSettings>()
+            .insert_resource(CameraSettings::default())
+            .register_action("Toggle orthographic camera", toggle_orthographic)
             .add_systems(Startup, setup_camera_rig)
-            .add_systems(Update, handle_camera_movement);
+            .add_systems(
+                Update,
+                (
+                    handle_camera_movement,
+                    toggle_orthographic.run_if(input_just_pressed(KeyCode::Numpad5)),
+                    canonical_view_shortcuts,
+                    camera_bookmark_shortcuts,
+                    frame_selection,
+                ),
+            )
+            // After every camera system in `Update`, showcase included
+            .add_systems(
+                PostUpdate,
+                avoid_camera_collisions.before(TransformSystems::Propagate),
+            );
     }
 }
 
@@ -44,6 +114,7 @@ fn setup_camera_rig(mut commands: Commands) {
         Transform::from_translation(Vec3::ZERO),
         Children::spawn(Spawn((
             Name::new("Camera"),
+            MainCamera,
             Camera3d::default(),
             Transform::from_xyz(-CAMERA_DISTANCE, CAMERA_DISTANCE / 2., CAMERA_DISTANCE)
                 .looking_at(CAMERA_TARGET, Dir3::Y),
@@ -52,7 +123,7 @@ fn setup_camera_rig(mut commands: Commands) {
 }
 
 fn handle_camera_movement(
-    mut camera_pivot: Single<&mut Transform, With<Camera>>,
+    mut camera_pivot: Single<&mut Transform, With<MainCamera>>,
     mouse_button_input: Res<ButtonInput<MouseButton>>,
     camera_settings: Res<CameraSettings>,
     mouse_motion: Res<AccumulatedMouseMotion>,
@@ -79,8 +150,269 @@ fn handle_camera_movement(
         camera_pivot.rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, roll);
 
         // Adjust the translation to maintain the correct orientation toward the orbit target.
-        // In our example it's a static target, but this could easily be customized.
-        let target = CAMERA_TARGET;
+        let target = camera_settings.target;
         camera_pivot.translation = target - camera_pivot.forward() * camera_settings.orbit_distance;
     }
 }
+
+/// Closest the camera is pulled towards its target by collisions
+const MIN_BOOM_LENGTH: f32 = 0.1;
+
+/// Where the camera systems last put the camera, and where collisions moved it from there.
+#[derive(Debug, Clone, Copy)]
+struct Boom {
+    desired: Vec3,
+    applied: Vec3,
+}
+
+/// Pulls the camera in along the boom from its orbit target, so it stays above the floor and
+/// doesn't clip into generated objects. The sphere cast is approximated by five rays, one
+/// through the sphere's center and four along its edge. The position the camera systems set is
+/// kept, so the camera moves back out once the way is clear.
+fn avoid_camera_collisions(
+    camera_settings: Res<CameraSettings>,
+    mut camera: Single<&mut Transform, With<MainCamera>>,
+    mut ray_cast: MeshRayCast,
+    generated: Query<(), With<GeneratedObject>>,
+    parents: Query<&ChildOf>,
+    bounds: Query<(&Aabb, &GlobalTransform)>,
+    mut boom: Local<Option<Boom>>,
+) {
+    let desired = match *boom {
+        Some(boom) if boom.applied == camera.translation => boom.desired,
+        _ => camera.translation,
+    };
+    if !camera_settings.collision {
+        camera.translation = desired;
+        *boom = None;
+        return;
+    }
+    let target = camera_settings.target;
+    let Ok(direction) = Dir3::new(desired - target) else {
+        return;
+    };
+    let radius = camera_settings.collision_radius;
+    let mut length = desired.distance(target);
+
+    let floor = FLOOR_HEIGHT / 2. + radius;
+    if direction.y < 0. && target.y > floor {
+        length = length.min((target.y - floor) / -direction.y);
+    }
+
+    let is_obstacle = |entity| {
+        is_generated(entity, &generated, &parents)
+            && bounds.get(entity).is_ok_and(|(aabb, transform)| {
+                let size = Vec3::from(aabb.half_extents) * transform.scale() * 2.;
+                size.max_element() > radius * 2.
+            })
+    };
+    let settings = MeshRayCastSettings::default().with_filter(&is_obstacle);
+    let (side, up) = direction.any_orthonormal_pair();
+    for offset in [Vec3::ZERO, side, -side, up, -up] {
+        let ray = Ray3d::new(target + offset * radius, direction);
+        if let Some((_, hit)) = ray_cast.cast_ray(ray, &settings).first() {
+            length = length.min(hit.distance - radius);
+        }
+    }
+
+    let applied = target + direction * length.max(MIN_BOOM_LENGTH);
+    camera.translation = applied;
+    *boom = Some(Boom { desired, applied });
+}
+
+/// Height of the view an orthographic camera shows at `distance` to match a perspective one.
+fn matching_orthographic_height(perspective: &PerspectiveProjection, distance: f32) -> f32 {
+    2. * distance * (perspective.fov / 2.).tan()
+}
+
+/// Switches between perspective and orthographic projection. Numpad 5 does this, like in Blender.
+fn toggle_orthographic(
+    camera_settings: Res<CameraSettings>,
+    mut projection: Single<&mut Projection, With<MainCamera>>,
+) {
+    **projection = match &**projection {
+        Projection::Perspective(perspective) => {
+            let viewport_height =
+                matching_orthographic_height(perspective, camera_settings.orbit_distance);
+            Projection::Orthographic(OrthographicProjection {
+                scaling_mode: ScalingMode::FixedVertical { viewport_height },
+                ..OrthographicProjection::default_3d()
+            })
+        }
+        _ => Projection::Perspective(PerspectiveProjection::default()),
+    };
+}
+
+/// Orbit distance at which `aabb` fills the view. An orthographic view is zoomed to fit instead,
+/// as its distance doesn't change the size on screen.
+fn framing_distance(aabb: &Aabb3d, projection: &mut Projection) -> f32 {
+    let radius = aabb.half_size().length();
+    match projection {
+        Projection::Perspective(perspective) => radius / (perspective.fov / 2.).sin() * 1.1,
+        Projection::Orthographic(orthographic) => {
+            orthographic.scaling_mode = ScalingMode::FixedVertical {
+                viewport_height: radius * 2.2,
+            };
+            radius * 3.
+        }
+        Projection::Custom(_) => radius * 3.,
+    }
+}
+
+/// F glides the orbit target and distance over to the selected object so it fills the view.
+#[allow(clippy::too_many_arguments)]
+fn frame_selection(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut camera_settings: ResMut<CameraSettings>,
+    camera: Single<(&mut Transform, &mut Projection), With<MainCamera>>,
+    selected: Query<Entity, With<Selected>>,
+    staffs: Query<Entity, With<Staff>>,
+    bounds: Query<(&Aabb, &GlobalTransform)>,
+    children: Query<&Children>,
+    mut focus: Local<Option<FocusAnimation>>,
+) {
+    let (mut transform, mut projection) = camera.into_inner();
+    if keyboard_input.just_pressed(KeyCode::KeyF)
+        && let Some(aabb) = selected_or_staff(&selected, &staffs)
+            .and_then(|entity| world_aabb(entity, &bounds, &children))
+    {
+        *focus = Some(FocusAnimation {
+            from: (camera_settings.target, camera_settings.orbit_distance),
+            to: (
+                Vec3::from(aabb.center()),
+                framing_distance(&aabb, &mut projection),
+            ),
+            elapsed: 0.,
+        });
+    }
+    let Some(animation) = focus.as_mut() else {
+        return;
+    };
+    // Another shortcut moved the camera since the last frame, so stop fighting it
+    if animation.elapsed > 0. && camera_settings.target != animation.current().0 {
+        *focus = None;
+        return;
+    }
+    animation.elapsed = (animation.elapsed + time.delta_secs() / FOCUS_DURATION).min(1.);
+    let (target, distance) = animation.current();
+    camera_settings.target = target;
+    camera_settings.orbit_distance = distance;
+    transform.translation = target - transform.forward() * distance;
+    if animation.elapsed >= 1. {
+        *focus = None;
+    }
+}
+
+/// Seconds the camera takes to frame an object
+const FOCUS_DURATION: f32 = 0.4;
+
+/// Orbit target and distance before and after framing an object.
+struct FocusAnimation {
+    from: (Vec3, f32),
+    to: (Vec3, f32),
+    /// Fraction of [`FOCUS_DURATION`] passed
+    elapsed: f32,
+}
+
+impl FocusAnimation {
+    fn current(&self) -> (Vec3, f32) {
+        let t = EaseFunction::SmoothStep.sample_clamped(self.elapsed);
+        (
+            self.from.0.lerp(self.to.0, t),
+            self.from.1.lerp(self.to.1, t),
+        )
+    }
+}
+
+/// Numpad 1, 3 and 7 look at the selected object from the front, right and top, framing it.
+/// Holding Ctrl looks from the back, left and bottom instead.
+#[allow(clippy::too_many_arguments)]
+fn canonical_view_shortcuts(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut camera_settings: ResMut<CameraSettings>,
+    camera: Single<(&mut Transform, &mut Projection), With<MainCamera>>,
+    selected: Query<Entity, With<Selected>>,
+    staffs: Query<Entity, With<Staff>>,
+    bounds: Query<(&Aabb, &GlobalTransform)>,
+    children: Query<&Children>,
+) {
+    let (direction, up) = if keyboard_input.just_pressed(KeyCode::Numpad1) {
+        (Vec3::Z, Dir3::Y)
+    } else if keyboard_input.just_pressed(KeyCode::Numpad3) {
+        (Vec3::X, Dir3::Y)
+    } else if keyboard_input.just_pressed(KeyCode::Numpad7) {
+        (Vec3::Y, Dir3::NEG_Z)
+    } else {
+        return;
+    };
+    let flip = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let (direction, up) = if flip {
+        (-direction, if up == Dir3::Y { up } else { -up })
+    } else {
+        (direction, up)
+    };
+
+    let Some(aabb) = selected_or_staff(&selected, &staffs)
+        .and_then(|entity| world_aabb(entity, &bounds, &children))
+    else {
+        return;
+    };
+    let (mut transform, mut projection) = camera.into_inner();
+    let target = Vec3::from(aabb.center());
+    camera_settings.orbit_distance = framing_distance(&aabb, &mut projection);
+    camera_settings.target = target;
+    *transform = Transform::from_translation(target + direction * camera_settings.orbit_distance)
+        .looking_at(target, up);
+}
+
+fn camera_bookmark_shortcuts(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut camera_settings: ResMut<CameraSettings>,
+    mut graphics_settings: ResMut<GraphicsSettings>,
+    mut camera: Single<&mut Transform, With<MainCamera>>,
+    locale: Res<Locale>,
+) {
+    let Some(slot) = BOOKMARK_KEYS
+        .iter()
+        .position(|&key| keyboard_input.just_pressed(key))
+        .map(|index| index as u8 + 1)
+    else {
+        return;
+    };
+    let bookmarks = &graphics_settings.camera_bookmarks;
+    let existing = bookmarks.iter().position(|bookmark| bookmark.slot == slot);
+
+    if keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        // Keep a name given in the settings file when overwriting a slot
+        let name = existing.map_or_else(
+            || locale.format("camera.bookmark", &[("slot", &slot)]),
+            |index| bookmarks[index].name.clone(),
+        );
+        let bookmark = CameraBookmark {
+            slot,
+            name,
+            translation: camera.translation,
+            rotation: camera.rotation,
+            target: camera_settings.target,
+            orbit_distance: camera_settings.orbit_distance,
+        };
+        info!("Stored camera bookmark {slot} \"{}\"", bookmark.name);
+        let bookmarks = &mut graphics_settings.camera_bookmarks;
+        match existing {
+            Some(index) => bookmarks[index] = bookmark,
+            None => bookmarks.push(bookmark),
+        }
+        return;
+    }
+
+    let Some(bookmark) = existing.map(|index| &bookmarks[index]) else {
+        info!("No camera bookmark {slot}, store one with Ctrl+{slot}");
+        return;
+    };
+    camera.translation = bookmark.translation;
+    camera.rotation = bookmark.rotation;
+    camera_settings.target = bookmark.target;
+    camera_settings.orbit_distance = bookmark.orbit_distance;
+    info!("Camera bookmark {slot} \"{}\"", bookmark.name);
+}
@@ -0,0 +1,143 @@
+#[cfg(feature = "particles")]
+use std::f32::consts::TAU;
+
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use staff_gen::assembly::StaffAssembly;
+use staff_gen::sockets;
+
+#[cfg(feature = "particles")]
+use crate::camera::MainCamera;
+
+/// Flame tongues rising from each campfire at once
+#[cfg(feature = "particles")]
+const TONGUES: u32 = 5;
+/// How high tongues rise before they burn out
+const FLAME_HEIGHT: f32 = 0.35;
+#[cfg(feature = "particles")]
+const TONGUE_SIZE: Vec2 = vec2(0.12, 0.2);
+/// Burnouts per second of each tongue
+#[cfg(feature = "particles")]
+const TONGUE_RATE: f32 = 1.6;
+/// In lumens
+const FIRE_INTENSITY: f32 = 60_000.;
+
+/// An assembly built by [`staff_gen::campfire::CampfireConfig`], lit once it's spawned.
+#[derive(Component, Debug)]
+pub struct Campfire;
+
+/// A quad that rises from the embers, shrinking as it goes, and starts over. `phase` staggers
+/// the tongues of one fire.
+#[cfg(feature = "particles")]
+#[derive(Component, Debug)]
+struct FlameTongue {
+    phase: f32,
+    /// Point the tongue rises from, relative to the fire
+    base: Vec3,
+}
+
+#[derive(Component, Debug)]
+struct FireLight;
+
+/// A flickering light for campfires, and flames in builds with the `particles` feature.
+pub struct CampfirePlugin;
+
+impl Plugin for CampfirePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (light_campfires, flicker_fire_lights));
+
+        #[cfg(feature = "particles")]
+        app.add_systems(Update, (kindle_flames, burn_flames));
+    }
+}
+
+/// Top of the embers, where the flames rise from.
+fn embers_top(assembly: &StaffAssembly) -> Vec3 {
+    assembly
+        .part(0)
+        .and_then(|embers| embers.shape.socket(sockets::TOP))
+        .unwrap_or_default()
+}
+
+fn light_campfires(
+    mut commands: Commands,
+    campfires: Query<(Entity, &StaffAssembly), Added<Campfire>>,
+) {
+    for (campfire, assembly) in &campfires {
+        commands.spawn((
+            Name::new("FireLight"),
+            FireLight,
+            PointLight {
+                color: Color::from(css::ORANGE),
+                intensity: FIRE_INTENSITY,
+                range: 6.,
+                ..default()
+            },
+            Transform::from_translation(embers_top(assembly) + Vec3::Y * FLAME_HEIGHT / 2.),
+            ChildOf(campfire),
+        ));
+    }
+}
+
+#[cfg(feature = "particles")]
+fn kindle_flames(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    campfires: Query<(Entity, &StaffAssembly), Added<Campfire>>,
+) {
+    for (campfire, assembly) in &campfires {
+        let base = embers_top(assembly);
+        let mesh = meshes.add(Rectangle::from_size(TONGUE_SIZE));
+        let material = materials.add(StandardMaterial {
+            base_color: Color::from(css::ORANGE).with_alpha(0.8),
+            emissive: LinearRgba::from(css::ORANGE) * 6.,
+            alpha_mode: AlphaMode::Add,
+            unlit: true,
+            cull_mode: None,
+            ..default()
+        });
+        for i in 0..TONGUES {
+            let phase = i as f32 / TONGUES as f32;
+            // Spread the tongues over the embers so they don't rise in single file
+            let offset = Vec2::from_angle(phase * TAU * 2.) * 0.04;
+            commands.spawn((
+                Name::new("FlameTongue"),
+                FlameTongue {
+                    phase,
+                    base: base + vec3(offset.x, 0., offset.y),
+                },
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::from_translation(base),
+                ChildOf(campfire),
+            ));
+        }
+    }
+}
+
+/// Rises and shrinks every tongue, turned about the vertical to face the camera.
+#[cfg(feature = "particles")]
+fn burn_flames(
+    time: Res<Time>,
+    camera: Single<&GlobalTransform, With<MainCamera>>,
+    mut tongues: Query<(&FlameTongue, &mut Transform, &GlobalTransform)>,
+) {
+    let camera = camera.translation();
+    for (tongue, mut transform, global) in &mut tongues {
+        let life = (time.elapsed_secs() * TONGUE_RATE + tongue.phase).fract();
+        let to_camera = camera - global.translation();
+        transform.translation = tongue.base + Vec3::Y * (life * FLAME_HEIGHT + TONGUE_SIZE.y / 2.);
+        transform.rotation = Quat::from_rotation_y(to_camera.x.atan2(to_camera.z));
+        transform.scale = Vec3::splat(1. - life * 0.8);
+    }
+}
+
+/// A few sines out of step make the light waver without repeating often.
+fn flicker_fire_lights(time: Res<Time>, mut lights: Query<&mut PointLight, With<FireLight>>) {
+    let t = time.elapsed_secs();
+    let flicker = ((t * 13.).sin() + (t * 7.3 + 1.).sin() + (t * 23.1 + 2.).sin()) / 3.;
+    for mut light in &mut lights {
+        light.intensity = FIRE_INTENSITY * (0.85 + 0.15 * flicker);
+    }
+}
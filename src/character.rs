@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+
+use crate::actions::RegisterAction;
+use crate::camera::{CameraSettings, MainCamera};
+use crate::environment::EnvironmentConfig;
+use crate::state::AppState;
+use crate::units::UnitsConfig;
+
+/// The Laura model, walked around the floor in [`AppState::Walking`].
+#[derive(Component, Debug)]
+pub struct Character;
+
+/// How the character walks and how closely the camera follows it. Lengths are in meters.
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct CharacterSettings {
+    /// Meters per second
+    pub walk_speed: f32,
+    /// How quickly the character turns to face where it walks, in radians per second
+    pub turn_speed: f32,
+    /// Height above the character's feet the camera looks at
+    pub eye_height: f32,
+    pub follow_distance: f32,
+}
+
+impl Default for CharacterSettings {
+    fn default() -> Self {
+        Self {
+            walk_speed: 1.5,
+            turn_speed: 10.,
+            eye_height: 1.4,
+            follow_distance: 3.,
+        }
+    }
+}
+
+/// The orbit the camera had before it started following the character, restored afterwards.
+#[derive(Resource, Debug)]
+struct SavedOrbit {
+    target: Vec3,
+    orbit_distance: f32,
+}
+
+/// A third person controller: WASD walks the character around the floor relative to the
+/// camera, which follows it and still orbits with the mouse. Staffs can be judged at the scale
+/// they're held at.
+pub struct CharacterPlugin;
+
+impl Plugin for CharacterPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<CharacterSettings>()
+            .init_resource::<CharacterSettings>()
+            .register_action(
+                "Walk around as Laura",
+                |mut next_state: ResMut<NextState<AppState>>| next_state.set(AppState::Walking),
+            )
+            .add_systems(OnEnter(AppState::Walking), start_following)
+            .add_systems(OnExit(AppState::Walking), stop_following)
+            .add_systems(
+                Update,
+                (walk_character, follow_character)
+                    .chain()
+                    .run_if(in_state(AppState::Walking)),
+            );
+    }
+}
+
+fn start_following(
+    mut commands: Commands,
+    settings: Res<CharacterSettings>,
+    units: Res<UnitsConfig>,
+    mut camera_settings: ResMut<CameraSettings>,
+) {
+    commands.insert_resource(SavedOrbit {
+        target: camera_settings.target,
+        orbit_distance: camera_settings.orbit_distance,
+    });
+    camera_settings.orbit_distance = settings.follow_distance * units.from_meters();
+}
+
+fn stop_following(
+    mut commands: Commands,
+    saved: Option<Res<SavedOrbit>>,
+    mut camera_settings: ResMut<CameraSettings>,
+    mut camera: Single<&mut Transform, With<MainCamera>>,
+) {
+    let Some(saved) = saved else {
+        return;
+    };
+    camera_settings.target = saved.target;
+    camera_settings.orbit_distance = saved.orbit_distance;
+    camera.translation = saved.target - camera.forward() * saved.orbit_distance;
+    commands.remove_resource::<SavedOrbit>();
+}
+
+/// Forward walks away from the camera, and the character turns towards where it's going.
+/// It stays on the floor.
+fn walk_character(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    settings: Res<CharacterSettings>,
+    units: Res<UnitsConfig>,
+    environment: Res<EnvironmentConfig>,
+    camera: Single<&Transform, (With<MainCamera>, Without<Character>)>,
+    mut character: Single<&mut Transform, With<Character>>,
+) {
+    let input = [
+        (KeyCode::KeyW, Vec2::Y),
+        (KeyCode::KeyS, Vec2::NEG_Y),
+        (KeyCode::KeyA, Vec2::NEG_X),
+        (KeyCode::KeyD, Vec2::X),
+    ]
+    .into_iter()
+    .filter(|(key, _)| keyboard_input.pressed(*key))
+    .map(|(_, direction)| direction)
+    .sum::<Vec2>();
+    if input == Vec2::ZERO {
+        return;
+    }
+    let forward = camera.forward().with_y(0.).normalize_or_zero();
+    let right = camera.right().with_y(0.).normalize_or_zero();
+    let Ok(direction) = Dir3::new(forward * input.y + right * input.x) else {
+        return;
+    };
+
+    let delta = time.delta_secs();
+    let step = settings.walk_speed * units.from_meters() * delta;
+    let half_floor = environment.floor_length / 2.;
+    let translation = character.translation + direction * step;
+    character.translation = translation
+        .with_x(translation.x.clamp(-half_floor, half_floor))
+        .with_z(translation.z.clamp(-half_floor, half_floor));
+
+    // The model faces +Z
+    let facing = Quat::from_rotation_y(direction.x.atan2(direction.z));
+    character.rotation = character
+        .rotation
+        .slerp(facing, (settings.turn_speed * delta).min(1.));
+}
+
+/// Keeps the orbit target on the character, so the camera follows it around.
+fn follow_character(
+    settings: Res<CharacterSettings>,
+    units: Res<UnitsConfig>,
+    mut camera_settings: ResMut<CameraSettings>,
+    character: Single<&Transform, (With<Character>, Without<MainCamera>)>,
+    mut camera: Single<&mut Transform, With<MainCamera>>,
+) {
+    let target = character.translation + Vec3::Y * settings.eye_height * units.from_meters();
+    camera_settings.target = target;
+    camera.translation = target - camera.forward() * camera_settings.orbit_distance;
+}
@@ -0,0 +1,140 @@
+use std::f32::consts::TAU;
+
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use staff_gen::charms::{Charm, CharmConfig, CharmKind};
+use staff_gen::staff::StaffConfig;
+
+use crate::mesh_source::MeshSource;
+
+/// Degrees of swing either side of hanging straight down
+const SWING_AMPLITUDE: f32 = 12.;
+const GRAVITY: f32 = 9.81;
+
+/// A charm swinging like a pendulum on its cord, around its parent staff's anchor point.
+#[derive(Component, Debug)]
+pub struct CharmSwing {
+    /// Swings per second, from the cord length
+    pub frequency: f32,
+    pub phase: f32,
+}
+
+#[derive(Resource, Debug)]
+struct CharmMaterials {
+    feather: Handle<StandardMaterial>,
+    bead: Handle<StandardMaterial>,
+    bone: Handle<StandardMaterial>,
+}
+
+impl FromWorld for CharmMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        Self {
+            feather: materials.add(Color::from(css::WHITE_SMOKE)),
+            bead: materials.add(StandardMaterial {
+                base_color: Color::from(css::TEAL),
+                perceptual_roughness: 0.2,
+                ..default()
+            }),
+            bone: materials.add(Color::from(css::BEIGE)),
+        }
+    }
+}
+
+impl CharmMaterials {
+    fn get(&self, kind: CharmKind) -> Handle<StandardMaterial> {
+        match kind {
+            CharmKind::Feather => self.feather.clone(),
+            CharmKind::Bead => self.bead.clone(),
+            CharmKind::Bone => self.bone.clone(),
+        }
+    }
+}
+
+/// Hangs charms from every staff with a [`CharmConfig`], respawning them whenever the config or
+/// the staff changes. Charms hang from the staff's generated surface once it has a
+/// [`MeshSource`], so styling that moves the surface off the ideal rings doesn't leave them
+/// floating or sunk.
+pub struct CharmPlugin;
+
+impl Plugin for CharmPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<CharmConfig>()
+            .init_resource::<CharmMaterials>()
+            .add_systems(Update, (rebuild_changed_charms, swing_charms).chain());
+    }
+}
+
+type CharmedStaff = (
+    Entity,
+    Ref<'static, CharmConfig>,
+    Ref<'static, StaffConfig>,
+    Option<Ref<'static, MeshSource>>,
+);
+
+fn rebuild_changed_charms(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    materials: Res<CharmMaterials>,
+    staffs: Query<CharmedStaff>,
+    charms: Query<(Entity, &ChildOf), With<CharmSwing>>,
+) {
+    for (staff, config, staff_config, source) in &staffs {
+        if !config.is_changed()
+            && !staff_config.is_changed()
+            && !source.as_ref().is_some_and(Ref::is_changed)
+        {
+            continue;
+        }
+        for (charm, child_of) in &charms {
+            if child_of.parent() == staff {
+                commands.entity(charm).despawn();
+            }
+        }
+        // A simple pendulum: f = sqrt(g / L) / 2π
+        let frequency = (GRAVITY / config.cord_length.max(0.01)).sqrt() / TAU;
+        for (i, charm) in config.charms(&staff_config).into_iter().enumerate() {
+            commands.spawn((
+                Name::new(format!("Charm {:?}", charm.kind)),
+                CharmSwing {
+                    frequency,
+                    phase: i as f32 * 1.7,
+                },
+                Mesh3d(meshes.add(charm.generate_mesh(config.cord_length))),
+                MeshMaterial3d(materials.get(charm.kind)),
+                Transform::from_translation(surface_anchor(
+                    &charm,
+                    &staff_config,
+                    source.as_deref(),
+                )),
+                ChildOf(staff),
+            ));
+        }
+    }
+}
+
+/// Where `charm` meets the staff's surface, cast in from outside along its ring's radius.
+/// Falls back to its anchor on the ideal ring.
+fn surface_anchor(charm: &Charm, staff: &StaffConfig, source: Option<&MeshSource>) -> Vec3 {
+    let Ok(inward) = Dir3::new(-charm.outward) else {
+        return charm.anchor;
+    };
+    let outside = charm.anchor + charm.outward * staff.radius * 2.;
+    source
+        .and_then(|source| source.raycast(Ray3d::new(outside, inward)))
+        .map_or(charm.anchor, |hit| hit.position)
+}
+
+fn swing_charms(time: Res<Time>, mut charms: Query<(&CharmSwing, &mut Transform)>) {
+    let amplitude = SWING_AMPLITUDE.to_radians();
+    for (swing, mut transform) in &mut charms {
+        let angle = time.elapsed_secs() * swing.frequency * TAU + swing.phase;
+        // A slower sideways swing makes each charm trace a lazy ellipse
+        transform.rotation = Quat::from_euler(
+            EulerRot::XZY,
+            amplitude * angle.sin(),
+            amplitude * 0.5 * (angle * 0.7).cos(),
+            0.,
+        );
+    }
+}
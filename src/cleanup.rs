@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use staff_gen::cube::CubeNormals;
+use staff_gen::cylinder::CylinderNormals;
+use staff_gen::style::StyleConfig;
+
+use crate::actions::RegisterAction;
+use crate::environment::spawn_generated_objects;
+use crate::generation::{GeneratedObject, GeneratorKind};
+
+/// Despawns [`GeneratedObject`]s. Their meshes are removed right away, materials and images
+/// are freed by Bevy once nothing else holds a handle to them.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanupRequest {
+    DespawnAll,
+    Despawn(GeneratorKind),
+    /// Despawns everything, then spawns the viewer's generated shapes again
+    Regenerate,
+}
+
+pub struct CleanupPlugin;
+
+impl Plugin for CleanupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<CleanupRequest>()
+            .register_action(
+                "Regenerate scene",
+                |mut requests: MessageWriter<CleanupRequest>| {
+                    requests.write(CleanupRequest::Regenerate);
+                },
+            )
+            .register_action(
+                "Clear scene",
+                |mut requests: MessageWriter<CleanupRequest>| {
+                    requests.write(CleanupRequest::DespawnAll);
+                },
+            )
+            .add_systems(
+                Update,
+                (
+                    request_cleanup_on_key,
+                    regenerate_on_style_change,
+                    despawn_generated_objects,
+                    spawn_generated_objects.run_if(regenerate_requested),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// R regenerates the scene, Delete clears it.
+fn request_cleanup_on_key(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut requests: MessageWriter<CleanupRequest>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyR) {
+        requests.write(CleanupRequest::Regenerate);
+    }
+    if keyboard_input.just_pressed(KeyCode::Delete) {
+        requests.write(CleanupRequest::DespawnAll);
+    }
+}
+
+/// Every generator reads the [`StyleConfig`] when it runs, so a new style regenerates the scene.
+fn regenerate_on_style_change(
+    style: Res<StyleConfig>,
+    mut requests: MessageWriter<CleanupRequest>,
+) {
+    if style.is_changed() && !style.is_added() {
+        requests.write(CleanupRequest::Regenerate);
+    }
+}
+
+fn despawn_generated_objects(
+    mut commands: Commands,
+    mut requests: MessageReader<CleanupRequest>,
+    objects: Query<(Entity, &GeneratedObject, Option<&Mesh3d>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut cube_normals: ResMut<CubeNormals>,
+    mut cylinder_normals: ResMut<CylinderNormals>,
+) {
+    for request in requests.read() {
+        let despawned = |kind: GeneratorKind| match request {
+            CleanupRequest::DespawnAll | CleanupRequest::Regenerate => true,
+            CleanupRequest::Despawn(despawned) => *despawned == kind,
+        };
+        let mut count = 0;
+        for (entity, object, mesh) in &objects {
+            if !despawned(object.0) {
+                continue;
+            }
+            // Every generator builds its own mesh, so nothing else is using it
+            if let Some(mesh) = mesh.and_then(|mesh| meshes.remove(&mesh.0))
+                && let Some(morph_targets) = mesh.morph_targets()
+            {
+                images.remove(morph_targets);
+            }
+            commands.entity(entity).despawn();
+            count += 1;
+        }
+        // The debug normal gizmos would otherwise outlive their shapes
+        if despawned(GeneratorKind::Cube) {
+            *cube_normals = CubeNormals::default();
+        }
+        if despawned(GeneratorKind::Cylinder) {
+            *cylinder_normals = CylinderNormals::default();
+        }
+        info!("Despawned {count} generated objects for {request:?}");
+    }
+}
+
+fn regenerate_requested(mut requests: MessageReader<CleanupRequest>) -> bool {
+    // Read every request, so none are left to trigger a second regeneration next frame
+    let mut regenerate = false;
+    for request in requests.read() {
+        regenerate |= *request == CleanupRequest::Regenerate;
+    }
+    regenerate
+}
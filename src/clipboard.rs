@@ -0,0 +1,300 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::ecs::query::QueryData;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use staff_gen::cone::ConeConfig;
+use staff_gen::crystal::CrystalConfig;
+use staff_gen::cylinder::CylinderConfig;
+use staff_gen::staff::StaffConfig;
+use staff_gen::wrapping::WrappingConfig;
+
+use crate::crystal::{GenerateCrystal, default_crystal};
+use crate::generation::{GeneratedObject, GeneratorKind};
+use crate::morph::StaffMorph;
+use crate::selection::Selected;
+use crate::staff::{GenerateStaff, Staff};
+
+/// A generation config as it is copied to the clipboard, tagged with its generator so it can
+/// only be pasted onto the same kind of object.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum CopiedConfig {
+    Staff(StaffConfig),
+    Crystal(CrystalConfig),
+    Cone(ConeConfig),
+    Cylinder(CylinderConfig),
+    Wrapping(WrappingConfig),
+}
+
+impl CopiedConfig {
+    pub fn kind(&self) -> GeneratorKind {
+        match self {
+            Self::Staff(_) => GeneratorKind::Staff,
+            Self::Crystal(_) => GeneratorKind::Crystal,
+            Self::Cone(_) => GeneratorKind::Cone,
+            Self::Cylinder(_) => GeneratorKind::Cylinder,
+            Self::Wrapping(_) => GeneratorKind::Wrapping,
+        }
+    }
+
+    /// The config `object` was generated from, if it has one.
+    pub fn of(
+        (staff, crystal, cone, cylinder, wrapping): <CopiedObject as QueryData>::Item<'_, '_>,
+    ) -> Option<Self> {
+        staff
+            .cloned()
+            .map(Self::Staff)
+            .or_else(|| crystal.cloned().map(Self::Crystal))
+            .or_else(|| cone.cloned().map(Self::Cone))
+            .or_else(|| cylinder.cloned().map(Self::Cylinder))
+            .or_else(|| wrapping.cloned().map(Self::Wrapping))
+    }
+
+    /// Seed of generators that have one.
+    pub fn seed(&self) -> Option<u64> {
+        match self {
+            Self::Staff(config) => Some(config.seed),
+            _ => None,
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, default())
+    }
+
+    /// Reads RON, or JSON too in builds with the `export` feature.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let ron_error = match ron::from_str(text) {
+            Ok(config) => return Ok(config),
+            Err(error) => error,
+        };
+        #[cfg(feature = "export")]
+        if let Ok(config) = serde_json::from_str(text) {
+            return Ok(config);
+        }
+        Err(ron_error.to_string())
+    }
+
+    /// Replaces the config on `entity`, which regenerates it like any other edit.
+    fn insert_into(self, entity: &mut EntityCommands) {
+        match self {
+            Self::Staff(config) => entity.insert(config),
+            Self::Crystal(config) => entity.insert(config),
+            Self::Cone(config) => entity.insert(config),
+            Self::Cylinder(config) => entity.insert(config),
+            Self::Wrapping(config) => entity.insert(config),
+        };
+    }
+}
+
+/// Clipboard text on its way to being pasted. Reading the browser clipboard finishes in a later
+/// frame, so the read fills this in whenever it is done.
+#[derive(Resource, Default, Clone)]
+struct PastedText(Arc<Mutex<Option<Result<String, String>>>>);
+
+/// Ctrl+C copies the selected generated object's config to the system clipboard as RON.
+/// Ctrl+V pastes a copied config onto the selected object of the same kind, or generates a new
+/// staff or crystal from it when nothing like it is selected.
+pub struct ClipboardPlugin;
+
+impl Plugin for ClipboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PastedText>().add_systems(
+            Update,
+            (
+                copy_parameters_on_key,
+                paste_parameters_on_key,
+                apply_pasted_parameters,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// The config components a generated object may have, for [`CopiedConfig::of`].
+pub type CopiedObject = (
+    Option<&'static StaffConfig>,
+    Option<&'static CrystalConfig>,
+    Option<&'static ConeConfig>,
+    Option<&'static CylinderConfig>,
+    Option<&'static WrappingConfig>,
+);
+
+fn copy_parameters_on_key(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    selected: Query<CopiedObject, With<Selected>>,
+) {
+    if !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+        || !keyboard_input.just_pressed(KeyCode::KeyC)
+    {
+        return;
+    }
+    let Some(config) = selected.iter().find_map(CopiedConfig::of) else {
+        warn!("Select a generated object to copy its parameters");
+        return;
+    };
+    match config.to_ron() {
+        Ok(text) => {
+            write_clipboard(text);
+            info!("Copied {} parameters", config.kind());
+        }
+        Err(error) => error!("Failed to serialize {} parameters: {error}", config.kind()),
+    }
+}
+
+fn paste_parameters_on_key(keyboard_input: Res<ButtonInput<KeyCode>>, pasted: Res<PastedText>) {
+    if keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+        && keyboard_input.just_pressed(KeyCode::KeyV)
+    {
+        read_clipboard(pasted.0.clone());
+    }
+}
+
+/// Pastes clipboard text once it has been read. The viewer's own staff follows the morph, so a
+/// staff config pasted onto it becomes the morph's start, with the slider moved back there.
+fn apply_pasted_parameters(
+    mut commands: Commands,
+    pasted: Res<PastedText>,
+    selected: Query<(Entity, &GeneratedObject, Has<Staff>), With<Selected>>,
+    mut morph: ResMut<StaffMorph>,
+    mut generate_staff: MessageWriter<GenerateStaff>,
+    mut generate_crystal: MessageWriter<GenerateCrystal>,
+) {
+    let Some(text) = pasted.0.lock().ok().and_then(|mut text| text.take()) else {
+        return;
+    };
+    let config = match text.and_then(|text| CopiedConfig::parse(&text)) {
+        Ok(config) => config,
+        Err(error) => {
+            warn!("Clipboard holds no parameters to paste: {error}");
+            return;
+        }
+    };
+    let kind = config.kind();
+    let target = selected
+        .iter()
+        .find(|&(_, &GeneratedObject(selected_kind), _)| selected_kind == kind);
+
+    match (config, target) {
+        (CopiedConfig::Staff(config), Some((_, _, true))) => {
+            morph.from = config;
+            morph.t = 0.;
+        }
+        (config, Some((entity, ..))) => config.insert_into(&mut commands.entity(entity)),
+        (CopiedConfig::Staff(config), None) => {
+            generate_staff.write(GenerateStaff::new(config));
+        }
+        (CopiedConfig::Crystal(config), None) => {
+            generate_crystal.write(GenerateCrystal {
+                config,
+                ..default_crystal()
+            });
+        }
+        (_, None) => {
+            warn!("Select a {kind} to paste these parameters onto");
+            return;
+        }
+    }
+    info!("Pasted {kind} parameters");
+}
+
+/// Copies `text` to the system clipboard through the platform's command line tools.
+#[cfg(not(target_arch = "wasm32"))]
+fn write_clipboard(text: String) {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let copied = clipboard_commands(false).iter().any(|(program, args)| {
+        let Ok(mut child) = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            return false;
+        };
+        let written = child
+            .stdin
+            .take()
+            .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        child.wait().is_ok_and(|status| status.success()) && written
+    });
+    if !copied {
+        warn!("No clipboard tool found, parameters:\n{text}");
+    }
+}
+
+/// Reads the system clipboard through the platform's command line tools.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_clipboard(pasted: Arc<Mutex<Option<Result<String, String>>>>) {
+    use std::process::Command;
+
+    let text = clipboard_commands(true)
+        .iter()
+        .find_map(|(program, args)| {
+            let output = Command::new(program).args(*args).output().ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+        })
+        .ok_or_else(|| "no clipboard tool found".to_string());
+    if let Ok(mut pasted) = pasted.lock() {
+        *pasted = Some(text);
+    }
+}
+
+/// Programs to try in order for copying to, or pasting from, the clipboard.
+#[cfg(not(target_arch = "wasm32"))]
+fn clipboard_commands(paste: bool) -> &'static [(&'static str, &'static [&'static str])] {
+    match (cfg!(target_os = "macos"), cfg!(windows), paste) {
+        (true, _, false) => &[("pbcopy", &[])],
+        (true, _, true) => &[("pbpaste", &[])],
+        (_, true, false) => &[("clip", &[])],
+        (_, true, true) => &[("powershell", &["-NoProfile", "-Command", "Get-Clipboard"])],
+        // Wayland first, then X11
+        (false, false, false) => &[
+            ("wl-copy", &[]),
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ],
+        (false, false, true) => &[
+            ("wl-paste", &["--no-newline"]),
+            ("xclip", &["-selection", "clipboard", "-o"]),
+            ("xsel", &["--clipboard", "--output"]),
+        ],
+    }
+}
+
+/// Copies `text` through the browser's async clipboard API, which needs a secure context.
+#[cfg(target_arch = "wasm32")]
+fn write_clipboard(text: String) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let promise = window.navigator().clipboard().write_text(&text);
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(error) = wasm_bindgen_futures::JsFuture::from(promise).await {
+            warn!("Failed to copy parameters: {error:?}");
+        }
+    });
+}
+
+/// Reads the browser clipboard, which asks the user's permission and resolves in a later frame.
+#[cfg(target_arch = "wasm32")]
+fn read_clipboard(pasted: Arc<Mutex<Option<Result<String, String>>>>) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let promise = window.navigator().clipboard().read_text();
+    wasm_bindgen_futures::spawn_local(async move {
+        let text = wasm_bindgen_futures::JsFuture::from(promise)
+            .await
+            .map(|text| text.as_string().unwrap_or_default())
+            .map_err(|error| format!("{error:?}"));
+        if let Ok(mut pasted) = pasted.lock() {
+            *pasted = Some(text);
+        }
+    });
+}
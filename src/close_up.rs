@@ -0,0 +1,146 @@
+use bevy::camera::Viewport;
+use bevy::prelude::*;
+use bevy::ui::IsDefaultUiCamera;
+use bevy::window::PrimaryWindow;
+use staff_gen::sockets::{self, Sockets};
+
+use crate::camera::MainCamera;
+use crate::staff::Staff;
+
+/// Gap between the picture-in-picture viewport and the window corner, in logical pixels
+const CLOSE_UP_MARGIN: f32 = 12.;
+
+/// A second camera showing a close-up of the staff head in a corner of the window.
+/// P swaps it with the main view.
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
+pub struct CloseUpSettings {
+    pub enabled: bool,
+    /// The close-up fills the window and the main view is the picture-in-picture
+    pub swapped: bool,
+    /// Height of the picture-in-picture as a fraction of the window's
+    pub size: f32,
+    /// How far the close-up camera stands from the staff head
+    pub distance: f32,
+}
+
+impl Default for CloseUpSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            swapped: false,
+            size: 0.3,
+            distance: 0.8,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct CloseUpCamera;
+
+pub struct CloseUpPlugin;
+
+impl Plugin for CloseUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<CloseUpSettings>()
+            .init_resource::<CloseUpSettings>()
+            .add_systems(Startup, setup_close_up_camera)
+            .add_systems(
+                Update,
+                (swap_viewports_on_key, layout_viewports, follow_staff_head).chain(),
+            );
+    }
+}
+
+fn setup_close_up_camera(mut commands: Commands) {
+    commands.spawn((
+        Name::new("CloseUpCamera"),
+        CloseUpCamera,
+        Camera3d::default(),
+        Camera {
+            order: 1,
+            ..default()
+        },
+    ));
+}
+
+/// P swaps which camera fills the window.
+fn swap_viewports_on_key(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<CloseUpSettings>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyP) {
+        settings.swapped = !settings.swapped;
+    }
+}
+
+type ViewportCamera = (Entity, &'static mut Camera, Has<IsDefaultUiCamera>);
+
+/// Gives the enlarged camera the whole window and the other one the bottom right corner.
+/// The enlarged camera renders first and also draws the UI.
+fn layout_viewports(
+    mut commands: Commands,
+    settings: Res<CloseUpSettings>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    main: Single<ViewportCamera, With<MainCamera>>,
+    close_up: Single<ViewportCamera, (With<CloseUpCamera>, Without<MainCamera>)>,
+) {
+    let (large, small) = if settings.enabled && settings.swapped {
+        (close_up.into_inner(), main.into_inner())
+    } else {
+        (main.into_inner(), close_up.into_inner())
+    };
+    let (large, mut large_camera, large_draws_ui) = large;
+    let (small, mut small_camera, _) = small;
+
+    let scale = window.scale_factor();
+    let window_size = window.physical_size().as_vec2();
+    let height = window_size.y * settings.size.clamp(0.05, 1.);
+    let size = vec2(height * window_size.x / window_size.y.max(1.), height);
+    let position = (window_size - size - CLOSE_UP_MARGIN * scale)
+        .max(Vec2::ZERO)
+        .as_uvec2();
+    let size = size.as_uvec2().max(UVec2::ONE);
+
+    // Only touch the cameras when something moved, so their change ticks stay meaningful
+    if large_camera.viewport.is_some() || large_camera.order != 0 || !large_camera.is_active {
+        large_camera.viewport = None;
+        large_camera.order = 0;
+        large_camera.is_active = true;
+    }
+    if !large_draws_ui {
+        commands.entity(large).insert(IsDefaultUiCamera);
+        commands.entity(small).remove::<IsDefaultUiCamera>();
+    }
+    let placed = small_camera.viewport.as_ref().is_some_and(|viewport| {
+        viewport.physical_position == position && viewport.physical_size == size
+    });
+    if !placed || small_camera.order != 1 {
+        small_camera.viewport = Some(Viewport {
+            physical_position: position,
+            physical_size: size,
+            ..default()
+        });
+        small_camera.order = 1;
+    }
+    if small_camera.is_active != settings.enabled {
+        small_camera.is_active = settings.enabled;
+    }
+}
+
+/// Keeps the close-up on the viewer's staff head, seen from the same side as the main view.
+fn follow_staff_head(
+    settings: Res<CloseUpSettings>,
+    staff: Single<(&Sockets, &GlobalTransform), With<Staff>>,
+    main: Single<&GlobalTransform, (With<MainCamera>, Without<CloseUpCamera>)>,
+    mut close_up: Single<&mut Transform, With<CloseUpCamera>>,
+) {
+    let (staff_sockets, staff_transform) = *staff;
+    let Some(top) = staff_sockets.get(sockets::TOP) else {
+        return;
+    };
+    let head = staff_transform.transform_point(top.translation);
+    let from_head = (main.translation() - head).normalize_or(Vec3::Z);
+    **close_up =
+        Transform::from_translation(head + from_head * settings.distance).looking_at(head, Dir3::Y);
+}
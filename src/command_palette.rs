@@ -0,0 +1,209 @@
+use bevy::color::palettes::css;
+use bevy::input::InputSystems;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+use crate::actions::{ActionRegistry, KeyBindings, run_bound_actions};
+use crate::locale::Locale;
+use crate::settings::PersistPlugin;
+
+const PALETTE_FONT_SIZE: f32 = 14.;
+/// Matches listed below the search text
+const MAX_PALETTE_ROWS: usize = 12;
+
+/// Whether the command palette is open, what has been typed into it and which match Enter runs.
+#[derive(Resource, Debug, Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    pub query: String,
+    pub highlighted: usize,
+}
+
+#[derive(Component, Debug)]
+struct CommandPalettePanel;
+
+/// A listed action, run when clicked.
+#[derive(Component, Debug, Clone, Copy)]
+struct PaletteRow(usize);
+
+/// Ctrl+P opens a palette listing every action in the [`ActionRegistry`], narrowed down by
+/// fuzzy search as you type. Up and Down pick a match, Enter runs it and Escape closes the
+/// palette. Keys typed into the palette don't reach the rest of the viewer. Actions can also be
+/// bound to keys through [`KeyBindings`].
+pub struct CommandPalettePlugin;
+
+impl Plugin for CommandPalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActionRegistry>()
+            .init_resource::<CommandPalette>()
+            .init_resource::<KeyBindings>()
+            .add_plugins(PersistPlugin::<KeyBindings>::default())
+            .add_observer(run_clicked_action)
+            .add_systems(Startup, setup_command_palette)
+            .add_systems(PreUpdate, type_into_palette.after(InputSystems))
+            .add_systems(
+                Update,
+                (
+                    run_bound_actions,
+                    rebuild_command_palette
+                        .run_if(resource_changed::<CommandPalette>.or(resource_changed::<Locale>)),
+                ),
+            );
+    }
+}
+
+fn setup_command_palette(mut commands: Commands) {
+    commands.spawn((
+        Name::new("CommandPalettePanel"),
+        CommandPalettePanel,
+        Visibility::Hidden,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(15.),
+            left: Val::Percent(30.),
+            width: Val::Percent(40.),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(2.),
+            padding: UiRect::all(Val::Px(8.)),
+            ..default()
+        },
+        BackgroundColor(Color::from(css::DARK_SLATE_GRAY).with_alpha(0.95)),
+        GlobalZIndex(1),
+    ));
+}
+
+/// Opens and closes the palette, and while it is open edits the search text and consumes the
+/// keyboard so typing doesn't trigger shortcuts.
+fn type_into_palette(
+    mut commands: Commands,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut typed: MessageReader<KeyboardInput>,
+    mut palette: ResMut<CommandPalette>,
+    registry: Res<ActionRegistry>,
+    locale: Res<Locale>,
+) {
+    let ctrl = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if ctrl && keyboard_input.just_pressed(KeyCode::KeyP) {
+        *palette = CommandPalette {
+            open: !palette.open,
+            ..default()
+        };
+        typed.clear();
+        keyboard_input.reset_all();
+        return;
+    }
+    if !palette.open {
+        typed.clear();
+        return;
+    }
+
+    let mut run = None;
+    for input in typed.read().filter(|input| input.state.is_pressed()) {
+        match &input.logical_key {
+            Key::Escape => {
+                *palette = default();
+                break;
+            }
+            Key::Enter => {
+                run = registry
+                    .search(&palette.query, &locale)
+                    .get(palette.highlighted)
+                    .copied();
+                *palette = default();
+                break;
+            }
+            Key::ArrowUp => palette.highlighted = palette.highlighted.saturating_sub(1),
+            Key::ArrowDown => palette.highlighted += 1,
+            Key::Backspace => {
+                palette.query.pop();
+                palette.highlighted = 0;
+            }
+            _ => {
+                if let Some(text) = input.text.as_ref().filter(|_| !ctrl) {
+                    palette
+                        .query
+                        .extend(text.chars().filter(|c| !c.is_control()));
+                    palette.highlighted = 0;
+                }
+            }
+        }
+    }
+    let last = registry
+        .search(&palette.query, &locale)
+        .len()
+        .min(MAX_PALETTE_ROWS)
+        .saturating_sub(1);
+    if palette.highlighted > last {
+        palette.highlighted = last;
+    }
+    if let Some(action) = run.and_then(|i| registry.actions.get(i)) {
+        info!("Running {}", action.name);
+        commands.run_system(action.system);
+    }
+    keyboard_input.reset_all();
+}
+
+fn rebuild_command_palette(
+    mut commands: Commands,
+    palette: Res<CommandPalette>,
+    registry: Res<ActionRegistry>,
+    locale: Res<Locale>,
+    panel: Single<(Entity, &mut Visibility), With<CommandPalettePanel>>,
+) {
+    let (panel, mut visibility) = panel.into_inner();
+    commands.entity(panel).despawn_related::<Children>();
+    if !palette.open {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+
+    let text = |text: String, color: Srgba| {
+        (
+            Text::new(text),
+            TextFont::from_font_size(PALETTE_FONT_SIZE),
+            TextColor(Color::from(color)),
+        )
+    };
+    commands.spawn((
+        text(format!("> {}_", palette.query), css::WHITE),
+        ChildOf(panel),
+    ));
+    let matches = registry.search(&palette.query, &locale);
+    if matches.is_empty() {
+        commands.spawn((
+            text(locale.text("palette.no_matches").into(), css::GRAY),
+            ChildOf(panel),
+        ));
+    }
+    for (row, &i) in matches.iter().take(MAX_PALETTE_ROWS).enumerate() {
+        let color = if row == palette.highlighted {
+            css::ORANGE
+        } else {
+            css::LIGHT_GRAY
+        };
+        commands.spawn((
+            text(locale.action_name(&registry.actions[i].name).into(), color),
+            PaletteRow(i),
+            ChildOf(panel),
+        ));
+    }
+}
+
+fn run_clicked_action(
+    mut click: On<Pointer<Click>>,
+    mut commands: Commands,
+    rows: Query<&PaletteRow>,
+    registry: Res<ActionRegistry>,
+    mut palette: ResMut<CommandPalette>,
+) {
+    let Ok(&PaletteRow(i)) = rows.get(click.entity) else {
+        return;
+    };
+    click.propagate(false);
+    if let Some(action) = registry.actions.get(i) {
+        info!("Running {}", action.name);
+        commands.run_system(action.system);
+    }
+    *palette = default();
+}
@@ -0,0 +1,383 @@
+use bevy::color::palettes::css;
+use bevy::light::NotShadowCaster;
+use bevy::pbr::ExtendedMaterial;
+use bevy::picking::mesh_picking::MeshPickingPlugin;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use staff_gen::mesh_util::plane_section;
+
+use crate::actions::RegisterAction;
+use crate::camera::MainCamera;
+use crate::cross_section_material::{ClippedMaterial, CrossSectionExtension, CrossSectionSettings};
+use crate::debug_view::{DebugView, ShadedMaterial};
+use crate::generation::{GeneratedObject, is_generated};
+use crate::units::UnitsConfig;
+
+/// Radius of the handle the plane is dragged by, in meters
+const HANDLE_RADIUS: f32 = 0.04;
+
+/// A plane cutting generated objects open, to look at what's inside them, like the carving of
+/// a socket. Everything on the side the normal points to is cut away.
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct CrossSection {
+    pub enabled: bool,
+    /// A point on the plane, in scene units
+    pub origin: Vec3,
+    pub normal: Vec3,
+    /// Width and height of the plane drawn around the origin, in meters
+    pub size: f32,
+    /// Draw where the plane cuts through each surface
+    pub outline: bool,
+    pub outline_color: Color,
+    /// Color of the inside of objects, seen through the cut
+    pub cap_color: Color,
+}
+
+impl Default for CrossSection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            origin: Vec3::Y,
+            normal: Vec3::X,
+            size: 2.5,
+            outline: true,
+            outline_color: Color::from(css::YELLOW),
+            cap_color: Color::srgb(0.8, 0.25, 0.2),
+        }
+    }
+}
+
+impl CrossSection {
+    pub fn normal(&self) -> Vec3 {
+        self.normal.normalize_or(Vec3::X)
+    }
+
+    /// The plane for the shaders: its normal, and its distance from the origin along it. While
+    /// the cross section is off, a zero plane that cuts nothing.
+    pub fn clip_plane(&self) -> Vec4 {
+        if !self.enabled {
+            return Vec4::ZERO;
+        }
+        let normal = self.normal();
+        normal.extend(normal.dot(self.origin))
+    }
+
+    /// Turns the plane so it lies in its own XY plane.
+    fn rotation(&self) -> Quat {
+        Quat::from_rotation_arc(Vec3::Z, self.normal())
+    }
+}
+
+/// The clipped copy of each generated object's own material, shared like the originals are.
+#[derive(Resource, Debug, Default)]
+struct ClippedMaterials(HashMap<AssetId<StandardMaterial>, Handle<ClippedMaterial>>);
+
+/// Where the plane cut an object's mesh, in the mesh's own space, and the plane it was cut
+/// with there. Recut when either changes.
+#[derive(Debug)]
+struct Section {
+    mesh: AssetId<Mesh>,
+    plane: Vec4,
+    segments: Vec<[Vec3; 2]>,
+}
+
+#[derive(Resource, Debug, Default)]
+struct Sections(HashMap<Entity, Section>);
+
+/// Dragged along the plane's normal to move the plane. It sits on the edge of the drawn plane
+/// rather than the origin, which is usually inside the object being cut.
+#[derive(Component, Debug)]
+struct CrossSectionHandle;
+
+/// Cuts generated objects open along a plane, toggled from the command palette. Their
+/// materials are swapped for [`ClippedMaterial`] copies while it's on, and the debug views cut
+/// along the same plane. The part cut away still casts its shadow.
+pub struct CrossSectionPlugin;
+
+impl Plugin for CrossSectionPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<MeshPickingPlugin>() {
+            app.add_plugins(MeshPickingPlugin);
+        }
+        app.add_plugins(MaterialPlugin::<ClippedMaterial>::default())
+            .register_type::<CrossSection>()
+            .init_resource::<CrossSection>()
+            .init_resource::<ClippedMaterials>()
+            .init_resource::<Sections>()
+            .register_action(
+                "Toggle cross section",
+                |mut cross_section: ResMut<CrossSection>| {
+                    cross_section.enabled = !cross_section.enabled;
+                },
+            )
+            .register_action(
+                "Flip cross section",
+                |mut cross_section: ResMut<CrossSection>| {
+                    cross_section.normal = -cross_section.normal;
+                },
+            )
+            .add_systems(Startup, setup_cross_section_handle)
+            .add_systems(
+                Update,
+                (
+                    restore_unclipped_materials,
+                    clip_generated_materials,
+                    update_clipped_materials,
+                    place_cross_section_handle,
+                    draw_cross_section,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn setup_cross_section_handle(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands
+        .spawn((
+            Name::new("CrossSectionHandle"),
+            CrossSectionHandle,
+            Mesh3d(meshes.add(Sphere::new(1.))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: Color::from(css::YELLOW),
+                unlit: true,
+                ..default()
+            })),
+            NotShadowCaster,
+            Transform::default(),
+            Visibility::Hidden,
+        ))
+        .observe(drag_cross_section);
+}
+
+/// Moves the plane to where the pointer is along the line the handle slides on.
+fn drag_cross_section(
+    mut drag: On<Pointer<Drag>>,
+    mut cross_section: ResMut<CrossSection>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    handles: Query<&GlobalTransform, With<CrossSectionHandle>>,
+) {
+    if drag.event.button != PointerButton::Primary {
+        return;
+    }
+    let Ok(handle) = handles.get(drag.entity) else {
+        return;
+    };
+    drag.propagate(false);
+    let (camera, camera_transform) = *camera;
+    let Ok(ray) = camera.viewport_to_world(camera_transform, drag.pointer_location.position) else {
+        return;
+    };
+    // Closest point on the handle's line to the pointer's ray
+    let normal = cross_section.normal();
+    let along = normal.dot(*ray.direction);
+    let facing = 1. - along * along;
+    // Looking straight along the line, the pointer can't say where on it the handle should be
+    if facing < 1e-4 {
+        return;
+    }
+    let to_handle = handle.translation() - ray.origin;
+    let distance = (along * ray.direction.dot(to_handle) - normal.dot(to_handle)) / facing;
+    cross_section.origin += normal * distance;
+}
+
+fn place_cross_section_handle(
+    cross_section: Res<CrossSection>,
+    units: Res<UnitsConfig>,
+    mut handles: Query<(&mut Transform, &mut Visibility), With<CrossSectionHandle>>,
+) {
+    if !cross_section.is_changed() && !units.is_changed() {
+        return;
+    }
+    let from_meters = units.from_meters();
+    for (mut transform, mut visibility) in &mut handles {
+        let edge = cross_section.rotation() * Vec3::X * cross_section.size / 2. * from_meters;
+        *transform = Transform::from_translation(cross_section.origin + edge)
+            .with_scale(Vec3::splat(HANDLE_RADIUS * from_meters));
+        *visibility = if cross_section.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+/// Runs while the cross section is off or a debug view is on, which cuts along the plane by
+/// itself. The clipped copies are removed once nothing uses them.
+fn restore_unclipped_materials(
+    mut commands: Commands,
+    cross_section: Res<CrossSection>,
+    debug_view: Res<DebugView>,
+    mut clipped_materials: ResMut<ClippedMaterials>,
+    mut materials: ResMut<Assets<ClippedMaterial>>,
+    clipped: Query<(Entity, &ShadedMaterial), With<MeshMaterial3d<ClippedMaterial>>>,
+) {
+    if cross_section.enabled && *debug_view == DebugView::Shaded {
+        return;
+    }
+    for (entity, shaded) in &clipped {
+        commands
+            .entity(entity)
+            .remove::<(ShadedMaterial, MeshMaterial3d<ClippedMaterial>)>()
+            .insert(MeshMaterial3d(shaded.0.clone()));
+    }
+    for (_, material) in clipped_materials.0.drain() {
+        materials.remove(&material);
+    }
+}
+
+/// Runs every frame while the cross section is on, so objects generated meanwhile are cut too.
+#[allow(clippy::too_many_arguments)]
+fn clip_generated_materials(
+    mut commands: Commands,
+    cross_section: Res<CrossSection>,
+    debug_view: Res<DebugView>,
+    mut clipped_materials: ResMut<ClippedMaterials>,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    mut materials: ResMut<Assets<ClippedMaterial>>,
+    shaded: Query<(Entity, &MeshMaterial3d<StandardMaterial>), Without<ShadedMaterial>>,
+    generated: Query<(), With<GeneratedObject>>,
+    parents: Query<&ChildOf>,
+) {
+    if !cross_section.enabled || *debug_view != DebugView::Shaded {
+        return;
+    }
+    for (entity, material) in &shaded {
+        if !is_generated(entity, &generated, &parents) {
+            continue;
+        }
+        let Some(base) = standard_materials.get(&material.0) else {
+            continue;
+        };
+        let clipped = clipped_materials
+            .0
+            .entry(material.id())
+            .or_insert_with(|| materials.add(clipped_material(base, &cross_section)))
+            .clone();
+        commands
+            .entity(entity)
+            .remove::<MeshMaterial3d<StandardMaterial>>()
+            .insert((ShadedMaterial(material.0.clone()), MeshMaterial3d(clipped)));
+    }
+}
+
+/// Copies of `base` cut along the plane. Back faces are drawn, since they're what's seen of
+/// the inside through the cut.
+fn clipped_material(base: &StandardMaterial, cross_section: &CrossSection) -> ClippedMaterial {
+    ExtendedMaterial {
+        base: StandardMaterial {
+            cull_mode: None,
+            ..base.clone()
+        },
+        extension: CrossSectionExtension {
+            settings: CrossSectionSettings {
+                plane: cross_section.clip_plane(),
+                cap_color: cross_section.cap_color.to_linear(),
+            },
+        },
+    }
+}
+
+/// Follows the plane, and the original materials as they're edited, like by the palette.
+fn update_clipped_materials(
+    mut events: MessageReader<AssetEvent<StandardMaterial>>,
+    cross_section: Res<CrossSection>,
+    clipped_materials: Res<ClippedMaterials>,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    mut materials: ResMut<Assets<ClippedMaterial>>,
+) {
+    let edited: Vec<AssetId<StandardMaterial>> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .filter(|id| clipped_materials.0.contains_key(id))
+        .collect();
+    if !cross_section.is_changed() && edited.is_empty() {
+        return;
+    }
+    for (id, handle) in &clipped_materials.0 {
+        if !cross_section.is_changed() && !edited.contains(id) {
+            continue;
+        }
+        let (Some(base), Some(material)) = (standard_materials.get(*id), materials.get_mut(handle))
+        else {
+            continue;
+        };
+        *material = clipped_material(base, &cross_section);
+    }
+}
+
+/// Draws the plane, and with the outline on, where it cuts every generated mesh. Meshes are
+/// only recut when they change or the plane moves relative to them.
+#[allow(clippy::too_many_arguments)]
+fn draw_cross_section(
+    mut gizmos: Gizmos,
+    mut events: MessageReader<AssetEvent<Mesh>>,
+    mut sections: ResMut<Sections>,
+    cross_section: Res<CrossSection>,
+    units: Res<UnitsConfig>,
+    meshes: Res<Assets<Mesh>>,
+    objects: Query<(Entity, &Mesh3d, &GlobalTransform)>,
+    generated: Query<(), With<GeneratedObject>>,
+    parents: Query<&ChildOf>,
+) {
+    for event in events.read() {
+        if let AssetEvent::Modified { id } | AssetEvent::Removed { id } = event {
+            sections.0.retain(|_, section| section.mesh != *id);
+        }
+    }
+    if !cross_section.enabled {
+        sections.0.clear();
+        return;
+    }
+    let size = cross_section.size * units.from_meters();
+    gizmos.rect(
+        Isometry3d::new(cross_section.origin, cross_section.rotation()),
+        Vec2::splat(size),
+        cross_section.outline_color.with_alpha(0.4),
+    );
+    if !cross_section.outline {
+        return;
+    }
+
+    let normal = cross_section.normal();
+    sections.0.retain(|&entity, _| objects.contains(entity));
+    for (entity, mesh, transform) in &objects {
+        if !is_generated(entity, &generated, &parents) {
+            continue;
+        }
+        let Some(mesh_data) = meshes.get(mesh) else {
+            continue;
+        };
+        // Normals go into the mesh's space by the transpose, as the inverse of the inverse
+        let affine = transform.affine();
+        let local_origin = affine.inverse().transform_point3(cross_section.origin);
+        let local_normal = affine.matrix3.transpose() * normal;
+        let plane = local_normal.extend(local_normal.dot(local_origin));
+        let section = sections.0.entry(entity).or_insert_with(|| Section {
+            mesh: mesh.id(),
+            plane: Vec4::NAN,
+            segments: Vec::new(),
+        });
+        if section.mesh != mesh.id() || section.plane != plane {
+            *section = Section {
+                mesh: mesh.id(),
+                plane,
+                segments: plane_section(mesh_data, local_origin, local_normal),
+            };
+        }
+        for &[a, b] in &section.segments {
+            gizmos.line(
+                transform.transform_point(a),
+                transform.transform_point(b),
+                cross_section.outline_color,
+            );
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use bevy::pbr::{ExtendedMaterial, MaterialExtension};
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, ShaderType};
+use bevy::shader::ShaderRef;
+
+const SHADER_ASSET_PATH: &str = "shaders/cross_section.wgsl";
+
+/// A generated object's own material, cut away on the far side of the cross section plane.
+pub type ClippedMaterial = ExtendedMaterial<StandardMaterial, CrossSectionExtension>;
+
+/// Discards everything in front of a plane, in the main pass and the prepass alike, and draws
+/// the back faces seen through the cut in a flat color so the inside reads as solid.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct CrossSectionExtension {
+    #[uniform(100)]
+    pub settings: CrossSectionSettings,
+}
+
+impl MaterialExtension for CrossSectionExtension {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_ASSET_PATH.into()
+    }
+
+    fn prepass_fragment_shader() -> ShaderRef {
+        SHADER_ASSET_PATH.into()
+    }
+}
+
+#[derive(ShaderType, Reflect, Clone, Debug, PartialEq)]
+pub struct CrossSectionSettings {
+    /// Normal of the plane in xyz, and its distance from the origin along it in w. Fragments
+    /// further along the normal than that are discarded.
+    pub plane: Vec4,
+    /// Color of back faces, the inside of the mesh showing through the cut
+    pub cap_color: LinearRgba,
+}
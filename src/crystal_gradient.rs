@@ -0,0 +1,125 @@
+use bevy::mesh::VertexAttributeValues;
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+use staff_gen::crystal::CrystalConfig;
+use staff_gen::mesh_util::vertical_gradient;
+
+use crate::formation::FormationCrystals;
+use crate::staff::StaffGem;
+
+/// A color on the crystal gradient, at a height from 0 at the base to 1 at the tip.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct GradientStop {
+    pub height: f32,
+    pub color: Color,
+}
+
+/// Vertical gradient crystals are tinted with, multiplied with their material's color.
+/// Crystals are dark at the base and bright at the tip by default, so the facets of a cluster
+/// stand apart from each other.
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct CrystalGradient {
+    pub enabled: bool,
+    /// Blended between in order of height. Above the last stop and below the first, the
+    /// nearest stop's color holds.
+    pub stops: Vec<GradientStop>,
+}
+
+impl Default for CrystalGradient {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            stops: vec![
+                GradientStop {
+                    height: 0.,
+                    color: Color::srgb(0.15, 0.15, 0.3),
+                },
+                GradientStop {
+                    height: 0.6,
+                    color: Color::srgb(0.6, 0.6, 0.8),
+                },
+                GradientStop {
+                    height: 1.,
+                    color: Color::WHITE,
+                },
+            ],
+        }
+    }
+}
+
+/// Linear color at `height` on a gradient whose stops are sorted by height.
+fn sample_sorted(stops: &[GradientStop], height: f32) -> LinearRgba {
+    let above = stops.partition_point(|stop| stop.height <= height);
+    match (
+        above.checked_sub(1).map(|below| stops[below]),
+        stops.get(above).copied(),
+    ) {
+        (Some(below), Some(above)) => {
+            let t = (height - below.height) / (above.height - below.height).max(f32::EPSILON);
+            below.color.to_linear().mix(&above.color.to_linear(), t)
+        }
+        (Some(stop), None) | (None, Some(stop)) => stop.color.to_linear(),
+        (None, None) => LinearRgba::WHITE,
+    }
+}
+
+type CrystalMesh = Or<(With<CrystalConfig>, With<FormationCrystals>, With<StaffGem>)>;
+
+/// Tints crystals, formation crystals and staff gems with the [`CrystalGradient`] through
+/// their vertex colors, whenever their mesh is generated or the gradient is edited.
+pub struct CrystalGradientPlugin;
+
+impl Plugin for CrystalGradientPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<CrystalGradient>()
+            .init_resource::<CrystalGradient>()
+            // After the commands spawning crystals in `Update` are applied
+            .add_systems(PostUpdate, paint_crystal_gradients);
+    }
+}
+
+/// Painting a mesh changes it too, so meshes whose colors already match are left alone.
+fn paint_crystal_gradients(
+    gradient: Res<CrystalGradient>,
+    mut events: MessageReader<AssetEvent<Mesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    crystals: Query<Ref<Mesh3d>, CrystalMesh>,
+) {
+    let changed: HashSet<AssetId<Mesh>> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    let mut stops = gradient.stops.clone();
+    stops.sort_by(|a, b| a.height.total_cmp(&b.height));
+    for mesh3d in &crystals {
+        if !(gradient.is_changed() || mesh3d.is_added() || changed.contains(&mesh3d.id())) {
+            continue;
+        }
+        let Some(mesh) = meshes.get(&mesh3d.0) else {
+            continue;
+        };
+        let colors = gradient.enabled.then(|| {
+            vertical_gradient(mesh, |height| sample_sorted(&stops, height).to_f32_array())
+        });
+        let current = match mesh.attribute(Mesh::ATTRIBUTE_COLOR) {
+            Some(VertexAttributeValues::Float32x4(colors)) => Some(colors.as_slice()),
+            _ => None,
+        };
+        if current == colors.as_deref() {
+            continue;
+        }
+        let Some(mesh) = meshes.get_mut(&mesh3d.0) else {
+            continue;
+        };
+        match colors {
+            Some(colors) => mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors),
+            None => {
+                mesh.remove_attribute(Mesh::ATTRIBUTE_COLOR);
+            }
+        }
+    }
+}
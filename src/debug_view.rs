@@ -0,0 +1,295 @@
+use bevy::color::palettes::css;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use staff_gen::mesh_util::{degenerate_triangles, triangles};
+
+use crate::actions::RegisterAction;
+use crate::cross_section::CrossSection;
+use crate::debug_view_material::{DebugViewMaterial, DebugViewSettings};
+use crate::environment::uv_debug_texture;
+use crate::generation::{GeneratedObject, is_generated};
+use crate::units::UnitsConfig;
+
+/// Triangles with no more area than this, in square scene units, are marked as degenerate
+const DEGENERATE_AREA: f32 = 1e-10;
+/// Radius of the degenerate triangle markers, in meters
+const DEGENERATE_MARKER_RADIUS: f32 = 0.01;
+
+/// What generated objects are drawn with. Anything but [`DebugView::Shaded`] swaps their
+/// materials for a [`DebugViewMaterial`] until the view is switched back.
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[reflect(Resource)]
+pub enum DebugView {
+    #[default]
+    Shaded,
+    /// The floor's checker texture, to spot stretched, flipped or seamed UVs
+    UvChecker,
+    /// Texels per unit of surface, blue where UVs are sparse and red where they're crowded
+    TexelDensity,
+    /// World space normals as colors, red along +X, green along +Y and blue along +Z
+    Normals,
+    /// World space tangents colored like the normals
+    Tangents,
+    VertexColors,
+    /// Front faces blue and back faces red, to find flipped winding
+    FaceOrientation,
+    /// Front faces plain gray and back faces red, with a marker on every zero-area triangle,
+    /// to find the winding and index mistakes caps are prone to
+    BackFaces,
+}
+
+impl DebugView {
+    pub const ALL: [Self; 8] = [
+        Self::Shaded,
+        Self::UvChecker,
+        Self::TexelDensity,
+        Self::Normals,
+        Self::Tangents,
+        Self::VertexColors,
+        Self::FaceOrientation,
+        Self::BackFaces,
+    ];
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&view| view == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Mode number in shaders/debug_view.wgsl, or `None` for the objects' own materials.
+    /// Meshes without the attribute a view shows are magenta.
+    fn shader_mode(self) -> Option<u32> {
+        match self {
+            Self::Shaded => None,
+            Self::UvChecker => Some(0),
+            Self::TexelDensity => Some(1),
+            Self::Normals => Some(2),
+            Self::Tangents => Some(3),
+            Self::VertexColors => Some(4),
+            Self::FaceOrientation => Some(5),
+            Self::BackFaces => Some(6),
+        }
+    }
+
+    fn action_name(self) -> &'static str {
+        match self {
+            Self::Shaded => "Show shaded materials",
+            Self::UvChecker => "Show UV checker",
+            Self::TexelDensity => "Show texel density",
+            Self::Normals => "Show normals",
+            Self::Tangents => "Show tangents",
+            Self::VertexColors => "Show vertex colors",
+            Self::FaceOrientation => "Show face orientation",
+            Self::BackFaces => "Show back faces",
+        }
+    }
+}
+
+/// What the texel density heatmap measures against. Densities are in texels per meter.
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct TexelDensityConfig {
+    /// Texture resolution the generated UVs would be used with
+    pub texture_size: f32,
+    pub target_density: f32,
+}
+
+impl Default for TexelDensityConfig {
+    fn default() -> Self {
+        Self {
+            texture_size: 1024.,
+            target_density: 512.,
+        }
+    }
+}
+
+/// The material an object had before a debug view or the cross section replaced it.
+#[derive(Component, Debug)]
+pub struct ShadedMaterial(pub Handle<StandardMaterial>);
+
+/// The material every debug view shares, updated as the view changes.
+#[derive(Resource, Debug)]
+struct DebugViewHandle(Handle<DebugViewMaterial>);
+
+/// Centers of the degenerate triangles of each generated mesh, kept until the mesh changes.
+#[derive(Resource, Debug, Default)]
+struct DegenerateTriangles(HashMap<AssetId<Mesh>, Vec<Vec3>>);
+
+/// Debug views of generated objects, switched from the command palette or cycled with F6.
+pub struct DebugViewPlugin;
+
+impl Plugin for DebugViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<DebugViewMaterial>::default())
+            .register_type::<DebugView>()
+            .register_type::<TexelDensityConfig>()
+            .init_resource::<DebugView>()
+            .init_resource::<TexelDensityConfig>()
+            .init_resource::<DegenerateTriangles>()
+            .add_systems(Startup, setup_debug_view_material)
+            .add_systems(
+                Update,
+                (
+                    cycle_debug_view_on_key,
+                    update_debug_view_material.run_if(
+                        resource_changed::<DebugView>
+                            .or(resource_changed::<TexelDensityConfig>)
+                            .or(resource_changed::<UnitsConfig>)
+                            .or(resource_changed::<CrossSection>),
+                    ),
+                    restore_shaded_materials.run_if(resource_changed::<DebugView>),
+                    replace_generated_materials,
+                    forget_changed_meshes,
+                    mark_degenerate_triangles
+                        .run_if(|view: Res<DebugView>| *view == DebugView::BackFaces),
+                )
+                    .chain(),
+            );
+        for view in DebugView::ALL {
+            app.register_action(
+                view.action_name(),
+                move |mut debug_view: ResMut<DebugView>| {
+                    *debug_view = view;
+                },
+            );
+        }
+    }
+}
+
+fn setup_debug_view_material(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<DebugViewMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let material = materials.add(DebugViewMaterial {
+        settings: DebugViewSettings {
+            mode: 0,
+            texture_size: 0.,
+            target_density: 0.,
+            clip_plane: Vec4::ZERO,
+        },
+        checker: images.add(uv_debug_texture()),
+    });
+    commands.insert_resource(DebugViewHandle(material));
+}
+
+/// F6 cycles through the debug views.
+fn cycle_debug_view_on_key(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut debug_view: ResMut<DebugView>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F6) {
+        *debug_view = debug_view.next();
+        info!("Debug view: {:?}", *debug_view);
+    }
+}
+
+/// The density target is in meters and the shader works in scene units.
+fn update_debug_view_material(
+    debug_view: Res<DebugView>,
+    density: Res<TexelDensityConfig>,
+    units: Res<UnitsConfig>,
+    cross_section: Res<CrossSection>,
+    handle: Res<DebugViewHandle>,
+    mut materials: ResMut<Assets<DebugViewMaterial>>,
+) {
+    let Some(mode) = debug_view.shader_mode() else {
+        return;
+    };
+    if let Some(material) = materials.get_mut(&handle.0) {
+        material.settings = DebugViewSettings {
+            mode,
+            texture_size: density.texture_size,
+            target_density: density.target_density * units.meters_per_unit,
+            clip_plane: cross_section.clip_plane(),
+        };
+    }
+}
+
+fn restore_shaded_materials(
+    mut commands: Commands,
+    debug_view: Res<DebugView>,
+    replaced: Query<(Entity, &ShadedMaterial), With<MeshMaterial3d<DebugViewMaterial>>>,
+) {
+    if debug_view.shader_mode().is_some() {
+        return;
+    }
+    for (entity, shaded) in &replaced {
+        commands
+            .entity(entity)
+            .remove::<(ShadedMaterial, MeshMaterial3d<DebugViewMaterial>)>()
+            .insert(MeshMaterial3d(shaded.0.clone()));
+    }
+}
+
+/// Runs every frame while a debug view is on, so objects generated meanwhile are shown in it
+/// too. Parts like heads and pedestals are generated with the object they're children of.
+fn replace_generated_materials(
+    mut commands: Commands,
+    debug_view: Res<DebugView>,
+    handle: Res<DebugViewHandle>,
+    shaded: Query<(Entity, &MeshMaterial3d<StandardMaterial>), Without<ShadedMaterial>>,
+    generated: Query<(), With<GeneratedObject>>,
+    parents: Query<&ChildOf>,
+) {
+    if debug_view.shader_mode().is_none() {
+        return;
+    }
+    for (entity, material) in &shaded {
+        if !is_generated(entity, &generated, &parents) {
+            continue;
+        }
+        commands
+            .entity(entity)
+            .remove::<MeshMaterial3d<StandardMaterial>>()
+            .insert((
+                ShadedMaterial(material.0.clone()),
+                MeshMaterial3d(handle.0.clone()),
+            ));
+    }
+}
+
+/// Runs even while the back face view is off, so no change is missed in the meantime.
+fn forget_changed_meshes(
+    mut events: MessageReader<AssetEvent<Mesh>>,
+    mut degenerate: ResMut<DegenerateTriangles>,
+) {
+    for event in events.read() {
+        if let AssetEvent::Modified { id } | AssetEvent::Removed { id } = event {
+            degenerate.0.remove(id);
+        }
+    }
+}
+
+fn mark_degenerate_triangles(
+    mut gizmos: Gizmos,
+    mut degenerate: ResMut<DegenerateTriangles>,
+    units: Res<UnitsConfig>,
+    meshes: Res<Assets<Mesh>>,
+    objects: Query<(Entity, &Mesh3d, &GlobalTransform)>,
+    generated: Query<(), With<GeneratedObject>>,
+    parents: Query<&ChildOf>,
+) {
+    let radius = DEGENERATE_MARKER_RADIUS / units.meters_per_unit;
+    for (entity, mesh, transform) in &objects {
+        if !is_generated(entity, &generated, &parents) {
+            continue;
+        }
+        let Some(mesh_data) = meshes.get(mesh) else {
+            continue;
+        };
+        let centers = degenerate.0.entry(mesh.id()).or_insert_with(|| {
+            let corners: Vec<[Vec3; 3]> = triangles(mesh_data).collect();
+            degenerate_triangles(mesh_data, DEGENERATE_AREA)
+                .into_iter()
+                .map(|triangle| corners[triangle].iter().sum::<Vec3>() / 3.)
+                .collect()
+        });
+        for &center in centers.iter() {
+            gizmos.sphere(
+                Isometry3d::from_translation(transform.transform_point(center)),
+                radius,
+                css::YELLOW,
+            );
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use bevy::mesh::MeshVertexBufferLayoutRef;
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
+use bevy::prelude::*;
+use bevy::render::render_resource::{
+    AsBindGroup, RenderPipelineDescriptor, ShaderType, SpecializedMeshPipelineError,
+};
+use bevy::shader::ShaderRef;
+
+const SHADER_ASSET_PATH: &str = "shaders/debug_view.wgsl";
+
+/// Unlit material showing something about the mesh instead of its surface, for the debug views.
+/// One material serves every mode, picked by [`DebugViewSettings::mode`].
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct DebugViewMaterial {
+    #[uniform(0)]
+    pub settings: DebugViewSettings,
+    #[texture(1)]
+    #[sampler(2)]
+    pub checker: Handle<Image>,
+}
+
+impl Material for DebugViewMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_ASSET_PATH.into()
+    }
+
+    /// Back faces are drawn so the face orientation and back face views can tint them. The other
+    /// views discard them in the shader.
+    fn specialize(
+        _pipeline: &MaterialPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
+
+#[derive(ShaderType, Reflect, Clone, Debug, PartialEq)]
+pub struct DebugViewSettings {
+    /// Which view the shader draws, see `DebugView::shader_mode`
+    pub mode: u32,
+    /// Size of the texture the texel density is measured for, in texels along each side
+    pub texture_size: f32,
+    /// Texels per world unit shown as green in the texel density heatmap
+    pub target_density: f32,
+    /// Cross section plane, as in `CrossSectionSettings::plane`. A zero normal cuts nothing.
+    pub clip_plane: Vec4,
+}
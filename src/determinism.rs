@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use staff_gen::cone::ConeConfig;
+use staff_gen::crook::CrookConfig;
+use staff_gen::crystal::CrystalConfig;
+use staff_gen::cylinder::{CylinderConfig, CylinderNormals};
+use staff_gen::dripstone::DripstoneConfig;
+use staff_gen::fork::ForkConfig;
+use staff_gen::formation::FormationConfig;
+use staff_gen::head::{HeadRegistry, HeadStyle};
+use staff_gen::mesh_util::mesh_hash;
+use staff_gen::orb::OrbConfig;
+use staff_gen::pedestal::PedestalConfig;
+use staff_gen::staff::StaffConfig;
+
+use crate::actions::RegisterAction;
+use crate::settings::{PersistPlugin, Persistent};
+
+/// Seeds every seeded generator is audited with
+const AUDIT_SEEDS: [u64; 3] = [1, 42, 19878367467713];
+
+/// Mesh hashes from the last audit on this machine, to compare the next one against. Native
+/// builds keep it in a file and web builds in localStorage, so comparing native against wasm
+/// means comparing the logged hashes.
+#[derive(Resource, Serialize, Deserialize, Debug, Default)]
+pub struct DeterminismRecord {
+    /// Target the hashes were recorded on, e.g. `x86_64 linux` or `wasm32 unknown`
+    pub platform: String,
+    pub hashes: BTreeMap<String, u64>,
+}
+
+impl Persistent for DeterminismRecord {
+    const FILE_NAME: &'static str = "determinism.ron";
+}
+
+/// Checks that the generators are reproducible: "Audit generator determinism" builds a fixed
+/// set of meshes from fixed seeds twice, logs their hashes and compares them with the last
+/// audit's.
+pub struct DeterminismPlugin;
+
+impl Plugin for DeterminismPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DeterminismRecord>()
+            .add_plugins(PersistPlugin::<DeterminismRecord>::default())
+            .register_action("Audit generator determinism", audit_determinism);
+    }
+}
+
+/// Hashes are printed as 16 hex digits everywhere they're shown.
+pub fn format_hash(hash: u64) -> String {
+    format!("{hash:016x}")
+}
+
+/// The audited meshes by name. Generator defaults only, so the audit doesn't follow the
+/// viewer's settings.
+fn audit_meshes() -> Vec<(String, Mesh)> {
+    let mut meshes = Vec::new();
+    for seed in AUDIT_SEEDS {
+        let staff = StaffConfig { seed, ..default() };
+        meshes.push((format!("staff {seed}"), staff.generate_mesh()));
+        let dripstone = DripstoneConfig { seed, ..default() };
+        meshes.push((format!("dripstone {seed}"), dripstone.generate_mesh()));
+        let formation = FormationConfig { seed, ..default() };
+        meshes.push((format!("rock {seed}"), formation.generate_rock_mesh()));
+        meshes.push((
+            format!("crystals {seed}"),
+            formation.generate_crystals_mesh(),
+        ));
+    }
+    let heads = HeadRegistry::default();
+    for (name, head) in [
+        ("crystal", HeadStyle::Crystal(CrystalConfig::default())),
+        ("crook", HeadStyle::Crook(CrookConfig::default())),
+        ("forked", HeadStyle::Forked(ForkConfig::default())),
+        ("orb", HeadStyle::Orb(OrbConfig::default())),
+    ] {
+        let staff = StaffConfig { head, ..default() };
+        let head = staff.generate_head(&heads);
+        meshes.push((
+            format!("staff with {name} head"),
+            staff.generate_mesh_with_head(head.as_ref()),
+        ));
+    }
+    meshes.push(("crystal".into(), CrystalConfig::default().generate_mesh()));
+    meshes.push(("cone".into(), ConeConfig::default().generate_mesh()));
+    meshes.push((
+        "cylinder".into(),
+        CylinderConfig::default().generate_mesh(&mut CylinderNormals::default()),
+    ));
+    meshes.push(("pedestal".into(), PedestalConfig::default().generate_mesh()));
+    meshes
+}
+
+/// Logs every hash and whether it matches the last audit's, then records this audit. A
+/// generator whose two builds in this run differ isn't deterministic even on one machine.
+fn audit_determinism(mut record: ResMut<DeterminismRecord>) {
+    let platform = format!("{} {}", std::env::consts::ARCH, std::env::consts::OS);
+    let first = audit_meshes();
+    let second = audit_meshes();
+    let mut hashes = BTreeMap::new();
+    let (mut changed, mut unstable) = (0, 0);
+    for ((name, mesh), (_, again)) in first.iter().zip(&second) {
+        let hash = mesh_hash(mesh);
+        if mesh_hash(again) != hash {
+            warn!("{name}: {} differs between two builds", format_hash(hash));
+            unstable += 1;
+        }
+        match record.hashes.get(name) {
+            Some(&recorded) if recorded != hash => {
+                warn!(
+                    "{name}: {} differs from {} recorded on {}",
+                    format_hash(hash),
+                    format_hash(recorded),
+                    record.platform
+                );
+                changed += 1;
+            }
+            _ => info!("{name}: {}", format_hash(hash)),
+        }
+        hashes.insert(name.clone(), hash);
+    }
+
+    let fingerprint = hashes
+        .values()
+        .fold(0u64, |fingerprint, hash| fingerprint.rotate_left(5) ^ hash);
+    info!(
+        "Audited {} meshes on {platform}, fingerprint {}: {unstable} unstable, {changed} changed \
+         since the last audit",
+        hashes.len(),
+        format_hash(fingerprint)
+    );
+    *record = DeterminismRecord { platform, hashes };
+}
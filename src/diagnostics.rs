@@ -0,0 +1,284 @@
+use bevy::color::palettes::css;
+use bevy::diagnostic::{
+    Diagnostic, DiagnosticPath, Diagnostics, DiagnosticsStore, EntityCountDiagnosticsPlugin,
+    FrameTimeDiagnosticsPlugin, RegisterDiagnostic,
+};
+use bevy::prelude::*;
+
+use crate::actions::RegisterAction;
+use crate::generation::MeshGenCompleted;
+use crate::locale::Locale;
+
+/// Frames in the frame time graph, one bar each, and kept by every diagnostic it shows
+const GRAPH_FRAMES: usize = 120;
+const GRAPH_HEIGHT: f32 = 40.;
+/// Frame time at the top of the graph. Slower frames are cut off.
+const GRAPH_MAX_MS: f64 = 50.;
+const OVERLAY_FONT_SIZE: f32 = 12.;
+
+/// Whether F3 has the overlay open.
+#[derive(Resource, Debug, Default)]
+pub struct DiagnosticsOverlay {
+    pub open: bool,
+}
+
+/// Generator timings as Bevy diagnostics, so they're logged and shown like the frame time.
+pub struct GenerationDiagnosticsPlugin;
+
+impl GenerationDiagnosticsPlugin {
+    /// How long each generated mesh took, one measurement per mesh
+    pub const GENERATION_TIME: DiagnosticPath = DiagnosticPath::const_new("generation_time");
+    /// Meshes generated each frame
+    pub const GENERATED_MESHES: DiagnosticPath = DiagnosticPath::const_new("generated_meshes");
+    /// Vertex cache misses per triangle of each generated mesh, before and after reordering
+    pub const ACMR_BEFORE: DiagnosticPath = DiagnosticPath::const_new("acmr_before");
+    pub const ACMR_AFTER: DiagnosticPath = DiagnosticPath::const_new("acmr_after");
+}
+
+impl Plugin for GenerationDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(
+            Diagnostic::new(Self::GENERATION_TIME)
+                .with_suffix("ms")
+                .with_max_history_length(GRAPH_FRAMES),
+        )
+        .register_diagnostic(
+            Diagnostic::new(Self::GENERATED_MESHES).with_max_history_length(GRAPH_FRAMES),
+        )
+        .register_diagnostic(
+            Diagnostic::new(Self::ACMR_BEFORE).with_max_history_length(GRAPH_FRAMES),
+        )
+        .register_diagnostic(
+            Diagnostic::new(Self::ACMR_AFTER).with_max_history_length(GRAPH_FRAMES),
+        )
+        .add_systems(PostUpdate, measure_generations);
+    }
+}
+
+#[derive(Component, Debug)]
+struct OverlayPanel;
+
+#[derive(Component, Debug)]
+struct OverlayText;
+
+/// One bar of the frame time graph, counting back from the latest frame.
+#[derive(Component, Debug)]
+struct FrameBar(usize);
+
+/// Frame rate, a frame time graph, entity and mesh counts, generator timings and vertex cache
+/// efficiency, toggled by F3.
+/// Bevy doesn't count draw calls, so the overlay shows how many meshes are visible instead,
+/// which is the most they can take.
+pub struct DiagnosticsOverlayPlugin;
+
+impl Plugin for DiagnosticsOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            FrameTimeDiagnosticsPlugin::new(GRAPH_FRAMES),
+            EntityCountDiagnosticsPlugin::default(),
+            GenerationDiagnosticsPlugin,
+        ))
+        .init_resource::<DiagnosticsOverlay>()
+        .register_action(
+            "Toggle diagnostics overlay",
+            |mut overlay: ResMut<DiagnosticsOverlay>| overlay.open = !overlay.open,
+        )
+        .add_systems(Startup, setup_overlay)
+        .add_systems(
+            Update,
+            (
+                toggle_overlay_on_key,
+                show_overlay.run_if(resource_changed::<DiagnosticsOverlay>),
+                (update_overlay_text, update_frame_graph)
+                    .run_if(|overlay: Res<DiagnosticsOverlay>| overlay.open),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Generations finish in `Update`, so their messages are all in by now.
+fn measure_generations(
+    mut completed: MessageReader<MeshGenCompleted>,
+    mut diagnostics: Diagnostics,
+) {
+    let mut count = 0;
+    for generation in completed.read() {
+        count += 1;
+        let ms = generation.stats.duration.as_secs_f64() * 1000.;
+        diagnostics.add_measurement(&GenerationDiagnosticsPlugin::GENERATION_TIME, || ms);
+        if let Some((before, after)) = generation.stats.acmr {
+            diagnostics
+                .add_measurement(&GenerationDiagnosticsPlugin::ACMR_BEFORE, || before as f64);
+            diagnostics.add_measurement(&GenerationDiagnosticsPlugin::ACMR_AFTER, || after as f64);
+        }
+    }
+    diagnostics.add_measurement(&GenerationDiagnosticsPlugin::GENERATED_MESHES, || {
+        count as f64
+    });
+}
+
+fn setup_overlay(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("DiagnosticsOverlay"),
+            OverlayPanel,
+            Visibility::Hidden,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(40.),
+                justify_self: JustifySelf::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.),
+                padding: UiRect::all(Val::Px(6.)),
+                ..default()
+            },
+            BackgroundColor(Color::from(css::DARK_SLATE_GRAY).with_alpha(0.8)),
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                OverlayText,
+                Text::default(),
+                TextFont::from_font_size(OVERLAY_FONT_SIZE),
+            ));
+            panel
+                .spawn(Node {
+                    height: Val::Px(GRAPH_HEIGHT),
+                    align_items: AlignItems::FlexEnd,
+                    ..default()
+                })
+                .with_children(|graph| {
+                    // Oldest on the left, so the graph scrolls left like a timeline
+                    for age in (0..GRAPH_FRAMES).rev() {
+                        graph.spawn((
+                            FrameBar(age),
+                            Node {
+                                width: Val::Px(2.),
+                                height: Val::Px(0.),
+                                ..default()
+                            },
+                            BackgroundColor(Color::from(css::LIME)),
+                        ));
+                    }
+                });
+        });
+}
+
+/// F3 opens and closes the overlay.
+fn toggle_overlay_on_key(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<DiagnosticsOverlay>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F3) {
+        overlay.open = !overlay.open;
+    }
+}
+
+fn show_overlay(
+    overlay: Res<DiagnosticsOverlay>,
+    mut panel: Single<&mut Visibility, With<OverlayPanel>>,
+) {
+    **panel = if overlay.open {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+}
+
+fn update_overlay_text(
+    store: Res<DiagnosticsStore>,
+    locale: Res<Locale>,
+    meshes: Query<&ViewVisibility, With<Mesh3d>>,
+    mut text: Single<&mut Text, With<OverlayText>>,
+) {
+    let smoothed = |path| {
+        store
+            .get(path)
+            .and_then(Diagnostic::smoothed)
+            .unwrap_or_default()
+    };
+    let generated = store
+        .get(&GenerationDiagnosticsPlugin::GENERATED_MESHES)
+        .map(|diagnostic| (diagnostic.values().sum::<f64>(), diagnostic.history_len()))
+        .unwrap_or_default();
+    let visible = meshes.iter().filter(|view| view.get()).count();
+    text.0 = [
+        locale.format(
+            "diagnostics.fps",
+            &[
+                (
+                    "fps",
+                    &format_args!("{:.0}", smoothed(&FrameTimeDiagnosticsPlugin::FPS)),
+                ),
+                (
+                    "ms",
+                    &format_args!("{:.1}", smoothed(&FrameTimeDiagnosticsPlugin::FRAME_TIME)),
+                ),
+            ],
+        ),
+        locale.format(
+            "diagnostics.entities",
+            &[
+                (
+                    "entities",
+                    &store
+                        .get_measurement(&EntityCountDiagnosticsPlugin::ENTITY_COUNT)
+                        .map_or(0., |measurement| measurement.value),
+                ),
+                ("visible", &visible),
+                ("meshes", &meshes.iter().len()),
+            ],
+        ),
+        locale.format(
+            "diagnostics.generation",
+            &[
+                ("count", &generated.0),
+                ("frames", &generated.1),
+                (
+                    "ms",
+                    &format_args!(
+                        "{:.2}",
+                        smoothed(&GenerationDiagnosticsPlugin::GENERATION_TIME)
+                    ),
+                ),
+            ],
+        ),
+        locale.format(
+            "diagnostics.vertex_cache",
+            &[
+                (
+                    "before",
+                    &format_args!("{:.2}", smoothed(&GenerationDiagnosticsPlugin::ACMR_BEFORE)),
+                ),
+                (
+                    "after",
+                    &format_args!("{:.2}", smoothed(&GenerationDiagnosticsPlugin::ACMR_AFTER)),
+                ),
+            ],
+        ),
+    ]
+    .join("\n");
+}
+
+/// Bars are green within 60 FPS, yellow within 30 FPS and red below.
+fn update_frame_graph(
+    store: Res<DiagnosticsStore>,
+    mut bars: Query<(&FrameBar, &mut Node, &mut BackgroundColor)>,
+) {
+    let Some(frame_time) = store.get(&FrameTimeDiagnosticsPlugin::FRAME_TIME) else {
+        return;
+    };
+    let frames: Vec<f64> = frame_time.values().copied().collect();
+    for (bar, mut node, mut color) in &mut bars {
+        let ms = frames
+            .len()
+            .checked_sub(bar.0 + 1)
+            .map_or(0., |frame| frames[frame]);
+        node.height = Val::Px((ms / GRAPH_MAX_MS).min(1.) as f32 * GRAPH_HEIGHT);
+        color.0 = Color::from(match ms {
+            ..=16.7 => css::LIME,
+            ..=33.4 => css::YELLOW,
+            _ => css::RED,
+        });
+    }
+}
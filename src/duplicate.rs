@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use staff_gen::crystal::CrystalConfig;
+use staff_gen::staff::StaffConfig;
+
+use crate::crystal::GenerateCrystal;
+use crate::generation::{GeneratedObject, GeneratorKind};
+use crate::selection::Selected;
+use crate::staff::GenerateStaff;
+
+/// How far a duplicate stands from the object it was copied from
+const DUPLICATE_OFFSET: Vec3 = vec3(0.3, 0., 0.3);
+
+/// Ctrl+D copies the selected generated object from the config stored on it, beside the
+/// original, and selects the copy. Ctrl+Shift+D gives the copy the next seed.
+pub struct DuplicatePlugin;
+
+impl Plugin for DuplicatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, duplicate_selected_on_key);
+    }
+}
+
+type DuplicatedObject = (
+    Entity,
+    &'static GeneratedObject,
+    &'static Transform,
+    Option<&'static StaffConfig>,
+    Option<&'static CrystalConfig>,
+);
+
+fn duplicate_selected_on_key(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    selected: Query<DuplicatedObject, With<Selected>>,
+    mut generate_staff: MessageWriter<GenerateStaff>,
+    mut generate_crystal: MessageWriter<GenerateCrystal>,
+) {
+    if !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+        || !keyboard_input.just_pressed(KeyCode::KeyD)
+    {
+        return;
+    }
+    let new_seed = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let Some((original, &GeneratedObject(kind), transform, staff, crystal)) =
+        selected.iter().next()
+    else {
+        return;
+    };
+    let transform = transform.with_translation(transform.translation + DUPLICATE_OFFSET);
+
+    match (kind, staff, crystal) {
+        (GeneratorKind::Staff, Some(config), _) => {
+            let duplicate = commands.spawn(Selected).id();
+            generate_staff.write(GenerateStaff {
+                config: config.clone(),
+                seed: new_seed.then(|| config.seed.wrapping_add(1)),
+                transform,
+                entity: Some(duplicate),
+            });
+        }
+        (GeneratorKind::Crystal, _, Some(config)) => {
+            let duplicate = commands.spawn(Selected).id();
+            generate_crystal.write(GenerateCrystal {
+                config: config.clone(),
+                transform,
+                entity: Some(duplicate),
+            });
+        }
+        _ => {
+            warn!("Duplicating {kind} objects is not supported");
+            return;
+        }
+    }
+    commands.entity(original).remove::<Selected>();
+}
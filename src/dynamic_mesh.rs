@@ -0,0 +1,312 @@
+use std::ops::Range;
+
+use bevy::mesh::{Indices, MeshVertexAttribute, VertexAttributeValues};
+use bevy::prelude::*;
+
+/// A vertex attribute or index buffer of a [`DynamicMesh`], remembering which of its values
+/// were changed since it was last written to the mesh.
+#[derive(Debug, Clone)]
+pub struct DynamicBuffer<T> {
+    values: Vec<T>,
+    /// Values changed in place since the last write
+    dirty: Option<Range<usize>>,
+    /// Whether values were added or removed since the last write, so the mesh's copy no
+    /// longer lines up with this one
+    resized: bool,
+}
+
+impl<T> Default for DynamicBuffer<T> {
+    fn default() -> Self {
+        Self {
+            values: Vec::new(),
+            dirty: None,
+            resized: false,
+        }
+    }
+}
+
+impl<T: Copy> DynamicBuffer<T> {
+    pub fn as_slice(&self) -> &[T] {
+        &self.values
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The values in `range`, to be changed in place.
+    pub fn slice_mut(&mut self, range: Range<usize>) -> &mut [T] {
+        self.mark(range.clone());
+        &mut self.values[range]
+    }
+
+    pub fn set(&mut self, index: usize, value: T) {
+        self.slice_mut(index..index + 1)[0] = value;
+    }
+
+    /// Empties the buffer without giving up its memory, to be refilled with `push`.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.resized = true;
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.values.push(value);
+        self.resized = true;
+    }
+
+    pub fn extend_from_slice(&mut self, values: &[T]) {
+        self.values.extend_from_slice(values);
+        self.resized = true;
+    }
+
+    fn mark(&mut self, range: Range<usize>) {
+        self.dirty = Some(match self.dirty.take() {
+            Some(dirty) => dirty.start.min(range.start)..dirty.end.max(range.end),
+            None => range,
+        });
+    }
+
+    fn has_changed(&self) -> bool {
+        self.resized || self.dirty.is_some()
+    }
+
+    /// Copies the values changed in place into the mesh's own copy, or refills it when values
+    /// were added or removed. Returns a replacement if the mesh has no copy to write into.
+    fn write(&mut self, target: Option<&mut Vec<T>>) -> Option<Vec<T>> {
+        let dirty = self.dirty.take();
+        let resized = std::mem::take(&mut self.resized);
+        match (target, dirty) {
+            (Some(target), Some(dirty)) if !resized && target.len() == self.values.len() => {
+                target[dirty.clone()].copy_from_slice(&self.values[dirty]);
+                None
+            }
+            (Some(target), None) if !resized && target.len() == self.values.len() => None,
+            (Some(target), _) => {
+                // Refilled in place, keeping the mesh's allocation
+                target.clear();
+                target.extend_from_slice(&self.values);
+                None
+            }
+            (None, _) => Some(self.values.clone()),
+        }
+    }
+}
+
+/// Vertex attribute values a [`DynamicBuffer`] can hold.
+pub trait DynamicAttribute: Copy + Sized {
+    fn values_mut(values: &mut VertexAttributeValues) -> Option<&mut Vec<Self>>;
+}
+
+impl DynamicAttribute for [f32; 2] {
+    fn values_mut(values: &mut VertexAttributeValues) -> Option<&mut Vec<Self>> {
+        match values {
+            VertexAttributeValues::Float32x2(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl DynamicAttribute for [f32; 3] {
+    fn values_mut(values: &mut VertexAttributeValues) -> Option<&mut Vec<Self>> {
+        match values {
+            VertexAttributeValues::Float32x3(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl DynamicAttribute for [f32; 4] {
+    fn values_mut(values: &mut VertexAttributeValues) -> Option<&mut Vec<Self>> {
+        match values {
+            VertexAttributeValues::Float32x4(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// A mesh edited every frame through CPU-side copies of its attributes, written to the
+/// entity's [`Mesh3d`] asset in [`DynamicMeshSync`]. Only buffers that changed are written, into
+/// the mesh's existing vectors where they line up, so they aren't reallocated every frame.
+/// Frames where nothing changed leave the asset alone, so it isn't uploaded again. Any change
+/// still uploads the whole mesh, Bevy has no partial uploads of mesh assets.
+///
+/// Buffers that were never filled aren't written, so a mesh can keep attributes it was
+/// created with, like normals of a shape that only moves its vertices.
+#[derive(Component, Debug, Clone, Default)]
+pub struct DynamicMesh {
+    pub positions: DynamicBuffer<[f32; 3]>,
+    pub normals: DynamicBuffer<[f32; 3]>,
+    pub uvs: DynamicBuffer<[f32; 2]>,
+    pub colors: DynamicBuffer<[f32; 4]>,
+    pub indices: DynamicBuffer<u32>,
+}
+
+impl DynamicMesh {
+    pub fn has_changed(&self) -> bool {
+        self.positions.has_changed()
+            || self.normals.has_changed()
+            || self.uvs.has_changed()
+            || self.colors.has_changed()
+            || self.indices.has_changed()
+    }
+
+    /// Writes every changed buffer into `mesh`.
+    pub fn write_to(&mut self, mesh: &mut Mesh) {
+        write_attribute(mesh, Mesh::ATTRIBUTE_POSITION, &mut self.positions);
+        write_attribute(mesh, Mesh::ATTRIBUTE_NORMAL, &mut self.normals);
+        write_attribute(mesh, Mesh::ATTRIBUTE_UV_0, &mut self.uvs);
+        write_attribute(mesh, Mesh::ATTRIBUTE_COLOR, &mut self.colors);
+        if self.indices.has_changed() {
+            let target = match mesh.indices_mut() {
+                Some(Indices::U32(indices)) => Some(indices),
+                _ => None,
+            };
+            if let Some(indices) = self.indices.write(target) {
+                mesh.insert_indices(Indices::U32(indices));
+            }
+        }
+    }
+}
+
+fn write_attribute<T: DynamicAttribute>(
+    mesh: &mut Mesh,
+    attribute: MeshVertexAttribute,
+    buffer: &mut DynamicBuffer<T>,
+) where
+    VertexAttributeValues: From<Vec<T>>,
+{
+    if !buffer.has_changed() {
+        return;
+    }
+    let target = mesh.attribute_mut(attribute).and_then(T::values_mut);
+    if let Some(values) = buffer.write(target) {
+        mesh.insert_attribute(attribute, values);
+    }
+}
+
+/// Writes [`DynamicMesh`]es to their meshes. Systems editing them run before it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DynamicMeshSync;
+
+pub struct DynamicMeshPlugin;
+
+impl Plugin for DynamicMeshPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, sync_dynamic_meshes.in_set(DynamicMeshSync));
+    }
+}
+
+fn sync_dynamic_meshes(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut dynamic_meshes: Query<(&mut DynamicMesh, &Mesh3d), Changed<DynamicMesh>>,
+) {
+    for (mut dynamic_mesh, mesh3d) in &mut dynamic_meshes {
+        // Checked first, since borrowing the asset mutably marks it as modified
+        if !dynamic_mesh.has_changed() {
+            continue;
+        }
+        if let Some(mesh) = meshes.get_mut(&mesh3d.0) {
+            dynamic_mesh.bypass_change_detection().write_to(mesh);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::RenderAssetUsages;
+    use bevy::mesh::PrimitiveTopology;
+
+    use super::*;
+
+    fn mesh(positions: Vec<[f32; 3]>) -> Mesh {
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions.clone())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0., 1., 0.]; positions.len()])
+    }
+
+    fn positions(mesh: &Mesh) -> &[[f32; 3]] {
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+            .and_then(VertexAttributeValues::as_float3)
+            .unwrap()
+    }
+
+    fn filled(positions: &[[f32; 3]]) -> DynamicMesh {
+        let mut dynamic_mesh = DynamicMesh::default();
+        dynamic_mesh.positions.extend_from_slice(positions);
+        dynamic_mesh
+    }
+
+    #[test]
+    fn only_the_dirty_range_is_copied() {
+        let mut mesh = mesh(vec![[0.; 3]; 4]);
+        let mut dynamic_mesh = filled(&[[0.; 3]; 4]);
+        dynamic_mesh.write_to(&mut mesh);
+        // Changed behind the buffer's back, outside the range written next
+        if let Some(VertexAttributeValues::Float32x3(values)) =
+            mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+        {
+            values[0] = [9.; 3];
+        }
+        dynamic_mesh.positions.set(2, [1.; 3]);
+        dynamic_mesh.positions.set(1, [2.; 3]);
+        assert!(dynamic_mesh.has_changed());
+        dynamic_mesh.write_to(&mut mesh);
+        assert_eq!(positions(&mesh), [[9.; 3], [2.; 3], [1.; 3], [0.; 3]]);
+        assert!(!dynamic_mesh.has_changed());
+    }
+
+    #[test]
+    fn resized_buffers_replace_the_mesh_values() {
+        let mut mesh = mesh(vec![[0.; 3]; 4]);
+        let mut dynamic_mesh = filled(&[[1.; 3]; 4]);
+        dynamic_mesh.positions.push([2.; 3]);
+        dynamic_mesh.indices.extend_from_slice(&[0, 1, 2, 2, 3, 4]);
+        dynamic_mesh.write_to(&mut mesh);
+        assert_eq!(
+            positions(&mesh),
+            [[1.; 3], [1.; 3], [1.; 3], [1.; 3], [2.; 3]]
+        );
+        assert_eq!(
+            mesh.indices().unwrap().iter().collect::<Vec<_>>(),
+            [0, 1, 2, 2, 3, 4]
+        );
+
+        dynamic_mesh.positions.clear();
+        dynamic_mesh.positions.push([3.; 3]);
+        dynamic_mesh.write_to(&mut mesh);
+        assert_eq!(positions(&mesh), [[3.; 3]]);
+    }
+
+    #[test]
+    fn untouched_buffers_leave_the_mesh_alone() {
+        let mut mesh = mesh(vec![[0.; 3]; 2]);
+        let mut dynamic_mesh = filled(&[[1.; 3]; 2]);
+        dynamic_mesh.write_to(&mut mesh);
+        // Never filled, so the mesh keeps the normals it was created with
+        assert_eq!(
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+                .and_then(VertexAttributeValues::as_float3),
+            Some(&[[0., 1., 0.]; 2][..])
+        );
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_COLOR).is_none());
+        assert!(mesh.indices().is_none());
+
+        // Nothing changed since, so values changed behind the buffer's back stay
+        if let Some(VertexAttributeValues::Float32x3(values)) =
+            mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+        {
+            values[1] = [9.; 3];
+        }
+        assert!(!dynamic_mesh.has_changed());
+        dynamic_mesh.write_to(&mut mesh);
+        assert_eq!(positions(&mesh), [[1.; 3], [9.; 3]]);
+    }
+}
@@ -1,57 +1,154 @@
 use bevy::{
     asset::RenderAssetUsages,
     color::palettes::css,
+    mesh::skinning::SkinnedMeshInverseBindposes,
     prelude::*,
     render::render_resource::{Extent3d, TextureDimension, TextureFormat},
 };
+use staff_gen::{
+    assembly::StaffAssembly,
+    campfire::CampfireConfig,
+    charms::CharmConfig,
+    crook::CrookConfig,
+    cube::CubeNormals,
+    cylinder::CylinderNormals,
+    fork::ForkConfig,
+    head::{HeadRegistry, HeadStyle},
+    orb::OrbConfig,
+    staff::StaffConfig,
+    wrapping::WrappingConfig,
+};
 
 use crate::{
+    assembly::{rebuild_changed_assemblies, spawn_assembly},
     asset_loader::SceneAssets,
-    cone::spawn_cone_mesh,
-    crystal::spawn_crystal_mesh,
-    cube::{CubeNormals, display_cube_vertex_normals, spawn_cube_mesh},
-    cylinder::{CylinderNormals, display_cylinder_vertex_normals, spawn_cylinder_mesh},
-    staff::spawn_staff_mesh,
+    campfire::Campfire,
+    character::Character,
+    cone::{rebuild_changed_cones, spawn_cone_mesh},
+    crystal::{GenerateCrystal, default_crystal, rebuild_changed_crystals},
+    cube::{display_cube_vertex_normals, spawn_cube_mesh},
+    cylinder::{display_cylinder_vertex_normals, rebuild_changed_cylinders, spawn_cylinder_mesh},
+    generation::MeshGenMessages,
+    grid_material::{GridMaterial, GridSettings},
+    morph::StaffMorph,
+    morph_targets::{GnarlBlend, spawn_morph_target_staff},
+    shadows::{ShadowQuality, ShadowSettings, apply_shadow_config, cycle_shadow_quality},
+    skinning::{StaffSwing, spawn_skinned_staff},
+    staff::{
+        GenerateStaff, rebuild_changed_staffs, spawn_staff_mesh, staff_translation,
+        update_staff_stats,
+    },
+    state::AppState,
+    wind::WindSway,
+    wrapping::{rebuild_changed_wrappings, spawn_wrapping},
 };
 
+mod weather;
+#[cfg(feature = "particles")]
+mod weather_particles;
+
+use weather::soak;
+pub use weather::{Precipitation, Weather};
+#[cfg(feature = "particles")]
+use weather_particles::{fall_weather_particles, setup_weather_assets, spawn_weather_particles};
+
 const SUN_DISTANCE: f32 = 100.;
+/// In lux
+pub const SUN_ILLUMINANCE: f32 = 2500.;
 pub const FLOOR_LENGTH: f32 = 40.;
 pub const FLOOR_HEIGHT: f32 = 1.;
-pub const FLOOR_SIZE: Vec3 = vec3(FLOOR_LENGTH, FLOOR_HEIGHT, FLOOR_LENGTH);
 
 #[derive(Component)]
 pub struct Floor;
 
+#[derive(Component)]
+pub struct Sun;
+
+#[derive(Reflect, Clone, Debug, PartialEq, Default)]
+pub enum FloorStyle {
+    /// Colored checker texture, handy for spotting UV problems
+    #[default]
+    DebugTexture,
+    /// World-space grid lines, handy for judging scale
+    Grid,
+    Solid(Color),
+}
+
+/// Floor appearance, shadow quality and weather, applied whenever the resource changes.
+#[derive(Resource, Reflect, Clone, Debug)]
+#[reflect(Resource)]
+pub struct EnvironmentConfig {
+    /// Width and depth of the floor. Its top always sits at `FLOOR_HEIGHT / 2`.
+    pub floor_length: f32,
+    pub floor_style: FloorStyle,
+    pub grid: GridSettings,
+    /// Preset the shadow settings were last taken from
+    pub shadow_quality: ShadowQuality,
+    pub shadows: ShadowSettings,
+    pub weather: Weather,
+}
+
+impl Default for EnvironmentConfig {
+    fn default() -> Self {
+        Self {
+            floor_length: FLOOR_LENGTH,
+            floor_style: FloorStyle::default(),
+            grid: GridSettings::default(),
+            shadow_quality: ShadowQuality::platform_default(),
+            shadows: ShadowQuality::platform_default().settings(),
+            weather: Weather::default(),
+        }
+    }
+}
+
 pub struct EnvironmentPlugin;
 
 impl Plugin for EnvironmentPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(CubeNormals::default())
+        app.add_plugins(MaterialPlugin::<GridMaterial>::default())
+            .register_type::<EnvironmentConfig>()
+            .init_resource::<EnvironmentConfig>()
+            .insert_resource(CubeNormals::default())
             .insert_resource(CylinderNormals::default())
-            .add_systems(Startup, setup_environment)
-            .add_systems(Update, display_cube_vertex_normals)
-            .add_systems(Update, display_cylinder_vertex_normals);
+            .add_systems(Startup, (setup_environment, spawn_generated_objects))
+            .add_systems(Update, apply_floor_config)
+            .add_systems(Update, (cycle_shadow_quality, apply_shadow_config).chain())
+            .add_systems(
+                Update,
+                (display_cube_vertex_normals, display_cylinder_vertex_normals)
+                    .run_if(in_state(AppState::Editing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    rebuild_changed_cones,
+                    rebuild_changed_crystals,
+                    rebuild_changed_cylinders,
+                    rebuild_changed_staffs,
+                    rebuild_changed_assemblies,
+                    rebuild_changed_wrappings,
+                ),
+            )
+            .add_systems(PostUpdate, update_staff_stats);
+
+        #[cfg(feature = "particles")]
+        app.add_systems(Startup, setup_weather_assets).add_systems(
+            Update,
+            (spawn_weather_particles, fall_weather_particles).chain(),
+        );
     }
 }
 
 fn setup_environment(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut images: ResMut<Assets<Image>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     scene_assets: Res<SceneAssets>,
-    cylinder_normals: ResMut<CylinderNormals>,
-    cube_normals: ResMut<CubeNormals>,
 ) {
-    let debug_material = materials.add(StandardMaterial {
-        base_color_texture: Some(images.add(uv_debug_texture())),
-        ..default()
-    });
-
     commands.spawn((
         Name::new("Sun"),
+        Sun,
         DirectionalLight {
-            illuminance: 2500.,
+            illuminance: SUN_ILLUMINANCE,
             shadows_enabled: true,
             ..Default::default()
         },
@@ -59,30 +156,200 @@ fn setup_environment(
             .looking_at(Vec3::ZERO, Dir3::Y),
     ));
 
+    // Mesh and material are added by apply_floor_config
     commands.spawn((
         Name::new("Floor"),
         Floor,
-        Mesh3d(meshes.add(Cuboid::new(FLOOR_SIZE.x, FLOOR_SIZE.y, FLOOR_SIZE.z))),
-        MeshMaterial3d(debug_material.clone()),
         Transform::from_translation(Vec3::ZERO),
     ));
 
     commands.spawn((
         Name::new("Laura"),
+        Character,
         SceneRoot(scene_assets.laura.clone()),
         MeshMaterial3d(materials.add(Color::from(css::DARK_GREEN))),
         Transform::from_xyz(0., FLOOR_HEIGHT / 2., 0.),
         Visibility::default(),
     ));
+}
+
+/// Spawns the viewer's generated shapes, at startup and again whenever the scene is regenerated.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_generated_objects(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut inverse_bindposes: ResMut<Assets<SkinnedMeshInverseBindposes>>,
+    cylinder_normals: ResMut<CylinderNormals>,
+    cube_normals: ResMut<CubeNormals>,
+    mut mesh_gen: MeshGenMessages,
+    heads: Res<HeadRegistry>,
+    morph: Res<StaffMorph>,
+    gnarl: Res<GnarlBlend>,
+    mut generate_staff: MessageWriter<GenerateStaff>,
+    mut generate_crystal: MessageWriter<GenerateCrystal>,
+) {
+    spawn_cube_mesh(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        cube_normals,
+        &mut mesh_gen,
+    );
+    spawn_cone_mesh(&mut commands, &mut meshes, &mut materials, &mut mesh_gen);
+    spawn_cylinder_mesh(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        cylinder_normals,
+        &mut mesh_gen,
+    );
+    let staff_config = morph.config();
+    let staff = spawn_staff_mesh(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &mut mesh_gen,
+        &heads,
+        staff_config.clone(),
+    );
+    commands.entity(staff).insert(CharmConfig::default());
+    spawn_wrapping(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &mut mesh_gen,
+        staff,
+        &staff_config,
+        WrappingConfig::default(),
+    );
+    spawn_morph_target_staff(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &mut images,
+        &mut mesh_gen,
+        &morph.from,
+        gnarl.weight,
+    );
+    let skinned_staff = spawn_skinned_staff(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &mut inverse_bindposes,
+        &mut mesh_gen,
+        &morph.from,
+        4,
+        vec2(-4., 0.),
+    );
+    commands
+        .entity(skinned_staff)
+        .insert((StaffSwing::default(), WindSway::default()));
+    generate_crystal.write(default_crystal());
+    match StaffAssembly::try_wizard_staff(morph.from.clone()) {
+        Ok(wizard_staff) => {
+            spawn_assembly(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &mut mesh_gen,
+                wizard_staff,
+                vec2(2., 1.),
+            );
+        }
+        Err(error) => warn!("Can't build the wizard staff: {error}"),
+    }
+    let campfire = spawn_assembly(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &mut mesh_gen,
+        CampfireConfig::default().assembly(),
+        vec2(-3., 3.),
+    );
+    commands
+        .entity(campfire)
+        .insert((Name::new("Campfire"), Campfire));
+    for (head, position) in [
+        (HeadStyle::Crook(CrookConfig::default()), vec2(-1., 1.5)),
+        (HeadStyle::Forked(ForkConfig::default()), vec2(1., 2.)),
+        (HeadStyle::Orb(OrbConfig::default()), vec2(0., 2.5)),
+    ] {
+        let config = StaffConfig {
+            head,
+            ..morph.from.clone()
+        };
+        let translation = staff_translation(&config)
+            .with_x(position.x)
+            .with_z(position.y);
+        generate_staff.write(GenerateStaff {
+            transform: Transform::from_translation(translation),
+            ..GenerateStaff::new(config)
+        });
+    }
+}
 
-    spawn_cube_mesh(&mut commands, &mut meshes, &mut materials, cube_normals);
-    spawn_cone_mesh(&mut commands, &mut meshes, &mut materials);
-    spawn_cylinder_mesh(&mut commands, &mut meshes, &mut materials, cylinder_normals);
-    spawn_staff_mesh(&mut commands, &mut meshes, &mut materials);
-    spawn_crystal_mesh(&mut commands, &mut meshes, &mut materials);
+#[allow(clippy::too_many_arguments)]
+fn apply_floor_config(
+    mut commands: Commands,
+    config: Res<EnvironmentConfig>,
+    mut applied: Local<Option<(f32, FloorStyle, GridSettings, bool)>>,
+    floor: Single<Entity, With<Floor>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut standard_materials: ResMut<Assets<StandardMaterial>>,
+    mut grid_materials: ResMut<Assets<GridMaterial>>,
+) {
+    let floor_config = (
+        config.floor_length,
+        config.floor_style.clone(),
+        config.grid.clone(),
+        config.weather.soaks_floor(),
+    );
+    if applied.as_ref() == Some(&floor_config) {
+        return;
+    }
+    *applied = Some(floor_config);
+    let mut floor = commands.entity(*floor);
+    floor.insert(Mesh3d(meshes.add(Cuboid::new(
+        config.floor_length,
+        FLOOR_HEIGHT,
+        config.floor_length,
+    ))));
+    let mut floor_material = |mut material: StandardMaterial| {
+        if config.weather.soaks_floor() {
+            soak(&mut material);
+        }
+        MeshMaterial3d(standard_materials.add(material))
+    };
+    // The grid's lines are drawn the same in any weather
+    match &config.floor_style {
+        FloorStyle::DebugTexture => {
+            floor
+                .remove::<MeshMaterial3d<GridMaterial>>()
+                .insert(floor_material(StandardMaterial {
+                    base_color_texture: Some(images.add(uv_debug_texture())),
+                    ..default()
+                }));
+        }
+        FloorStyle::Grid => {
+            floor
+                .remove::<MeshMaterial3d<StandardMaterial>>()
+                .insert(MeshMaterial3d(grid_materials.add(GridMaterial {
+                    settings: config.grid.clone(),
+                })));
+        }
+        FloorStyle::Solid(color) => {
+            floor
+                .remove::<MeshMaterial3d<GridMaterial>>()
+                .insert(floor_material(StandardMaterial::from(*color)));
+        }
+    }
 }
 
-fn uv_debug_texture() -> Image {
+/// The 8×8 checker Bevy's examples use, for checking UVs.
+pub fn uv_debug_texture() -> Image {
     const TEXTURE_SIZE: usize = 8;
 
     let mut palette: [u8; 32] = [
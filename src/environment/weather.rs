@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// Luminance a soaked floor keeps
+const WET_BRIGHTNESS: f32 = 0.45;
+/// Roughness of a soaked floor, low enough for the sky and sun to glint off it
+const WET_ROUGHNESS: f32 = 0.15;
+
+/// What falls from the sky.
+#[derive(Reflect, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Precipitation {
+    #[default]
+    None,
+    Rain,
+    Snow,
+}
+
+/// Rain or snow falling around the camera, wherever it goes.
+#[derive(Reflect, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Weather {
+    pub precipitation: Precipitation,
+    /// From 0 for nothing to 1 for a downpour or a blizzard
+    pub intensity: f32,
+    /// Whether rain darkens the floor and makes it glossy, as if it's soaked through
+    pub wet_floor: bool,
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Self {
+            precipitation: Precipitation::None,
+            intensity: 0.5,
+            wet_floor: true,
+        }
+    }
+}
+
+impl Weather {
+    /// Whether the floor is drawn wet.
+    pub(super) fn soaks_floor(&self) -> bool {
+        self.wet_floor && self.precipitation == Precipitation::Rain && self.intensity > 0.
+    }
+}
+
+/// Darkens a floor material and makes it glossy, the way soaked ground looks.
+pub(super) fn soak(material: &mut StandardMaterial) {
+    let luminance = material.base_color.luminance();
+    material.base_color = material
+        .base_color
+        .with_luminance(luminance * WET_BRIGHTNESS);
+    material.perceptual_roughness = WET_ROUGHNESS;
+}
@@ -0,0 +1,208 @@
+use std::f32::consts::TAU;
+
+use bevy::light::NotShadowCaster;
+use bevy::prelude::*;
+use rand::Rng;
+
+use super::{EnvironmentConfig, FLOOR_HEIGHT, Precipitation, Weather};
+use crate::camera::MainCamera;
+use crate::units::UnitsConfig;
+use crate::wind::Wind;
+
+/// Half the width and depth of the volume particles fall in around the camera, in meters
+const EMITTER_RADIUS: f32 = 10.;
+/// How far above the camera particles start falling from, in meters
+const EMITTER_HEIGHT: f32 = 8.;
+
+/// How one kind of precipitation falls.
+#[derive(Debug, Clone, Copy)]
+struct Fall {
+    /// Particles falling at full intensity
+    particles: u32,
+    /// Meters per second
+    speed: f32,
+    /// Share of the wind's speed particles are carried along with
+    drift: f32,
+    /// Sideways wobble, in meters per second
+    flutter: f32,
+    /// Seconds a particle takes to fade away once it reaches the ground
+    fade: f32,
+}
+
+impl Precipitation {
+    fn fall(self) -> Option<Fall> {
+        match self {
+            Self::None => None,
+            Self::Rain => Some(Fall {
+                particles: 2000,
+                speed: 7.,
+                drift: 0.3,
+                flutter: 0.,
+                fade: 0.08,
+            }),
+            Self::Snow => Some(Fall {
+                particles: 1200,
+                speed: 0.8,
+                drift: 0.6,
+                flutter: 0.25,
+                fade: 1.5,
+            }),
+        }
+    }
+}
+
+impl Weather {
+    fn particles(&self) -> u32 {
+        self.precipitation.fall().map_or(0, |fall| {
+            (fall.particles as f32 * self.intensity.clamp(0., 1.)).round() as u32
+        })
+    }
+}
+
+/// A raindrop or snowflake. It fades away where it lands and falls again from above the camera.
+#[derive(Component, Debug)]
+pub(super) struct WeatherParticle {
+    /// Seconds since it landed
+    landed: Option<f32>,
+    /// Offset into the flutter, so flakes don't all wobble together
+    phase: f32,
+}
+
+#[derive(Resource, Debug)]
+pub(super) struct WeatherAssets {
+    raindrop: Handle<Mesh>,
+    rain_material: Handle<StandardMaterial>,
+    snowflake: Handle<Mesh>,
+    snow_material: Handle<StandardMaterial>,
+}
+
+pub(super) fn setup_weather_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(WeatherAssets {
+        // A streak along the direction it falls in
+        raindrop: meshes.add(Cuboid::new(0.004, 0.25, 0.004)),
+        rain_material: materials.add(StandardMaterial {
+            base_color: Color::srgba(0.75, 0.8, 0.9, 0.35),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        snowflake: meshes.add(Sphere::new(0.015).mesh().ico(0).expect("ico 0 is valid")),
+        snow_material: materials.add(StandardMaterial {
+            base_color: Color::WHITE,
+            perceptual_roughness: 1.,
+            ..default()
+        }),
+    });
+}
+
+/// Respawns every particle whenever the precipitation or how many particles it falls with
+/// changes, scattered through the whole volume so the weather doesn't start as one sheet.
+pub(super) fn spawn_weather_particles(
+    mut commands: Commands,
+    config: Res<EnvironmentConfig>,
+    units: Res<UnitsConfig>,
+    assets: Res<WeatherAssets>,
+    mut spawned: Local<(Precipitation, u32)>,
+    camera: Single<&GlobalTransform, With<MainCamera>>,
+    particles: Query<Entity, With<WeatherParticle>>,
+) {
+    let weather = (config.weather.precipitation, config.weather.particles());
+    if *spawned == weather {
+        return;
+    }
+    *spawned = weather;
+    for particle in &particles {
+        commands.entity(particle).despawn();
+    }
+    let (mesh, material) = match weather.0 {
+        Precipitation::None => return,
+        Precipitation::Rain => (&assets.raindrop, &assets.rain_material),
+        Precipitation::Snow => (&assets.snowflake, &assets.snow_material),
+    };
+    let from_meters = units.from_meters();
+    let radius = EMITTER_RADIUS * from_meters;
+    let center = camera.translation();
+    let floor = FLOOR_HEIGHT / 2.;
+    let top = center.y.max(floor) + EMITTER_HEIGHT * from_meters;
+    let mut rand = rand::rng();
+    for _ in 0..weather.1 {
+        let translation = vec3(
+            center.x + rand.random_range(-radius..radius),
+            rand.random_range(floor..top),
+            center.z + rand.random_range(-radius..radius),
+        );
+        commands.spawn((
+            Name::new("WeatherParticle"),
+            WeatherParticle {
+                landed: None,
+                phase: rand.random_range(0. ..TAU),
+            },
+            Mesh3d(mesh.clone()),
+            MeshMaterial3d(material.clone()),
+            NotShadowCaster,
+            Transform::from_translation(translation).with_scale(Vec3::splat(from_meters)),
+        ));
+    }
+}
+
+/// Particles fall carried along by the wind, and wrap around to the far side of the volume as
+/// the camera moves, so it's always surrounded. Once they reach the floor's top they shrink away
+/// and start again above the camera.
+pub(super) fn fall_weather_particles(
+    time: Res<Time>,
+    config: Res<EnvironmentConfig>,
+    units: Res<UnitsConfig>,
+    wind: Res<Wind>,
+    camera: Single<&GlobalTransform, With<MainCamera>>,
+    mut particles: Query<(&mut WeatherParticle, &mut Transform)>,
+) {
+    let Some(fall) = config.weather.precipitation.fall() else {
+        return;
+    };
+    let delta = time.delta_secs();
+    let elapsed = time.elapsed_secs();
+    let from_meters = units.from_meters();
+    let radius = EMITTER_RADIUS * from_meters;
+    let center = camera.translation();
+    let floor = FLOOR_HEIGHT / 2.;
+    let top = center.y.max(floor) + EMITTER_HEIGHT * from_meters;
+    let mut rand = rand::rng();
+    for (mut particle, mut transform) in &mut particles {
+        if let Some(landed) = &mut particle.landed {
+            *landed += delta;
+            let t = *landed / fall.fade;
+            if t < 1. {
+                transform.scale = Vec3::splat(from_meters * (1. - t));
+                continue;
+            }
+            particle.landed = None;
+            transform.translation = vec3(
+                center.x + rand.random_range(-radius..radius),
+                top,
+                center.z + rand.random_range(-radius..radius),
+            );
+            transform.scale = Vec3::splat(from_meters);
+        }
+
+        let flutter = Vec2::from_angle(elapsed * 2. + particle.phase)
+            * fall.flutter
+            * (elapsed * 1.3 + particle.phase).sin();
+        let velocity = (Vec3::NEG_Y * fall.speed + vec3(flutter.x, 0., flutter.y)) * from_meters
+            + wind.at(transform.translation, elapsed) * fall.drift * from_meters;
+        transform.translation += velocity * delta;
+        let offset = transform.translation - center;
+        transform.translation.x = center.x + (offset.x + radius).rem_euclid(2. * radius) - radius;
+        transform.translation.z = center.z + (offset.z + radius).rem_euclid(2. * radius) - radius;
+        if let Ok(direction) = Dir3::new(velocity) {
+            transform.rotation = Quat::from_rotation_arc(Vec3::NEG_Y, *direction);
+        }
+        if transform.translation.y <= floor {
+            transform.translation.y = floor;
+            particle.landed = Some(0.);
+        }
+    }
+}
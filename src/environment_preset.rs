@@ -0,0 +1,213 @@
+use std::error::Error;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use serde::{Deserialize, Deserializer};
+use staff_gen::scatter::ScatterKind;
+
+use crate::actions::RegisterAction;
+use crate::camera::MainCamera;
+use crate::environment::{EnvironmentConfig, FloorStyle, SUN_ILLUMINANCE, Sun, Weather};
+use crate::foliage::ScatterSettings;
+
+/// Preset files, in the order the presets are cycled through.
+const PRESET_FILES: [&str; 3] = [
+    "environments/forest_clearing.environment.ron",
+    "environments/cave.environment.ron",
+    "environments/desert_ruin.environment.ron",
+];
+
+/// A color written as a hex string like `"#87CEEB"`.
+#[derive(Debug, Clone, Copy)]
+pub struct HexColor(pub Color);
+
+impl<'de> Deserialize<'de> for HexColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Srgba::hex(&hex)
+            .map(|color| Self(color.into()))
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FogPreset {
+    pub color: HexColor,
+    /// Distance the fog starts at, and where it hides everything
+    pub start: f32,
+    pub end: f32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SunPreset {
+    pub color: HexColor,
+    /// In lux
+    pub illuminance: f32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AmbientPreset {
+    pub color: HexColor,
+    pub brightness: f32,
+}
+
+/// A place to preview staffs in: floor, sky, fog, lighting, weather and which props are
+/// scattered around, loaded from an `.environment.ron` file.
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct EnvironmentPreset {
+    pub name: String,
+    pub floor: HexColor,
+    pub sky: HexColor,
+    pub fog: Option<FogPreset>,
+    pub sun: SunPreset,
+    pub ambient: AmbientPreset,
+    pub formations: Option<ScatterKind>,
+    /// Stalagmites, and stalactites too if there's a ceiling
+    pub dripstones: Option<ScatterKind>,
+    /// Height of a ceiling closing the scene in
+    pub ceiling: Option<f32>,
+    pub rocks: Option<ScatterKind>,
+    pub grass: Option<ScatterKind>,
+    /// Clear skies if left out
+    #[serde(default)]
+    pub weather: Weather,
+}
+
+#[derive(Default, TypePath)]
+struct EnvironmentPresetLoader;
+
+impl AssetLoader for EnvironmentPresetLoader {
+    type Asset = EnvironmentPreset;
+    type Settings = ();
+    type Error = Box<dyn Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<EnvironmentPreset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["environment.ron"]
+    }
+}
+
+/// The loaded presets and which one the scene is dressed in, if any.
+#[derive(Resource, Debug, Default)]
+pub struct EnvironmentPresets {
+    pub presets: Vec<Handle<EnvironmentPreset>>,
+    /// Index into `presets`, or `None` for the plain studio look the viewer starts with
+    pub selected: Option<usize>,
+}
+
+/// Dresses the scene in an environment preset, cycled through from the command palette.
+/// Editing a preset file while it's selected applies the change.
+pub struct EnvironmentPresetPlugin;
+
+impl Plugin for EnvironmentPresetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<EnvironmentPreset>()
+            .init_asset_loader::<EnvironmentPresetLoader>()
+            .init_resource::<EnvironmentPresets>()
+            .register_action(
+                "Next environment preset",
+                |mut presets: ResMut<EnvironmentPresets>| {
+                    presets.selected = match presets.selected {
+                        None => Some(0),
+                        Some(i) if i + 1 < presets.presets.len() => Some(i + 1),
+                        Some(_) => None,
+                    };
+                },
+            )
+            .add_systems(Startup, load_environment_presets)
+            .add_systems(Update, apply_environment_preset);
+    }
+}
+
+fn load_environment_presets(
+    mut presets: ResMut<EnvironmentPresets>,
+    asset_server: Res<AssetServer>,
+) {
+    presets.presets = PRESET_FILES
+        .iter()
+        .map(|&path| asset_server.load(path))
+        .collect();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn apply_environment_preset(
+    mut commands: Commands,
+    presets: Res<EnvironmentPresets>,
+    preset_assets: Res<Assets<EnvironmentPreset>>,
+    mut asset_events: MessageReader<AssetEvent<EnvironmentPreset>>,
+    mut environment: ResMut<EnvironmentConfig>,
+    mut scatter: ResMut<ScatterSettings>,
+    mut clear_color: ResMut<ClearColor>,
+    mut ambient: ResMut<AmbientLight>,
+    mut sun: Single<&mut DirectionalLight, With<Sun>>,
+    cameras: Query<Entity, With<MainCamera>>,
+) {
+    let selected = presets.selected.map(|i| &presets.presets[i]);
+    // Presets may finish loading, or be edited, after they're picked
+    let reloaded = asset_events.read().any(|event| match event {
+        AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => {
+            selected.is_some_and(|handle| handle.id() == *id)
+        }
+        _ => false,
+    });
+    // The studio look is already in place when the app starts
+    let picked = presets.is_changed() && !presets.is_added();
+    if !picked && !reloaded {
+        return;
+    }
+
+    let Some(handle) = selected else {
+        // Back to the studio look
+        environment.floor_style = EnvironmentConfig::default().floor_style;
+        environment.weather = Weather::default();
+        *scatter = ScatterSettings::default();
+        *clear_color = ClearColor::default();
+        *ambient = AmbientLight::default();
+        sun.color = Color::WHITE;
+        sun.illuminance = SUN_ILLUMINANCE;
+        for camera in &cameras {
+            commands.entity(camera).remove::<DistanceFog>();
+        }
+        return;
+    };
+    let Some(preset) = preset_assets.get(handle) else {
+        return;
+    };
+    info!("Environment preset: {}", preset.name);
+    environment.floor_style = FloorStyle::Solid(preset.floor.0);
+    environment.weather = preset.weather.clone();
+    scatter.formations = preset.formations;
+    scatter.dripstones = preset.dripstones;
+    scatter.ceiling = preset.ceiling;
+    scatter.rocks = preset.rocks;
+    scatter.grass = preset.grass;
+    clear_color.0 = preset.sky.0;
+    ambient.color = preset.ambient.color.0;
+    ambient.brightness = preset.ambient.brightness;
+    sun.color = preset.sun.color.0;
+    sun.illuminance = preset.sun.illuminance;
+    for camera in &cameras {
+        match &preset.fog {
+            Some(fog) => commands.entity(camera).insert(DistanceFog {
+                color: fog.color.0,
+                falloff: FogFalloff::Linear {
+                    start: fog.start,
+                    end: fog.end,
+                },
+                ..default()
+            }),
+            None => commands.entity(camera).remove::<DistanceFog>(),
+        };
+    }
+}
@@ -0,0 +1,185 @@
+use bevy::prelude::*;
+use staff_gen::staff::StaffConfig;
+
+use crate::actions::RegisterAction;
+use crate::locale::Locale;
+use crate::morph::StaffMorph;
+use crate::randomizer::StaffRandomizerSettings;
+use crate::staff::{GenerateStaff, staff_translation};
+use crate::state::AppState;
+use crate::units::UnitsConfig;
+
+/// Staffs along each side of the grid. The favorite stands in the middle.
+const GRID_SIZE: usize = 3;
+/// Middle of the grid, where the gallery would stand
+const GRID_CENTER: Vec2 = vec2(0., -4.5);
+const GRID_SPACING: f32 = 0.9;
+
+/// The staff the shown generation was bred from, in scene units. Picking a favorite among its
+/// children breeds the next generation.
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
+pub struct Exploration {
+    pub favorite: StaffConfig,
+    pub generation: u32,
+    /// How far children stray from the favorite, as passed to
+    /// [`StaffRandomizer::mutate`](staff_gen::randomize::StaffRandomizer::mutate)
+    pub mutation: f32,
+}
+
+impl Default for Exploration {
+    fn default() -> Self {
+        Self {
+            favorite: StaffConfig::default(),
+            generation: 0,
+            mutation: 0.25,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+struct ExplorationStaff;
+
+#[derive(Component, Debug)]
+struct ExplorationLabel;
+
+/// Evolves staffs by taste: a 3×3 grid of variants of the viewer's staff, where clicking a
+/// favorite replaces the grid with mutations of it. Fields locked for the randomizer stay fixed.
+pub struct ExplorationPlugin;
+
+impl Plugin for ExplorationPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Exploration>()
+            .init_resource::<Exploration>()
+            .register_action(
+                "Explore staff variants",
+                |mut next_state: ResMut<NextState<AppState>>| next_state.set(AppState::Exploring),
+            )
+            .register_action("Use favorite staff", use_favorite)
+            .add_systems(Startup, setup_exploration_label)
+            .add_systems(OnEnter(AppState::Exploring), start_exploration)
+            .add_systems(OnExit(AppState::Exploring), end_exploration)
+            .add_systems(
+                Update,
+                (
+                    breed_generation.run_if(resource_changed::<Exploration>),
+                    update_exploration_label
+                        .run_if(resource_changed::<Exploration>.or(resource_changed::<Locale>)),
+                )
+                    .run_if(in_state(AppState::Exploring)),
+            );
+    }
+}
+
+fn setup_exploration_label(mut commands: Commands) {
+    commands.spawn((
+        Name::new("ExplorationLabel"),
+        ExplorationLabel,
+        Visibility::Hidden,
+        Text::default(),
+        TextFont::from_font_size(16.),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.),
+            justify_self: JustifySelf::Center,
+            ..default()
+        },
+    ));
+}
+
+/// Starts from the viewer's staff.
+fn start_exploration(
+    mut exploration: ResMut<Exploration>,
+    morph: Res<StaffMorph>,
+    mut label: Single<&mut Visibility, With<ExplorationLabel>>,
+) {
+    exploration.favorite = morph.config();
+    exploration.generation = 0;
+    **label = Visibility::Inherited;
+}
+
+fn end_exploration(
+    mut commands: Commands,
+    staffs: Query<Entity, With<ExplorationStaff>>,
+    mut label: Single<&mut Visibility, With<ExplorationLabel>>,
+) {
+    for entity in &staffs {
+        commands.entity(entity).despawn();
+    }
+    **label = Visibility::Hidden;
+}
+
+/// Replaces the grid with the favorite in the middle and its mutants around it. Mutation works
+/// in meters, like the randomizer's bounds.
+fn breed_generation(
+    mut commands: Commands,
+    mut generate_staff: MessageWriter<GenerateStaff>,
+    exploration: Res<Exploration>,
+    randomizer: Res<StaffRandomizerSettings>,
+    units: Res<UnitsConfig>,
+    staffs: Query<Entity, With<ExplorationStaff>>,
+) {
+    for entity in &staffs {
+        commands.entity(entity).despawn();
+    }
+    let parent = exploration.favorite.scaled(units.meters_per_unit);
+    let mut rand = rand::rng();
+    let middle = GRID_SIZE / 2;
+    for row in 0..GRID_SIZE {
+        for column in 0..GRID_SIZE {
+            let config = if (row, column) == (middle, middle) {
+                exploration.favorite.clone()
+            } else {
+                randomizer
+                    .randomizer
+                    .mutate(&parent, &randomizer.locked, exploration.mutation, &mut rand)
+                    .scaled(units.from_meters())
+            };
+            let offset = vec2(column as f32, row as f32) - middle as f32;
+            let position = GRID_CENTER + offset * GRID_SPACING;
+            let translation = staff_translation(&config)
+                .with_x(position.x)
+                .with_z(position.y);
+            let staff = commands.spawn(ExplorationStaff).observe(pick_favorite).id();
+            generate_staff.write(GenerateStaff {
+                config,
+                seed: None,
+                transform: Transform::from_translation(translation),
+                entity: Some(staff),
+            });
+        }
+    }
+}
+
+fn pick_favorite(
+    mut click: On<Pointer<Click>>,
+    staffs: Query<&StaffConfig, With<ExplorationStaff>>,
+    mut exploration: ResMut<Exploration>,
+) {
+    if click.event.button != PointerButton::Primary {
+        return;
+    }
+    let Ok(config) = staffs.get(click.entity) else {
+        return;
+    };
+    click.propagate(false);
+    exploration.favorite = config.clone();
+    exploration.generation += 1;
+}
+
+fn update_exploration_label(
+    exploration: Res<Exploration>,
+    locale: Res<Locale>,
+    mut label: Single<&mut Text, With<ExplorationLabel>>,
+) {
+    label.0 = locale.format(
+        "exploration.label",
+        &[("generation", &exploration.generation)],
+    );
+}
+
+/// Makes the favorite the viewer's staff, as the morph's start.
+fn use_favorite(exploration: Res<Exploration>, mut morph: ResMut<StaffMorph>) {
+    morph.from = exploration.favorite.clone();
+    morph.t = 0.;
+}
@@ -0,0 +1,303 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use serde::Serialize;
+use staff_gen::mesh_util::{AtlasTexel, bake_atlas, mesh_hash, pack_uv_atlas, to_obj, wood_grain};
+use staff_gen::naming::staff_name;
+use staff_gen::staff::StaffConfig;
+use staff_gen::stats::StaffStats;
+use staff_gen::style::StyleConfig;
+use staff_gen::topology::TopologyReport;
+
+use crate::actions::RegisterAction;
+use crate::determinism::format_hash;
+use crate::generation::IndexOptimization;
+use crate::morph::StaffMorph;
+use crate::staff::Staff;
+use crate::units::UnitsConfig;
+
+const EXPORT_DIR: &str = "exports";
+/// Space between the charts of an exported staff's UV atlas, as a fraction of the atlas
+const ATLAS_PADDING: f32 = 0.01;
+/// Width and height of the textures a staff's material is baked into
+const BAKE_SIZE: u32 = 512;
+/// How much darker the latewood bands of the baked wood grain are than the material's color
+const LATEWOOD_DARKENING: f32 = 0.45;
+/// How much rougher the latewood bands of the baked wood grain are than the material
+const LATEWOOD_ROUGHENING: f32 = 0.15;
+
+/// Writes generated meshes to disk. Native only, there is no file system on the web.
+pub struct ExportPlugin;
+
+impl Plugin for ExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<ExportStaff>()
+            .register_action("Export staff", |mut exports: MessageWriter<ExportStaff>| {
+                exports.write(ExportStaff {
+                    bake: false,
+                    config: None,
+                });
+            })
+            .register_action(
+                "Export staff with baked textures",
+                |mut exports: MessageWriter<ExportStaff>| {
+                    exports.write(ExportStaff {
+                        bake: true,
+                        config: None,
+                    });
+                },
+            )
+            .add_systems(Update, (export_staff_on_key, export_staff).chain());
+    }
+}
+
+/// Saves the current staff, baking its material into textures too when `bake` is set.
+#[derive(Message, Debug, Clone)]
+pub struct ExportStaff {
+    pub bake: bool,
+    /// Generates a staff from this config to export instead
+    pub config: Option<StaffConfig>,
+}
+
+/// Everything a game needs to use an exported staff, written next to its OBJ.
+#[derive(Serialize)]
+struct StaffExport<'a> {
+    name: String,
+    config: &'a StaffConfig,
+    /// Of the generated mesh, before it's converted to meters and its UVs are packed
+    mesh_hash: String,
+    stats: &'a StaffStats,
+    /// Of the generated mesh, so a game can check it's closed and in one piece
+    topology: TopologyReport,
+}
+
+type ExportedStaff = (
+    &'static Mesh3d,
+    &'static MeshMaterial3d<StandardMaterial>,
+    Option<&'static StaffStats>,
+);
+
+/// X exports the current staff, Shift+X also bakes its material.
+fn export_staff_on_key(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut exports: MessageWriter<ExportStaff>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyX) {
+        let bake = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        exports.write(ExportStaff { bake, config: None });
+    }
+}
+
+/// Saves a staff as an OBJ file named after its seed, with UVs packed for baking, plus its stats
+/// as JSON. That is the current staff, unless the request brings a config to generate one from.
+#[allow(clippy::too_many_arguments)]
+fn export_staff(
+    mut exports: MessageReader<ExportStaff>,
+    morph: Res<StaffMorph>,
+    meshes: Res<Assets<Mesh>>,
+    materials: Res<Assets<StandardMaterial>>,
+    staffs: Query<ExportedStaff, With<Staff>>,
+    style: Res<StyleConfig>,
+    units: Res<UnitsConfig>,
+    optimization: Res<IndexOptimization>,
+) {
+    for export in exports.read() {
+        let current = staffs.iter().find_map(|(mesh3d, material, stats)| {
+            Some((meshes.get(&mesh3d.0)?, materials.get(&material.0), stats))
+        });
+        let material = current.and_then(|(_, material, _)| material);
+        match &export.config {
+            Some(config) => {
+                let mesh = match style.staff(config).try_generate_mesh() {
+                    Ok(mesh) => mesh,
+                    Err(error) => {
+                        warn!("Can't export the staff: {error}");
+                        continue;
+                    }
+                };
+                let stats = StaffStats::new(config, &mesh, units.meters_per_unit);
+                write_staff(
+                    config,
+                    &mesh,
+                    material,
+                    Some(&stats),
+                    export.bake,
+                    &units,
+                    &optimization,
+                );
+            }
+            None => {
+                let Some((mesh, _, stats)) = current else {
+                    warn!("No staff mesh to export");
+                    continue;
+                };
+                write_staff(
+                    &morph.config(),
+                    mesh,
+                    material,
+                    stats,
+                    export.bake,
+                    &units,
+                    &optimization,
+                );
+            }
+        }
+    }
+}
+
+fn write_staff(
+    config: &StaffConfig,
+    mesh: &Mesh,
+    material: Option<&StandardMaterial>,
+    stats: Option<&StaffStats>,
+    bake: bool,
+    units: &UnitsConfig,
+    optimization: &IndexOptimization,
+) {
+    let name = staff_name(config);
+    let hash = format_hash(mesh_hash(mesh));
+    let topology = TopologyReport::new(mesh);
+    let obj_path = PathBuf::from(EXPORT_DIR).join(format!("staff_{}.obj", config.seed));
+    let mut mesh = mesh.clone().scaled_by(Vec3::splat(units.meters_per_unit));
+    pack_uv_atlas(&mut mesh, ATLAS_PADDING);
+    // OBJ has no unit field, so write meters and say so in a comment
+    let mut header = format!("# {name}\n# Units: meters\n# Mesh hash: {hash}\n");
+    match material {
+        Some(material) if bake => {
+            let mtl = bake_material(&name, &mesh, material, &obj_path);
+            header.push_str(&format!("mtllib {mtl}\nusemtl staff\n"));
+        }
+        None if bake => warn!("Staff material is not loaded, skipping the texture bake"),
+        _ => {}
+    }
+    // OBJ holds a single UV set, and baking lightmaps or AO needs charts that don't overlap
+    if let Some(atlas) = mesh.remove_attribute(Mesh::ATTRIBUTE_UV_1) {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, atlas);
+    }
+    optimization.reorder(&mut mesh);
+    write_export(&obj_path, header + to_obj(&mesh).as_str());
+
+    let Some(stats) = stats else {
+        warn!("Staff stats are not ready yet, skipping the JSON export");
+        return;
+    };
+    let json_path = obj_path.with_extension("json");
+    let export = StaffExport {
+        name,
+        config,
+        mesh_hash: hash,
+        stats,
+        topology,
+    };
+    match serde_json::to_string_pretty(&export) {
+        Ok(json) => write_export(&json_path, json),
+        Err(error) => error!("Failed to serialize staff stats: {error}"),
+    }
+}
+
+/// Bakes procedural wood over the atlas UVs of `mesh` into albedo, normal and roughness PNGs next
+/// to `obj_path`, plus an MTL file using them, and returns the MTL file's name. The albedo and
+/// roughness are `material`'s, darkened and roughened by [`wood_grain`] at each texel's position.
+/// Normals are baked in object space, since the grain adds no relief to the mesh normals.
+fn bake_material(name: &str, mesh: &Mesh, material: &StandardMaterial, obj_path: &Path) -> String {
+    let texels = bake_atlas(mesh, BAKE_SIZE);
+    let stem = obj_path.file_stem().unwrap_or_default().to_string_lossy();
+    let texture = |kind: &str| format!("{stem}_{kind}.png");
+
+    let grain = |texel: Option<&AtlasTexel>| texel.map_or(0., |texel| wood_grain(texel.position));
+    let earlywood = material.base_color.to_linear();
+    let latewood = LinearRgba {
+        alpha: earlywood.alpha,
+        ..earlywood * (1. - LATEWOOD_DARKENING)
+    };
+    write_texture(
+        &texture("albedo"),
+        TextureFormat::Rgba8UnormSrgb,
+        &texels,
+        |texel| Srgba::from(earlywood.mix(&latewood, grain(texel))).to_u8_array(),
+    );
+    write_texture(
+        &texture("normal"),
+        TextureFormat::Rgba8Unorm,
+        &texels,
+        |texel| {
+            let normal = texel.map_or(Vec3::Z, |texel| texel.normal);
+            let [x, y, z] = ((normal * 0.5 + 0.5) * 255.)
+                .round()
+                .to_array()
+                .map(|c| c as u8);
+            [x, y, z, u8::MAX]
+        },
+    );
+    write_texture(
+        &texture("roughness"),
+        TextureFormat::Rgba8Unorm,
+        &texels,
+        |texel| {
+            let roughness = material.perceptual_roughness + grain(texel) * LATEWOOD_ROUGHENING;
+            let roughness = (roughness.clamp(0., 1.) * 255.).round() as u8;
+            [roughness, roughness, roughness, u8::MAX]
+        },
+    );
+
+    let [r, g, b, _] = material.base_color.to_linear().to_f32_array();
+    let mtl = format!(
+        "# {name}\n# Normal map in object space\nnewmtl staff\nKd {r:?} {g:?} {b:?}\nPr {:?}\nPm {:?}\n\
+         map_Kd {}\nmap_Pr {}\nnorm {}\n",
+        material.perceptual_roughness,
+        material.metallic,
+        texture("albedo"),
+        texture("roughness"),
+        texture("normal"),
+    );
+    let mtl_path = obj_path.with_extension("mtl");
+    write_export(&mtl_path, mtl);
+    mtl_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Writes one [`BAKE_SIZE`] texture to the export directory, colouring each texel with `color`.
+fn write_texture(
+    file_name: &str,
+    format: TextureFormat,
+    texels: &[Option<AtlasTexel>],
+    color: impl Fn(Option<&AtlasTexel>) -> [u8; 4],
+) {
+    let path = PathBuf::from(EXPORT_DIR).join(file_name);
+    let image = Image::new(
+        Extent3d {
+            width: BAKE_SIZE,
+            height: BAKE_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        texels
+            .iter()
+            .flat_map(|texel| color(texel.as_ref()))
+            .collect(),
+        format,
+        RenderAssetUsages::default(),
+    );
+    let saved = fs::create_dir_all(EXPORT_DIR)
+        .map_err(|error| error.to_string())
+        .and_then(|_| image.try_into_dynamic().map_err(|error| error.to_string()))
+        // OBJ's v runs up from the bottom of the image, the atlas's down from the top
+        .and_then(|image| image.flipv().save(&path).map_err(|error| error.to_string()));
+    match saved {
+        Ok(()) => info!("Baked texture to {}", path.display()),
+        Err(error) => error!("Failed to bake texture to {}: {error}", path.display()),
+    }
+}
+
+fn write_export(path: &Path, contents: String) {
+    match fs::create_dir_all(EXPORT_DIR).and_then(|_| fs::write(path, contents)) {
+        Ok(()) => info!("Exported staff to {}", path.display()),
+        Err(error) => error!("Failed to export staff to {}: {error}", path.display()),
+    }
+}
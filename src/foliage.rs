@@ -0,0 +1,305 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::color::palettes::css;
+use bevy::light::NotShadowCaster;
+use bevy::mesh::MeshTag;
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use staff_gen::dripstone::DripstoneConfig;
+use staff_gen::formation::FormationConfig;
+use staff_gen::scatter::{PoissonDisk, ScatterArea, ScatterKind};
+
+use crate::environment::FLOOR_HEIGHT;
+use crate::formation::{FormationAssets, spawn_formation};
+use crate::generation::IndexOptimization;
+use crate::grass_material::{GrassExtension, GrassMaterial};
+use crate::wind::Wind;
+
+const GRASS_PATCH_RADIUS: f32 = 1.2;
+const GRASS_PATCH_CENTER: Vec2 = vec2(3.5, -1.5);
+/// Rocks are strewn a little wider than the grass grows
+const ROCK_FIELD_RADIUS: f32 = 2.;
+/// Crystal formations and dripstones spread out furthest, since they're the biggest
+const FORMATION_FIELD_RADIUS: f32 = 3.;
+/// The ceiling reaches a little past the outermost stalactites
+const CEILING_RADIUS: f32 = FORMATION_FIELD_RADIUS + 1.;
+/// Longest a stalactite hangs, relative to the ceiling height, so it stays clear of the floor
+const STALACTITE_REACH: f32 = 0.4;
+
+/// One blade of grass and its unswayed rotation. Every blade shares a mesh and a
+/// [`GrassMaterial`], so the whole patch is drawn as instances, and its [`MeshTag`] sets how it
+/// sways in the wind.
+#[derive(Component, Debug)]
+pub struct GrassBlade {
+    pub rest: Quat,
+}
+
+/// How far apart crystal formations, dripstones, rocks and grass blades keep and how densely
+/// they're scattered around the grass patch, or `None` to leave them out. Bigger props are placed
+/// first and grass grows around them. Changing it scatters them again.
+#[derive(Resource, Reflect, Clone, Debug)]
+#[reflect(Resource)]
+pub struct ScatterSettings {
+    pub seed: u64,
+    pub formations: Option<ScatterKind>,
+    /// Stalagmites on the floor, and stalactites hanging from the ceiling if there is one
+    pub dripstones: Option<ScatterKind>,
+    /// Height of a cave ceiling over the patch, or `None` for open sky
+    pub ceiling: Option<f32>,
+    pub rocks: Option<ScatterKind>,
+    pub grass: Option<ScatterKind>,
+}
+
+impl Default for ScatterSettings {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            formations: None,
+            dripstones: None,
+            ceiling: None,
+            rocks: Some(ScatterKind {
+                radius: 0.18,
+                density: 0.6,
+            }),
+            grass: Some(ScatterKind {
+                radius: 0.035,
+                density: 55.,
+            }),
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+struct ScatterPatch;
+
+#[derive(Resource, Debug)]
+struct FoliageAssets {
+    blade: Handle<Mesh>,
+    grass: Handle<GrassMaterial>,
+    rock: Handle<Mesh>,
+    stone: Handle<StandardMaterial>,
+}
+
+pub struct FoliagePlugin;
+
+impl Plugin for FoliagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<GrassMaterial>::default())
+            .register_type::<ScatterSettings>()
+            .init_resource::<ScatterSettings>()
+            .add_systems(Startup, setup_foliage_assets)
+            .add_systems(
+                Update,
+                scatter_grass_patch.run_if(resource_changed::<ScatterSettings>),
+            );
+    }
+}
+
+/// A single blade of grass, rooted at the origin.
+pub fn grass_blade_mesh() -> Mesh {
+    Triangle3d::new(
+        vec3(-0.015, 0., 0.),
+        vec3(0.015, 0., 0.),
+        vec3(0., 0.25, 0.),
+    )
+    .into()
+}
+
+pub fn grass_material(wind: &Wind) -> GrassMaterial {
+    GrassMaterial {
+        base: StandardMaterial {
+            base_color: Color::from(css::YELLOW_GREEN),
+            double_sided: true,
+            cull_mode: None,
+            ..default()
+        },
+        extension: GrassExtension { wind: wind.into() },
+    }
+}
+
+fn setup_foliage_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut grass_materials: ResMut<Assets<GrassMaterial>>,
+    wind: Res<Wind>,
+) {
+    let blade = meshes.add(grass_blade_mesh());
+    let grass = grass_materials.add(grass_material(&wind));
+    // A coarse icosphere with flat normals reads as a faceted stone
+    let rock = Sphere::new(1.)
+        .mesh()
+        .ico(1)
+        .expect("subdivision 1 is within the icosphere limit")
+        .with_duplicated_vertices()
+        .with_computed_flat_normals();
+    let stone = materials.add(StandardMaterial {
+        base_color: Color::from(css::DIM_GRAY),
+        perceptual_roughness: 0.9,
+        ..default()
+    });
+    commands.insert_resource(FoliageAssets {
+        blade,
+        grass,
+        rock: meshes.add(rock),
+        stone,
+    });
+}
+
+fn scatter_grass_patch(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    settings: Res<ScatterSettings>,
+    assets: Res<FoliageAssets>,
+    formation_assets: Res<FormationAssets>,
+    optimization: Res<IndexOptimization>,
+    patches: Query<Entity, With<ScatterPatch>>,
+) {
+    for patch in &patches {
+        commands.entity(patch).despawn();
+    }
+    let patch = commands
+        .spawn((
+            Name::new("GrassPatch"),
+            ScatterPatch,
+            Transform::from_xyz(
+                GRASS_PATCH_CENTER.x,
+                FLOOR_HEIGHT / 2.,
+                GRASS_PATCH_CENTER.y,
+            ),
+            Visibility::default(),
+        ))
+        .id();
+
+    let mut sampler = PoissonDisk::new(settings.seed);
+    let mut rand = ChaCha8Rng::seed_from_u64(settings.seed);
+    let formations = settings.formations.map_or_else(Vec::new, |formations| {
+        let area = ScatterArea::Disc {
+            center: Vec2::ZERO,
+            radius: FORMATION_FIELD_RADIUS,
+        };
+        sampler
+            .scatter(formations, area)
+            .into_iter()
+            .map(|spot| (spot, formations.radius))
+            .collect()
+    });
+    for (spot, radius) in formations {
+        // The longest crystals reach about twice the rock's radius out of it
+        let config = FormationConfig {
+            seed: rand.random(),
+            rock_radius: radius * rand.random_range(0.35..0.5),
+            ..default()
+        };
+        let formation = spawn_formation(
+            &mut commands,
+            &mut meshes,
+            &formation_assets,
+            &optimization,
+            config,
+            Transform::from_xyz(spot.x, 0., spot.y),
+        );
+        commands.entity(formation).insert(ChildOf(patch));
+    }
+
+    let dripstone_field = ScatterArea::Disc {
+        center: Vec2::ZERO,
+        radius: FORMATION_FIELD_RADIUS,
+    };
+    if let Some(dripstones) = settings.dripstones {
+        for spot in sampler.scatter(dripstones, dripstone_field) {
+            let config = random_dripstone(&mut rand, dripstones, f32::INFINITY);
+            commands.spawn((
+                Name::new("Stalagmite"),
+                Mesh3d(meshes.add(optimization.optimized(config.generate_mesh()))),
+                MeshMaterial3d(assets.stone.clone()),
+                Transform::from_xyz(spot.x, 0., spot.y),
+                ChildOf(patch),
+            ));
+        }
+    }
+    if let Some(height) = settings.ceiling {
+        commands.spawn((
+            Name::new("Ceiling"),
+            Mesh3d(meshes.add(Circle::new(CEILING_RADIUS))),
+            MeshMaterial3d(assets.stone.clone()),
+            // Facing down, and letting the sun through so there's still something to see by
+            Transform::from_xyz(0., height, 0.).with_rotation(Quat::from_rotation_x(PI / 2.)),
+            NotShadowCaster,
+            ChildOf(patch),
+        ));
+    }
+    if let (Some(height), Some(dripstones)) = (settings.ceiling, settings.dripstones) {
+        // Nothing else hangs from the ceiling to keep clear of
+        let mut ceiling_sampler = PoissonDisk::new(settings.seed.wrapping_add(1));
+        for spot in ceiling_sampler.scatter(dripstones, dripstone_field) {
+            let config = random_dripstone(&mut rand, dripstones, height * STALACTITE_REACH);
+            commands.spawn((
+                Name::new("Stalactite"),
+                Mesh3d(meshes.add(optimization.optimized(config.generate_mesh()))),
+                MeshMaterial3d(assets.stone.clone()),
+                Transform::from_xyz(spot.x, height, spot.y)
+                    .with_rotation(Quat::from_rotation_x(PI)),
+                ChildOf(patch),
+            ));
+        }
+    }
+
+    let rocks = settings.rocks.map_or_else(Vec::new, |rocks| {
+        let area = ScatterArea::Disc {
+            center: Vec2::ZERO,
+            radius: ROCK_FIELD_RADIUS,
+        };
+        sampler
+            .scatter(rocks, area)
+            .into_iter()
+            .map(|spot| (spot, rocks.radius))
+            .collect()
+    });
+    for (spot, radius) in rocks {
+        // Squashed and turned so no two look alike, and half sunk into the floor
+        let size = radius * rand.random_range(0.6..1.);
+        let scale = size * vec3(1., rand.random_range(0.4..0.8), rand.random_range(0.7..1.));
+        commands.spawn((
+            Name::new("Rock"),
+            Mesh3d(assets.rock.clone()),
+            MeshMaterial3d(assets.stone.clone()),
+            Transform::from_xyz(spot.x, 0., spot.y)
+                .with_rotation(Quat::from_rotation_y(rand.random_range(0. ..TAU)))
+                .with_scale(scale),
+            ChildOf(patch),
+        ));
+    }
+
+    let blades = settings.grass.map_or_else(Vec::new, |grass| {
+        let area = ScatterArea::Disc {
+            center: Vec2::ZERO,
+            radius: GRASS_PATCH_RADIUS,
+        };
+        sampler.scatter(grass, area)
+    });
+    for spot in blades {
+        let rest = Quat::from_rotation_y(rand.random_range(0. ..TAU));
+        commands.spawn((
+            Name::new("GrassBlade"),
+            GrassBlade { rest },
+            Mesh3d(assets.blade.clone()),
+            MeshMaterial3d(assets.grass.clone()),
+            MeshTag(rand.random()),
+            Transform::from_xyz(spot.x, 0., spot.y).with_rotation(rest),
+            ChildOf(patch),
+        ));
+    }
+}
+
+/// A dripstone about as wide as `kind` keeps apart, and no longer than `max_length`.
+fn random_dripstone(rand: &mut impl Rng, kind: ScatterKind, max_length: f32) -> DripstoneConfig {
+    let radius = kind.radius * rand.random_range(0.5..0.9);
+    DripstoneConfig {
+        seed: rand.random(),
+        length: (radius * rand.random_range(4. ..9.)).min(max_length),
+        radius,
+        ..default()
+    }
+}
@@ -0,0 +1,115 @@
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use staff_gen::formation::FormationConfig;
+
+use crate::environment::FLOOR_HEIGHT;
+use crate::generation::IndexOptimization;
+
+/// Where the standalone geode sits, on the floor
+const GEODE_POSITION: Vec2 = vec2(-3., -2.);
+
+/// The mesh holding a formation's crystals, a child of the rock.
+#[derive(Component, Debug)]
+pub struct FormationCrystals;
+
+#[derive(Resource, Debug)]
+pub struct FormationAssets {
+    rock: Handle<StandardMaterial>,
+    crystal: Handle<StandardMaterial>,
+}
+
+/// Crystal formations: a geode prop in the studio, and the ones the cave preset scatters.
+/// Editing a formation's [`FormationConfig`] grows it again.
+pub struct FormationPlugin;
+
+impl Plugin for FormationPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<FormationConfig>()
+            .add_systems(Startup, (setup_formation_assets, spawn_geode).chain())
+            .add_systems(Update, rebuild_changed_formations);
+    }
+}
+
+fn setup_formation_assets(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+    commands.insert_resource(FormationAssets {
+        rock: materials.add(StandardMaterial {
+            base_color: Color::from(css::DIM_GRAY),
+            perceptual_roughness: 0.9,
+            ..default()
+        }),
+        // A faint glow keeps them showing in a dark cave
+        crystal: materials.add(StandardMaterial {
+            base_color: Color::from(css::MEDIUM_PURPLE),
+            emissive: LinearRgba::from(css::MEDIUM_PURPLE) * 0.3,
+            perceptual_roughness: 0.1,
+            ..default()
+        }),
+    });
+}
+
+fn spawn_geode(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    assets: Res<FormationAssets>,
+    optimization: Res<IndexOptimization>,
+) {
+    let geode = spawn_formation(
+        &mut commands,
+        &mut meshes,
+        &assets,
+        &optimization,
+        FormationConfig::default(),
+        Transform::from_xyz(GEODE_POSITION.x, FLOOR_HEIGHT / 2., GEODE_POSITION.y),
+    );
+    commands.entity(geode).insert(Name::new("Geode"));
+}
+
+/// Spawns a rock with its crystals as a child, resting on `transform`'s translation.
+pub fn spawn_formation(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    assets: &FormationAssets,
+    optimization: &IndexOptimization,
+    config: FormationConfig,
+    transform: Transform,
+) -> Entity {
+    let crystals = commands
+        .spawn((
+            Name::new("FormationCrystals"),
+            FormationCrystals,
+            Mesh3d(meshes.add(optimization.optimized(config.generate_crystals_mesh()))),
+            MeshMaterial3d(assets.crystal.clone()),
+        ))
+        .id();
+    commands
+        .spawn((
+            Name::new("Formation"),
+            Mesh3d(meshes.add(optimization.optimized(config.generate_rock_mesh()))),
+            MeshMaterial3d(assets.rock.clone()),
+            transform,
+            config,
+        ))
+        .add_child(crystals)
+        .id()
+}
+
+fn rebuild_changed_formations(
+    mut meshes: ResMut<Assets<Mesh>>,
+    formations: Query<(Ref<FormationConfig>, &Mesh3d, &Children)>,
+    crystals: Query<&Mesh3d, With<FormationCrystals>>,
+    optimization: Res<IndexOptimization>,
+) {
+    for (config, rock, children) in &formations {
+        if !config.is_changed() || config.is_added() {
+            continue;
+        }
+        if let Some(mesh) = meshes.get_mut(&rock.0) {
+            *mesh = optimization.optimized(config.generate_rock_mesh());
+        }
+        for crystals in crystals.iter_many(children) {
+            if let Some(mesh) = meshes.get_mut(&crystals.0) {
+                *mesh = optimization.optimized(config.generate_crystals_mesh());
+            }
+        }
+    }
+}
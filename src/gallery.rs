@@ -0,0 +1,269 @@
+use bevy::color::palettes::css;
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, futures_lite::future};
+use staff_gen::scatter::{PoissonDisk, ScatterArea, ScatterKind};
+use staff_gen::staff::StaffConfig;
+use staff_gen::stats::StaffStats;
+
+use crate::environment::FLOOR_HEIGHT;
+use crate::generation::{GeneratedObject, GeneratorKind, MeshGenMessages, MeshGenStats};
+use crate::labels::{StaffName, spawn_world_label};
+use crate::pedestal::{PedestalDisplay, PedestalMaterial};
+use crate::staff::label_offset;
+use crate::state::AppState;
+use crate::units::UnitsConfig;
+
+/// Room each gallery staff keeps around itself, and how many stand per square unit
+const GALLERY_SCATTER: ScatterKind = ScatterKind {
+    radius: 0.22,
+    density: 2.,
+};
+const GALLERY_WIDTH: f32 = 4.8;
+/// Middle of the front edge of the gallery, which grows back from there with more staffs
+const GALLERY_FRONT: Vec2 = vec2(0., -4.);
+
+/// Generates `count` staff variants of `base`, seeded `first_seed`, `first_seed + 1`, ...
+/// Generation is spread across the async compute task pool and replaces any previous gallery.
+/// The staffs are scattered with seed `first_seed` so they never stand too close together.
+#[derive(Message, Debug, Clone)]
+pub struct BatchGenerateRequest {
+    pub base: StaffConfig,
+    pub count: u32,
+    pub first_seed: u64,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct BatchProgress {
+    pub total: u32,
+    pub completed: u32,
+    started: Option<f64>,
+}
+
+impl BatchProgress {
+    pub fn is_finished(&self) -> bool {
+        self.completed == self.total
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct GalleryStaff {
+    pub config: StaffConfig,
+}
+
+#[derive(Component)]
+struct GenerateStaffTask(Task<(Mesh, MeshGenStats, StaffStats)>);
+
+#[derive(Resource, Debug)]
+struct GalleryMaterial(Handle<StandardMaterial>);
+
+#[derive(Resource, Debug, Default)]
+struct NextGallerySeed(u64);
+
+pub struct GalleryPlugin;
+
+impl Plugin for GalleryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<BatchGenerateRequest>()
+            .init_resource::<BatchProgress>()
+            .init_resource::<NextGallerySeed>()
+            .add_systems(Startup, setup_gallery_material)
+            .add_systems(OnEnter(AppState::Gallery), request_batch)
+            .add_systems(OnExit(AppState::Gallery), despawn_gallery)
+            .add_systems(
+                Update,
+                (
+                    request_batch_on_key.run_if(in_state(AppState::Gallery)),
+                    start_batch_generation,
+                    complete_generation_tasks,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn setup_gallery_material(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+    commands.insert_resource(GalleryMaterial(
+        materials.add(Color::from(css::SADDLE_BROWN)),
+    ));
+}
+
+/// Generates a gallery of 32 staffs with new seeds.
+fn request_batch(
+    mut requests: MessageWriter<BatchGenerateRequest>,
+    mut next_seed: ResMut<NextGallerySeed>,
+    units: Res<UnitsConfig>,
+) {
+    let count = 32;
+    requests.write(BatchGenerateRequest {
+        base: units.staff_defaults(),
+        count,
+        first_seed: next_seed.0,
+    });
+    next_seed.0 += count as u64;
+}
+
+/// B regenerates the gallery while it's open.
+fn request_batch_on_key(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    requests: MessageWriter<BatchGenerateRequest>,
+    next_seed: ResMut<NextGallerySeed>,
+    units: Res<UnitsConfig>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyB) {
+        request_batch(requests, next_seed, units);
+    }
+}
+
+fn despawn_gallery(mut commands: Commands, gallery: Query<Entity, With<GalleryStaff>>) {
+    for entity in &gallery {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_batch_generation(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut requests: MessageReader<BatchGenerateRequest>,
+    mut progress: ResMut<BatchProgress>,
+    gallery: Query<Entity, With<GalleryStaff>>,
+    time: Res<Time<Real>>,
+    units: Res<UnitsConfig>,
+    display: Res<PedestalDisplay>,
+    pedestal_material: Res<PedestalMaterial>,
+    mut mesh_gen: MeshGenMessages,
+) {
+    let Some(request) = requests.read().last() else {
+        return;
+    };
+
+    for entity in &gallery {
+        commands.entity(entity).despawn();
+    }
+
+    let depth = request.count as f32 / (GALLERY_SCATTER.density * GALLERY_WIDTH);
+    let area = ScatterArea::Rect {
+        min: GALLERY_FRONT - vec2(GALLERY_WIDTH / 2., depth),
+        max: GALLERY_FRONT + vec2(GALLERY_WIDTH / 2., 0.),
+    };
+    let spots = PoissonDisk::new(request.first_seed).scatter(GALLERY_SCATTER, area);
+    if spots.len() < request.count as usize {
+        warn!(
+            "Only found room for {} of {} gallery staffs",
+            spots.len(),
+            request.count
+        );
+    }
+
+    let count = spots.len() as u32;
+    let pedestal_mesh = display
+        .enabled
+        .then(|| meshes.add(display.pedestal.generate_mesh()));
+    let task_pool = AsyncComputeTaskPool::get();
+    for (i, spot) in spots.into_iter().enumerate() {
+        let config = StaffConfig {
+            seed: request.first_seed + i as u64,
+            ..request.base.clone()
+        };
+        let pedestal = display.under(&config);
+        let translation = match pedestal {
+            Some(pedestal) => {
+                vec3(
+                    spot.x,
+                    FLOOR_HEIGHT / 2. + display.pedestal.height / 2.,
+                    spot.y,
+                ) - pedestal.translation
+            }
+            None => vec3(spot.x, config.height / 2. + FLOOR_HEIGHT / 2. + 0.5, spot.y),
+        };
+
+        let task_config = config.clone();
+        let style = mesh_gen.style().clone();
+        let optimization = mesh_gen.optimization().clone();
+        let meters_per_unit = units.meters_per_unit;
+        let task = task_pool.spawn(async move {
+            // On the task pool's thread, so batch generation shows up as parallel tracks
+            let _span = info_span!("gallery_staff", seed = task_config.seed).entered();
+            let start = Instant::now();
+            let mut mesh = style.apply(style.staff(&task_config).generate_mesh());
+            let acmr = optimization.apply(&mut mesh);
+            let stats = MeshGenStats {
+                acmr,
+                ..MeshGenStats::new(GeneratorKind::Staff, &mesh, start.elapsed())
+            };
+            let staff_stats = StaffStats::new(&task_config, &mesh, meters_per_unit);
+            (mesh, stats, staff_stats)
+        });
+        let sockets = config.sockets();
+        let offset = label_offset(&sockets);
+        let entity = commands
+            .spawn((
+                Name::new(format!("GalleryStaff {}", config.seed)),
+                StaffName::default(),
+                sockets,
+                GalleryStaff { config },
+                GeneratedObject(GeneratorKind::Staff),
+                GenerateStaffTask(task),
+                Transform::from_translation(translation),
+                Visibility::default(),
+            ))
+            .id();
+        if let (Some(pedestal), Some(mesh)) = (pedestal, &pedestal_mesh) {
+            commands.spawn((
+                Name::new("Pedestal"),
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(pedestal_material.0.clone()),
+                pedestal,
+                ChildOf(entity),
+            ));
+        }
+        spawn_world_label(&mut commands, entity, offset, 10.);
+        mesh_gen.started(entity, GeneratorKind::Staff);
+    }
+
+    *progress = BatchProgress {
+        total: count,
+        completed: 0,
+        started: Some(time.elapsed_secs_f64()),
+    };
+    info!("Generating {count} staffs");
+}
+
+fn complete_generation_tasks(
+    mut commands: Commands,
+    mut tasks: Query<(Entity, &mut GenerateStaffTask)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    material: Res<GalleryMaterial>,
+    mut progress: ResMut<BatchProgress>,
+    time: Res<Time<Real>>,
+    mut mesh_gen: MeshGenMessages,
+) {
+    for (entity, mut task) in &mut tasks {
+        let Some((mesh, stats, staff_stats)) = block_on(future::poll_once(&mut task.0)) else {
+            continue;
+        };
+        mesh_gen.completed(entity, stats);
+        let _span = info_span!("upload").entered();
+        commands
+            .entity(entity)
+            .remove::<GenerateStaffTask>()
+            .insert((
+                Mesh3d(meshes.add(mesh)),
+                MeshMaterial3d(material.0.clone()),
+                staff_stats,
+            ));
+
+        progress.completed += 1;
+        info!("Generated {}/{} staffs", progress.completed, progress.total);
+        if progress.is_finished()
+            && let Some(started) = progress.started.take()
+        {
+            info!(
+                "Batch of {} staffs took {:.1}ms",
+                progress.total,
+                (time.elapsed_secs_f64() - started) * 1000.
+            );
+        }
+    }
+}
@@ -0,0 +1,592 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::color::palettes::css;
+use bevy::ecs::system::SystemParam;
+use bevy::mesh::PrimitiveTopology;
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+
+use staff_gen::cone::ConeConfig;
+use staff_gen::crystal::CrystalConfig;
+use staff_gen::cylinder::CylinderConfig;
+use staff_gen::error::GenError;
+use staff_gen::head::{HeadRegistry, HeadStyle};
+use staff_gen::mesh_util::{acmr, to_triangle_strip};
+use staff_gen::pool::{BufferPool, MeshBuffers};
+use staff_gen::repair::check_watertight;
+use staff_gen::sockets::Sockets;
+use staff_gen::staff::StaffConfig;
+use staff_gen::stats::StaffStats;
+use staff_gen::style::StyleConfig;
+use staff_gen::wrapping::WrappingConfig;
+
+use crate::crystal::{GenerateCrystal, handle_generate_crystal};
+use crate::locale::Locale;
+use crate::rebuild::Rebuild;
+use crate::staff::{GenerateStaff, handle_generate_staff};
+use crate::units::UnitsConfig;
+
+const TOAST_LIFETIME: f32 = 5.;
+const TOAST_FONT_SIZE: f32 = 12.;
+const MAX_TOASTS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeneratorKind {
+    Cube,
+    Cone,
+    Cylinder,
+    Crystal,
+    Staff,
+    /// A part of a [`StaffAssembly`](staff_gen::assembly::StaffAssembly)
+    Assembly,
+    /// A cord wound around a staff
+    Wrapping,
+}
+
+impl fmt::Display for GeneratorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl GeneratorKind {
+    /// The kind's name in the UI's language.
+    pub fn localized(self, locale: &Locale) -> String {
+        locale.text(&format!("kind.{self}")).to_string()
+    }
+}
+
+/// Marks entities spawned by a generator, so they can be cleaned up together.
+/// See [`CleanupRequest`](crate::cleanup::CleanupRequest).
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeneratedObject(pub GeneratorKind);
+
+/// Whether `entity` is a generated object or one of its parts, like a staff's head.
+pub fn is_generated(
+    entity: Entity,
+    generated: &Query<(), With<GeneratedObject>>,
+    parents: &Query<&ChildOf>,
+) -> bool {
+    generated.contains(entity)
+        || parents
+            .iter_ancestors(entity)
+            .any(|ancestor| generated.contains(ancestor))
+}
+
+#[derive(Debug, Clone)]
+pub struct MeshGenStats {
+    pub kind: GeneratorKind,
+    pub vertices: usize,
+    pub triangles: usize,
+    pub duration: Duration,
+    /// Average cache misses per triangle before and after [`IndexOptimization`] reordered the
+    /// mesh, see [`acmr`]
+    pub acmr: Option<(f32, f32)>,
+}
+
+impl MeshGenStats {
+    pub fn new(kind: GeneratorKind, mesh: &Mesh, duration: Duration) -> Self {
+        let indices = mesh
+            .indices()
+            .map_or(mesh.count_vertices(), |indices| indices.len());
+        Self {
+            kind,
+            vertices: mesh.count_vertices(),
+            triangles: indices / 3,
+            duration,
+            acmr: None,
+        }
+    }
+}
+
+impl fmt::Display for MeshGenStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} vertices, {} triangles in {:.2}ms",
+            self.kind,
+            self.vertices,
+            self.triangles,
+            self.duration.as_secs_f64() * 1000.
+        )?;
+        if let Some((before, after)) = self.acmr {
+            write!(f, ", ACMR {before:.2} -> {after:.2}")?;
+        }
+        Ok(())
+    }
+}
+
+/// How the indices of generated meshes are rearranged once they're built.
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct IndexOptimization {
+    /// Reorders triangles for the GPU's vertex cache, before upload and export
+    pub vertex_cache: bool,
+    /// Writes glTF as triangle strips. Meshes in the scene and OBJ exports stay triangle lists,
+    /// which picking and the mesh tools need and OBJ has no other way to store.
+    pub triangle_strips: bool,
+    /// Uploads meshes with fewer than 65535 vertices with 16 bit indices, see
+    /// [`compact_indices`](staff_gen::mesh_util::compact_indices)
+    pub u16_indices: bool,
+}
+
+impl Default for IndexOptimization {
+    fn default() -> Self {
+        Self {
+            vertex_cache: true,
+            triangle_strips: false,
+            u16_indices: true,
+        }
+    }
+}
+
+impl IndexOptimization {
+    /// Readies a generated `mesh` for upload, giving its ACMR before and after reordering.
+    pub fn apply(&self, mesh: &mut Mesh) -> Option<(f32, f32)> {
+        self.apply_pooled(mesh, &mut BufferPool::default())
+    }
+
+    /// [`Self::apply`] with the new indices built in vectors from `pool`, which keeps the ones
+    /// they replace for the next mesh.
+    pub fn apply_pooled(&self, mesh: &mut Mesh, pool: &mut BufferPool) -> Option<(f32, f32)> {
+        let acmr = self.reorder_pooled(mesh, pool);
+        if self.u16_indices {
+            pool.compact_indices(mesh);
+        }
+        acmr
+    }
+
+    /// [`Self::apply`] for meshes added to the scene straight away.
+    pub fn optimized(&self, mut mesh: Mesh) -> Mesh {
+        self.apply(&mut mesh);
+        mesh
+    }
+
+    /// Reorders `mesh` if enabled, giving its ACMR before and after.
+    pub fn reorder(&self, mesh: &mut Mesh) -> Option<(f32, f32)> {
+        self.reorder_pooled(mesh, &mut BufferPool::default())
+    }
+
+    fn reorder_pooled(&self, mesh: &mut Mesh, pool: &mut BufferPool) -> Option<(f32, f32)> {
+        if !self.vertex_cache || mesh.indices().is_none() {
+            return None;
+        }
+        let _span = info_span!("optimize_vertex_cache").entered();
+        let before = acmr(mesh);
+        pool.optimize_vertex_cache(mesh);
+        Some((before, acmr(mesh)))
+    }
+
+    /// `mesh` as it should be written to glTF.
+    pub fn for_gltf(&self, mut mesh: Mesh) -> Mesh {
+        self.reorder(&mut mesh);
+        if self.triangle_strips {
+            to_triangle_strip(&mesh)
+        } else {
+            mesh
+        }
+    }
+}
+
+/// Sent when a generator starts building a mesh for `entity`.
+#[derive(Message, Debug, Clone)]
+pub struct MeshGenStarted {
+    pub entity: Entity,
+    pub kind: GeneratorKind,
+}
+
+/// Sent when a generator has finished building the mesh for `entity`.
+#[derive(Message, Debug, Clone)]
+pub struct MeshGenCompleted {
+    pub entity: Entity,
+    pub stats: MeshGenStats,
+}
+
+/// Sent instead of [`MeshGenCompleted`] when `entity`'s config can't be built.
+#[derive(Message, Debug, Clone)]
+pub struct MeshGenFailed {
+    pub entity: Entity,
+    pub kind: GeneratorKind,
+    pub error: GenError,
+}
+
+/// Writers for the generation messages, used by every generator system.
+#[derive(SystemParam)]
+pub struct MeshGenMessages<'w> {
+    started: MessageWriter<'w, MeshGenStarted>,
+    completed: MessageWriter<'w, MeshGenCompleted>,
+    failed: MessageWriter<'w, MeshGenFailed>,
+    style: Res<'w, StyleConfig>,
+    optimization: Res<'w, IndexOptimization>,
+    pool: ResMut<'w, BufferPool>,
+}
+
+impl MeshGenMessages<'_> {
+    /// Runs `generate` for `entity`, sending the started and completed messages around it.
+    /// The mesh is restyled by [`StyleConfig`] and reordered by [`IndexOptimization`]. All of it
+    /// runs in a `generate` span, which profilers built with `trace_tracy` or `trace_chrome` show
+    /// with the generator's stages nested in it.
+    pub fn generate(
+        &mut self,
+        entity: Entity,
+        kind: GeneratorKind,
+        generate: impl FnOnce() -> Mesh,
+    ) -> Mesh {
+        let _span = info_span!("generate", %kind).entered();
+        self.started(entity, kind);
+        let start = Instant::now();
+        let mesh = generate();
+        self.finish(entity, kind, start, mesh, true)
+    }
+
+    /// [`Self::generate`] for generators that validate their config. A config that can't be
+    /// built is logged and sent as [`MeshGenFailed`], and gives no mesh.
+    pub fn try_generate(
+        &mut self,
+        entity: Entity,
+        kind: GeneratorKind,
+        generate: impl FnOnce() -> Result<Mesh, GenError>,
+    ) -> Option<Mesh> {
+        self.try_generate_with(entity, kind, true, generate)
+    }
+
+    /// [`Self::try_generate`] for a rebuild paced by
+    /// [`RebuildScheduler`](crate::rebuild::RebuildScheduler). Previews skip the
+    /// [`IndexOptimization`], which takes longer than they're on screen.
+    pub fn try_rebuild(
+        &mut self,
+        entity: Entity,
+        kind: GeneratorKind,
+        rebuild: Rebuild,
+        generate: impl FnOnce() -> Result<Mesh, GenError>,
+    ) -> Option<Mesh> {
+        self.try_generate_with(entity, kind, rebuild == Rebuild::Full, generate)
+    }
+
+    fn try_generate_with(
+        &mut self,
+        entity: Entity,
+        kind: GeneratorKind,
+        optimize: bool,
+        generate: impl FnOnce() -> Result<Mesh, GenError>,
+    ) -> Option<Mesh> {
+        let _span = info_span!("generate", %kind).entered();
+        self.started(entity, kind);
+        let start = Instant::now();
+        match generate() {
+            Ok(mesh) => Some(self.finish(entity, kind, start, mesh, optimize)),
+            Err(error) => {
+                error!("Can't generate the {kind}: {error}");
+                self.failed.write(MeshGenFailed {
+                    entity,
+                    kind,
+                    error,
+                });
+                None
+            }
+        }
+    }
+
+    fn finish(
+        &mut self,
+        entity: Entity,
+        kind: GeneratorKind,
+        start: Instant,
+        mut mesh: Mesh,
+        optimize: bool,
+    ) -> Mesh {
+        // Morph targets are stored per vertex, so their meshes keep the vertices they have
+        if mesh.morph_targets().is_none() {
+            let _span = info_span!("style").entered();
+            mesh = self.style.apply(mesh);
+        }
+        let acmr = if optimize {
+            self.optimization.apply_pooled(&mut mesh, &mut self.pool)
+        } else {
+            None
+        };
+        let stats = MeshGenStats {
+            acmr,
+            ..MeshGenStats::new(kind, &mesh, start.elapsed())
+        };
+        self.completed(entity, stats);
+        // Catches cap and seam regressions in generators while developing
+        if cfg!(debug_assertions) {
+            let report = check_watertight(&mesh);
+            if !report.is_watertight() {
+                warn!(
+                    "{kind} mesh has {} open edges in {} holes",
+                    report.boundary_edges.len(),
+                    report.holes.len()
+                );
+            }
+        }
+        mesh
+    }
+
+    /// The style generators should cap their resolution to, see [`StyleConfig::staff`].
+    pub fn style(&self) -> &StyleConfig {
+        &self.style
+    }
+
+    /// Buffers for generators with a `_in` variant to build in, from meshes passed to
+    /// [`Self::replace`].
+    pub fn buffers(&mut self) -> MeshBuffers {
+        self.pool.take()
+    }
+
+    /// Swaps a regenerated mesh in, keeping the old one's buffers for the next rebuild.
+    pub fn replace(&mut self, mesh: &mut Mesh, new_mesh: Mesh) {
+        self.pool.recycle(std::mem::replace(mesh, new_mesh));
+    }
+
+    /// How meshes are readied for upload, for generators that build them off the main thread.
+    pub fn optimization(&self) -> &IndexOptimization {
+        &self.optimization
+    }
+
+    pub fn started(&mut self, entity: Entity, kind: GeneratorKind) {
+        self.started.write(MeshGenStarted { entity, kind });
+    }
+
+    pub fn completed(&mut self, entity: Entity, stats: MeshGenStats) {
+        self.completed.write(MeshGenCompleted { entity, stats });
+    }
+}
+
+/// A mesh with nothing to draw, for objects spawned from a config that failed to generate.
+/// Fixing the config regenerates it.
+pub fn empty_mesh() -> Mesh {
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, Vec::<[f32; 3]>::new())
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, Vec::<[f32; 3]>::new())
+}
+
+/// The entity a [`GenerateStaff`] or [`GenerateCrystal`] request builds on: the sender's, or a
+/// new one. None when the sender despawned its entity before the request was handled.
+pub(crate) fn generation_target(commands: &mut Commands, entity: Option<Entity>) -> Option<Entity> {
+    match entity {
+        Some(entity) => commands.get_entity(entity).ok().map(|entity| entity.id()),
+        None => Some(commands.spawn_empty().id()),
+    }
+}
+
+#[derive(Debug, Clone)]
+enum RecentGeneration {
+    Completed(MeshGenStats),
+    Failed(GeneratorKind, GenError),
+}
+
+#[derive(Resource, Debug, Default)]
+struct RecentGenerations(VecDeque<(RecentGeneration, f32)>);
+
+#[derive(Component)]
+struct GenerationToast;
+
+/// Generation messages and the toast listing recent generations.
+///
+/// Gameplay code, UI and tools spawn generated objects by writing [`GenerateStaff`] or
+/// [`GenerateCrystal`], and can follow their progress through [`MeshGenStarted`] and
+/// [`MeshGenCompleted`]. Senders that need the object, to select or parent it, spawn its entity
+/// themselves and pass it in the request.
+pub struct GenerationPlugin;
+
+impl Plugin for GenerationPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ConeConfig>()
+            .register_type::<CrystalConfig>()
+            .register_type::<CylinderConfig>()
+            .register_type::<HeadStyle>()
+            .register_type::<IndexOptimization>()
+            .register_type::<StaffConfig>()
+            .register_type::<StaffStats>()
+            .register_type::<StyleConfig>()
+            .register_type::<Sockets>()
+            .register_type::<UnitsConfig>()
+            .register_type::<WrappingConfig>()
+            .init_resource::<UnitsConfig>()
+            .init_resource::<HeadRegistry>()
+            .init_resource::<IndexOptimization>()
+            .init_resource::<BufferPool>()
+            .init_resource::<StyleConfig>()
+            .add_message::<MeshGenStarted>()
+            .add_message::<MeshGenCompleted>()
+            .add_message::<MeshGenFailed>()
+            .add_message::<GenerateStaff>()
+            .add_message::<GenerateCrystal>()
+            .init_resource::<RecentGenerations>()
+            .add_systems(Startup, setup_generation_toast)
+            .add_systems(
+                Update,
+                (
+                    handle_generate_staff,
+                    handle_generate_crystal,
+                    toggle_low_poly,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    record_generations,
+                    expire_generations,
+                    update_generation_toast,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// L switches the low-poly style on and off.
+fn toggle_low_poly(keyboard_input: Res<ButtonInput<KeyCode>>, mut style: ResMut<StyleConfig>) {
+    if keyboard_input.just_pressed(KeyCode::KeyL) {
+        style.low_poly = !style.low_poly;
+    }
+}
+
+fn setup_generation_toast(mut commands: Commands) {
+    commands.spawn((
+        Name::new("GenerationToast"),
+        GenerationToast,
+        Text::default(),
+        TextFont::from_font_size(TOAST_FONT_SIZE),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.),
+            right: Val::Px(12.),
+            ..default()
+        },
+    ));
+}
+
+fn record_generations(
+    mut completed: MessageReader<MeshGenCompleted>,
+    mut failed: MessageReader<MeshGenFailed>,
+    mut recent: ResMut<RecentGenerations>,
+    time: Res<Time<Real>>,
+) {
+    let completed = completed.read().map(|generation| {
+        debug!("Generated {}", generation.stats);
+        RecentGeneration::Completed(generation.stats.clone())
+    });
+    let failed = failed
+        .read()
+        .map(|failure| RecentGeneration::Failed(failure.kind, failure.error.clone()));
+    for generation in completed.chain(failed) {
+        recent.0.push_back((generation, time.elapsed_secs()));
+        if recent.0.len() > MAX_TOASTS {
+            recent.0.pop_front();
+        }
+    }
+}
+
+fn expire_generations(mut recent: ResMut<RecentGenerations>, time: Res<Time<Real>>) {
+    let now = time.elapsed_secs();
+    let expired = recent
+        .0
+        .iter()
+        .take_while(|(_, created)| now - created > TOAST_LIFETIME)
+        .count();
+    if expired > 0 {
+        recent.0.drain(..expired);
+    }
+}
+
+/// One line per generation, failures in red.
+fn update_generation_toast(
+    mut commands: Commands,
+    recent: Res<RecentGenerations>,
+    locale: Res<Locale>,
+    toast: Single<Entity, With<GenerationToast>>,
+) {
+    if !recent.is_changed() && !locale.is_changed() {
+        return;
+    }
+    commands.entity(*toast).despawn_related::<Children>();
+    for (i, (generation, _)) in recent.0.iter().enumerate() {
+        let (line, color) = match generation {
+            RecentGeneration::Completed(stats) => (
+                locale.format(
+                    "toast.generated",
+                    &[
+                        ("kind", &stats.kind.localized(&locale)),
+                        ("vertices", &stats.vertices),
+                        ("triangles", &stats.triangles),
+                        (
+                            "ms",
+                            &format_args!("{:.2}", stats.duration.as_secs_f64() * 1000.),
+                        ),
+                    ],
+                ),
+                css::WHITE,
+            ),
+            RecentGeneration::Failed(kind, error) => (
+                locale.format(
+                    "toast.failed",
+                    &[("kind", &kind.localized(&locale)), ("error", error)],
+                ),
+                css::TOMATO,
+            ),
+        };
+        let separator = if i == 0 { "" } else { "\n" };
+        commands.spawn((
+            TextSpan::new(format!("{separator}{line}")),
+            TextFont::from_font_size(TOAST_FONT_SIZE),
+            TextColor(Color::from(color)),
+            ChildOf(*toast),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::mesh::Indices;
+
+    use super::*;
+
+    fn indices_address(mesh: &Mesh) -> *const u8 {
+        match mesh.indices() {
+            Some(Indices::U32(indices)) => indices.as_ptr().cast(),
+            Some(Indices::U16(indices)) => indices.as_ptr().cast(),
+            None => panic!("missing indices"),
+        }
+    }
+
+    #[test]
+    fn rebuilds_reuse_the_optimized_index_buffers() {
+        let mut app = App::new();
+        app.init_resource::<StyleConfig>()
+            .init_resource::<IndexOptimization>()
+            .init_resource::<BufferPool>()
+            .add_message::<MeshGenStarted>()
+            .add_message::<MeshGenCompleted>()
+            .add_message::<MeshGenFailed>();
+        let entity = app.world_mut().spawn_empty().id();
+        let rebuild = move |mut mesh_gen: MeshGenMessages, mut mesh: Local<Option<Mesh>>| {
+            let buffers = mesh_gen.buffers();
+            let new_mesh = mesh_gen
+                .try_generate(entity, GeneratorKind::Staff, || {
+                    StaffConfig::default().try_generate_mesh_in(buffers)
+                })
+                .unwrap();
+            let address = indices_address(&new_mesh);
+            match mesh.as_mut() {
+                Some(mesh) => mesh_gen.replace(mesh, new_mesh),
+                None => *mesh = Some(new_mesh),
+            }
+            address
+        };
+        let system = app.world_mut().register_system(rebuild);
+        let mut addresses = Vec::new();
+        for _ in 0..3 {
+            addresses.push(app.world_mut().run_system(system).unwrap());
+        }
+        // The second rebuild still needs a new buffer, as the first mesh is still in use
+        assert_eq!(addresses[2], addresses[0]);
+    }
+}
@@ -0,0 +1,313 @@
+//! Experimental compute shader backend for the morphed staff.
+//!
+//! Ring parameters are still picked on the CPU so the result matches the seed, but expanding
+//! them into vertices runs on the GPU. The output is read back into the staff mesh, which keeps
+//! the CPU path (and export) authoritative while allowing very high resolutions.
+
+use bevy::{
+    platform::time::Instant,
+    prelude::*,
+    render::{
+        Render, RenderApp, RenderStartup, RenderSystems,
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        gpu_readback::{Readback, ReadbackComplete},
+        render_asset::RenderAssets,
+        render_graph::{self, RenderGraph, RenderLabel},
+        render_resource::{binding_types::storage_buffer_read_only, *},
+        renderer::{RenderContext, RenderDevice},
+        storage::{GpuShaderStorageBuffer, ShaderStorageBuffer},
+    },
+};
+use staff_gen::staff::build_staff_mesh;
+
+use crate::generation::{GeneratorKind, MeshGenMessages, MeshGenStats};
+use crate::morph::{CpuStaffRebuild, StaffMorph};
+use crate::staff::{Staff, staff_translation};
+
+const SHADER_ASSET_PATH: &str = "shaders/staff_rings.wgsl";
+const WORKGROUP_SIZE: u32 = 64;
+
+#[derive(Resource, Debug)]
+pub struct GpuStaffSettings {
+    pub enabled: bool,
+    /// Resolution and segments used instead of the morphed config's while enabled
+    pub resolution: u32,
+    pub segments: u32,
+}
+
+impl Default for GpuStaffSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            resolution: 256,
+            segments: 128,
+        }
+    }
+}
+
+#[derive(ShaderType, Clone, Copy, Default, Debug)]
+struct GpuStaffParams {
+    resolution: u32,
+    num_rings: u32,
+    generation: u32,
+    _padding: u32,
+}
+
+/// The buffers for the latest GPU staff generation, shared with the render world.
+#[derive(Resource, ExtractResource, Clone, Debug)]
+struct GpuStaffJob {
+    params: Handle<ShaderStorageBuffer>,
+    rings: Handle<ShaderStorageBuffer>,
+    output: Handle<ShaderStorageBuffer>,
+    vertex_count: u32,
+    generation: u32,
+    /// Cleared once the output has been read back so the shader stops running
+    active: bool,
+}
+
+pub struct GpuStaffPlugin;
+
+impl Plugin for GpuStaffPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GpuStaffSettings>()
+            .add_plugins(ExtractResourcePlugin::<GpuStaffJob>::default())
+            .configure_sets(Update, CpuStaffRebuild.run_if(gpu_staff_disabled))
+            .add_systems(
+                Update,
+                (toggle_gpu_staff, queue_gpu_staff.after(CpuStaffRebuild)),
+            );
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_systems(RenderStartup, (init_gpu_staff_pipeline, add_gpu_staff_node))
+            .add_systems(
+                Render,
+                prepare_gpu_staff_bind_group
+                    .in_set(RenderSystems::PrepareBindGroups)
+                    .run_if(resource_exists::<GpuStaffJob>),
+            );
+    }
+}
+
+fn gpu_staff_disabled(settings: Res<GpuStaffSettings>) -> bool {
+    !settings.enabled
+}
+
+/// G switches the morphed staff between the CPU and GPU generators.
+fn toggle_gpu_staff(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<GpuStaffSettings>,
+    mut morph: ResMut<StaffMorph>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyG) {
+        settings.enabled = !settings.enabled;
+        info!("GPU staff generation enabled: {}", settings.enabled);
+        // Rebuild with whichever backend is now active
+        morph.set_changed();
+    }
+}
+
+fn queue_gpu_staff(
+    mut commands: Commands,
+    settings: Res<GpuStaffSettings>,
+    morph: Res<StaffMorph>,
+    mut buffers: ResMut<Assets<ShaderStorageBuffer>>,
+    job: Option<Res<GpuStaffJob>>,
+    staffs: Query<Entity, With<Staff>>,
+    mut mesh_gen: MeshGenMessages,
+) {
+    if !settings.enabled || !morph.is_changed() {
+        return;
+    }
+    let start = Instant::now();
+    for entity in &staffs {
+        mesh_gen.started(entity, GeneratorKind::Staff);
+    }
+
+    let mut config = morph.config();
+    config.resolution = settings.resolution;
+    config.segments = settings.segments;
+    let rings = config.generate_rings();
+    let resolution = config.resolution;
+    let vertex_count = rings.len() as u32 * (resolution + 1);
+    let generation = job.map_or(1, |job| job.generation + 1);
+
+    let params = GpuStaffParams {
+        resolution,
+        num_rings: rings.len() as u32,
+        generation,
+        _padding: 0,
+    };
+    let ring_data: Vec<Vec4> = rings
+        .iter()
+        .map(|ring| vec4(ring.radius, ring.offset.x, ring.y, ring.offset.y))
+        .collect();
+    let mut output = ShaderStorageBuffer::from(vec![Vec4::ZERO; 1 + 2 * vertex_count as usize]);
+    output.buffer_description.usage |= BufferUsages::COPY_SRC;
+    let output = buffers.add(output);
+
+    commands.insert_resource(GpuStaffJob {
+        params: buffers.add(ShaderStorageBuffer::from(params)),
+        rings: buffers.add(ShaderStorageBuffer::from(ring_data)),
+        output: output.clone(),
+        vertex_count,
+        generation,
+        active: true,
+    });
+
+    let translation = staff_translation(&config);
+    commands.spawn(Readback::buffer(output)).observe(
+        move |event: On<ReadbackComplete>,
+              mut commands: Commands,
+              mut meshes: ResMut<Assets<Mesh>>,
+              mut staffs: Query<(Entity, &Mesh3d, &mut Transform), With<Staff>>,
+              mut job: ResMut<GpuStaffJob>,
+              mut mesh_gen: MeshGenMessages| {
+            // A newer generation was queued, this one will never be dispatched
+            if job.generation != generation {
+                commands.entity(event.entity).despawn();
+                return;
+            }
+            let data: Vec<Vec4> = event.to_shader_type();
+            // The shader hasn't run yet, wait for the next readback
+            if data.first().map(|header| header.x.to_bits()) != Some(generation) {
+                return;
+            }
+            commands.entity(event.entity).despawn();
+            job.active = false;
+
+            let count = vertex_count as usize;
+            let positions = data[1..=count].iter().map(|p| p.truncate().to_array());
+            let normals = data[1 + count..].iter().map(|n| n.truncate().to_array());
+            let mesh = build_staff_mesh(&rings, resolution, positions.collect(), normals.collect());
+            // Includes the wait for the GPU and readback, not just the dispatch
+            let stats = MeshGenStats::new(GeneratorKind::Staff, &mesh, start.elapsed());
+            for (entity, mesh3d, mut transform) in &mut staffs {
+                if let Some(staff_mesh) = meshes.get_mut(&mesh3d.0) {
+                    *staff_mesh = mesh.clone();
+                }
+                mesh_gen.completed(entity, stats.clone());
+                transform.translation = translation;
+            }
+        },
+    );
+}
+
+#[derive(Resource)]
+struct GpuStaffPipeline {
+    layout: BindGroupLayout,
+    pipeline: CachedComputePipelineId,
+}
+
+#[derive(Resource)]
+struct GpuStaffBindGroup {
+    bind_group: BindGroup,
+    generation: u32,
+}
+
+fn init_gpu_staff_pipeline(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    asset_server: Res<AssetServer>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    let layout = render_device.create_bind_group_layout(
+        "gpu_staff_bind_group_layout",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::COMPUTE,
+            (
+                storage_buffer_read_only::<GpuStaffParams>(false),
+                storage_buffer_read_only::<Vec<Vec4>>(false),
+                binding_types::storage_buffer::<Vec<Vec4>>(false),
+            ),
+        ),
+    );
+    let pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+        label: Some("gpu_staff_pipeline".into()),
+        layout: vec![layout.clone()],
+        shader: asset_server.load(SHADER_ASSET_PATH),
+        ..default()
+    });
+    commands.insert_resource(GpuStaffPipeline { layout, pipeline });
+}
+
+fn prepare_gpu_staff_bind_group(
+    mut commands: Commands,
+    job: Res<GpuStaffJob>,
+    pipeline: Res<GpuStaffPipeline>,
+    render_device: Res<RenderDevice>,
+    buffers: Res<RenderAssets<GpuShaderStorageBuffer>>,
+    bind_group: Option<Res<GpuStaffBindGroup>>,
+) {
+    if bind_group.is_some_and(|bind_group| bind_group.generation == job.generation) {
+        return;
+    }
+    // The buffers may not have been uploaded yet, try again next frame
+    let (Some(params), Some(rings), Some(output)) = (
+        buffers.get(&job.params),
+        buffers.get(&job.rings),
+        buffers.get(&job.output),
+    ) else {
+        return;
+    };
+    let bind_group = render_device.create_bind_group(
+        "gpu_staff_bind_group",
+        &pipeline.layout,
+        &BindGroupEntries::sequential((
+            params.buffer.as_entire_buffer_binding(),
+            rings.buffer.as_entire_buffer_binding(),
+            output.buffer.as_entire_buffer_binding(),
+        )),
+    );
+    commands.insert_resource(GpuStaffBindGroup {
+        bind_group,
+        generation: job.generation,
+    });
+}
+
+fn add_gpu_staff_node(mut render_graph: ResMut<RenderGraph>) {
+    render_graph.add_node(GpuStaffNodeLabel, GpuStaffNode);
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct GpuStaffNodeLabel;
+
+struct GpuStaffNode;
+
+impl render_graph::Node for GpuStaffNode {
+    fn run(
+        &self,
+        _graph: &mut render_graph::RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), render_graph::NodeRunError> {
+        let (Some(job), Some(bind_group)) = (
+            world.get_resource::<GpuStaffJob>(),
+            world.get_resource::<GpuStaffBindGroup>(),
+        ) else {
+            return Ok(());
+        };
+        if !job.active || bind_group.generation != job.generation {
+            return Ok(());
+        }
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let pipeline = world.resource::<GpuStaffPipeline>();
+        let Some(compute_pipeline) = pipeline_cache.get_compute_pipeline(pipeline.pipeline) else {
+            return Ok(());
+        };
+
+        let mut pass =
+            render_context
+                .command_encoder()
+                .begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("gpu_staff_pass"),
+                    ..default()
+                });
+        pass.set_bind_group(0, &bind_group.bind_group, &[]);
+        pass.set_pipeline(compute_pipeline);
+        pass.dispatch_workgroups(job.vertex_count.div_ceil(WORKGROUP_SIZE), 1, 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+use bevy::anti_alias::{fxaa::Fxaa, taa::TemporalAntiAliasing};
+use bevy::camera::primitives::Aabb;
+use bevy::core_pipeline::tonemapping::Tonemapping;
+use bevy::math::bounding::BoundingVolume;
+use bevy::post_process::bloom::Bloom;
+use bevy::post_process::dof::{DepthOfField, DepthOfFieldMode};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actions::RegisterAction;
+use crate::camera::{CameraBookmark, MainCamera};
+use crate::close_up::CloseUpCamera;
+use crate::selection::{Selected, selected_or_staff, world_aabb};
+use crate::settings::{PersistPlugin, Persistent};
+use crate::staff::Staff;
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AntiAliasing {
+    Off,
+    #[default]
+    Msaa4,
+    Fxaa,
+    /// Not supported on WebGL2
+    Taa,
+}
+
+impl AntiAliasing {
+    fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Msaa4,
+            Self::Msaa4 => Self::Fxaa,
+            Self::Fxaa => Self::Taa,
+            Self::Taa => Self::Off,
+        }
+    }
+}
+
+/// Tonemapping operators that work without extra cargo features.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TonemappingChoice {
+    None,
+    Reinhard,
+    AcesFitted,
+    AgX,
+    #[default]
+    TonyMcMapface,
+    BlenderFilmic,
+}
+
+impl TonemappingChoice {
+    fn next(self) -> Self {
+        match self {
+            Self::None => Self::Reinhard,
+            Self::Reinhard => Self::AcesFitted,
+            Self::AcesFitted => Self::AgX,
+            Self::AgX => Self::TonyMcMapface,
+            Self::TonyMcMapface => Self::BlenderFilmic,
+            Self::BlenderFilmic => Self::None,
+        }
+    }
+
+    fn tonemapping(self) -> Tonemapping {
+        match self {
+            Self::None => Tonemapping::None,
+            Self::Reinhard => Tonemapping::Reinhard,
+            Self::AcesFitted => Tonemapping::AcesFitted,
+            Self::AgX => Tonemapping::AgX,
+            Self::TonyMcMapface => Tonemapping::TonyMcMapface,
+            Self::BlenderFilmic => Tonemapping::BlenderFilmic,
+        }
+    }
+}
+
+/// Camera post-processing and bookmarks, kept in `graphics_settings.ron` across runs.
+#[derive(Resource, Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct GraphicsSettings {
+    pub anti_aliasing: AntiAliasing,
+    pub bloom: bool,
+    pub tonemapping: TonemappingChoice,
+    /// Keeps the selected staff sharp and blurs everything else, for beauty shots.
+    /// Not supported on WebGL2, where it stays off.
+    pub depth_of_field: bool,
+    /// Lower is blurrier
+    pub aperture_f_stops: f32,
+    /// Saved camera positions, recalled with the number keys
+    pub camera_bookmarks: Vec<CameraBookmark>,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            anti_aliasing: AntiAliasing::default(),
+            bloom: true,
+            tonemapping: TonemappingChoice::default(),
+            depth_of_field: false,
+            aperture_f_stops: 1.,
+            camera_bookmarks: Vec::new(),
+        }
+    }
+}
+
+impl Persistent for GraphicsSettings {
+    const FILE_NAME: &'static str = "graphics_settings.ron";
+}
+
+impl GraphicsSettings {
+    /// The web build renders with WebGL2, which lacks what depth of field needs.
+    pub fn depth_of_field_supported() -> bool {
+        !cfg!(target_arch = "wasm32")
+    }
+}
+
+pub struct GraphicsSettingsPlugin;
+
+impl Plugin for GraphicsSettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<GraphicsSettings>()
+            .init_resource::<GraphicsSettings>()
+            .add_plugins(PersistPlugin::<GraphicsSettings>::default())
+            .register_action(
+                "Cycle anti-aliasing",
+                |mut settings: ResMut<GraphicsSettings>| {
+                    settings.anti_aliasing = settings.anti_aliasing.next();
+                },
+            )
+            .register_action("Toggle bloom", |mut settings: ResMut<GraphicsSettings>| {
+                settings.bloom = !settings.bloom;
+            })
+            .register_action(
+                "Cycle tonemapping",
+                |mut settings: ResMut<GraphicsSettings>| {
+                    settings.tonemapping = settings.tonemapping.next();
+                },
+            )
+            .register_action(
+                "Toggle depth of field",
+                |mut settings: ResMut<GraphicsSettings>| {
+                    settings.depth_of_field = !settings.depth_of_field;
+                },
+            )
+            .add_systems(
+                Update,
+                (
+                    cycle_graphics_settings,
+                    apply_graphics_settings,
+                    focus_depth_of_field,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// F2 cycles anti-aliasing, F4 cycles tonemapping and F5 toggles depth of field. Bloom is only
+/// toggled from the command palette, since F3 opens the diagnostics overlay.
+fn cycle_graphics_settings(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<GraphicsSettings>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F2) {
+        settings.anti_aliasing = settings.anti_aliasing.next();
+        info!("Anti-aliasing: {:?}", settings.anti_aliasing);
+    }
+    if keyboard_input.just_pressed(KeyCode::F4) {
+        settings.tonemapping = settings.tonemapping.next();
+        info!("Tonemapping: {:?}", settings.tonemapping);
+    }
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        settings.depth_of_field = !settings.depth_of_field;
+        if GraphicsSettings::depth_of_field_supported() {
+            info!("Depth of field: {}", settings.depth_of_field);
+        } else {
+            info!("Depth of field isn't supported on this platform");
+        }
+    }
+}
+
+type ViewportCamera = Or<(With<MainCamera>, With<CloseUpCamera>)>;
+
+/// Applies the settings to the viewport cameras. Offscreen cameras like the thumbnails' render
+/// a few frames of a distant stage, where TAA jitter and depth of field only blur them. Depth of
+/// field is also left off the close-up camera, which frames the staff's head up close.
+fn apply_graphics_settings(
+    mut commands: Commands,
+    settings: Res<GraphicsSettings>,
+    mut applied: Local<Option<GraphicsSettings>>,
+    cameras: Query<(Entity, Has<MainCamera>), ViewportCamera>,
+) {
+    if applied.as_ref() == Some(&*settings) || cameras.is_empty() {
+        return;
+    }
+    for (camera, main) in &cameras {
+        let mut camera = commands.entity(camera);
+        apply_post_processing(&mut camera, &settings);
+        if main && settings.depth_of_field && GraphicsSettings::depth_of_field_supported() {
+            camera.insert(DepthOfField {
+                mode: DepthOfFieldMode::Bokeh,
+                aperture_f_stops: settings.aperture_f_stops,
+                ..default()
+            });
+        } else {
+            camera.remove::<DepthOfField>();
+        }
+    }
+    *applied = Some(settings.clone());
+}
+
+/// Anti-aliasing, bloom and tonemapping from `settings`, for any camera that should look like
+/// the viewport, such as the turntable's.
+pub(crate) fn apply_post_processing(camera: &mut EntityCommands, settings: &GraphicsSettings) {
+    // FXAA and TAA both require MSAA to be off
+    camera.remove::<(Fxaa, TemporalAntiAliasing)>();
+    match settings.anti_aliasing {
+        AntiAliasing::Off => {
+            camera.insert(Msaa::Off);
+        }
+        AntiAliasing::Msaa4 => {
+            camera.insert(Msaa::Sample4);
+        }
+        AntiAliasing::Fxaa => {
+            camera.insert((Msaa::Off, Fxaa::default()));
+        }
+        AntiAliasing::Taa => {
+            camera.insert((Msaa::Off, TemporalAntiAliasing::default()));
+        }
+    }
+    if settings.bloom {
+        camera.insert(Bloom::NATURAL);
+    } else {
+        camera.remove::<Bloom>();
+    }
+    camera.insert(settings.tonemapping.tonemapping());
+}
+
+/// Keeps the selected object, or the viewer's staff, in focus.
+fn focus_depth_of_field(
+    mut cameras: Query<(&mut DepthOfField, &GlobalTransform), With<MainCamera>>,
+    selected: Query<Entity, With<Selected>>,
+    staffs: Query<Entity, With<Staff>>,
+    bounds: Query<(&Aabb, &GlobalTransform)>,
+    children: Query<&Children>,
+) {
+    if cameras.is_empty() {
+        return;
+    }
+    let Some(aabb) = selected_or_staff(&selected, &staffs)
+        .and_then(|entity| world_aabb(entity, &bounds, &children))
+    else {
+        return;
+    };
+    let focus = Vec3::from(aabb.center());
+    for (mut depth_of_field, transform) in &mut cameras {
+        depth_of_field.focal_distance = transform.translation().distance(focus);
+    }
+}
@@ -0,0 +1,52 @@
+use bevy::pbr::{ExtendedMaterial, MaterialExtension};
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, ShaderType};
+use bevy::shader::ShaderRef;
+
+use crate::wind::Wind;
+
+const SHADER_ASSET_PATH: &str = "shaders/grass.wgsl";
+
+/// Grass blades swayed by the wind on the GPU. Every blade shares one mesh and one of these, so
+/// Bevy draws a whole patch as instances of a single draw, and each blade's
+/// [`MeshTag`](bevy::mesh::MeshTag) varies its stiffness and flutter.
+pub type GrassMaterial = ExtendedMaterial<StandardMaterial, GrassExtension>;
+
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct GrassExtension {
+    #[uniform(100)]
+    pub wind: GrassWind,
+}
+
+impl MaterialExtension for GrassExtension {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_ASSET_PATH.into()
+    }
+
+    fn prepass_vertex_shader() -> ShaderRef {
+        SHADER_ASSET_PATH.into()
+    }
+}
+
+/// The [`Wind`] as the grass shader sees it, which works out the gusts from the shader's time.
+#[derive(ShaderType, Reflect, Clone, Debug, Default, PartialEq)]
+pub struct GrassWind {
+    /// Normalized, so the shader doesn't have to
+    pub direction: Vec2,
+    pub strength: f32,
+    pub gust_strength: f32,
+    pub gust_frequency: f32,
+    pub gust_length: f32,
+}
+
+impl From<&Wind> for GrassWind {
+    fn from(wind: &Wind) -> Self {
+        Self {
+            direction: wind.direction.normalize_or_zero(),
+            strength: wind.strength,
+            gust_strength: wind.gust_strength,
+            gust_frequency: wind.gust_frequency,
+            gust_length: wind.gust_length,
+        }
+    }
+}
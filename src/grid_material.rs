@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, ShaderType};
+use bevy::shader::ShaderRef;
+
+const SHADER_ASSET_PATH: &str = "shaders/grid.wgsl";
+
+/// Unlit world-space grid with lines that fade out away from the camera.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct GridMaterial {
+    #[uniform(0)]
+    pub settings: GridSettings,
+}
+
+impl Material for GridMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_ASSET_PATH.into()
+    }
+}
+
+#[derive(ShaderType, Reflect, Clone, Debug, PartialEq)]
+pub struct GridSettings {
+    pub base_color: LinearRgba,
+    pub line_color: LinearRgba,
+    /// World units between grid lines
+    pub cell_size: f32,
+    /// Line width in pixels
+    pub line_width: f32,
+    /// Distance from the camera at which lines have fully faded out
+    pub fade_distance: f32,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        Self {
+            base_color: LinearRgba::gray(0.05),
+            line_color: LinearRgba::gray(0.6),
+            cell_size: 1.,
+            line_width: 1.,
+            fade_distance: 30.,
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::VecDeque;
+
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use staff_gen::mesh_util::mesh_hash;
+
+use crate::actions::RegisterAction;
+use crate::clipboard::{CopiedConfig, CopiedObject};
+use crate::crystal::{GenerateCrystal, default_crystal};
+use crate::determinism::format_hash;
+#[cfg(feature = "export")]
+use crate::export::ExportStaff;
+use crate::gallery::GalleryStaff;
+use crate::generation::MeshGenCompleted;
+use crate::locale::Locale;
+use crate::staff::GenerateStaff;
+
+const HISTORY_FONT_SIZE: f32 = 12.;
+/// Entries kept before the oldest are forgotten. A gallery batch alone makes 32.
+const MAX_HISTORY: usize = 64;
+
+/// A config something was generated from this session.
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    /// Counts up over the session, so entries keep their number as older ones are dropped
+    pub number: u32,
+    pub config: CopiedConfig,
+    /// [`mesh_hash`] of what was generated, to tell whether the same config still makes the
+    /// same mesh
+    pub hash: Option<u64>,
+}
+
+/// Everything generated this session, newest first. Generating the same config again moves
+/// its entry back to the top. H opens and closes the panel listing it.
+#[derive(Resource, Debug, Default)]
+pub struct GenerationHistory {
+    pub entries: VecDeque<HistoryEntry>,
+    pub open: bool,
+    next_number: u32,
+}
+
+impl GenerationHistory {
+    pub fn record(&mut self, config: CopiedConfig, hash: Option<u64>) {
+        if let Some(existing) = self.entries.iter().position(|entry| entry.config == config) {
+            self.entries.remove(existing);
+        }
+        self.next_number += 1;
+        self.entries.push_front(HistoryEntry {
+            number: self.next_number,
+            config,
+            hash,
+        });
+        self.entries.truncate(MAX_HISTORY);
+    }
+}
+
+#[derive(Component, Debug)]
+struct HistoryPanel;
+
+/// Part of a history row that does `action` with the entry numbered `number`.
+#[derive(Component, Debug, Clone, Copy)]
+struct HistoryButton {
+    number: u32,
+    action: HistoryAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HistoryAction {
+    Respawn,
+    #[cfg(feature = "export")]
+    Export,
+}
+
+/// A panel of every config generated this session, so a good result seen a few regenerations
+/// ago can be spawned again, or exported in builds with the `export` feature.
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GenerationHistory>()
+            .register_action(
+                "Toggle generation history",
+                |mut history: ResMut<GenerationHistory>| history.open = !history.open,
+            )
+            .add_observer(press_history_button)
+            .add_systems(Startup, setup_history_panel)
+            // Generators insert configs through commands, which have all landed by now
+            .add_systems(PostUpdate, record_generations)
+            .add_systems(
+                Update,
+                (
+                    toggle_history_on_key,
+                    rebuild_history_panel.run_if(
+                        resource_changed::<GenerationHistory>.or(resource_changed::<Locale>),
+                    ),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn setup_history_panel(mut commands: Commands) {
+    commands.spawn((
+        Name::new("HistoryPanel"),
+        HistoryPanel,
+        Visibility::Hidden,
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(40.),
+            right: Val::Px(12.),
+            max_height: Val::Percent(50.),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(2.),
+            padding: UiRect::all(Val::Px(6.)),
+            overflow: Overflow::clip_y(),
+            ..default()
+        },
+        BackgroundColor(Color::from(css::DARK_SLATE_GRAY).with_alpha(0.8)),
+    ));
+}
+
+/// H opens and closes the history.
+fn toggle_history_on_key(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut history: ResMut<GenerationHistory>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyH) {
+        history.open = !history.open;
+    }
+}
+
+fn record_generations(
+    mut completed: MessageReader<MeshGenCompleted>,
+    objects: Query<(CopiedObject, Option<&GalleryStaff>, Option<&Mesh3d>)>,
+    meshes: Res<Assets<Mesh>>,
+    mut history: ResMut<GenerationHistory>,
+) {
+    for completed in completed.read() {
+        let Ok((object, gallery_staff, mesh3d)) = objects.get(completed.entity) else {
+            continue;
+        };
+        // Gallery staffs keep their config to themselves
+        let config = gallery_staff
+            .map(|staff| CopiedConfig::Staff(staff.config.clone()))
+            .or_else(|| CopiedConfig::of(object));
+        let hash = mesh3d
+            .and_then(|mesh3d| meshes.get(&mesh3d.0))
+            .map(mesh_hash);
+        if let Some(config) = config {
+            history.record(config, hash);
+        }
+    }
+}
+
+fn rebuild_history_panel(
+    mut commands: Commands,
+    history: Res<GenerationHistory>,
+    locale: Res<Locale>,
+    panel: Single<(Entity, &mut Visibility), With<HistoryPanel>>,
+) {
+    let (panel, mut visibility) = panel.into_inner();
+    *visibility = if history.open {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    if !history.open {
+        return;
+    }
+    commands.entity(panel).despawn_related::<Children>();
+    let text = |text: String, color: Srgba| {
+        (
+            Text::new(text),
+            TextFont::from_font_size(HISTORY_FONT_SIZE),
+            TextColor(Color::from(color)),
+        )
+    };
+    if history.entries.is_empty() {
+        commands.spawn((
+            text(locale.text("history.empty").into(), css::GRAY),
+            ChildOf(panel),
+        ));
+    }
+    for entry in &history.entries {
+        let kind = entry.config.kind().localized(&locale);
+        let mut label = match entry.config.seed() {
+            Some(seed) => format!("#{} {kind} {seed}", entry.number),
+            None => format!("#{} {kind}", entry.number),
+        };
+        if let Some(hash) = entry.hash {
+            // Enough digits to tell meshes apart at a glance
+            label.push_str(&format!(" [{}]", &format_hash(hash)[..8]));
+        }
+        let button = |action| HistoryButton {
+            number: entry.number,
+            action,
+        };
+        commands
+            .spawn((
+                Node {
+                    column_gap: Val::Px(6.),
+                    ..default()
+                },
+                ChildOf(panel),
+            ))
+            .with_children(|row| {
+                row.spawn(text(label, css::WHITE));
+                if matches!(
+                    entry.config,
+                    CopiedConfig::Staff(_) | CopiedConfig::Crystal(_)
+                ) {
+                    row.spawn((
+                        text(locale.text("history.respawn").into(), css::LIGHT_GRAY),
+                        button(HistoryAction::Respawn),
+                    ));
+                }
+                #[cfg(feature = "export")]
+                if matches!(entry.config, CopiedConfig::Staff(_)) {
+                    row.spawn((
+                        text(locale.text("history.export").into(), css::LIGHT_GRAY),
+                        button(HistoryAction::Export),
+                    ));
+                }
+            });
+    }
+}
+
+fn press_history_button(
+    mut click: On<Pointer<Click>>,
+    buttons: Query<&HistoryButton>,
+    history: Res<GenerationHistory>,
+    mut generate_staff: MessageWriter<GenerateStaff>,
+    mut generate_crystal: MessageWriter<GenerateCrystal>,
+    #[cfg(feature = "export")] mut exports: MessageWriter<ExportStaff>,
+) {
+    let Ok(&HistoryButton { number, action }) = buttons.get(click.entity) else {
+        return;
+    };
+    click.propagate(false);
+    let Some(entry) = history.entries.iter().find(|entry| entry.number == number) else {
+        return;
+    };
+    match (action, entry.config.clone()) {
+        (HistoryAction::Respawn, CopiedConfig::Staff(config)) => {
+            generate_staff.write(GenerateStaff::new(config));
+        }
+        (HistoryAction::Respawn, CopiedConfig::Crystal(config)) => {
+            generate_crystal.write(GenerateCrystal {
+                config,
+                ..default_crystal()
+            });
+        }
+        #[cfg(feature = "export")]
+        (HistoryAction::Export, CopiedConfig::Staff(config)) => {
+            exports.write(ExportStaff {
+                bake: false,
+                config: Some(config),
+            });
+        }
+        (_, config) => warn!("Can't do that with a {}", config.kind()),
+    }
+}
@@ -0,0 +1,195 @@
+use std::fmt::Display;
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use staff_gen::crystal::CrystalConfig;
+use staff_gen::error::GenError;
+use staff_gen::mesh_util::to_glb;
+use staff_gen::staff::StaffConfig;
+use staff_gen::style::StyleConfig;
+use tiny_http::{Header, Method, Response, Server};
+
+use crate::crystal::{GenerateCrystal, default_crystal};
+use crate::generation::IndexOptimization;
+use crate::staff::GenerateStaff;
+use crate::units::UnitsConfig;
+
+const HTTP_API_ADDRESS: &str = "127.0.0.1:8787";
+/// How long a request waits for the viewer to answer, in case it is paused or closing
+const HTTP_API_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A config posted to the API, with where to send the generated GLB.
+struct ApiRequest {
+    config: ApiConfig,
+    reply: Sender<Result<Vec<u8>, GenError>>,
+}
+
+enum ApiConfig {
+    Staff(StaffConfig),
+    Crystal(CrystalConfig),
+}
+
+/// Requests the server thread has handed over to the viewer.
+#[derive(Resource)]
+struct ApiRequests(Mutex<Receiver<ApiRequest>>);
+
+/// Serves generation over HTTP on [`HTTP_API_ADDRESS`], for pipeline tools and scripts.
+/// POSTing a JSON `StaffConfig` to `/generate/staff`, or a `CrystalConfig` to
+/// `/generate/crystal`, generates the object in the scene and answers with its mesh as binary
+/// glTF in meters. Configs that fail validation are answered with 400, and requests browsers
+/// send from web pages, which carry an `Origin`, with 403. Native only.
+pub struct HttpApiPlugin;
+
+impl Plugin for HttpApiPlugin {
+    fn build(&self, app: &mut App) {
+        let server = match Server::http(HTTP_API_ADDRESS) {
+            Ok(server) => server,
+            Err(error) => {
+                error!("Failed to start the HTTP API on {HTTP_API_ADDRESS}: {error}");
+                return;
+            }
+        };
+        info!("HTTP API listening on http://{HTTP_API_ADDRESS}");
+        let (sender, receiver) = channel();
+        thread::spawn(move || serve(server, sender));
+        app.insert_resource(ApiRequests(Mutex::new(receiver)))
+            .add_systems(Update, answer_api_requests);
+    }
+}
+
+/// Answers requests one at a time until the viewer closes.
+fn serve(server: Server, requests: Sender<ApiRequest>) {
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+        let read = request
+            .as_reader()
+            .read_to_string(&mut body)
+            .map_err(|error| (400, error.to_string()));
+        let from_browser = request
+            .headers()
+            .iter()
+            .any(|header| header.field.equiv("Origin"));
+        let config = if from_browser {
+            Err((403, "Requests from web pages are not accepted".to_string()))
+        } else {
+            read.and_then(|_| parse_request(request.method(), request.url(), &body))
+        };
+        let answer = config.and_then(|config| {
+            let (reply, answer) = channel();
+            requests
+                .send(ApiRequest { config, reply })
+                .map_err(|_| (503, "The viewer has closed".to_string()))?;
+            answer
+                .recv_timeout(HTTP_API_TIMEOUT)
+                .map_err(|_| (503, "The viewer did not answer".to_string()))?
+                .map_err(|error| (400, error.to_string()))
+        });
+
+        let response = match answer {
+            Ok(glb) => {
+                Response::from_data(glb).with_header(header("Content-Type", "model/gltf-binary"))
+            }
+            Err((status, message)) => Response::from_string(message).with_status_code(status),
+        };
+        if let Err(error) = request.respond(response) {
+            warn!("Failed to answer an HTTP API request: {error}");
+        }
+    }
+}
+
+/// The config a request asks for, or the status and message to answer it with. Configs are
+/// validated here so a bad one never reaches the viewer's generators.
+fn parse_request(method: &Method, url: &str, body: &str) -> Result<ApiConfig, (u16, String)> {
+    match (method, url) {
+        (Method::Post, "/generate/staff") => {
+            let config: StaffConfig = serde_json::from_str(body).map_err(bad_request)?;
+            config.validate().map_err(bad_request)?;
+            Ok(ApiConfig::Staff(config))
+        }
+        (Method::Post, "/generate/crystal") => {
+            let config: CrystalConfig = serde_json::from_str(body).map_err(bad_request)?;
+            config.validate().map_err(bad_request)?;
+            Ok(ApiConfig::Crystal(config))
+        }
+        (_, url) => Err((404, format!("No endpoint at {url}"))),
+    }
+}
+
+fn bad_request(error: impl Display) -> (u16, String) {
+    (400, error.to_string())
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name, value).expect("header names and values are ASCII")
+}
+
+/// Generates every waiting request's object in the scene, and sends back the same mesh.
+fn answer_api_requests(
+    requests: Res<ApiRequests>,
+    style: Res<StyleConfig>,
+    units: Res<UnitsConfig>,
+    optimization: Res<IndexOptimization>,
+    mut generate_staff: MessageWriter<GenerateStaff>,
+    mut generate_crystal: MessageWriter<GenerateCrystal>,
+) {
+    let Ok(requests) = requests.0.lock() else {
+        return;
+    };
+    for ApiRequest { config, reply } in requests.try_iter() {
+        let mesh = match config {
+            ApiConfig::Staff(config) => style.staff(&config).try_generate_mesh().inspect(|_| {
+                generate_staff.write(GenerateStaff::new(config));
+            }),
+            ApiConfig::Crystal(config) => {
+                style.crystal(&config).try_generate_mesh().inspect(|_| {
+                    generate_crystal.write(GenerateCrystal {
+                        config,
+                        ..default_crystal()
+                    });
+                })
+            }
+        };
+        let glb = mesh.map(|mesh| {
+            let mesh = mesh.scaled_by(Vec3::splat(units.meters_per_unit));
+            to_glb(&optimization.for_gltf(mesh))
+        });
+        // The client may have given up waiting
+        let _ = reply.send(glb);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_configs_are_bad_requests() {
+        let staff = StaffConfig {
+            resolution: 0,
+            ..default()
+        };
+        let body = serde_json::to_string(&staff).unwrap();
+        let answer = parse_request(&Method::Post, "/generate/staff", &body);
+        assert!(matches!(answer, Err((400, _))));
+
+        let crystal = CrystalConfig {
+            radius: -1.,
+            ..default()
+        };
+        let body = serde_json::to_string(&crystal).unwrap();
+        let answer = parse_request(&Method::Post, "/generate/crystal", &body);
+        assert!(matches!(answer, Err((400, _))));
+    }
+
+    #[test]
+    fn valid_configs_are_passed_on() {
+        let body = serde_json::to_string(&StaffConfig::default()).unwrap();
+        let answer = parse_request(&Method::Post, "/generate/staff", &body);
+        assert!(matches!(answer, Ok(ApiConfig::Staff(_))));
+        let answer = parse_request(&Method::Get, "/generate/staff", &body);
+        assert!(matches!(answer, Err((404, _))));
+    }
+}
@@ -0,0 +1,205 @@
+use std::f32::consts::FRAC_PI_4;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::camera::primitives::Aabb;
+use bevy::camera::visibility::RenderLayers;
+use bevy::camera::{RenderTarget, ScalingMode};
+use bevy::math::bounding::BoundingVolume;
+use bevy::prelude::*;
+use bevy::render::render_resource::{TextureFormat, TextureUsages};
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured};
+
+use crate::actions::RegisterAction;
+use crate::selection::{Selected, selected_or_staff, world_aabb};
+use crate::staff::Staff;
+
+const ICON_DIR: &str = "exports";
+/// Render layer the icon camera sees, so nothing but the rendered object ends up in the icon
+const ICON_LAYER: usize = 2;
+
+/// How "Render staff icon" frames its icons.
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct IconSettings {
+    /// Width and height of the square icon, in pixels
+    pub size: u32,
+    /// Angle the camera looks down at the object from, in radians
+    pub elevation: f32,
+    /// How far the object is turned in the picture, in radians. A quarter turn lays a staff
+    /// across the diagonal, the way inventory icons usually show long items.
+    pub roll: f32,
+    /// Empty border around the object, as a fraction of the icon
+    pub padding: f32,
+}
+
+impl Default for IconSettings {
+    fn default() -> Self {
+        Self {
+            size: 128,
+            elevation: 0.3,
+            roll: FRAC_PI_4,
+            padding: 0.05,
+        }
+    }
+}
+
+/// An icon waiting for its screenshot. The rendered entities were moved onto [`ICON_LAYER`] as
+/// well as the main one for the frame and are put back once it's saved.
+#[derive(Resource, Debug)]
+struct IconRender {
+    camera: Entity,
+    light: Entity,
+    layered: Vec<Entity>,
+}
+
+/// Bakes the selected object, or the viewer's staff, into a small PNG with a transparent
+/// background, for inventory icons in games using the exported assets. Native only.
+pub struct IconPlugin;
+
+impl Plugin for IconPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<IconSettings>()
+            .init_resource::<IconSettings>()
+            .register_action("Render staff icon", render_icon);
+    }
+}
+
+/// Frames the object from the front with an orthographic camera, so icons of different staffs
+/// line up, and captures a single frame of it.
+#[allow(clippy::too_many_arguments)]
+fn render_icon(
+    mut commands: Commands,
+    settings: Res<IconSettings>,
+    rendering: Option<Res<IconRender>>,
+    mut images: ResMut<Assets<Image>>,
+    selected: Query<Entity, With<Selected>>,
+    staffs: Query<Entity, With<Staff>>,
+    bounds: Query<(&Aabb, &GlobalTransform)>,
+    children: Query<&Children>,
+    layers: Query<(), With<RenderLayers>>,
+) {
+    if rendering.is_some() {
+        return;
+    }
+    let Some((entity, aabb)) = selected_or_staff(&selected, &staffs)
+        .and_then(|entity| Some((entity, world_aabb(entity, &bounds, &children)?)))
+    else {
+        warn!("Nothing to render an icon of");
+        return;
+    };
+    if let Err(error) = fs::create_dir_all(ICON_DIR) {
+        error!("Failed to create {ICON_DIR}: {error}");
+        return;
+    }
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let path = PathBuf::from(ICON_DIR).join(format!("icon_{seconds}.png"));
+
+    let center = Vec3::from(aabb.center());
+    let radius = aabb.half_size().length();
+    let direction = Quat::from_rotation_x(-settings.elevation) * Vec3::Z;
+    let mut transform =
+        Transform::from_translation(center + direction * radius * 2.).looking_at(center, Dir3::Y);
+    transform.rotate_local_z(settings.roll);
+    // Fit the box's corners as seen by the camera, rather than its bounding sphere, so long
+    // thin staffs fill the icon
+    let to_view = transform.compute_affine().inverse();
+    let (min, max) = (Vec3::from(aabb.min), Vec3::from(aabb.max));
+    let extent = (0..8)
+        .map(|corner| {
+            let point = vec3(
+                if corner & 1 == 0 { min.x } else { max.x },
+                if corner & 2 == 0 { min.y } else { max.y },
+                if corner & 4 == 0 { min.z } else { max.z },
+            );
+            let view = to_view.transform_point3(point);
+            view.x.abs().max(view.y.abs())
+        })
+        .fold(0., f32::max);
+    let view_size = extent * 2. / (1. - settings.padding * 2.).max(0.1);
+
+    // Straight alpha in a non-HDR target, so the clear color's alpha survives to the readback
+    let mut image =
+        Image::new_target_texture(settings.size, settings.size, TextureFormat::Rgba8UnormSrgb);
+    image.texture_descriptor.usage |= TextureUsages::COPY_SRC;
+    let image = images.add(image);
+    let camera = commands
+        .spawn((
+            Name::new("IconCamera"),
+            Camera3d::default(),
+            Camera {
+                target: RenderTarget::Image(image.clone().into()),
+                clear_color: ClearColorConfig::Custom(Color::NONE),
+                order: -1,
+                ..default()
+            },
+            Projection::from(OrthographicProjection {
+                scaling_mode: ScalingMode::Fixed {
+                    width: view_size,
+                    height: view_size,
+                },
+                ..OrthographicProjection::default_3d()
+            }),
+            transform,
+            RenderLayers::layer(ICON_LAYER),
+        ))
+        .id();
+    let light = commands
+        .spawn((
+            Name::new("IconLight"),
+            DirectionalLight::default(),
+            Transform::from_translation(direction + Vec3::X * 0.5).looking_at(Vec3::ZERO, Dir3::Y),
+            RenderLayers::layer(ICON_LAYER),
+        ))
+        .id();
+
+    // Entities with layers of their own, like the thumbnail staffs, are left where they are
+    let layered: Vec<Entity> = std::iter::once(entity)
+        .chain(children.iter_descendants(entity))
+        .filter(|&entity| !layers.contains(entity))
+        .collect();
+    for &entity in &layered {
+        commands
+            .entity(entity)
+            .insert(RenderLayers::from_layers(&[0, ICON_LAYER]));
+    }
+    commands.insert_resource(IconRender {
+        camera,
+        light,
+        layered,
+    });
+    commands
+        .spawn(Screenshot::image(image))
+        .observe(save_icon(path));
+}
+
+/// Saves the capture with its alpha channel, which Bevy's own `save_to_disk` drops, then takes
+/// the icon camera and layers down again.
+fn save_icon(
+    path: PathBuf,
+) -> impl FnMut(On<ScreenshotCaptured>, Commands, Option<Res<IconRender>>) {
+    move |captured, mut commands, render| {
+        match captured.image.clone().try_into_dynamic() {
+            Ok(image) => match image.to_rgba8().save(&path) {
+                Ok(()) => info!("Icon saved to {}", path.display()),
+                Err(error) => error!("Failed to save {}: {error}", path.display()),
+            },
+            Err(error) => error!("Failed to read back the icon: {error}"),
+        }
+
+        let Some(render) = render else {
+            return;
+        };
+        commands.entity(render.camera).despawn();
+        commands.entity(render.light).despawn();
+        for &entity in &render.layered {
+            if let Ok(mut entity) = commands.get_entity(entity) {
+                entity.remove::<RenderLayers>();
+            }
+        }
+        commands.remove_resource::<IconRender>();
+    }
+}
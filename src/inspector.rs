@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{EguiContext, EguiPlugin, PrimaryEguiContext};
+use bevy_inspector_egui::quick::{
+    FilterQueryInspectorPlugin, ResourceInspectorPlugin, WorldInspectorPlugin,
+};
+use staff_gen::charms::CharmConfig;
+use staff_gen::cone::ConeConfig;
+use staff_gen::crystal::CrystalConfig;
+use staff_gen::cylinder::CylinderConfig;
+use staff_gen::stats::StaffStats;
+use staff_gen::style::StyleConfig;
+use staff_gen::topology::TopologyReport;
+use staff_gen::wrapping::WrappingConfig;
+
+use crate::bolt::BoltSettings;
+use crate::budget::GenBudget;
+use crate::camera::CameraSettings;
+use crate::character::CharacterSettings;
+use crate::close_up::CloseUpSettings;
+use crate::cross_section::CrossSection;
+use crate::crystal_gradient::CrystalGradient;
+use crate::environment::EnvironmentConfig;
+use crate::generation::IndexOptimization;
+use crate::graphics::GraphicsSettings;
+use crate::morph::StaffMorph;
+use crate::morph_targets::GnarlBlend;
+use crate::palette::PaletteSettings;
+use crate::placement::PlacementSettings;
+use crate::rebuild::RebuildScheduler;
+use crate::showcase::ShowcaseSettings;
+use crate::sky_light::SkyLightSettings;
+use crate::thumbnails::ThumbnailBrowser;
+#[cfg(feature = "export")]
+use crate::turntable::TurntableSettings;
+use crate::units::UnitsConfig;
+use crate::wind::Wind;
+
+/// Debug windows from bevy-inspector-egui: the whole world, plus one window per
+/// settings resource and generator config. Editing a generator config rebuilds its mesh, paced
+/// by the [`RebuildScheduler`] while a value is dragged.
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin::default());
+        }
+        app.add_plugins(WorldInspectorPlugin::new())
+            .add_plugins(ResourceInspectorPlugin::<BoltSettings>::default())
+            .add_plugins(ResourceInspectorPlugin::<CameraSettings>::default())
+            .add_plugins(ResourceInspectorPlugin::<CharacterSettings>::default())
+            .add_plugins(ResourceInspectorPlugin::<CloseUpSettings>::default())
+            .add_plugins(ResourceInspectorPlugin::<CrossSection>::default())
+            .add_plugins(ResourceInspectorPlugin::<CrystalGradient>::default())
+            .add_plugins(ResourceInspectorPlugin::<EnvironmentConfig>::default())
+            .add_plugins(ResourceInspectorPlugin::<GenBudget>::default())
+            .add_plugins(ResourceInspectorPlugin::<GnarlBlend>::default())
+            .add_plugins(ResourceInspectorPlugin::<GraphicsSettings>::default())
+            .add_plugins(ResourceInspectorPlugin::<IndexOptimization>::default())
+            .add_plugins(ResourceInspectorPlugin::<StaffMorph>::default())
+            .add_plugins(ResourceInspectorPlugin::<PaletteSettings>::default())
+            .add_plugins(ResourceInspectorPlugin::<PlacementSettings>::default())
+            .add_plugins(ResourceInspectorPlugin::<RebuildScheduler>::default())
+            .add_plugins(ResourceInspectorPlugin::<ShowcaseSettings>::default())
+            .add_plugins(ResourceInspectorPlugin::<SkyLightSettings>::default())
+            .add_plugins(ResourceInspectorPlugin::<ThumbnailBrowser>::default())
+            .add_plugins(ResourceInspectorPlugin::<UnitsConfig>::default())
+            .add_plugins(ResourceInspectorPlugin::<StyleConfig>::default())
+            .add_plugins(ResourceInspectorPlugin::<Wind>::default())
+            .add_plugins(FilterQueryInspectorPlugin::<With<CharmConfig>>::default())
+            .add_plugins(FilterQueryInspectorPlugin::<With<ConeConfig>>::default())
+            .add_plugins(FilterQueryInspectorPlugin::<With<CrystalConfig>>::default())
+            .add_plugins(FilterQueryInspectorPlugin::<With<CylinderConfig>>::default())
+            .add_plugins(FilterQueryInspectorPlugin::<With<StaffStats>>::default())
+            .add_plugins(FilterQueryInspectorPlugin::<With<TopologyReport>>::default())
+            .add_plugins(FilterQueryInspectorPlugin::<With<WrappingConfig>>::default())
+            .add_systems(Update, scrub_inspector_drags);
+
+        #[cfg(feature = "export")]
+        app.add_plugins(ResourceInspectorPlugin::<TurntableSettings>::default());
+    }
+}
+
+/// Dragging a value in an inspector window scrubs it.
+fn scrub_inspector_drags(
+    mut context: Single<&mut EguiContext, With<PrimaryEguiContext>>,
+    mut scheduler: ResMut<RebuildScheduler>,
+) {
+    if context.get_mut().is_using_pointer() {
+        scheduler.scrub();
+    }
+}
@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+
+use crate::camera::MainCamera;
+
+/// Display name of a generated staff, kept in the current language by
+/// [`LocalePlugin`](crate::locale::LocalePlugin).
+#[derive(Component, Debug, Clone, Default)]
+pub struct StaffName(pub String);
+
+/// UI text that follows `target` around the screen, showing its [`StaffName`].
+/// Despawns itself once the target is gone.
+#[derive(Component, Debug)]
+pub struct WorldLabel {
+    pub target: Entity,
+    /// World-space offset from the target's origin
+    pub offset: Vec3,
+}
+
+pub struct LabelPlugin;
+
+impl Plugin for LabelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            update_world_labels.after(TransformSystems::Propagate),
+        );
+    }
+}
+
+pub fn spawn_world_label(commands: &mut Commands, target: Entity, offset: Vec3, font_size: f32) {
+    commands.spawn((
+        Name::new("WorldLabel"),
+        WorldLabel { target, offset },
+        Text::default(),
+        TextFont::from_font_size(font_size),
+        Node {
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        Visibility::Hidden,
+    ));
+}
+
+fn update_world_labels(
+    mut commands: Commands,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut labels: Query<(
+        Entity,
+        &WorldLabel,
+        &mut Text,
+        &mut Node,
+        &mut Visibility,
+        &ComputedNode,
+    )>,
+    targets: Query<(&GlobalTransform, Option<&StaffName>)>,
+) {
+    let (camera, camera_transform) = *camera;
+    for (entity, label, mut text, mut node, mut visibility, computed) in &mut labels {
+        let Ok((target, name)) = targets.get(label.target) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        let position = target.translation() + label.offset;
+        let Ok(viewport) = camera.world_to_viewport(camera_transform, position) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        // Center the text horizontally on the point
+        let size = computed.size() * computed.inverse_scale_factor();
+        node.left = Val::Px(viewport.x - size.x / 2.);
+        node.top = Val::Px(viewport.y - size.y);
+        let name = name.map_or("", |name| name.0.as_str());
+        if text.0 != name {
+            text.0 = name.to_string();
+        }
+    }
+}
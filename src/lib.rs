@@ -0,0 +1,84 @@
+pub mod actions;
+pub mod assembly;
+pub mod asset_loader;
+pub mod audio;
+pub mod bolt;
+pub mod budget;
+pub mod camera;
+pub mod campfire;
+pub mod character;
+pub mod charms;
+pub mod cleanup;
+pub mod clipboard;
+pub mod close_up;
+pub mod command_palette;
+pub mod cone;
+pub mod cross_section;
+pub mod cross_section_material;
+pub mod crystal;
+pub mod crystal_gradient;
+pub mod cube;
+pub mod cylinder;
+pub mod debug_view;
+pub mod debug_view_material;
+pub mod determinism;
+pub mod diagnostics;
+pub mod duplicate;
+pub mod dynamic_mesh;
+pub mod environment;
+pub mod environment_preset;
+pub mod exploration;
+#[cfg(feature = "export")]
+pub mod export;
+pub mod foliage;
+pub mod formation;
+pub mod gallery;
+pub mod generation;
+#[cfg(feature = "gpu_staff")]
+pub mod gpu_staff;
+pub mod graphics;
+pub mod grass_material;
+pub mod grid_material;
+pub mod history;
+#[cfg(feature = "http_api")]
+pub mod http_api;
+#[cfg(feature = "export")]
+pub mod icon;
+#[cfg(feature = "inspector")]
+pub mod inspector;
+pub mod labels;
+#[cfg(feature = "live_link")]
+pub mod live_link;
+pub mod locale;
+pub mod measure;
+pub mod mesh_source;
+pub mod morph;
+pub mod morph_targets;
+pub mod object_inspector;
+pub mod outliner;
+pub mod palette;
+pub mod pedestal;
+pub mod pickup;
+pub mod placement;
+pub mod randomizer;
+pub mod rebuild;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod selection;
+pub mod settings;
+pub mod shadows;
+pub mod showcase;
+pub mod skinning;
+pub mod sky_light;
+pub mod staff;
+pub mod staff_plant;
+pub mod state;
+pub mod stress_test;
+pub mod thumbnails;
+pub mod topology;
+pub mod trail;
+#[cfg(feature = "export")]
+pub mod turntable;
+pub mod units;
+pub mod wind;
+pub mod wrapping;
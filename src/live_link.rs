@@ -0,0 +1,168 @@
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use staff_gen::staff::StaffConfig;
+use tungstenite::{Message, WebSocket};
+
+use crate::morph::StaffMorph;
+
+const LIVE_LINK_ADDRESS: &str = "127.0.0.1:8788";
+/// How long a client connection waits for messages before sending its own
+const LIVE_LINK_POLL: Duration = Duration::from_millis(20);
+
+/// What goes over the live link, as JSON text frames like `{"staff": {...}}` or `{"morph": 0.5}`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveLinkMessage {
+    /// The staff's current parameters. Received ones become the morph's start, as pasted ones do
+    Staff(StaffConfig),
+    /// Where the staff sits between the two morph presets
+    Morph(f32),
+}
+
+/// Both ends of the live link on the viewer's side.
+#[derive(Resource)]
+struct LiveLink {
+    /// Every connected client's outbox
+    clients: Arc<Mutex<Vec<Sender<String>>>>,
+    /// The last broadcast, sent to clients as soon as they connect
+    latest: Arc<Mutex<Option<String>>>,
+    received: Mutex<Receiver<LiveLinkMessage>>,
+}
+
+/// A WebSocket server on [`LIVE_LINK_ADDRESS`] that broadcasts the staff's parameters whenever
+/// they change and applies parameters clients send, so an external tool or another browser tab
+/// can drive the viewer. Clients see their own updates echoed back. Native only.
+pub struct LiveLinkPlugin;
+
+impl Plugin for LiveLinkPlugin {
+    fn build(&self, app: &mut App) {
+        let listener = match TcpListener::bind(LIVE_LINK_ADDRESS) {
+            Ok(listener) => listener,
+            Err(error) => {
+                error!("Failed to start the live link on {LIVE_LINK_ADDRESS}: {error}");
+                return;
+            }
+        };
+        info!("Live link listening on ws://{LIVE_LINK_ADDRESS}");
+        let (sender, receiver) = channel();
+        let link = LiveLink {
+            clients: default(),
+            latest: default(),
+            received: Mutex::new(receiver),
+        };
+        let (clients, latest) = (link.clients.clone(), link.latest.clone());
+        thread::spawn(move || accept_clients(listener, clients, latest, sender));
+        app.insert_resource(link).add_systems(
+            Update,
+            (apply_live_link_messages, broadcast_staff_changes).chain(),
+        );
+    }
+}
+
+fn accept_clients(
+    listener: TcpListener,
+    clients: Arc<Mutex<Vec<Sender<String>>>>,
+    latest: Arc<Mutex<Option<String>>>,
+    received: Sender<LiveLinkMessage>,
+) {
+    for stream in listener.incoming().flatten() {
+        let (outbox_sender, outbox) = channel();
+        if let Some(latest) = latest.lock().ok().and_then(|latest| latest.clone()) {
+            let _ = outbox_sender.send(latest);
+        }
+        if let Ok(mut clients) = clients.lock() {
+            clients.push(outbox_sender);
+        }
+        let received = received.clone();
+        thread::spawn(move || {
+            if let Err(error) = serve_client(stream, outbox, received) {
+                debug!("Live link client disconnected: {error}");
+            }
+        });
+    }
+}
+
+/// Relays messages both ways until the client goes away. The socket polls for incoming frames so
+/// one thread can also send the outgoing ones.
+fn serve_client(
+    stream: TcpStream,
+    outbox: Receiver<String>,
+    received: Sender<LiveLinkMessage>,
+) -> Result<(), String> {
+    let mut socket: WebSocket<TcpStream> =
+        tungstenite::accept(stream).map_err(|error| error.to_string())?;
+    socket
+        .get_mut()
+        .set_read_timeout(Some(LIVE_LINK_POLL))
+        .map_err(|error| error.to_string())?;
+    loop {
+        for text in outbox.try_iter() {
+            socket
+                .write(Message::text(text))
+                .map_err(|error| error.to_string())?;
+        }
+        socket.flush().map_err(|error| error.to_string())?;
+        match socket.read() {
+            Ok(Message::Text(text)) => match serde_json::from_str(&text) {
+                Ok(message) => {
+                    let _ = received.send(message);
+                }
+                Err(error) => warn!("Ignoring a live link message: {error}"),
+            },
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(error))
+                if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(error) => return Err(error.to_string()),
+        }
+    }
+}
+
+fn apply_live_link_messages(link: Res<LiveLink>, mut morph: ResMut<StaffMorph>) {
+    let Ok(received) = link.received.lock() else {
+        return;
+    };
+    for message in received.try_iter() {
+        match message {
+            LiveLinkMessage::Staff(config) => {
+                // Never hand the generators a config they can't build
+                if let Err(error) = config.validate() {
+                    warn!("Ignoring a live link staff: {error}");
+                    continue;
+                }
+                morph.from = config;
+                morph.t = 0.;
+            }
+            LiveLinkMessage::Morph(t) if t.is_nan() => warn!("Ignoring a live link morph of NaN"),
+            LiveLinkMessage::Morph(t) => morph.t = t.clamp(0., 1.),
+        }
+    }
+}
+
+fn broadcast_staff_changes(link: Res<LiveLink>, morph: Res<StaffMorph>) {
+    if !morph.is_changed() {
+        return;
+    }
+    let message = LiveLinkMessage::Staff(morph.config());
+    let text = match serde_json::to_string(&message) {
+        Ok(text) => text,
+        Err(error) => {
+            error!("Failed to serialize live link parameters: {error}");
+            return;
+        }
+    };
+    if let Ok(mut clients) = link.clients.lock() {
+        // Clients whose thread has ended are dropped
+        clients.retain(|client| client.send(text.clone()).is_ok());
+    }
+    if let Ok(mut latest) = link.latest.lock() {
+        *latest = Some(text);
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use staff_gen::naming::NameWords;
+use staff_gen::staff::StaffConfig;
+
+use crate::actions::RegisterAction;
+use crate::gallery::GalleryStaff;
+use crate::labels::StaffName;
+use crate::settings::{PersistPlugin, Persistent};
+
+/// Languages the UI has text for, each with a file in `assets/locales`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Self; 2] = [Self::English, Self::German];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&language| language == self);
+        Self::ALL[index.map_or(0, |index| (index + 1) % Self::ALL.len())]
+    }
+
+    /// The language file, built into the binary so text is there before the first frame and in
+    /// the web build.
+    fn file(self) -> &'static str {
+        match self {
+            Self::English => include_str!("../assets/locales/en.ron"),
+            Self::German => include_str!("../assets/locales/de.ron"),
+        }
+    }
+}
+
+/// The language the UI is shown in, kept in `language.ron` across runs.
+#[derive(Resource, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct LanguageSetting {
+    pub language: Language,
+}
+
+impl Persistent for LanguageSetting {
+    const FILE_NAME: &'static str = "language.ron";
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct LanguageFile {
+    texts: HashMap<String, String>,
+    actions: HashMap<String, String>,
+    names: NameWords,
+}
+
+impl LanguageFile {
+    fn parse(language: Language) -> Self {
+        ron::from_str(language.file())
+            .inspect_err(|error| warn!("Ignoring invalid {language:?} language file: {error}"))
+            .unwrap_or_default()
+    }
+}
+
+/// UI text in the current language, looked up by key. Keys a language leaves out fall back to
+/// English, and keys English leaves out show as themselves.
+#[derive(Resource, Debug)]
+pub struct Locale {
+    language: Language,
+    texts: HashMap<String, String>,
+    actions: HashMap<String, String>,
+    names: NameWords,
+}
+
+impl Locale {
+    pub fn new(language: Language) -> Self {
+        let mut english = LanguageFile::parse(Language::English);
+        if language == Language::English {
+            return Self {
+                language,
+                texts: english.texts,
+                actions: english.actions,
+                names: english.names,
+            };
+        }
+        let file = LanguageFile::parse(language);
+        english.texts.extend(file.texts);
+        english.actions.extend(file.actions);
+        Self {
+            language,
+            texts: english.texts,
+            actions: english.actions,
+            names: file.names,
+        }
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        self.texts.get(key).map_or(key, String::as_str)
+    }
+
+    /// The text for `key` with each `{name}` in it replaced by the matching argument.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        args.iter()
+            .fold(self.text(key).to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), &value.to_string())
+            })
+    }
+
+    /// An action's name as the command palette shows it. Actions are registered and bound to keys
+    /// by their English names.
+    pub fn action_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.actions.get(name).map_or(name, String::as_str)
+    }
+
+    pub fn staff_name(&self, config: &StaffConfig) -> String {
+        self.names.staff_name(config)
+    }
+}
+
+/// Looks UI text and staff names up in language files, so they can be translated. The language
+/// is switched from the command palette.
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LanguageSetting>()
+            .add_plugins(PersistPlugin::<LanguageSetting>::default());
+        let language = app.world().resource::<LanguageSetting>().language;
+        app.insert_resource(Locale::new(language))
+            .register_action("Switch language", |mut setting: ResMut<LanguageSetting>| {
+                setting.language = setting.language.next();
+            })
+            .add_systems(PreUpdate, switch_language)
+            .add_systems(PostUpdate, name_staffs);
+    }
+}
+
+fn switch_language(setting: Res<LanguageSetting>, mut locale: ResMut<Locale>) {
+    if locale.language != setting.language {
+        *locale = Locale::new(setting.language);
+    }
+}
+
+/// Names staffs in the current language when they're spawned, their config changes or the
+/// language does.
+fn name_staffs(
+    locale: Res<Locale>,
+    mut staffs: Query<(&mut StaffName, Ref<StaffConfig>)>,
+    mut gallery: Query<(&mut StaffName, Ref<GalleryStaff>), Without<StaffConfig>>,
+) {
+    let configs = staffs
+        .iter_mut()
+        .map(|(name, config)| (name, config.is_changed(), config.into_inner()))
+        .chain(
+            gallery
+                .iter_mut()
+                .map(|(name, staff)| (name, staff.is_changed(), &staff.into_inner().config)),
+        );
+    for (mut name, config_changed, config) in configs {
+        if !(config_changed || locale.is_changed()) {
+            continue;
+        }
+        let new_name = locale.staff_name(config);
+        if name.0 != new_name {
+            name.0 = new_name;
+        }
+    }
+}
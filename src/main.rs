@@ -1,34 +1,160 @@
-mod asset_loader;
-mod camera;
-mod cone;
-mod crystal;
-mod cube;
-mod cylinder;
-mod environment;
-mod staff;
-
 use bevy::prelude::*;
 
-use self::asset_loader::AssetLoaderPlugin;
-use self::camera::CameraPlugin;
-use self::environment::EnvironmentPlugin;
+use staff_test::asset_loader::AssetLoaderPlugin;
+use staff_test::audio::SoundPlugin;
+use staff_test::bolt::BoltPlugin;
+use staff_test::budget::BudgetPlugin;
+use staff_test::camera::CameraPlugin;
+use staff_test::campfire::CampfirePlugin;
+use staff_test::character::CharacterPlugin;
+use staff_test::charms::CharmPlugin;
+use staff_test::cleanup::CleanupPlugin;
+use staff_test::clipboard::ClipboardPlugin;
+use staff_test::close_up::CloseUpPlugin;
+use staff_test::command_palette::CommandPalettePlugin;
+use staff_test::cross_section::CrossSectionPlugin;
+use staff_test::crystal_gradient::CrystalGradientPlugin;
+use staff_test::debug_view::DebugViewPlugin;
+use staff_test::determinism::DeterminismPlugin;
+use staff_test::diagnostics::DiagnosticsOverlayPlugin;
+use staff_test::duplicate::DuplicatePlugin;
+use staff_test::dynamic_mesh::DynamicMeshPlugin;
+use staff_test::environment::EnvironmentPlugin;
+use staff_test::environment_preset::EnvironmentPresetPlugin;
+use staff_test::exploration::ExplorationPlugin;
+#[cfg(feature = "export")]
+use staff_test::export::ExportPlugin;
+use staff_test::foliage::FoliagePlugin;
+use staff_test::formation::FormationPlugin;
+use staff_test::gallery::GalleryPlugin;
+use staff_test::generation::GenerationPlugin;
+#[cfg(feature = "gpu_staff")]
+use staff_test::gpu_staff::GpuStaffPlugin;
+use staff_test::graphics::GraphicsSettingsPlugin;
+use staff_test::history::HistoryPlugin;
+#[cfg(feature = "http_api")]
+use staff_test::http_api::HttpApiPlugin;
+#[cfg(feature = "export")]
+use staff_test::icon::IconPlugin;
+#[cfg(feature = "inspector")]
+use staff_test::inspector::InspectorPlugin;
+use staff_test::labels::LabelPlugin;
+#[cfg(feature = "live_link")]
+use staff_test::live_link::LiveLinkPlugin;
+use staff_test::locale::LocalePlugin;
+use staff_test::measure::MeasurePlugin;
+use staff_test::mesh_source::MeshSourcePlugin;
+use staff_test::morph::StaffMorphPlugin;
+use staff_test::morph_targets::MorphTargetPlugin;
+use staff_test::object_inspector::ObjectInspectorPlugin;
+use staff_test::outliner::OutlinerPlugin;
+use staff_test::palette::PalettePlugin;
+use staff_test::pedestal::PedestalPlugin;
+use staff_test::pickup::PickupPlugin;
+use staff_test::placement::PlacementPlugin;
+use staff_test::randomizer::RandomizerPlugin;
+use staff_test::rebuild::RebuildPlugin;
+#[cfg(feature = "scripting")]
+use staff_test::scripting::ScriptingPlugin;
+use staff_test::selection::SelectionPlugin;
+use staff_test::showcase::ShowcasePlugin;
+use staff_test::skinning::SkinningPlugin;
+use staff_test::sky_light::SkyLightPlugin;
+use staff_test::staff_plant::StaffPlantPlugin;
+use staff_test::state::AppStatePlugin;
+use staff_test::stress_test::StressTestPlugin;
+use staff_test::thumbnails::ThumbnailPlugin;
+use staff_test::topology::TopologyPlugin;
+use staff_test::trail::TrailPlugin;
+#[cfg(feature = "export")]
+use staff_test::turntable::TurntablePlugin;
+use staff_test::wind::WindPlugin;
 
 fn main() {
-    App::new()
-        .add_plugins(
-            DefaultPlugins
-                .set(ImagePlugin::default_nearest())
-                .set(WindowPlugin {
-                    primary_window: Some(Window {
-                        canvas: Some("#canvas".into()),
-                        fit_canvas_to_parent: true,
-                        ..default()
-                    }),
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(ImagePlugin::default_nearest())
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    canvas: Some("#canvas".into()),
+                    fit_canvas_to_parent: true,
                     ..default()
                 }),
-        )
-        .add_plugins(CameraPlugin)
-        .add_plugins(EnvironmentPlugin)
-        .add_plugins(AssetLoaderPlugin)
-        .run();
+                ..default()
+            }),
+    )
+    .add_plugins(AppStatePlugin)
+    .add_plugins(CameraPlugin)
+    .add_plugins(CharacterPlugin)
+    .add_plugins(PickupPlugin)
+    .add_plugins(StaffPlantPlugin)
+    .add_plugins(BoltPlugin)
+    .add_plugins(GraphicsSettingsPlugin)
+    .add_plugins(LocalePlugin)
+    .add_plugins(GenerationPlugin)
+    .add_plugins(RebuildPlugin)
+    .add_plugins(EnvironmentPlugin)
+    .add_plugins(EnvironmentPresetPlugin)
+    .add_plugins(SkyLightPlugin)
+    .add_plugins(AssetLoaderPlugin)
+    .add_plugins(SoundPlugin)
+    .add_plugins(StaffMorphPlugin)
+    .add_plugins(RandomizerPlugin)
+    .add_plugins(PalettePlugin)
+    .add_plugins(MorphTargetPlugin)
+    .add_plugins(SkinningPlugin)
+    .add_plugins(DynamicMeshPlugin)
+    .add_plugins(TrailPlugin)
+    .add_plugins(FoliagePlugin)
+    .add_plugins(FormationPlugin)
+    .add_plugins(CrystalGradientPlugin)
+    .add_plugins(CampfirePlugin)
+    .add_plugins(WindPlugin)
+    .add_plugins(CharmPlugin)
+    .add_plugins(ShowcasePlugin)
+    .add_plugins(MeasurePlugin)
+    .add_plugins(MeshSourcePlugin)
+    .add_plugins(PlacementPlugin)
+    .add_plugins(SelectionPlugin)
+    .add_plugins(DuplicatePlugin)
+    .add_plugins(ClipboardPlugin)
+    .add_plugins(CommandPalettePlugin)
+    .add_plugins(OutlinerPlugin)
+    .add_plugins(HistoryPlugin)
+    .add_plugins(DeterminismPlugin)
+    .add_plugins(DiagnosticsOverlayPlugin)
+    .add_plugins(DebugViewPlugin)
+    .add_plugins(TopologyPlugin)
+    .add_plugins(CrossSectionPlugin)
+    .add_plugins(ObjectInspectorPlugin)
+    .add_plugins(CloseUpPlugin)
+    .add_plugins(LabelPlugin)
+    .add_plugins(StressTestPlugin)
+    .add_plugins(PedestalPlugin)
+    .add_plugins(GalleryPlugin)
+    .add_plugins(ExplorationPlugin)
+    .add_plugins(ThumbnailPlugin)
+    .add_plugins(CleanupPlugin)
+    .add_plugins(BudgetPlugin);
+
+    #[cfg(feature = "export")]
+    app.add_plugins((ExportPlugin, IconPlugin, TurntablePlugin));
+
+    #[cfg(feature = "gpu_staff")]
+    app.add_plugins(GpuStaffPlugin);
+
+    #[cfg(feature = "http_api")]
+    app.add_plugins(HttpApiPlugin);
+
+    #[cfg(feature = "live_link")]
+    app.add_plugins(LiveLinkPlugin);
+
+    #[cfg(feature = "inspector")]
+    app.add_plugins(InspectorPlugin);
+
+    #[cfg(feature = "scripting")]
+    app.add_plugins(ScriptingPlugin);
+
+    app.run();
 }
@@ -0,0 +1,196 @@
+use bevy::color::palettes::css;
+use bevy::picking::mesh_picking::MeshPickingPlugin;
+use bevy::prelude::*;
+
+use crate::camera::MainCamera;
+use crate::mesh_source::MeshSource;
+
+/// How much nearer than a generated surface another mesh's hit must be to be measured instead
+const PICK_TOLERANCE: f32 = 1e-3;
+
+/// Two clicked points and the distance between them.
+/// M toggles the tool, each left click on a mesh or the floor places a point. Points on generated
+/// objects are cast against their [`MeshSource`], so they land exactly on the generated surface,
+/// and stay attached to it while the object moves or is regenerated.
+#[derive(Resource, Debug, Default)]
+pub struct MeasureTool {
+    pub active: bool,
+    pub points: Vec<Vec3>,
+    /// The generated object under each point, with the point in its mesh's space
+    attachments: Vec<Option<(Entity, Vec3)>>,
+}
+
+impl MeasureTool {
+    fn clear(&mut self) {
+        self.points.clear();
+        self.attachments.clear();
+    }
+
+    pub fn distance(&self) -> Option<f32> {
+        match self.points[..] {
+            [a, b] => Some(a.distance(b)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Component)]
+struct MeasureLabel;
+
+pub struct MeasurePlugin;
+
+impl Plugin for MeasurePlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<MeshPickingPlugin>() {
+            app.add_plugins(MeshPickingPlugin);
+        }
+        app.init_resource::<MeasureTool>()
+            .add_observer(place_measure_point)
+            .add_systems(Startup, setup_measure_label)
+            .add_systems(
+                Update,
+                (
+                    toggle_measure_tool,
+                    follow_attached_points,
+                    draw_measurement,
+                    update_measure_label,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn setup_measure_label(mut commands: Commands) {
+    commands.spawn((
+        Name::new("MeasureLabel"),
+        MeasureLabel,
+        Text::default(),
+        TextFont::from_font_size(14.),
+        Node {
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        Visibility::Hidden,
+    ));
+}
+
+fn toggle_measure_tool(keyboard_input: Res<ButtonInput<KeyCode>>, mut tool: ResMut<MeasureTool>) {
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        tool.active = !tool.active;
+        tool.clear();
+        info!("Measure tool: {}", tool.active);
+    }
+}
+
+fn place_measure_point(
+    mut click: On<Pointer<Click>>,
+    mut tool: ResMut<MeasureTool>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    sources: Query<(Entity, &MeshSource, &GlobalTransform)>,
+    nodes: Query<(), With<Node>>,
+) {
+    if !tool.active || click.event.button != PointerButton::Primary {
+        return;
+    }
+    // Only the first hit matters, not every ancestor it bubbles up to
+    click.propagate(false);
+    if nodes.contains(click.entity) {
+        return;
+    }
+    let (camera, camera_transform) = *camera;
+    let source_hit = camera
+        .viewport_to_world(camera_transform, click.pointer_location.position)
+        .ok()
+        .and_then(|ray| {
+            sources
+                .iter()
+                .filter_map(|(entity, source, transform)| {
+                    let hit = source.raycast_world(transform, ray)?;
+                    let local = transform.affine().inverse().transform_point3(hit.position);
+                    Some((hit, (entity, local)))
+                })
+                .min_by(|(a, _), (b, _)| a.distance.total_cmp(&b.distance))
+        });
+    // Meshes without a source, like the floor, keep the picking backend's hit when it's nearer
+    let picked = &click.event.hit;
+    let (position, attachment) = match (source_hit, picked.position) {
+        (Some((hit, _)), Some(position)) if picked.depth + PICK_TOLERANCE < hit.distance => {
+            (position, None)
+        }
+        (Some((hit, attachment)), _) => (hit.position, Some(attachment)),
+        (None, Some(position)) => (position, None),
+        (None, None) => return,
+    };
+    if tool.points.len() == 2 {
+        tool.clear();
+    }
+    tool.points.push(position);
+    tool.attachments.push(attachment);
+    if let Some(distance) = tool.distance() {
+        info!("Measured {distance:.3}");
+    }
+}
+
+/// Moves attached points with their objects, snapping them back onto the nearest point of the
+/// surface when it was regenerated. Points whose object is gone stay where they were.
+fn follow_attached_points(
+    mut tool: ResMut<MeasureTool>,
+    sources: Query<(Ref<MeshSource>, Ref<GlobalTransform>)>,
+) {
+    if !tool.active {
+        return;
+    }
+    let tool = &mut *tool;
+    for (point, attachment) in tool.points.iter_mut().zip(&mut tool.attachments) {
+        let Some((entity, local)) = *attachment else {
+            continue;
+        };
+        let Ok((source, transform)) = sources.get(entity) else {
+            *attachment = None;
+            continue;
+        };
+        if !source.is_changed() && !transform.is_changed() {
+            continue;
+        }
+        let local = source
+            .closest_point(local)
+            .map_or(local, |hit| hit.position);
+        *point = transform.transform_point(local);
+    }
+}
+
+fn draw_measurement(mut gizmos: Gizmos, tool: Res<MeasureTool>) {
+    if !tool.active {
+        return;
+    }
+    for &point in &tool.points {
+        gizmos.sphere(Isometry3d::from_translation(point), 0.02, css::YELLOW);
+    }
+    if let [a, b] = tool.points[..] {
+        gizmos.line(a, b, css::YELLOW);
+    }
+}
+
+fn update_measure_label(
+    tool: Res<MeasureTool>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    label: Single<(&mut Text, &mut Node, &mut Visibility), With<MeasureLabel>>,
+) {
+    let (camera, camera_transform) = *camera;
+    let (mut text, mut node, mut visibility) = label.into_inner();
+    let midpoint = match tool.points[..] {
+        [a, b] if tool.active => a.midpoint(b),
+        _ => {
+            *visibility = Visibility::Hidden;
+            return;
+        }
+    };
+    let Ok(viewport) = camera.world_to_viewport(camera_transform, midpoint) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+    node.left = Val::Px(viewport.x);
+    node.top = Val::Px(viewport.y);
+    text.0 = format!("{:.3}", tool.distance().unwrap_or_default());
+}
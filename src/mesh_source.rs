@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+use staff_gen::mesh_util::{Bvh, MeshSourceData, RayHit, SurfaceHit};
+
+use crate::generation::MeshGenCompleted;
+
+/// CPU copy of a generated object's triangles with a [`Bvh`] over them, for tools that need to
+/// hit or follow the surface exactly. Rebuilt whenever the object's mesh is regenerated.
+#[derive(Component, Debug, Clone)]
+pub struct MeshSource {
+    pub data: MeshSourceData,
+    pub bvh: Bvh,
+}
+
+impl MeshSource {
+    pub fn new(mesh: &Mesh) -> Self {
+        let data = MeshSourceData::from_mesh(mesh);
+        let bvh = Bvh::new(&data);
+        Self { data, bvh }
+    }
+
+    /// Where `ray`, in the mesh's space, first meets the surface.
+    pub fn raycast(&self, ray: Ray3d) -> Option<RayHit> {
+        self.bvh.raycast(&self.data, ray)
+    }
+
+    /// [`Self::raycast`] for a world space `ray` against the mesh placed at `transform`.
+    pub fn raycast_world(&self, transform: &GlobalTransform, ray: Ray3d) -> Option<RayHit> {
+        let world_from_local = transform.affine();
+        let local_from_world = world_from_local.inverse();
+        let direction = Dir3::new(local_from_world.transform_vector3(*ray.direction)).ok()?;
+        let local_ray = Ray3d::new(local_from_world.transform_point3(ray.origin), direction);
+        let hit = self.raycast(local_ray)?;
+        let position = world_from_local.transform_point3(hit.position);
+        Some(RayHit {
+            distance: ray.origin.distance(position),
+            position,
+            // Normals go through the inverse transpose, so they stay normal under scaling
+            normal: (local_from_world.matrix3.transpose() * hit.normal).normalize_or_zero(),
+            triangle: hit.triangle,
+        })
+    }
+
+    /// Nearest point on the surface to `point`, both in the mesh's space.
+    pub fn closest_point(&self, point: Vec3) -> Option<SurfaceHit> {
+        self.bvh.closest_point(&self.data, point)
+    }
+}
+
+/// Keeps a [`MeshSource`] on every generated object, built from its mesh after each generation.
+pub struct MeshSourcePlugin;
+
+impl Plugin for MeshSourcePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, update_mesh_sources);
+    }
+}
+
+fn update_mesh_sources(
+    mut commands: Commands,
+    mut completed: MessageReader<MeshGenCompleted>,
+    objects: Query<&Mesh3d>,
+    meshes: Res<Assets<Mesh>>,
+) {
+    for generation in completed.read() {
+        let Ok(mesh) = objects.get(generation.entity) else {
+            continue;
+        };
+        let Some(mesh) = meshes.get(&mesh.0) else {
+            continue;
+        };
+        let _span = info_span!("mesh_source", kind = %generation.stats.kind).entered();
+        commands
+            .entity(generation.entity)
+            .insert(MeshSource::new(mesh));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use staff_gen::cube::{CubeNormals, generate_cube_mesh};
+
+    use super::*;
+
+    #[test]
+    fn world_raycast_follows_the_transform() {
+        let source = MeshSource::new(&generate_cube_mesh(&mut CubeNormals::default()));
+        let transform =
+            GlobalTransform::from(Transform::from_xyz(3., 0., 0.).with_scale(vec3(1., 4., 1.)));
+        let ray = Ray3d::new(vec3(3.1, 10., 0.2), Dir3::NEG_Y);
+        let hit = source.raycast_world(&transform, ray).unwrap();
+        assert!(
+            hit.position.abs_diff_eq(vec3(3.1, 2., 0.2), 1e-5),
+            "{hit:?}"
+        );
+        assert!((hit.distance - 8.).abs() < 1e-5, "{hit:?}");
+        assert!(hit.normal.abs_diff_eq(Vec3::Y, 1e-5), "{hit:?}");
+        let beside = Ray3d::new(vec3(0., 10., 0.), Dir3::NEG_Y);
+        assert!(source.raycast_world(&transform, beside).is_none());
+    }
+}
@@ -0,0 +1,275 @@
+use std::error::Error;
+
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use serde::{Deserialize, Serialize};
+use staff_gen::error::check_finite;
+use staff_gen::sockets::Sockets;
+use staff_gen::staff::StaffConfig;
+
+use crate::actions::RegisterAction;
+use crate::generation::{GeneratorKind, MeshGenMessages};
+use crate::labels::WorldLabel;
+use crate::locale::Locale;
+use crate::rebuild::RebuildScheduler;
+use crate::settings::{PersistPlugin, Persistent};
+use crate::staff::{Staff, label_offset, staff_translation};
+use crate::state::AppState;
+use crate::units::UnitsConfig;
+
+pub const SLIDER_WIDTH: f32 = 300.;
+const SLIDER_HEIGHT: f32 = 16.;
+
+/// Two saved staff presets and how far the current staff sits between them, kept in
+/// `staff_morph.ron` across runs as the last used staff parameters.
+#[derive(Resource, Reflect, Serialize, Deserialize, Debug)]
+#[reflect(Resource)]
+pub struct StaffMorph {
+    pub from: StaffConfig,
+    pub to: StaffConfig,
+    pub t: f32,
+}
+
+impl FromWorld for StaffMorph {
+    fn from_world(world: &mut World) -> Self {
+        let units = world
+            .get_resource::<UnitsConfig>()
+            .cloned()
+            .unwrap_or_default();
+        Self::new(&units)
+    }
+}
+
+impl Persistent for StaffMorph {
+    const FILE_NAME: &'static str = "staff_morph.ron";
+
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        self.from.validate()?;
+        self.to.validate()?;
+        check_finite("t", self.t)?;
+        Ok(())
+    }
+}
+
+impl StaffMorph {
+    pub fn new(units: &UnitsConfig) -> Self {
+        let from = units.staff_defaults();
+        // A thicker, more gnarled staff to morph towards, in meters
+        let to = StaffConfig {
+            radius: 0.09,
+            radial_variance: 0.06,
+            height: 2.4,
+            resolution: 12,
+            segments: 12,
+            horizontal_variance: 0.25,
+            seed: 73491,
+            ..default()
+        }
+        .scaled(units.from_meters());
+        Self { from, to, t: 0. }
+    }
+
+    pub fn config(&self) -> StaffConfig {
+        self.from.lerp(&self.to, self.t)
+    }
+}
+
+/// Rebuilds the morphed staff mesh on the CPU.
+/// Alternative mesh backends can disable this set and rebuild from [`StaffMorph`] themselves.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CpuStaffRebuild;
+
+#[derive(Component)]
+struct MorphPanel;
+
+#[derive(Component)]
+struct MorphSlider;
+
+#[derive(Component)]
+struct MorphSliderFill;
+
+#[derive(Component)]
+struct MorphLabel;
+
+pub struct StaffMorphPlugin;
+
+impl Plugin for StaffMorphPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<StaffMorph>()
+            .init_resource::<StaffMorph>()
+            .add_plugins(PersistPlugin::<StaffMorph>::default())
+            .register_action(
+                "Load preset: slender staff",
+                |mut morph: ResMut<StaffMorph>| {
+                    morph.t = 0.;
+                },
+            )
+            .register_action(
+                "Load preset: gnarled staff",
+                |mut morph: ResMut<StaffMorph>| {
+                    morph.t = 1.;
+                },
+            )
+            .add_systems(Startup, setup_morph_slider)
+            .add_systems(OnEnter(AppState::Editing), show_morph_panel)
+            .add_systems(OnExit(AppState::Editing), hide_morph_panel)
+            .add_systems(
+                Update,
+                (
+                    drag_morph_slider.run_if(in_state(AppState::Editing)),
+                    update_morph_slider,
+                    rebuild_morphed_staff.in_set(CpuStaffRebuild),
+                    reconfigure_morphed_staff,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn setup_morph_slider(mut commands: Commands, morph: Res<StaffMorph>, locale: Res<Locale>) {
+    commands.spawn((
+        Name::new("MorphPanel"),
+        MorphPanel,
+        Visibility::Hidden,
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.),
+            left: Val::Px(12.),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.),
+            ..default()
+        },
+        children![
+            (
+                MorphLabel,
+                Text::new(morph_label(&locale, morph.t)),
+                TextFont::from_font_size(14.)
+            ),
+            (
+                MorphSlider,
+                RelativeCursorPosition::default(),
+                Node {
+                    width: Val::Px(SLIDER_WIDTH),
+                    height: Val::Px(SLIDER_HEIGHT),
+                    ..default()
+                },
+                BackgroundColor(Color::from(css::DARK_SLATE_GRAY)),
+                children![(
+                    MorphSliderFill,
+                    Node {
+                        width: Val::Percent(morph.t * 100.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    BackgroundColor(Color::from(css::SADDLE_BROWN)),
+                )],
+            ),
+        ],
+    ));
+}
+
+fn show_morph_panel(mut panel: Single<&mut Visibility, With<MorphPanel>>) {
+    **panel = Visibility::Inherited;
+}
+
+fn hide_morph_panel(mut panel: Single<&mut Visibility, With<MorphPanel>>) {
+    **panel = Visibility::Hidden;
+}
+
+fn morph_label(locale: &Locale, t: f32) -> String {
+    locale.format("morph.label", &[("t", &format_args!("{t:.2}"))])
+}
+
+fn drag_morph_slider(
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    slider: Single<&RelativeCursorPosition, With<MorphSlider>>,
+    mut morph: ResMut<StaffMorph>,
+    mut scheduler: ResMut<RebuildScheduler>,
+    mut dragging: Local<bool>,
+) {
+    // Only start a drag on the slider, but keep tracking the cursor once it leaves it
+    if mouse_button_input.just_pressed(MouseButton::Left) {
+        *dragging = slider.cursor_over();
+    }
+    if !mouse_button_input.pressed(MouseButton::Left) {
+        *dragging = false;
+    }
+    if !*dragging {
+        return;
+    }
+    scheduler.scrub();
+    let Some(normalized) = slider.normalized else {
+        return;
+    };
+    // normalized is relative to the node center, ranging -0.5..0.5 inside it
+    let t = (normalized.x + 0.5).clamp(0., 1.);
+    if morph.t != t {
+        morph.t = t;
+    }
+}
+
+fn update_morph_slider(
+    morph: Res<StaffMorph>,
+    mut fill: Single<&mut Node, With<MorphSliderFill>>,
+    mut label: Single<&mut Text, With<MorphLabel>>,
+    locale: Res<Locale>,
+) {
+    if !((morph.is_changed() && !morph.is_added()) || locale.is_changed()) {
+        return;
+    }
+    fill.width = Val::Percent(morph.t * 100.);
+    label.0 = morph_label(&locale, morph.t);
+}
+
+/// Keeps configs and labels in sync with the morph, whichever backend rebuilds the mesh.
+/// Staffs are renamed for their new config by [`LocalePlugin`](crate::locale::LocalePlugin).
+fn reconfigure_morphed_staff(
+    morph: Res<StaffMorph>,
+    mut staffs: Query<(Entity, &mut StaffConfig, &mut Sockets), With<Staff>>,
+    mut labels: Query<&mut WorldLabel>,
+) {
+    if !morph.is_changed() || morph.is_added() {
+        return;
+    }
+    let config = morph.config();
+    // The staff keeps its last valid config, the rebuild reports why this one isn't
+    if config.validate().is_err() {
+        return;
+    }
+    let new_sockets = config.sockets();
+    for (entity, mut staff_config, mut sockets) in &mut staffs {
+        *staff_config = config.clone();
+        *sockets = new_sockets.clone();
+        for mut label in labels.iter_mut().filter(|label| label.target == entity) {
+            label.offset = label_offset(&new_sockets);
+        }
+    }
+}
+
+fn rebuild_morphed_staff(
+    morph: Res<StaffMorph>,
+    mut scheduler: ResMut<RebuildScheduler>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut staffs: Query<(Entity, &Mesh3d, &mut Transform), With<Staff>>,
+    mut mesh_gen: MeshGenMessages,
+) {
+    let changed = morph.is_changed() && !morph.is_added();
+    for (entity, mesh, mut transform) in &mut staffs {
+        let Some(rebuild) = scheduler.rebuild(entity, changed) else {
+            continue;
+        };
+        let config = morph.config();
+        let styled = mesh_gen.style().staff(&rebuild.staff(&config));
+        let buffers = mesh_gen.buffers();
+        let Some(new_mesh) = mesh_gen.try_rebuild(entity, GeneratorKind::Staff, rebuild, || {
+            styled.try_generate_mesh_in(buffers)
+        }) else {
+            continue;
+        };
+        if let Some(mesh) = meshes.get_mut(&mesh.0) {
+            mesh_gen.replace(mesh, new_mesh);
+        }
+        transform.translation = staff_translation(&config);
+    }
+}
@@ -0,0 +1,106 @@
+use bevy::color::palettes::css;
+use bevy::mesh::morph::MeshMorphWeights;
+use bevy::prelude::*;
+use staff_gen::morph_targets::StaffMorphTargets;
+use staff_gen::staff::StaffConfig;
+
+use crate::environment::FLOOR_HEIGHT;
+use crate::generation::{GeneratedObject, GeneratorKind, MeshGenMessages};
+
+/// How far staffs with morph targets are blended from straight (0) to gnarled (1).
+/// Hold `[` or `]` to change it. Blending happens on the GPU, the mesh is never rebuilt.
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
+pub struct GnarlBlend {
+    pub weight: f32,
+    /// Weight change per second while a key is held
+    pub speed: f32,
+}
+
+impl Default for GnarlBlend {
+    fn default() -> Self {
+        Self {
+            weight: 0.5,
+            speed: 0.5,
+        }
+    }
+}
+
+/// A staff whose mesh carries straight and gnarled morph targets.
+#[derive(Component, Debug)]
+pub struct MorphTargetStaff;
+
+pub struct MorphTargetPlugin;
+
+impl Plugin for MorphTargetPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<GnarlBlend>()
+            .init_resource::<GnarlBlend>()
+            .add_systems(Update, (adjust_gnarl_blend, apply_gnarl_blend).chain());
+    }
+}
+
+pub fn spawn_morph_target_staff(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    images: &mut ResMut<Assets<Image>>,
+    mesh_gen: &mut MeshGenMessages,
+    config: &StaffConfig,
+    weight: f32,
+) {
+    let targets = match StaffMorphTargets::try_straight_and_gnarled(config) {
+        Ok(targets) => targets,
+        Err(error) => {
+            warn!("Can't build staff morph targets: {error}");
+            return;
+        }
+    };
+    let image = match targets.image() {
+        Ok(image) => images.add(image.0),
+        Err(error) => {
+            warn!("Can't build staff morph targets: {error}");
+            return;
+        }
+    };
+    let entity = commands.spawn(Name::new("MorphTargetStaff")).id();
+    let mesh = mesh_gen.generate(entity, GeneratorKind::Staff, || targets.into_mesh(image));
+    commands.entity(entity).insert((
+        MorphTargetStaff,
+        Mesh3d(meshes.add(mesh)),
+        MeshMaterial3d(materials.add(Color::from(css::SIENNA))),
+        MeshMorphWeights::new(vec![weight]).expect("a single morph target"),
+        Transform::from_xyz(-3., config.height / 2. + FLOOR_HEIGHT / 2. + 0.5, 1.),
+        GeneratedObject(GeneratorKind::Staff),
+    ));
+}
+
+fn adjust_gnarl_blend(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut blend: ResMut<GnarlBlend>,
+) {
+    let direction = match (
+        keyboard_input.pressed(KeyCode::BracketLeft),
+        keyboard_input.pressed(KeyCode::BracketRight),
+    ) {
+        (true, false) => -1.,
+        (false, true) => 1.,
+        _ => return,
+    };
+    blend.weight = (blend.weight + direction * blend.speed * time.delta_secs()).clamp(0., 1.);
+}
+
+fn apply_gnarl_blend(
+    blend: Res<GnarlBlend>,
+    mut staffs: Query<&mut MeshMorphWeights, With<MorphTargetStaff>>,
+) {
+    if !blend.is_changed() {
+        return;
+    }
+    for mut weights in &mut staffs {
+        if let Some(weight) = weights.weights_mut().first_mut() {
+            *weight = blend.weight;
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use std::any::TypeId;
+
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use bevy::reflect::{ReflectMut, ReflectRef};
+use staff_gen::cone::ConeConfig;
+use staff_gen::crystal::CrystalConfig;
+use staff_gen::cylinder::CylinderConfig;
+use staff_gen::staff::StaffConfig;
+use staff_gen::wrapping::WrappingConfig;
+
+use crate::generation::{GeneratedObject, GeneratorKind, MeshGenCompleted};
+use crate::labels::StaffName;
+use crate::locale::Locale;
+use crate::randomizer::StaffRandomizerSettings;
+use crate::selection::Selected;
+use crate::staff::Staff;
+use crate::state::AppState;
+
+const INSPECTOR_FONT_SIZE: f32 = 12.;
+/// Fraction of a number's value one click of its buttons moves it by
+const INSPECTOR_STEP: f32 = 0.1;
+/// Smallest step, so numbers at zero can still move
+const INSPECTOR_MIN_STEP: f32 = 0.01;
+
+/// The generated object the inspector shows. Changes whenever the panel needs redrawing.
+#[derive(Resource, Debug, Default)]
+struct InspectedObject(Option<Entity>);
+
+#[derive(Component, Debug)]
+struct ObjectInspectorPanel;
+
+/// A button nudging one field of the config `entity` was generated from.
+#[derive(Component, Debug, Clone, Copy)]
+struct FieldEdit {
+    entity: Entity,
+    kind: GeneratorKind,
+    field: usize,
+    /// Up or down for numbers, switches flip either way
+    up: bool,
+}
+
+/// A button locking one field of the viewer's staff against "I'm feeling lucky".
+#[derive(Component, Debug)]
+struct FieldLock(String);
+
+/// A panel showing the selected generated object's generator, config, triangle count and
+/// material while editing. Nudging a config field regenerates the object.
+pub struct ObjectInspectorPlugin;
+
+impl Plugin for ObjectInspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InspectedObject>()
+            .add_observer(edit_config_field)
+            .add_observer(toggle_field_lock)
+            .add_systems(Startup, setup_object_inspector)
+            .add_systems(OnEnter(AppState::Editing), refresh_object_inspector)
+            .add_systems(OnExit(AppState::Editing), hide_object_inspector)
+            .add_systems(
+                Update,
+                (
+                    track_inspected_object,
+                    rebuild_object_inspector.run_if(
+                        resource_changed::<InspectedObject>
+                            .or(resource_changed::<Locale>)
+                            .or(resource_changed::<StaffRandomizerSettings>),
+                    ),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Editing)),
+            );
+    }
+}
+
+/// Type of the config component a generator builds its mesh from, if it has one.
+fn config_type(kind: GeneratorKind) -> Option<TypeId> {
+    match kind {
+        GeneratorKind::Staff => Some(TypeId::of::<StaffConfig>()),
+        GeneratorKind::Crystal => Some(TypeId::of::<CrystalConfig>()),
+        GeneratorKind::Cone => Some(TypeId::of::<ConeConfig>()),
+        GeneratorKind::Cylinder => Some(TypeId::of::<CylinderConfig>()),
+        GeneratorKind::Wrapping => Some(TypeId::of::<WrappingConfig>()),
+        GeneratorKind::Cube | GeneratorKind::Assembly => None,
+    }
+}
+
+fn setup_object_inspector(mut commands: Commands) {
+    commands.spawn((
+        Name::new("ObjectInspectorPanel"),
+        ObjectInspectorPanel,
+        Visibility::Hidden,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.),
+            left: Val::Px(12.),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(2.),
+            padding: UiRect::all(Val::Px(6.)),
+            ..default()
+        },
+        BackgroundColor(Color::from(css::DARK_SLATE_GRAY).with_alpha(0.8)),
+    ));
+}
+
+fn refresh_object_inspector(mut inspected: ResMut<InspectedObject>) {
+    inspected.set_changed();
+}
+
+fn hide_object_inspector(mut panel: Single<&mut Visibility, With<ObjectInspectorPanel>>) {
+    **panel = Visibility::Hidden;
+}
+
+/// Follows the selection, and redraws once the inspected object is regenerated.
+fn track_inspected_object(
+    selected: Query<Entity, With<Selected>>,
+    mut completed: MessageReader<MeshGenCompleted>,
+    mut inspected: ResMut<InspectedObject>,
+) {
+    let current = selected.iter().next();
+    let regenerated = completed
+        .read()
+        .any(|completed| Some(completed.entity) == current);
+    if inspected.0 != current || regenerated {
+        inspected.0 = current;
+    }
+}
+
+/// What an inspector row shows for a config field.
+enum FieldValue {
+    Number(String),
+    Switch(bool),
+    /// Shown but not editable here, like the variant of an enum
+    Fixed(String),
+}
+
+impl FieldValue {
+    fn of(value: &dyn PartialReflect) -> Option<Self> {
+        if let Some(value) = value.try_downcast_ref::<f32>() {
+            Some(Self::Number(format!("{value:.3}")))
+        } else if let Some(value) = value.try_downcast_ref::<u32>() {
+            Some(Self::Number(value.to_string()))
+        } else if let Some(value) = value.try_downcast_ref::<u64>() {
+            Some(Self::Number(value.to_string()))
+        } else if let Some(&value) = value.try_downcast_ref::<bool>() {
+            Some(Self::Switch(value))
+        } else if let ReflectRef::Enum(value) = value.reflect_ref() {
+            Some(Self::Fixed(value.variant_name().to_string()))
+        } else {
+            None
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn rebuild_object_inspector(
+    mut commands: Commands,
+    inspected: Res<InspectedObject>,
+    locale: Res<Locale>,
+    randomizer: Res<StaffRandomizerSettings>,
+    panel: Single<(Entity, &mut Visibility), With<ObjectInspectorPanel>>,
+    objects: Query<EntityRef, Without<ObjectInspectorPanel>>,
+    registry: Res<AppTypeRegistry>,
+    meshes: Res<Assets<Mesh>>,
+    materials: Res<Assets<StandardMaterial>>,
+) {
+    let (panel, mut visibility) = panel.into_inner();
+    commands.entity(panel).despawn_related::<Children>();
+    let Some((object, kind)) = inspected
+        .0
+        .and_then(|entity| objects.get(entity).ok())
+        .and_then(|object| Some((object, object.get::<GeneratedObject>()?.0)))
+    else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+
+    let text = |text: String, color: Srgba| {
+        (
+            Text::new(text),
+            TextFont::from_font_size(INSPECTOR_FONT_SIZE),
+            TextColor(Color::from(color)),
+        )
+    };
+    let name = object
+        .get::<StaffName>()
+        .map(|name| name.0.clone())
+        .or_else(|| object.get::<Name>().map(Name::to_string))
+        .unwrap_or_default();
+    let heading = format!("{}: {name}", kind.localized(&locale));
+    commands.spawn((text(heading, css::ORANGE), ChildOf(panel)));
+    if let Some(mesh) = object
+        .get::<Mesh3d>()
+        .and_then(|mesh3d| meshes.get(&mesh3d.0))
+    {
+        let corners = mesh
+            .indices()
+            .map_or(mesh.count_vertices(), |indices| indices.len());
+        let triangles = locale.format("inspector.triangles", &[("count", &(corners / 3))]);
+        commands.spawn((text(triangles, css::WHITE), ChildOf(panel)));
+    }
+    if let Some(material) = object
+        .get::<MeshMaterial3d<StandardMaterial>>()
+        .and_then(|material| materials.get(&material.0))
+    {
+        let material = locale.format(
+            "inspector.material",
+            &[
+                ("color", &material.base_color.to_srgba().to_hex()),
+                (
+                    "roughness",
+                    &format_args!("{:.2}", material.perceptual_roughness),
+                ),
+                ("metallic", &format_args!("{:.2}", material.metallic)),
+            ],
+        );
+        commands.spawn((text(material, css::WHITE), ChildOf(panel)));
+    }
+
+    let registry = registry.read();
+    let Some(config) = config_type(kind)
+        .and_then(|config_type| registry.get_type_data::<ReflectComponent>(config_type))
+        .and_then(|reflect_component| reflect_component.reflect(object))
+    else {
+        return;
+    };
+    let ReflectRef::Struct(config) = config.reflect_ref() else {
+        return;
+    };
+    // Only the viewer's staff is randomized
+    let lockable = object.contains::<Staff>();
+    for field in 0..config.field_len() {
+        let (Some(name), Some(value)) = (
+            config.name_at(field),
+            config.field_at(field).and_then(FieldValue::of),
+        ) else {
+            continue;
+        };
+        let edit = |up| FieldEdit {
+            entity: object.id(),
+            kind,
+            field,
+            up,
+        };
+        commands
+            .spawn((
+                Node {
+                    column_gap: Val::Px(6.),
+                    ..default()
+                },
+                ChildOf(panel),
+            ))
+            .with_children(|row| match value {
+                FieldValue::Number(value) => {
+                    row.spawn(text(format!("{name}: {value}"), css::WHITE));
+                    row.spawn((text("-".into(), css::LIGHT_GRAY), edit(false)));
+                    row.spawn((text("+".into(), css::LIGHT_GRAY), edit(true)));
+                }
+                FieldValue::Switch(on) => {
+                    row.spawn(text(format!("{name}:"), css::WHITE));
+                    let label = locale.text(if on { "inspector.on" } else { "inspector.off" });
+                    row.spawn((text(label.into(), css::LIGHT_GRAY), edit(true)));
+                }
+                FieldValue::Fixed(value) => {
+                    row.spawn(text(format!("{name}: {value}"), css::GRAY));
+                }
+            })
+            .with_children(|row| {
+                if lockable {
+                    let (label, color) = if randomizer.locked.contains(name) {
+                        ("inspector.locked", css::ORANGE)
+                    } else {
+                        ("inspector.lock", css::GRAY)
+                    };
+                    row.spawn((
+                        text(locale.text(label).into(), color),
+                        FieldLock(name.into()),
+                    ));
+                }
+            });
+    }
+}
+
+fn edit_config_field(
+    mut click: On<Pointer<Click>>,
+    mut commands: Commands,
+    edits: Query<&FieldEdit>,
+) {
+    let Ok(&edit) = edits.get(click.entity) else {
+        return;
+    };
+    click.propagate(false);
+    commands.queue(move |world: &mut World| nudge_config_field(world, edit));
+}
+
+fn toggle_field_lock(
+    mut click: On<Pointer<Click>>,
+    locks: Query<&FieldLock>,
+    mut randomizer: ResMut<StaffRandomizerSettings>,
+) {
+    let Ok(FieldLock(field)) = locks.get(click.entity) else {
+        return;
+    };
+    click.propagate(false);
+    if !randomizer.locked.remove(field) {
+        randomizer.locked.insert(field.clone());
+    }
+}
+
+/// Changes a config field through reflection, which rebuilds the object like any other edit.
+/// Numbers move by [`INSPECTOR_STEP`] of their value without crossing zero, counts by one.
+fn nudge_config_field(world: &mut World, edit: FieldEdit) {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let Some(reflect_component) = config_type(edit.kind)
+        .and_then(|config_type| registry.get_type_data::<ReflectComponent>(config_type))
+    else {
+        return;
+    };
+    let Ok(mut entity) = world.get_entity_mut(edit.entity) else {
+        return;
+    };
+    let Some(mut config) = reflect_component.reflect_mut(&mut entity) else {
+        return;
+    };
+    let ReflectMut::Struct(config) = config.reflect_mut() else {
+        return;
+    };
+    // Rings need at least three sides
+    let min_count = if config.name_at(edit.field) == Some("resolution") {
+        3
+    } else {
+        1
+    };
+    let Some(field) = config.field_at_mut(edit.field) else {
+        return;
+    };
+    if let Some(value) = field.try_downcast_mut::<f32>() {
+        let step = (value.abs() * INSPECTOR_STEP).max(INSPECTOR_MIN_STEP);
+        *value = match (edit.up, *value >= 0.) {
+            (true, _) => *value + step,
+            (false, true) => (*value - step).max(0.),
+            (false, false) => *value - step,
+        };
+    } else if let Some(value) = field.try_downcast_mut::<u32>() {
+        *value = if edit.up {
+            *value + 1
+        } else {
+            value.saturating_sub(1).max(min_count)
+        };
+    } else if let Some(value) = field.try_downcast_mut::<u64>() {
+        *value = if edit.up {
+            value.wrapping_add(1)
+        } else {
+            value.wrapping_sub(1)
+        };
+    } else if let Some(value) = field.try_downcast_mut::<bool>() {
+        *value = !*value;
+    }
+}
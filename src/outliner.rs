@@ -0,0 +1,347 @@
+use std::collections::HashSet;
+
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+
+use crate::generation::GeneratedObject;
+use crate::labels::StaffName;
+use crate::locale::Locale;
+use crate::selection::Selected;
+
+const OUTLINER_FONT_SIZE: f32 = 12.;
+/// Indentation of each level of the hierarchy, and the width of the expand button
+const OUTLINER_INDENT: f32 = 12.;
+/// Rows listed before the rest are cut off, which keeps the stress test from flooding the UI
+const MAX_OUTLINER_ROWS: usize = 200;
+
+/// Whether the outliner is open, and which of its entities list their children.
+#[derive(Resource, Debug, Default)]
+pub struct Outliner {
+    pub open: bool,
+    pub expanded: HashSet<Entity>,
+}
+
+#[derive(Component, Debug)]
+struct OutlinerPanel;
+
+/// Part of an outliner row that does `action` to `target` when clicked.
+#[derive(Component, Debug, Clone, Copy)]
+struct OutlinerButton {
+    target: Entity,
+    action: OutlinerAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutlinerAction {
+    Expand,
+    Select,
+    ToggleVisibility,
+    Delete,
+}
+
+/// A panel listing every named entity in the scene under its parent, where entities can be
+/// selected and hidden, and generated objects deleted. O opens and closes it.
+pub struct OutlinerPlugin;
+
+impl Plugin for OutlinerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Outliner>()
+            .add_observer(press_outliner_button)
+            .add_systems(Startup, setup_outliner)
+            .add_systems(Update, (toggle_outliner_on_key, rebuild_outliner).chain());
+    }
+}
+
+fn setup_outliner(mut commands: Commands) {
+    commands.spawn((
+        Name::new("OutlinerPanel"),
+        OutlinerPanel,
+        Visibility::Hidden,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(48.),
+            right: Val::Px(12.),
+            max_height: Val::Percent(80.),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(2.),
+            padding: UiRect::all(Val::Px(6.)),
+            overflow: Overflow::clip_y(),
+            ..default()
+        },
+        BackgroundColor(Color::from(css::DARK_SLATE_GRAY).with_alpha(0.8)),
+    ));
+}
+
+/// O opens and closes the outliner.
+fn toggle_outliner_on_key(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut outliner: ResMut<Outliner>,
+    mut panel: Single<&mut Visibility, With<OutlinerPanel>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyO) {
+        outliner.open = !outliner.open;
+        **panel = if outliner.open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+type OutlinerEntry = (
+    Entity,
+    &'static Name,
+    Option<&'static StaffName>,
+    Option<&'static Visibility>,
+    Option<&'static Children>,
+    Has<ChildOf>,
+    Has<Selected>,
+    Has<GeneratedObject>,
+);
+
+type OutlinerChanges = (
+    Or<(
+        Changed<Name>,
+        Changed<StaffName>,
+        Changed<ChildOf>,
+        Changed<Visibility>,
+        Added<Selected>,
+    )>,
+    // The outliner's own rows would otherwise rebuild it every frame
+    Without<Node>,
+);
+
+/// Lists the scene again whenever an entity is named, renamed, moved, hidden, selected or
+/// removed, or the language changes. UI nodes are left out.
+#[allow(clippy::too_many_arguments)]
+fn rebuild_outliner(
+    mut commands: Commands,
+    outliner: Res<Outliner>,
+    locale: Res<Locale>,
+    panel: Single<Entity, With<OutlinerPanel>>,
+    changed: Query<(), OutlinerChanges>,
+    mut removed_names: RemovedComponents<Name>,
+    mut removed_parents: RemovedComponents<ChildOf>,
+    mut removed_selections: RemovedComponents<Selected>,
+    entries: Query<OutlinerEntry, Without<Node>>,
+) {
+    // Read every removal, so none are left to trigger another rebuild next frame
+    let removed = removed_names.read().count()
+        + removed_parents.read().count()
+        + removed_selections.read().count()
+        > 0;
+    if !outliner.open
+        || !(outliner.is_changed() || locale.is_changed() || removed || !changed.is_empty())
+    {
+        return;
+    }
+
+    commands.entity(*panel).despawn_related::<Children>();
+    let mut roots: Vec<(&str, Entity)> = entries
+        .iter()
+        .filter(|&(.., has_parent, _, _)| !has_parent)
+        .map(|(entity, name, ..)| (name.as_str(), entity))
+        .collect();
+    roots.sort();
+    let mut stack: Vec<(Entity, usize)> = roots.iter().rev().map(|&(_, root)| (root, 0)).collect();
+    let mut rows = 0;
+    while let Some((entity, depth)) = stack.pop() {
+        if rows == MAX_OUTLINER_ROWS {
+            commands.spawn((
+                Text::new(locale.text("outliner.more")),
+                TextFont::from_font_size(OUTLINER_FONT_SIZE),
+                TextColor(Color::from(css::GRAY)),
+                ChildOf(*panel),
+            ));
+            break;
+        }
+        rows += 1;
+        let Ok((_, name, staff_name, visibility, children, _, selected, generated)) =
+            entries.get(entity)
+        else {
+            continue;
+        };
+        let named_children: Vec<Entity> = children
+            .map(|children| {
+                children
+                    .iter()
+                    .filter(|&child| entries.contains(child))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let expanded = outliner.expanded.contains(&entity);
+        let hidden = visibility == Some(&Visibility::Hidden);
+
+        commands
+            .spawn((
+                Node {
+                    padding: UiRect::left(Val::Px(depth as f32 * OUTLINER_INDENT)),
+                    column_gap: Val::Px(6.),
+                    ..default()
+                },
+                ChildOf(*panel),
+            ))
+            .with_children(|row| {
+                let expand = match (named_children.is_empty(), expanded) {
+                    (true, _) => "",
+                    (false, true) => "-",
+                    (false, false) => "+",
+                };
+                row.spawn((
+                    outliner_button(expand, css::WHITE, entity, OutlinerAction::Expand),
+                    Node {
+                        width: Val::Px(OUTLINER_INDENT),
+                        ..default()
+                    },
+                ));
+                let label = staff_name.map_or(name.as_str(), |staff_name| &staff_name.0);
+                let color = match (selected, hidden) {
+                    (true, _) => css::ORANGE,
+                    (false, true) => css::GRAY,
+                    (false, false) => css::WHITE,
+                };
+                row.spawn(outliner_button(
+                    label,
+                    color,
+                    entity,
+                    OutlinerAction::Select,
+                ));
+                if visibility.is_some() {
+                    row.spawn(outliner_button(
+                        locale.text(if hidden {
+                            "outliner.show"
+                        } else {
+                            "outliner.hide"
+                        }),
+                        css::LIGHT_GRAY,
+                        entity,
+                        OutlinerAction::ToggleVisibility,
+                    ));
+                }
+                // The camera, lights and floor are there for good
+                if generated {
+                    row.spawn(outliner_button(
+                        "x",
+                        css::TOMATO,
+                        entity,
+                        OutlinerAction::Delete,
+                    ));
+                }
+            });
+
+        if expanded {
+            stack.extend(named_children.iter().rev().map(|&child| (child, depth + 1)));
+        }
+    }
+}
+
+fn outliner_button(
+    text: &str,
+    color: Srgba,
+    target: Entity,
+    action: OutlinerAction,
+) -> impl Bundle {
+    (
+        OutlinerButton { target, action },
+        Text::new(text),
+        TextFont::from_font_size(OUTLINER_FONT_SIZE),
+        TextColor(Color::from(color)),
+    )
+}
+
+fn press_outliner_button(
+    mut click: On<Pointer<Click>>,
+    mut commands: Commands,
+    buttons: Query<&OutlinerButton>,
+    mut outliner: ResMut<Outliner>,
+    mut visibilities: Query<&mut Visibility>,
+    selected: Query<Entity, With<Selected>>,
+    generated: Query<(), With<GeneratedObject>>,
+) {
+    let Ok(&OutlinerButton { target, action }) = buttons.get(click.entity) else {
+        return;
+    };
+    click.propagate(false);
+    match action {
+        OutlinerAction::Expand => {
+            if !outliner.expanded.remove(&target) {
+                outliner.expanded.insert(target);
+            }
+        }
+        // The same selection clicking the object in the scene makes
+        OutlinerAction::Select => {
+            for entity in &selected {
+                commands.entity(entity).remove::<Selected>();
+            }
+            commands.entity(target).try_insert(Selected);
+        }
+        OutlinerAction::ToggleVisibility => {
+            if let Ok(mut visibility) = visibilities.get_mut(target) {
+                *visibility = match *visibility {
+                    Visibility::Hidden => Visibility::Inherited,
+                    _ => Visibility::Hidden,
+                };
+            }
+        }
+        OutlinerAction::Delete if generated.contains(target) => {
+            outliner.expanded.remove(&target);
+            commands.entity(target).try_despawn();
+        }
+        OutlinerAction::Delete => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::camera::NormalizedRenderTarget;
+    use bevy::picking::backend::HitData;
+    use bevy::picking::pointer::{Location, PointerButton, PointerId};
+
+    use super::*;
+    use crate::generation::GeneratorKind;
+
+    fn click(world: &mut World, button: Entity) {
+        world.trigger(Pointer::new(
+            PointerId::Mouse,
+            Location {
+                target: NormalizedRenderTarget::None {
+                    width: 1,
+                    height: 1,
+                },
+                position: Vec2::ZERO,
+            },
+            Click {
+                button: PointerButton::Primary,
+                hit: HitData::new(Entity::PLACEHOLDER, 0., None, None),
+                duration: Duration::ZERO,
+            },
+            button,
+        ));
+        world.flush();
+    }
+
+    #[test]
+    fn only_generated_objects_are_deleted() {
+        let mut app = App::new();
+        app.init_resource::<Outliner>()
+            .add_observer(press_outliner_button);
+        let world = app.world_mut();
+        let camera = world.spawn(Name::new("Camera")).id();
+        let staff = world
+            .spawn((Name::new("Staff"), GeneratedObject(GeneratorKind::Staff)))
+            .id();
+        for target in [camera, staff] {
+            let button = world
+                .spawn(OutlinerButton {
+                    target,
+                    action: OutlinerAction::Delete,
+                })
+                .id();
+            click(world, button);
+        }
+        assert!(world.get_entity(camera).is_ok());
+        assert!(world.get_entity(staff).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use staff_gen::staff::StaffConfig;
+
+use crate::actions::RegisterAction;
+use crate::debug_view::ShadedMaterial;
+use crate::morph::StaffMorph;
+use crate::staff::{StaffGem, StaffHeadPart};
+
+/// Stream of the seed's random numbers palettes are drawn from, apart from the shape's own
+const PALETTE_STREAM: u64 = 1;
+/// Hues of the wood, from reddish to yellowish browns
+const WOOD_HUES: (f32, f32) = (18., 42.);
+const WOOD: HslRamp = HslRamp {
+    saturation: (0.3, 0.55),
+    lightness: (0.18, 0.38),
+};
+const CRYSTAL: HslRamp = HslRamp {
+    saturation: (0.55, 0.9),
+    lightness: (0.55, 0.72),
+};
+const ACCENT: HslRamp = HslRamp {
+    saturation: (0.45, 0.75),
+    lightness: (0.45, 0.6),
+};
+
+/// Saturation and lightness bounds a palette color of some hue is drawn from.
+#[derive(Debug, Clone, Copy)]
+struct HslRamp {
+    saturation: (f32, f32),
+    lightness: (f32, f32),
+}
+
+impl HslRamp {
+    fn sample(&self, hue: f32, rand: &mut impl Rng) -> Color {
+        Color::hsl(
+            hue.rem_euclid(360.),
+            rand.random_range(self.saturation.0..self.saturation.1),
+            rand.random_range(self.lightness.0..self.lightness.1),
+        )
+    }
+}
+
+/// Colors of a staff's shaft, gem and trim.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub struct StaffPalette {
+    pub wood: Color,
+    pub crystal: Color,
+    pub accent: Color,
+}
+
+impl StaffPalette {
+    /// Wood from a range of browns, a gem of any hue and a trim split complementary to the gem,
+    /// a little to one side of its opposite hue.
+    pub fn from_seed(seed: u64) -> Self {
+        let mut rand = ChaCha8Rng::seed_from_u64(seed);
+        rand.set_stream(PALETTE_STREAM);
+        let wood_hue = rand.random_range(WOOD_HUES.0..WOOD_HUES.1);
+        let crystal_hue = rand.random_range(0. ..360.);
+        let accent_hue = crystal_hue + if rand.random() { 150. } else { 210. };
+        Self {
+            wood: WOOD.sample(wood_hue, &mut rand),
+            crystal: CRYSTAL.sample(crystal_hue, &mut rand),
+            accent: ACCENT.sample(accent_hue, &mut rand),
+        }
+    }
+}
+
+/// Which palette staffs are drawn in, edited from the inspector. Each staff's palette follows
+/// its seed unless the palette is locked, and overridden colors replace the seed's.
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct PaletteSettings {
+    /// Seed every staff takes its palette from instead of its own
+    pub locked_seed: Option<u64>,
+    pub wood: Option<Color>,
+    pub crystal: Option<Color>,
+    pub accent: Option<Color>,
+}
+
+impl PaletteSettings {
+    pub fn palette(&self, seed: u64) -> StaffPalette {
+        let palette = StaffPalette::from_seed(self.locked_seed.unwrap_or(seed));
+        StaffPalette {
+            wood: self.wood.unwrap_or(palette.wood),
+            crystal: self.crystal.unwrap_or(palette.crystal),
+            accent: self.accent.unwrap_or(palette.accent),
+        }
+    }
+}
+
+/// The object's own material, also while a debug view has replaced it.
+type PaletteMaterial = (
+    Option<&'static MeshMaterial3d<StandardMaterial>>,
+    Option<&'static ShadedMaterial>,
+);
+
+/// Colors every staff from a palette of its seed. Locking the palette from the command palette
+/// keeps the viewer staff's current colors on every staff while its seed changes.
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PaletteSettings>()
+            .init_resource::<PaletteSettings>()
+            .register_action("Toggle palette lock", toggle_palette_lock)
+            // After the commands spawning staffs and their heads in `Update` are applied
+            .add_systems(PostUpdate, apply_staff_palettes);
+    }
+}
+
+fn toggle_palette_lock(mut settings: ResMut<PaletteSettings>, morph: Res<StaffMorph>) {
+    settings.locked_seed = match settings.locked_seed {
+        Some(_) => None,
+        None => Some(morph.config().seed),
+    };
+}
+
+fn apply_staff_palettes(
+    settings: Res<PaletteSettings>,
+    staffs: Query<(Ref<StaffConfig>, PaletteMaterial)>,
+    parts: Query<(Ref<StaffHeadPart>, &ChildOf, Has<StaffGem>, PaletteMaterial)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (config, material) in &staffs {
+        if settings.is_changed() || config.is_changed() {
+            set_color(&mut materials, material, settings.palette(config.seed).wood);
+        }
+    }
+    for (part, child_of, is_gem, material) in &parts {
+        let Ok((config, _)) = staffs.get(child_of.parent()) else {
+            continue;
+        };
+        if settings.is_changed() || config.is_changed() || part.is_added() {
+            let palette = settings.palette(config.seed);
+            let color = if is_gem {
+                palette.crystal
+            } else {
+                palette.accent
+            };
+            set_color(&mut materials, material, color);
+        }
+    }
+}
+
+fn set_color(
+    materials: &mut Assets<StandardMaterial>,
+    (material, shaded): (
+        Option<&MeshMaterial3d<StandardMaterial>>,
+        Option<&ShadedMaterial>,
+    ),
+    color: Color,
+) {
+    let handle = material
+        .map(|material| &material.0)
+        .or(shaded.map(|shaded| &shaded.0));
+    if let Some(material) = handle.and_then(|handle| materials.get_mut(handle)) {
+        material.base_color = color;
+    }
+}
@@ -0,0 +1,130 @@
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use staff_gen::pedestal::PedestalConfig;
+use staff_gen::staff::StaffConfig;
+
+use crate::actions::RegisterAction;
+use crate::environment::FLOOR_HEIGHT;
+use crate::staff::{GenerateStaff, Staff};
+
+/// Where "Display staff on pedestal" puts the pedestal, on the floor
+const DISPLAY_POSITION: Vec2 = vec2(3., 3.);
+
+/// Whether gallery staffs and thumbnails stand on a pedestal, and what it looks like.
+#[derive(Resource, Reflect, Clone, Debug)]
+#[reflect(Resource)]
+pub struct PedestalDisplay {
+    pub enabled: bool,
+    pub pedestal: PedestalConfig,
+}
+
+impl Default for PedestalDisplay {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pedestal: PedestalConfig::default(),
+        }
+    }
+}
+
+impl PedestalDisplay {
+    /// The pedestal under `staff`, relative to the staff, or `None` when pedestals are off.
+    pub fn under(&self, staff: &StaffConfig) -> Option<Transform> {
+        self.enabled
+            .then(|| Transform::from_translation(-self.pedestal.staff_transform(staff).translation))
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct PedestalMaterial(pub Handle<StandardMaterial>);
+
+/// The pedestal "Display staff on pedestal" last spawned, with the displayed staff on it.
+#[derive(Component, Debug)]
+struct DisplayStand;
+
+/// Stone pedestals to show staffs on, under the gallery and thumbnail staffs and for a copy of
+/// the viewer's staff put on display from the command palette.
+pub struct PedestalPlugin;
+
+impl Plugin for PedestalPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PedestalDisplay>()
+            .register_type::<PedestalConfig>()
+            .init_resource::<PedestalDisplay>()
+            .register_action(
+                "Toggle display pedestals",
+                |mut display: ResMut<PedestalDisplay>| {
+                    display.enabled = !display.enabled;
+                },
+            )
+            .register_action("Display staff on pedestal", display_staff)
+            .add_systems(Startup, setup_pedestal_material);
+    }
+}
+
+fn setup_pedestal_material(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(PedestalMaterial(materials.add(StandardMaterial {
+        base_color: Color::from(css::LIGHT_GRAY),
+        perceptual_roughness: 0.8,
+        ..default()
+    })));
+}
+
+pub fn spawn_pedestal(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    material: &PedestalMaterial,
+    config: PedestalConfig,
+    transform: Transform,
+) -> Entity {
+    commands
+        .spawn((
+            Name::new("Pedestal"),
+            Mesh3d(meshes.add(config.generate_mesh())),
+            MeshMaterial3d(material.0.clone()),
+            transform,
+            config.sockets(),
+            config,
+        ))
+        .id()
+}
+
+/// Puts a copy of the viewer's staff upright on a pedestal, replacing the last one displayed.
+fn display_staff(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut generate_staff: MessageWriter<GenerateStaff>,
+    display: Res<PedestalDisplay>,
+    material: Res<PedestalMaterial>,
+    staff: Single<&StaffConfig, With<Staff>>,
+    stands: Query<Entity, With<DisplayStand>>,
+) {
+    for stand in &stands {
+        commands.entity(stand).despawn();
+    }
+    let config = display.pedestal.clone();
+    let staff_config = (*staff).clone();
+    let on_top = config.staff_transform(&staff_config);
+    let stand = spawn_pedestal(
+        &mut commands,
+        &mut meshes,
+        &material,
+        config.clone(),
+        Transform::from_xyz(
+            DISPLAY_POSITION.x,
+            FLOOR_HEIGHT / 2. + config.height / 2.,
+            DISPLAY_POSITION.y,
+        ),
+    );
+    commands.entity(stand).insert(DisplayStand);
+    let staff = commands.spawn(ChildOf(stand)).id();
+    generate_staff.write(GenerateStaff {
+        config: staff_config,
+        seed: None,
+        transform: on_top,
+        entity: Some(staff),
+    });
+}
@@ -0,0 +1,168 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use staff_gen::sockets::{self, Sockets};
+use staff_gen::staff::StaffConfig;
+
+use crate::character::Character;
+use crate::environment::FLOOR_HEIGHT;
+use crate::state::AppState;
+use crate::units::UnitsConfig;
+
+/// Where Laura's right hand is in her T-pose, relative to her feet, facing +Z
+const HAND_OFFSET: Vec3 = vec3(-0.72, 1.38, 0.03);
+/// How far from the character a staff can be picked up, in meters
+const REACH: f32 = 1.2;
+/// Meters per second squared
+const GRAVITY: f32 = 9.81;
+/// Tilt a dropped staff starts toppling with, since a perfectly upright one would stay standing
+const TOPPLE_TILT: f32 = 0.05;
+
+/// The point on the character staffs are held at.
+#[derive(Component, Debug)]
+pub struct HandSocket;
+
+/// A staff in the character's hand.
+#[derive(Component, Debug)]
+pub struct Held;
+
+/// A dropped staff falling until its base reaches the floor, then toppling over onto its side.
+#[derive(Component, Debug, Default)]
+struct Falling {
+    /// Downward speed in scene units per second
+    speed: f32,
+    topple: Option<Topple>,
+}
+
+/// A staff tipping over around the point its base touches the floor, like a rod pivoting on
+/// its end.
+#[derive(Debug)]
+struct Topple {
+    pivot: Vec3,
+    axis: Dir3,
+    angle: f32,
+    angular_velocity: f32,
+    /// The staff's transform when its base landed
+    landed: Transform,
+}
+
+/// E picks up the nearest staff within reach while walking around as Laura, attaching it at its
+/// grip socket to her hand, and drops it again. Dropped staffs fall and topple over away from her.
+/// Held staffs can be planted on the ground, see [`StaffPlantPlugin`](crate::staff_plant::StaffPlantPlugin).
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                add_hand_socket,
+                pick_up_or_drop.run_if(in_state(AppState::Walking)).run_if(
+                    |keyboard_input: Res<ButtonInput<KeyCode>>| {
+                        keyboard_input.just_pressed(KeyCode::KeyE)
+                    },
+                ),
+                fall,
+            ),
+        );
+    }
+}
+
+fn add_hand_socket(mut commands: Commands, characters: Query<Entity, Added<Character>>) {
+    for character in &characters {
+        commands.spawn((
+            Name::new("HandSocket"),
+            HandSocket,
+            Transform::from_translation(HAND_OFFSET),
+            Visibility::default(),
+            ChildOf(character),
+        ));
+    }
+}
+
+type LooseStaff = (Entity, &'static GlobalTransform, &'static Sockets);
+
+fn pick_up_or_drop(
+    mut commands: Commands,
+    units: Res<UnitsConfig>,
+    character: Single<&GlobalTransform, With<Character>>,
+    hand: Single<Entity, With<HandSocket>>,
+    held: Query<(Entity, &GlobalTransform), With<Held>>,
+    staffs: Query<LooseStaff, (With<StaffConfig>, Without<Held>)>,
+) {
+    if let Some((staff, transform)) = held.iter().next() {
+        commands
+            .entity(staff)
+            .remove::<(Held, ChildOf)>()
+            .insert((transform.compute_transform(), Falling::default()));
+        return;
+    }
+    let position = character.translation();
+    let reach = REACH * units.from_meters();
+    let nearest = staffs
+        .iter()
+        .map(|(staff, transform, sockets)| {
+            let offset = (transform.translation() - position).with_y(0.);
+            (staff, offset.length(), sockets)
+        })
+        .filter(|(_, distance, _)| *distance <= reach)
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+    let Some((staff, _, sockets)) = nearest else {
+        return;
+    };
+    let grip = sockets.get(sockets::GRIP).unwrap_or_default();
+    commands.entity(staff).remove::<Falling>().insert((
+        Held,
+        ChildOf(*hand),
+        Transform::from_matrix(grip.to_matrix().inverse()),
+    ));
+}
+
+/// Staffs are pushed out of the floor when they land, and stop once they lie flat.
+fn fall(
+    mut commands: Commands,
+    time: Res<Time>,
+    units: Res<UnitsConfig>,
+    character: Single<&GlobalTransform, With<Character>>,
+    mut staffs: Query<(Entity, &mut Transform, &mut Falling, &Sockets)>,
+) {
+    let delta = time.delta_secs();
+    let gravity = GRAVITY * units.from_meters();
+    for (staff, mut transform, mut falling, sockets) in &mut staffs {
+        let bottom = sockets.get(sockets::BOTTOM).unwrap_or_default().translation;
+        let Some(topple) = &mut falling.topple else {
+            falling.speed += gravity * delta;
+            transform.translation.y -= falling.speed * delta;
+            let base = transform.transform_point(bottom);
+            let floor = FLOOR_HEIGHT / 2.;
+            if base.y > floor {
+                continue;
+            }
+            transform.translation.y += floor - base.y;
+            // Away from the character that dropped it
+            let away = (base - character.translation()).with_y(0.);
+            let axis = Dir3::new(Vec3::Y.cross(away)).unwrap_or(Dir3::X);
+            falling.topple = Some(Topple {
+                pivot: base.with_y(floor),
+                axis,
+                angle: TOPPLE_TILT,
+                angular_velocity: 0.,
+                landed: *transform,
+            });
+            continue;
+        };
+
+        let top = sockets.get(sockets::TOP).unwrap_or_default().translation;
+        let length = bottom.distance(top).max(f32::EPSILON);
+        // A thin rod pivoting on its end
+        topple.angular_velocity += 3. * gravity / (2. * length) * topple.angle.sin() * delta;
+        topple.angle = (topple.angle + topple.angular_velocity * delta).min(FRAC_PI_2);
+        let rotation = Quat::from_axis_angle(*topple.axis, topple.angle);
+        transform.rotation = rotation * topple.landed.rotation;
+        transform.translation =
+            topple.pivot + rotation * (topple.landed.translation - topple.pivot);
+        if topple.angle >= FRAC_PI_2 {
+            commands.entity(staff).remove::<Falling>();
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use bevy::camera::primitives::Aabb;
+use bevy::prelude::*;
+
+use crate::camera::MainCamera;
+use crate::environment::FLOOR_HEIGHT;
+use crate::generation::GeneratedObject;
+use crate::selection::world_aabb;
+use crate::state::AppState;
+
+/// How generated objects snap while being dragged around in editing mode.
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct PlacementSettings {
+    /// Snap to the floor grid. Holding Ctrl while dragging inverts this.
+    pub grid_snap: bool,
+    pub cell_size: f32,
+    /// Rest the bottom of the object's bounding box on the floor
+    pub surface_snap: bool,
+}
+
+impl Default for PlacementSettings {
+    fn default() -> Self {
+        Self {
+            grid_snap: false,
+            cell_size: 0.25,
+            surface_snap: true,
+        }
+    }
+}
+
+impl PlacementSettings {
+    /// Where to put an object dragged to `translation`, with the current snapping applied.
+    /// `bottom` is the height of the object's lowest point above its origin, see [`bottom`].
+    pub fn snap(&self, mut translation: Vec3, invert_grid: bool, bottom: Option<f32>) -> Vec3 {
+        if self.grid_snap != invert_grid && self.cell_size > 0. {
+            translation.x = (translation.x / self.cell_size).round() * self.cell_size;
+            translation.z = (translation.z / self.cell_size).round() * self.cell_size;
+        }
+        if self.surface_snap
+            && let Some(bottom) = bottom
+        {
+            translation.y = FLOOR_HEIGHT / 2. - bottom;
+        }
+        translation
+    }
+}
+
+/// Height of the lowest point of `entity` and its parts above its origin, in world space, so
+/// tilted and toppled objects rest on their lowest corner.
+pub fn bottom(
+    entity: Entity,
+    bounds: &Query<(&Aabb, &GlobalTransform)>,
+    children: &Query<&Children>,
+    transform: &GlobalTransform,
+) -> Option<f32> {
+    let aabb = world_aabb(entity, bounds, children)?;
+    Some(aabb.min.y - transform.translation().y)
+}
+
+/// Offset from the grabbed point to the object's origin, and the height it is dragged at.
+#[derive(Component, Debug)]
+struct Dragged {
+    offset: Vec3,
+    height: f32,
+}
+
+pub struct PlacementPlugin;
+
+impl Plugin for PlacementPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PlacementSettings>()
+            .init_resource::<PlacementSettings>()
+            .add_observer(start_drag)
+            .add_observer(drag_object)
+            .add_observer(end_drag);
+    }
+}
+
+fn start_drag(
+    mut drag: On<Pointer<DragStart>>,
+    mut commands: Commands,
+    state: Res<State<AppState>>,
+    objects: Query<&GlobalTransform, With<GeneratedObject>>,
+) {
+    if *state.get() != AppState::Editing || drag.event.button != PointerButton::Primary {
+        return;
+    }
+    let Ok(transform) = objects.get(drag.entity) else {
+        return;
+    };
+    let Some(position) = drag.event.hit.position else {
+        return;
+    };
+    drag.propagate(false);
+    commands.entity(drag.entity).insert(Dragged {
+        offset: transform.translation() - position,
+        height: position.y,
+    });
+}
+
+fn drag_object(
+    drag: On<Pointer<Drag>>,
+    settings: Res<PlacementSettings>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut objects: Query<(&Dragged, &mut Transform, &GlobalTransform)>,
+    bounds: Query<(&Aabb, &GlobalTransform)>,
+    children: Query<&Children>,
+) {
+    let Ok((dragged, mut transform, global)) = objects.get_mut(drag.entity) else {
+        return;
+    };
+    let (camera, camera_transform) = *camera;
+    let Ok(ray) = camera.viewport_to_world(camera_transform, drag.pointer_location.position) else {
+        return;
+    };
+    // Slide along the horizontal plane through the grabbed point
+    let plane = InfinitePlane3d::new(Vec3::Y);
+    let origin = Vec3::Y * dragged.height;
+    let Some(distance) = ray.intersect_plane(origin, plane) else {
+        return;
+    };
+    let translation = ray.get_point(distance) + dragged.offset;
+    // Only rotation and scale move the bottom relative to the origin, so last frame's global
+    // transform is as good as this one's
+    transform.translation = settings.snap(
+        translation,
+        keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]),
+        bottom(drag.entity, &bounds, &children, global),
+    );
+}
+
+fn end_drag(drag: On<Pointer<DragEnd>>, mut commands: Commands, objects: Query<(), With<Dragged>>) {
+    if objects.contains(drag.entity) {
+        commands.entity(drag.entity).remove::<Dragged>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn toppled_objects_rest_on_their_lowest_point() {
+        let mut world = World::new();
+        // A staff lying on its side is only as tall as it is thick
+        let staff = world
+            .spawn((
+                Aabb::from_min_max(vec3(-0.05, -1., -0.05), vec3(0.05, 1., 0.05)),
+                GlobalTransform::from(
+                    Transform::from_xyz(0., 3., 0.)
+                        .with_rotation(Quat::from_rotation_z(FRAC_PI_2))
+                        .with_scale(Vec3::splat(2.)),
+                ),
+            ))
+            .id();
+        let bottom = world
+            .run_system_once(
+                move |bounds: Query<(&Aabb, &GlobalTransform)>,
+                      children: Query<&Children>,
+                      transforms: Query<&GlobalTransform>| {
+                    bottom(staff, &bounds, &children, transforms.get(staff).unwrap())
+                },
+            )
+            .unwrap();
+        let translation = PlacementSettings::default().snap(Vec3::ZERO, false, bottom);
+        assert!(
+            (translation.y - (FLOOR_HEIGHT / 2. + 0.1)).abs() < 1e-5,
+            "{translation}"
+        );
+    }
+}
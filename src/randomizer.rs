@@ -0,0 +1,104 @@
+use std::collections::HashSet;
+
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use staff_gen::randomize::StaffRandomizer;
+
+use crate::actions::RegisterAction;
+use crate::locale::Locale;
+use crate::morph::{SLIDER_WIDTH, StaffMorph};
+use crate::state::AppState;
+use crate::units::UnitsConfig;
+
+/// Bounds for randomized staffs, and the [`StaffConfig`](staff_gen::staff::StaffConfig) fields
+/// that stay as they are. Fields are locked from the object inspector.
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct StaffRandomizerSettings {
+    pub randomizer: StaffRandomizer,
+    pub locked: HashSet<String>,
+}
+
+#[derive(Component, Debug)]
+struct LuckyButton;
+
+/// Rolls every unlocked parameter of the viewer's staff at once, from the "I'm feeling lucky"
+/// button beside the morph slider or the command palette.
+pub struct RandomizerPlugin;
+
+impl Plugin for RandomizerPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<StaffRandomizerSettings>()
+            .init_resource::<StaffRandomizerSettings>()
+            .register_action("Randomize staff", randomize_staff)
+            .add_systems(Startup, setup_lucky_button)
+            .add_systems(OnEnter(AppState::Editing), show_lucky_button)
+            .add_systems(OnExit(AppState::Editing), hide_lucky_button)
+            .add_systems(
+                Update,
+                update_lucky_button.run_if(resource_changed::<Locale>),
+            );
+    }
+}
+
+fn setup_lucky_button(mut commands: Commands, locale: Res<Locale>) {
+    commands
+        .spawn((
+            Name::new("LuckyButton"),
+            LuckyButton,
+            Visibility::Hidden,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(12.),
+                left: Val::Px(SLIDER_WIDTH + 24.),
+                padding: UiRect::axes(Val::Px(8.), Val::Px(2.)),
+                ..default()
+            },
+            BackgroundColor(Color::from(css::SADDLE_BROWN)),
+            children![(
+                Text::new(locale.text("randomizer.button")),
+                TextFont::from_font_size(14.),
+            )],
+        ))
+        .observe(|mut click: On<Pointer<Click>>, mut commands: Commands| {
+            click.propagate(false);
+            commands.run_system_cached(randomize_staff);
+        });
+}
+
+fn show_lucky_button(mut button: Single<&mut Visibility, With<LuckyButton>>) {
+    **button = Visibility::Inherited;
+}
+
+fn hide_lucky_button(mut button: Single<&mut Visibility, With<LuckyButton>>) {
+    **button = Visibility::Hidden;
+}
+
+fn update_lucky_button(
+    locale: Res<Locale>,
+    button: Single<&Children, With<LuckyButton>>,
+    mut texts: Query<&mut Text>,
+) {
+    for &child in *button {
+        if let Ok(mut text) = texts.get_mut(child) {
+            text.0 = locale.text("randomizer.button").into();
+        }
+    }
+}
+
+/// The viewer's staff follows the morph, so the randomized staff becomes the morph's start,
+/// with the slider moved back there. Bounds are in meters and converted to scene units.
+fn randomize_staff(
+    settings: Res<StaffRandomizerSettings>,
+    units: Res<UnitsConfig>,
+    mut morph: ResMut<StaffMorph>,
+) {
+    let current = morph.config().scaled(units.meters_per_unit);
+    let config = settings
+        .randomizer
+        .randomize(&current, &settings.locked, &mut rand::rng())
+        .scaled(units.from_meters());
+    info!("Randomized the staff, seed {}", config.seed);
+    morph.from = config;
+    morph.t = 0.;
+}
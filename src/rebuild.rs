@@ -0,0 +1,137 @@
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use staff_gen::staff::StaffConfig;
+
+/// How to rebuild an object's mesh this frame, from [`RebuildScheduler::rebuild`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rebuild {
+    /// While scrubbing, at [`RebuildScheduler::preview_detail`] and without the index
+    /// optimization
+    Preview(f32),
+    Full,
+}
+
+impl Rebuild {
+    /// `config` with its ring resolution cut for previews. The resolution doesn't change the
+    /// staff's shape, which its segments would.
+    pub fn staff(self, config: &StaffConfig) -> StaffConfig {
+        match self {
+            Self::Preview(detail) => StaffConfig {
+                resolution: ((config.resolution as f32 * detail).round() as u32)
+                    .max(3)
+                    .min(config.resolution),
+                ..config.clone()
+            },
+            Self::Full => config.clone(),
+        }
+    }
+}
+
+/// Paces mesh rebuilds while a slider is dragged, so heavy staffs don't rebuild every frame and
+/// hold up the UI. While scrubbing, each object rebuilds at most every [`Self::interval`]
+/// seconds at preview detail, and once the drag ends it's rebuilt once more in full.
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
+pub struct RebuildScheduler {
+    /// Seconds between two rebuilds of the same object while scrubbing
+    pub interval: f32,
+    /// Share of the ring resolution previews keep, 1 for full detail
+    pub preview_detail: f32,
+    /// Whether something was scrubbed last frame
+    #[reflect(ignore)]
+    scrubbing: bool,
+    /// Whether something has been scrubbed so far this frame
+    #[reflect(ignore)]
+    scrubbed: bool,
+    /// Real time this frame started, in seconds
+    #[reflect(ignore)]
+    now: f32,
+    #[reflect(ignore)]
+    last_rebuilds: HashMap<Entity, f32>,
+    /// Objects changed since their last rebuild, which came too soon after it
+    #[reflect(ignore)]
+    pending: HashSet<Entity>,
+    /// Objects last built as a preview
+    #[reflect(ignore)]
+    previews: HashSet<Entity>,
+}
+
+impl Default for RebuildScheduler {
+    fn default() -> Self {
+        Self {
+            interval: 0.05,
+            preview_detail: 0.5,
+            scrubbing: false,
+            scrubbed: false,
+            now: 0.,
+            last_rebuilds: HashMap::default(),
+            pending: HashSet::default(),
+            previews: HashSet::default(),
+        }
+    }
+}
+
+impl RebuildScheduler {
+    /// Called by sliders every frame they're dragged.
+    pub fn scrub(&mut self) {
+        self.scrubbed = true;
+    }
+
+    pub fn is_scrubbing(&self) -> bool {
+        self.scrubbing || self.scrubbed
+    }
+
+    /// Whether and how to rebuild `entity`, whose parameters are `changed` this frame. Changes
+    /// held back while scrubbing come out once the interval is up, or when it ends.
+    pub fn rebuild(&mut self, entity: Entity, changed: bool) -> Option<Rebuild> {
+        if self.is_scrubbing() {
+            if !changed && !self.pending.contains(&entity) {
+                return None;
+            }
+            let due = self
+                .last_rebuilds
+                .get(&entity)
+                .is_none_or(|&last| self.now - last >= self.interval);
+            if !due {
+                self.pending.insert(entity);
+                return None;
+            }
+            self.pending.remove(&entity);
+            self.previews.insert(entity);
+            self.last_rebuilds.insert(entity, self.now);
+            return Some(Rebuild::Preview(self.preview_detail.clamp(0., 1.)));
+        }
+        let stale = self.pending.remove(&entity) | self.previews.remove(&entity);
+        (changed || stale).then_some(Rebuild::Full)
+    }
+}
+
+/// Throttles mesh rebuilds while parameters are scrubbed, see [`RebuildScheduler`].
+pub struct RebuildPlugin;
+
+impl Plugin for RebuildPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<RebuildScheduler>()
+            .init_resource::<RebuildScheduler>()
+            .add_systems(First, start_rebuild_frame)
+            .add_systems(Last, end_rebuild_frame);
+    }
+}
+
+fn start_rebuild_frame(time: Res<Time<Real>>, mut scheduler: ResMut<RebuildScheduler>) {
+    scheduler.bypass_change_detection().now = time.elapsed_secs();
+}
+
+/// Sliders drawn after the rebuilds, like the inspector's, count from the next frame on.
+fn end_rebuild_frame(mut scheduler: ResMut<RebuildScheduler>) {
+    let scheduler = scheduler.bypass_change_detection();
+    // Every rebuild this frame saw the drag was over, so anything left belongs to objects that
+    // are gone
+    if !scheduler.is_scrubbing() {
+        scheduler.last_rebuilds.clear();
+        scheduler.pending.clear();
+        scheduler.previews.clear();
+    }
+    scheduler.scrubbing = scheduler.scrubbed;
+    scheduler.scrubbed = false;
+}
@@ -0,0 +1,210 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use rhai::{Engine, FLOAT, INT};
+use staff_gen::crystal::CrystalConfig;
+use staff_gen::staff::StaffConfig;
+
+use crate::actions::RegisterAction;
+use crate::crystal::{GenerateCrystal, default_crystal};
+#[cfg(feature = "export")]
+use crate::export::ExportStaff;
+use crate::staff::GenerateStaff;
+use crate::units::UnitsConfig;
+
+const SCRIPT_DIR: &str = "scripts";
+/// Operations a script may run before it is stopped, so an endless loop can't hang the viewer
+const MAX_SCRIPT_OPERATIONS: u64 = 10_000_000;
+
+/// What a script asked the viewer to do, carried out once it has finished.
+#[derive(Debug, Clone)]
+enum ScriptCommand {
+    GenerateStaff(GenerateStaff),
+    GenerateCrystal(GenerateCrystal),
+    #[cfg(feature = "export")]
+    ExportStaff(ExportStaff),
+}
+
+/// Every Rhai script in the `scripts` directory at startup becomes a "Run script" action in the
+/// command palette. Scripts build staff and crystal configs, then generate and export objects
+/// through the same messages the rest of the viewer uses. Native only.
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        let Ok(entries) = fs::read_dir(SCRIPT_DIR) else {
+            return;
+        };
+        let mut scripts: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "rhai")
+            })
+            .collect();
+        scripts.sort();
+        for path in scripts {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            app.register_action(
+                format!("Run script: {name}"),
+                move |mut outputs: ScriptOutputs| outputs.run(&path),
+            );
+        }
+    }
+}
+
+#[derive(SystemParam)]
+struct ScriptOutputs<'w> {
+    units: Res<'w, UnitsConfig>,
+    staffs: MessageWriter<'w, GenerateStaff>,
+    crystals: MessageWriter<'w, GenerateCrystal>,
+    #[cfg(feature = "export")]
+    exports: MessageWriter<'w, ExportStaff>,
+}
+
+impl ScriptOutputs<'_> {
+    /// Runs the script at `path` to the end, then sends everything it asked for.
+    fn run(&mut self, path: &Path) {
+        let commands = Rc::new(RefCell::new(Vec::new()));
+        let engine = script_engine(&self.units, &commands);
+        if let Err(error) = engine.run_file(path.to_path_buf()) {
+            error!("Script {} failed: {error}", path.display());
+            return;
+        }
+        info!(
+            "Script {} sent {} commands",
+            path.display(),
+            commands.borrow().len()
+        );
+        for command in commands.take() {
+            match command {
+                ScriptCommand::GenerateStaff(generate) => {
+                    self.staffs.write(generate);
+                }
+                ScriptCommand::GenerateCrystal(generate) => {
+                    self.crystals.write(generate);
+                }
+                #[cfg(feature = "export")]
+                ScriptCommand::ExportStaff(export) => {
+                    self.exports.write(export);
+                }
+            }
+        }
+    }
+}
+
+/// An engine with the viewer's bindings, queueing what scripts ask for into `commands`.
+fn script_engine(units: &UnitsConfig, commands: &Rc<RefCell<Vec<ScriptCommand>>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_SCRIPT_OPERATIONS);
+    engine.on_print(|text| info!("[script] {text}"));
+    engine.on_debug(|text, _, position| debug!("[script] {position:?} {text}"));
+
+    // Rhai numbers are 64 bit, configs mostly hold 32 bit ones
+    let count = |value: INT| value.clamp(0, u32::MAX as INT) as u32;
+    engine
+        .register_type_with_name::<StaffConfig>("StaffConfig")
+        .register_get_set(
+            "radius",
+            |config: &mut StaffConfig| config.radius as FLOAT,
+            |config: &mut StaffConfig, value: FLOAT| config.radius = value as f32,
+        )
+        .register_get_set(
+            "radial_variance",
+            |config: &mut StaffConfig| config.radial_variance as FLOAT,
+            |config: &mut StaffConfig, value: FLOAT| config.radial_variance = value as f32,
+        )
+        .register_get_set(
+            "height",
+            |config: &mut StaffConfig| config.height as FLOAT,
+            |config: &mut StaffConfig, value: FLOAT| config.height = value as f32,
+        )
+        .register_get_set(
+            "horizontal_variance",
+            |config: &mut StaffConfig| config.horizontal_variance as FLOAT,
+            |config: &mut StaffConfig, value: FLOAT| config.horizontal_variance = value as f32,
+        )
+        .register_get_set(
+            "resolution",
+            |config: &mut StaffConfig| config.resolution as INT,
+            move |config: &mut StaffConfig, value: INT| config.resolution = count(value).max(3),
+        )
+        .register_get_set(
+            "segments",
+            |config: &mut StaffConfig| config.segments as INT,
+            move |config: &mut StaffConfig, value: INT| config.segments = count(value).max(1),
+        )
+        .register_get_set(
+            "seed",
+            |config: &mut StaffConfig| config.seed as INT,
+            |config: &mut StaffConfig, value: INT| config.seed = value as u64,
+        );
+    engine
+        .register_type_with_name::<CrystalConfig>("CrystalConfig")
+        .register_get_set(
+            "radius",
+            |config: &mut CrystalConfig| config.radius as FLOAT,
+            |config: &mut CrystalConfig, value: FLOAT| config.radius = value as f32,
+        )
+        .register_get_set(
+            "height",
+            |config: &mut CrystalConfig| config.height as FLOAT,
+            |config: &mut CrystalConfig, value: FLOAT| config.height = value as f32,
+        )
+        .register_get_set(
+            "resolution",
+            |config: &mut CrystalConfig| config.resolution as INT,
+            move |config: &mut CrystalConfig, value: INT| config.resolution = count(value).max(3),
+        );
+
+    let staff_defaults = units.staff_defaults();
+    engine.register_fn("staff", move || staff_defaults.clone());
+    engine.register_fn("crystal", CrystalConfig::default);
+
+    let queue = commands.clone();
+    engine.register_fn("generate_staff", move |config: StaffConfig| {
+        let command = ScriptCommand::GenerateStaff(GenerateStaff::new(config));
+        queue.borrow_mut().push(command);
+    });
+    let queue = commands.clone();
+    engine.register_fn(
+        "generate_staff",
+        move |config: StaffConfig, x: FLOAT, z: FLOAT| {
+            let mut generate = GenerateStaff::new(config);
+            generate.transform.translation += vec3(x as f32, 0., z as f32);
+            queue
+                .borrow_mut()
+                .push(ScriptCommand::GenerateStaff(generate));
+        },
+    );
+    let queue = commands.clone();
+    engine.register_fn(
+        "generate_crystal",
+        move |config: CrystalConfig, x: FLOAT, z: FLOAT| {
+            let mut generate = default_crystal();
+            generate.config = config;
+            generate.transform.translation.x = x as f32;
+            generate.transform.translation.z = z as f32;
+            queue
+                .borrow_mut()
+                .push(ScriptCommand::GenerateCrystal(generate));
+        },
+    );
+    #[cfg(feature = "export")]
+    {
+        let queue = commands.clone();
+        engine.register_fn("export_staff", move |config: StaffConfig, bake: bool| {
+            let export = ExportStaff {
+                bake,
+                config: Some(config),
+            };
+            queue.borrow_mut().push(ScriptCommand::ExportStaff(export));
+        });
+    }
+    engine
+}
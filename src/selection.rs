@@ -0,0 +1,87 @@
+use bevy::camera::primitives::Aabb;
+use bevy::color::palettes::css;
+use bevy::math::bounding::{Aabb3d, BoundingVolume};
+use bevy::prelude::*;
+
+use crate::generation::GeneratedObject;
+use crate::measure::MeasureTool;
+use crate::staff::Staff;
+use crate::state::AppState;
+
+/// The generated object last clicked on. Camera views frame it.
+#[derive(Component, Debug)]
+pub struct Selected;
+
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(select_on_click).add_systems(
+            Update,
+            draw_selection_bounds.run_if(in_state(AppState::Editing)),
+        );
+    }
+}
+
+/// World space bounds of `entity` and all of its descendants with an [`Aabb`], so an assembly
+/// is bounded by all of its parts.
+pub fn world_aabb(
+    entity: Entity,
+    bounds: &Query<(&Aabb, &GlobalTransform)>,
+    children: &Query<&Children>,
+) -> Option<Aabb3d> {
+    std::iter::once(entity)
+        .chain(children.iter_descendants(entity))
+        .filter_map(|entity| {
+            let (aabb, transform) = bounds.get(entity).ok()?;
+            let affine = transform.affine();
+            let center = affine.transform_point3a(aabb.center);
+            let half_size = affine.matrix3.abs() * aabb.half_extents;
+            Some(Aabb3d::new(center, half_size))
+        })
+        .reduce(|a, b| a.merge(&b))
+}
+
+/// The selected object, or the viewer's own staff when nothing is selected.
+pub fn selected_or_staff(
+    selected: &Query<Entity, With<Selected>>,
+    staffs: &Query<Entity, With<Staff>>,
+) -> Option<Entity> {
+    selected.iter().next().or_else(|| staffs.iter().next())
+}
+
+fn select_on_click(
+    mut click: On<Pointer<Click>>,
+    mut commands: Commands,
+    tool: Res<MeasureTool>,
+    objects: Query<(), With<GeneratedObject>>,
+    selected: Query<Entity, With<Selected>>,
+) {
+    // Clicks on assembly parts bubble up to the assembly, which is the generated object
+    if tool.active
+        || click.event.button != PointerButton::Primary
+        || !objects.contains(click.entity)
+    {
+        return;
+    }
+    click.propagate(false);
+    for entity in &selected {
+        commands.entity(entity).remove::<Selected>();
+    }
+    commands.entity(click.entity).insert(Selected);
+}
+
+fn draw_selection_bounds(
+    mut gizmos: Gizmos,
+    selected: Query<Entity, With<Selected>>,
+    bounds: Query<(&Aabb, &GlobalTransform)>,
+    children: Query<&Children>,
+) {
+    for entity in &selected {
+        if let Some(aabb) = world_aabb(entity, &bounds, &children) {
+            let transform = Transform::from_translation(aabb.center().into())
+                .with_scale((aabb.half_size() * 2.).into());
+            gizmos.cuboid(transform, css::ORANGE);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::error::Error;
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Name of the directory, or localStorage key prefix, settings are kept under
+const SETTINGS_DIR: &str = "staff_test";
+/// Seconds a persistent resource has to stay unchanged before it is saved, so dragging a slider
+/// doesn't write a file every frame
+const SAVE_DELAY: f32 = 1.;
+
+/// A resource kept in its own RON settings file across runs.
+pub trait Persistent: Resource + Serialize + DeserializeOwned {
+    const FILE_NAME: &'static str;
+
+    /// Checks what deserializing can't, e.g. that a generator config can be built. Settings
+    /// that fail are ignored, so a bad file can't break every launch.
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Loads `T` from its settings file when the app is built, replacing the resource's defaults,
+/// and saves it again shortly after it changes.
+pub struct PersistPlugin<T>(PhantomData<fn() -> T>);
+
+impl<T> Default for PersistPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Persistent> Plugin for PersistPlugin<T> {
+    fn build(&self, app: &mut App) {
+        let settings = load_settings::<T>(T::FILE_NAME).filter(|settings| {
+            settings
+                .validate()
+                .inspect_err(|error| warn!("Ignoring invalid {}: {error}", T::FILE_NAME))
+                .is_ok()
+        });
+        if let Some(settings) = settings {
+            app.insert_resource(settings);
+        }
+        app.add_systems(Last, save_settled_settings::<T>);
+    }
+}
+
+fn save_settled_settings<T: Persistent>(
+    settings: Res<T>,
+    time: Res<Time<Real>>,
+    mut changed_at: Local<Option<f32>>,
+) {
+    // The first change is the resource being loaded or initialized
+    if settings.is_changed() && !settings.is_added() {
+        *changed_at = Some(time.elapsed_secs());
+    }
+    if changed_at.is_some_and(|changed_at| time.elapsed_secs() - changed_at >= SAVE_DELAY) {
+        save_settings(T::FILE_NAME, &*settings);
+        *changed_at = None;
+    }
+}
+
+/// Reads a settings file, or nothing if it's missing or invalid.
+pub fn load_settings<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let text = read_settings(name)?;
+    ron::from_str(&text)
+        .inspect_err(|error| warn!("Ignoring invalid {name}: {error}"))
+        .ok()
+}
+
+pub fn save_settings<T: Serialize>(name: &str, settings: &T) {
+    let result = ron::ser::to_string_pretty(settings, default())
+        .map_err(|error| error.to_string())
+        .and_then(|text| write_settings(name, &text));
+    if let Err(error) = result {
+        warn!("Failed to save {name}: {error}");
+    }
+}
+
+/// The platform's per-user config directory: `%APPDATA%` on Windows, `Application Support` on
+/// macOS and `$XDG_CONFIG_HOME` or `~/.config` elsewhere.
+#[cfg(not(target_arch = "wasm32"))]
+fn settings_dir() -> Option<std::path::PathBuf> {
+    use std::env::var_os;
+    use std::path::PathBuf;
+
+    let base = if cfg!(windows) {
+        var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    Some(base?.join(SETTINGS_DIR))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_settings(name: &str) -> Option<String> {
+    std::fs::read_to_string(settings_dir()?.join(name)).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_settings(name: &str, text: &str) -> Result<(), String> {
+    let dir = settings_dir().ok_or("there is no config directory")?;
+    std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(dir.join(name), text))
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_settings(name: &str) -> Option<String> {
+    local_storage()?
+        .get_item(&format!("{SETTINGS_DIR}/{name}"))
+        .ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_settings(name: &str, text: &str) -> Result<(), String> {
+    local_storage()
+        .ok_or("localStorage is unavailable")?
+        .set_item(&format!("{SETTINGS_DIR}/{name}"), text)
+        .map_err(|error| format!("{error:?}"))
+}
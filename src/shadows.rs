@@ -0,0 +1,114 @@
+use bevy::light::{CascadeShadowConfigBuilder, DirectionalLightShadowMap, ShadowFilteringMethod};
+use bevy::prelude::*;
+
+use crate::environment::{EnvironmentConfig, Sun};
+
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ShadowQuality {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl ShadowQuality {
+    /// WebGL2 struggles with large shadow maps, so the web build starts on low.
+    pub fn platform_default() -> Self {
+        if cfg!(target_arch = "wasm32") {
+            Self::Low
+        } else {
+            Self::default()
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Low => Self::Medium,
+            Self::Medium => Self::High,
+            Self::High => Self::Low,
+        }
+    }
+
+    pub fn settings(self) -> ShadowSettings {
+        match self {
+            Self::Low => ShadowSettings {
+                cascades: 1,
+                map_size: 1024,
+                soft: false,
+            },
+            Self::Medium => ShadowSettings {
+                cascades: 2,
+                map_size: 2048,
+                soft: true,
+            },
+            Self::High => ShadowSettings {
+                cascades: 4,
+                map_size: 4096,
+                soft: true,
+            },
+        }
+    }
+}
+
+#[derive(Reflect, Clone, Debug, PartialEq)]
+pub struct ShadowSettings {
+    /// Number of directional light shadow cascades
+    pub cascades: usize,
+    /// Directional shadow map resolution, should be a power of two
+    pub map_size: usize,
+    /// Gaussian filtered shadows instead of hardware 2x2 PCF
+    pub soft: bool,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        ShadowQuality::default().settings()
+    }
+}
+
+/// Q cycles the shadow quality preset.
+pub fn cycle_shadow_quality(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut config: ResMut<EnvironmentConfig>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyQ) {
+        let quality = config.shadow_quality.next();
+        config.shadow_quality = quality;
+        config.shadows = quality.settings();
+        info!("Shadow quality: {quality:?}");
+    }
+}
+
+pub fn apply_shadow_config(
+    mut commands: Commands,
+    config: Res<EnvironmentConfig>,
+    mut applied: Local<Option<ShadowSettings>>,
+    mut shadow_map: ResMut<DirectionalLightShadowMap>,
+    suns: Query<Entity, With<Sun>>,
+    cameras: Query<Entity, With<Camera3d>>,
+) {
+    if applied.as_ref() == Some(&config.shadows) {
+        return;
+    }
+    let shadows = config.shadows.clone();
+
+    shadow_map.size = shadows.map_size;
+    for sun in &suns {
+        commands.entity(sun).insert(
+            CascadeShadowConfigBuilder {
+                num_cascades: shadows.cascades.max(1),
+                ..default()
+            }
+            .build(),
+        );
+    }
+    let filtering = if shadows.soft {
+        ShadowFilteringMethod::Gaussian
+    } else {
+        ShadowFilteringMethod::Hardware2x2
+    };
+    for camera in &cameras {
+        commands.entity(camera).insert(filtering);
+    }
+    *applied = Some(shadows);
+}
@@ -0,0 +1,87 @@
+use bevy::input::mouse::AccumulatedMouseMotion;
+use bevy::prelude::*;
+
+use crate::camera::MainCamera;
+use crate::staff::Staff;
+
+/// Seconds without mouse input before a paused showcase resumes.
+const RESUME_DELAY: f32 = 3.;
+
+/// Slow automatic camera orbit and staff spin for recordings and the web embed.
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
+pub struct ShowcaseSettings {
+    pub enabled: bool,
+    /// Camera orbit speed in radians per second
+    pub orbit_speed: f32,
+    /// Staff spin speed in radians per second
+    pub spin_speed: f32,
+    /// Point the camera orbits around
+    pub target: Vec3,
+}
+
+impl Default for ShowcaseSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            orbit_speed: 0.15,
+            spin_speed: 0.5,
+            target: vec3(0., 1.5, 0.),
+        }
+    }
+}
+
+pub struct ShowcasePlugin;
+
+impl Plugin for ShowcasePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ShowcaseSettings>()
+            .init_resource::<ShowcaseSettings>()
+            .add_systems(Update, (toggle_showcase, run_showcase).chain());
+    }
+}
+
+/// T toggles the showcase.
+fn toggle_showcase(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<ShowcaseSettings>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyT) {
+        settings.enabled = !settings.enabled;
+        info!("Showcase: {}", settings.enabled);
+    }
+}
+
+fn run_showcase(
+    settings: Res<ShowcaseSettings>,
+    time: Res<Time>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    mut idle: Local<f32>,
+    mut camera: Single<&mut Transform, With<MainCamera>>,
+    mut staffs: Query<&mut Transform, (With<Staff>, Without<MainCamera>)>,
+) {
+    if !settings.enabled {
+        // Start moving straight away when the showcase is turned on
+        *idle = RESUME_DELAY;
+        return;
+    }
+    // Any mouse input hands control back to the user for a while
+    if mouse_motion.delta != Vec2::ZERO || mouse_button_input.get_pressed().len() > 0 {
+        *idle = 0.;
+        return;
+    }
+    *idle += time.delta_secs();
+    if *idle < RESUME_DELAY {
+        return;
+    }
+
+    let delta = time.delta_secs();
+    camera.rotate_around(
+        settings.target,
+        Quat::from_rotation_y(settings.orbit_speed * delta),
+    );
+    for mut transform in &mut staffs {
+        transform.rotate_y(settings.spin_speed * delta);
+    }
+}
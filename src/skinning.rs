@@ -0,0 +1,147 @@
+use bevy::color::palettes::css;
+use bevy::mesh::skinning::{SkinnedMesh, SkinnedMeshInverseBindposes};
+use bevy::prelude::*;
+use staff_gen::skinning::StaffSkeleton;
+use staff_gen::staff::StaffConfig;
+
+use crate::environment::FLOOR_HEIGHT;
+use crate::generation::{GeneratedObject, GeneratorKind, MeshGenMessages, empty_mesh};
+
+/// How far a skinned staff bends, in radians around X and Z, spread evenly over its joints.
+/// The total bend is `pose + sway`, so an animation and the wind can bend a staff together.
+#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct StaffBend {
+    pub pose: Vec2,
+    /// Set by the wind
+    pub sway: Vec2,
+}
+
+/// Swings a skinned staff back and forth, a cartoonish idle animation.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct StaffSwing {
+    /// Total bend at the peak of the swing, in radians
+    pub amplitude: f32,
+    /// Swings per second
+    pub frequency: f32,
+}
+
+impl Default for StaffSwing {
+    fn default() -> Self {
+        Self {
+            amplitude: 0.4,
+            frequency: 0.5,
+        }
+    }
+}
+
+/// One joint of a skinned staff and its transform relative to the joint below when unbent.
+#[derive(Component, Debug)]
+pub struct StaffJoint {
+    rest: Transform,
+}
+
+pub struct SkinningPlugin;
+
+impl Plugin for SkinningPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<StaffBend>()
+            .register_type::<StaffSwing>()
+            .add_systems(Update, (swing_staffs, bend_staffs).chain());
+    }
+}
+
+/// Spawns a staff bent by a chain of `joint_count` joints, see [`StaffBend`].
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_skinned_staff(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    inverse_bindposes: &mut ResMut<Assets<SkinnedMeshInverseBindposes>>,
+    mesh_gen: &mut MeshGenMessages,
+    config: &StaffConfig,
+    joint_count: usize,
+    position: Vec2,
+) -> Entity {
+    let entity = commands.spawn(Name::new("SkinnedStaff")).id();
+    let transform = Transform::from_xyz(
+        position.x,
+        config.height / 2. + FLOOR_HEIGHT / 2.,
+        position.y,
+    );
+    let styled = mesh_gen.style().staff(config);
+    let mut skeleton = None;
+    let mesh = mesh_gen.try_generate(entity, GeneratorKind::Staff, || {
+        let mut mesh = styled.try_generate_mesh()?;
+        // Only built once the config is known to be valid, as it generates the rings
+        skeleton
+            .insert(StaffSkeleton::new(config, joint_count))
+            .skin(&mut mesh);
+        Ok(mesh)
+    });
+    let (Some(mesh), Some(skeleton)) = (mesh, skeleton) else {
+        // An invalid staff has no joints to bend
+        commands.entity(entity).insert((
+            Mesh3d(meshes.add(empty_mesh())),
+            transform,
+            GeneratedObject(GeneratorKind::Staff),
+        ));
+        return entity;
+    };
+
+    let mut parent = entity;
+    let joints = skeleton
+        .local_transforms()
+        .into_iter()
+        .enumerate()
+        .map(|(i, rest)| {
+            parent = commands
+                .spawn((
+                    Name::new(format!("StaffJoint {i}")),
+                    StaffJoint { rest },
+                    rest,
+                    ChildOf(parent),
+                ))
+                .id();
+            parent
+        })
+        .collect();
+
+    commands.entity(entity).insert((
+        Mesh3d(meshes.add(mesh)),
+        MeshMaterial3d(materials.add(Color::from(css::PERU))),
+        SkinnedMesh {
+            inverse_bindposes: inverse_bindposes.add(skeleton.inverse_bindposes()),
+            joints,
+        },
+        StaffBend::default(),
+        transform,
+        GeneratedObject(GeneratorKind::Staff),
+    ));
+    entity
+}
+
+fn swing_staffs(time: Res<Time>, mut staffs: Query<(&StaffSwing, &mut StaffBend)>) {
+    for (swing, mut bend) in &mut staffs {
+        let phase = time.elapsed_secs() * swing.frequency * std::f32::consts::TAU;
+        bend.pose = Vec2::new(0., swing.amplitude * phase.sin());
+    }
+}
+
+pub fn bend_staffs(
+    staffs: Query<(&StaffBend, &SkinnedMesh), Changed<StaffBend>>,
+    mut joints: Query<(&StaffJoint, &mut Transform)>,
+) {
+    for (bend, skinned_mesh) in &staffs {
+        // Rotating the tip joint moves nothing above it, so every other joint takes a share
+        let bent_joints = skinned_mesh.joints.len().saturating_sub(1).max(1);
+        let per_joint = (bend.pose + bend.sway) / bent_joints as f32;
+        let rotation = Quat::from_euler(EulerRot::XYZ, per_joint.x, 0., per_joint.y);
+        for &joint in skinned_mesh.joints.iter().take(bent_joints) {
+            if let Ok((joint, mut transform)) = joints.get_mut(joint) {
+                transform.rotation = joint.rest.rotation * rotation;
+            }
+        }
+    }
+}
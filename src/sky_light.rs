@@ -0,0 +1,205 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::light::GeneratedEnvironmentMapLight;
+use bevy::prelude::*;
+use bevy::render::render_resource::{
+    Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+};
+
+use crate::actions::RegisterAction;
+use crate::camera::MainCamera;
+use crate::environment::{EnvironmentConfig, FloorStyle, SUN_ILLUMINANCE, Sun};
+
+/// Width and height of each cube face, a power of two as the filtering requires
+const SKY_MAP_SIZE: u32 = 64;
+/// Share of white the sky fades towards at the horizon
+const HORIZON_HAZE: f32 = 0.35;
+/// How tightly the glow around the sun hugs it
+const SUN_GLOW_EXPONENT: f32 = 24.;
+/// Brightness of the glow around the sun at `SUN_ILLUMINANCE`, relative to the sky
+const SUN_GLOW: f32 = 4.;
+/// Ground below the horizon when the floor isn't a plain color
+const DEFAULT_GROUND: Color = Color::srgb(0.3, 0.3, 0.3);
+
+/// Lights the scene with reflections of the sky, so metal ferrules and glossy crystals have
+/// something to reflect besides the sun.
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct SkyLightSettings {
+    /// Not supported on WebGL2, where it stays off
+    pub enabled: bool,
+    /// In cd/m²
+    pub intensity: f32,
+}
+
+impl Default for SkyLightSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            intensity: 600.,
+        }
+    }
+}
+
+impl SkyLightSettings {
+    /// The sky map is filtered with compute shaders, which WebGL2 lacks.
+    pub fn supported() -> bool {
+        !cfg!(target_arch = "wasm32")
+    }
+}
+
+/// The cubemap the sky is painted into, filtered into the camera's environment map.
+#[derive(Resource, Debug)]
+struct SkyMap(Handle<Image>);
+
+/// Paints the sky's colors, the glow around the sun and the floor's color below the horizon
+/// into a cubemap the camera's environment map light is filtered from. It's repainted whenever
+/// the sky, sun or floor change, so environment presets and a moving sun show up in
+/// reflections.
+pub struct SkyLightPlugin;
+
+impl Plugin for SkyLightPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SkyLightSettings>()
+            .init_resource::<SkyLightSettings>()
+            .register_action(
+                "Toggle sky reflections",
+                |mut settings: ResMut<SkyLightSettings>| {
+                    settings.enabled = !settings.enabled;
+                },
+            )
+            .add_systems(Startup, setup_sky_map)
+            .add_systems(Update, (paint_sky_map, apply_sky_light));
+    }
+}
+
+fn setup_sky_map(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: SKY_MAP_SIZE,
+            height: SKY_MAP_SIZE,
+            depth_or_array_layers: 6,
+        },
+        TextureDimension::D2,
+        &[0; 8],
+        TextureFormat::Rgba16Float,
+        // Kept in the main world to be repainted
+        RenderAssetUsages::default(),
+    );
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
+        ..default()
+    });
+    commands.insert_resource(SkyMap(images.add(image)));
+}
+
+/// Direction through pixel `(x, y)` of cube face `face`, in the order +X, -X, +Y, -Y, +Z, -Z.
+fn cube_direction(face: u32, x: u32, y: u32) -> Vec3 {
+    let u = (x as f32 + 0.5) / SKY_MAP_SIZE as f32 * 2. - 1.;
+    let v = (y as f32 + 0.5) / SKY_MAP_SIZE as f32 * 2. - 1.;
+    match face {
+        0 => vec3(1., -v, -u),
+        1 => vec3(-1., -v, u),
+        2 => vec3(u, 1., v),
+        3 => vec3(u, -1., -v),
+        4 => vec3(u, -v, 1.),
+        _ => vec3(-u, -v, -1.),
+    }
+    .normalize()
+}
+
+/// The colors a sky map is painted with.
+struct SkyColors {
+    zenith: LinearRgba,
+    horizon: LinearRgba,
+    ground: LinearRgba,
+    sun: LinearRgba,
+    /// Direction towards the sun
+    sun_direction: Vec3,
+}
+
+impl SkyColors {
+    fn radiance(&self, direction: Vec3) -> LinearRgba {
+        let sky = if direction.y >= 0. {
+            self.horizon.mix(&self.zenith, direction.y.sqrt())
+        } else {
+            self.ground
+        };
+        let glow = direction
+            .dot(self.sun_direction)
+            .max(0.)
+            .powf(SUN_GLOW_EXPONENT);
+        sky + self.sun * glow
+    }
+}
+
+fn paint_sky_map(
+    clear_color: Res<ClearColor>,
+    environment: Res<EnvironmentConfig>,
+    sky_map: Res<SkyMap>,
+    sun: Single<(Ref<DirectionalLight>, Ref<GlobalTransform>), With<Sun>>,
+    mut images: ResMut<Assets<Image>>,
+    mut painted: Local<bool>,
+) {
+    let (light, transform) = &*sun;
+    let changed = clear_color.is_changed()
+        || environment.is_changed()
+        || light.is_changed()
+        || transform.is_changed();
+    if *painted && !changed {
+        return;
+    }
+    let Some(image) = images.get_mut(&sky_map.0) else {
+        return;
+    };
+    *painted = true;
+    let zenith = clear_color.0.to_linear();
+    let ground = match environment.floor_style {
+        FloorStyle::Solid(color) => color,
+        _ => DEFAULT_GROUND,
+    };
+    let colors = SkyColors {
+        zenith,
+        horizon: zenith.mix(&LinearRgba::WHITE, HORIZON_HAZE),
+        // Lit by the sky above it
+        ground: ground.to_linear() * zenith.luminance().max(0.1),
+        sun: light.color.to_linear() * SUN_GLOW * light.illuminance / SUN_ILLUMINANCE,
+        sun_direction: *transform.back(),
+    };
+    for face in 0..6 {
+        for y in 0..SKY_MAP_SIZE {
+            for x in 0..SKY_MAP_SIZE {
+                let color = colors.radiance(cube_direction(face, x, y));
+                image
+                    .set_color_at_3d(x, y, face, color.into())
+                    .expect("sky map is Rgba16Float");
+            }
+        }
+    }
+}
+
+fn apply_sky_light(
+    mut commands: Commands,
+    settings: Res<SkyLightSettings>,
+    sky_map: Res<SkyMap>,
+    cameras: Query<Entity, With<MainCamera>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for camera in &cameras {
+        if settings.enabled && SkyLightSettings::supported() {
+            commands
+                .entity(camera)
+                .insert(GeneratedEnvironmentMapLight {
+                    environment_map: sky_map.0.clone(),
+                    intensity: settings.intensity,
+                    ..default()
+                });
+        } else {
+            // Along with the environment map light filtered from it
+            commands
+                .entity(camera)
+                .remove::<(GeneratedEnvironmentMapLight, EnvironmentMapLight)>();
+        }
+    }
+}
@@ -1,191 +1,265 @@
-use std::f32::consts::TAU;
-
-use bevy::asset::RenderAssetUsages;
 use bevy::color::palettes::css;
-use bevy::math::ops::sin_cos;
-use bevy::mesh::{Indices, PrimitiveTopology};
 use bevy::prelude::*;
+use staff_gen::head::{Head, HeadRegistry};
+use staff_gen::sockets::{self, Sockets};
+use staff_gen::staff::StaffConfig;
+use staff_gen::stats::StaffStats;
+use staff_gen::style::StyleConfig;
 
 use crate::environment::FLOOR_HEIGHT;
-use rand::{Rng, SeedableRng};
-use rand_chacha::ChaCha8Rng;
+use crate::generation::{
+    GeneratedObject, GeneratorKind, MeshGenCompleted, MeshGenMessages, empty_mesh,
+    generation_target,
+};
+use crate::labels::{StaffName, spawn_world_label};
+use crate::rebuild::RebuildScheduler;
+use crate::units::UnitsConfig;
+
+#[derive(Component, Debug)]
+pub struct Staff;
+
+/// The gem or trim of its parent staff's head, drawn in their own materials.
+#[derive(Component, Debug)]
+pub struct StaffHeadPart;
+
+/// The glowing crystal of a staff's head.
+#[derive(Component, Debug)]
+pub struct StaffGem;
 
+/// Where the viewer places a staff generated from `config`, standing on the floor.
+pub fn staff_translation(config: &StaffConfig) -> Vec3 {
+    vec3(-2., config.height / 2. + FLOOR_HEIGHT / 2. + 0.5, 0.)
+}
+
+/// Spawns a staff generated from `config`. Handled by [`handle_generate_staff`].
+#[derive(Message, Debug, Clone)]
+pub struct GenerateStaff {
+    pub config: StaffConfig,
+    /// Replaces `config.seed` when set
+    pub seed: Option<u64>,
+    pub transform: Transform,
+    /// Entity to build the staff on, spawned by the sender to mark or parent it. A new one is
+    /// spawned when unset.
+    pub entity: Option<Entity>,
+}
+
+impl GenerateStaff {
+    /// Standing on the floor where the viewer places its own staff.
+    pub fn new(config: StaffConfig) -> Self {
+        Self {
+            transform: Transform::from_translation(staff_translation(&config)),
+            config,
+            seed: None,
+            entity: None,
+        }
+    }
+}
+
+/// The viewer's own staff, which follows the morph slider.
 pub fn spawn_staff_mesh(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
-) {
-    let radius = 0.05;
-    let radial_variance = radius * 0.5;
-    let height = 2.;
-    let resolution = 6;
-    let segments = 4;
-    let horizontal_variance = height * 0.05;
-    let mut rand = ChaCha8Rng::seed_from_u64(19878367467713);
-
-    let mesh = generate_staff_mesh(
-        radius,
-        radial_variance,
-        height,
-        resolution,
-        segments,
-        horizontal_variance,
-        &mut rand,
+    mesh_gen: &mut MeshGenMessages,
+    heads: &HeadRegistry,
+    config: StaffConfig,
+) -> Entity {
+    let transform = Transform::from_translation(staff_translation(&config));
+    let entity = commands.spawn(Staff).id();
+    insert_generated_staff(
+        commands, meshes, materials, mesh_gen, heads, entity, config, transform,
     );
+    entity
+}
+
+/// Builds a staff generated from `config` with its head on `entity`, labelled with its name.
+#[allow(clippy::too_many_arguments)]
+fn insert_generated_staff(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    mesh_gen: &mut MeshGenMessages,
+    heads: &HeadRegistry,
+    entity: Entity,
+    config: StaffConfig,
+    transform: Transform,
+) {
+    commands.entity(entity).insert(Name::new("Staff"));
+    let styled = mesh_gen.style().staff(&config);
+    let head = styled.try_generate_head(heads).unwrap_or_default();
+    let mesh = mesh_gen.try_generate(entity, GeneratorKind::Staff, || {
+        styled.try_generate_mesh_with_head(head.as_ref())
+    });
+    // Sockets of an invalid staff can't be placed either
+    let sockets = match mesh {
+        Some(_) => config.sockets_with_head(head.as_ref()),
+        None => Sockets::default(),
+    };
+    let offset = label_offset(&sockets);
+    let _span = info_span!("upload").entered();
+    spawn_head_parts(commands, meshes, materials, mesh_gen.style(), entity, head);
 
-    commands.spawn((
-        Mesh3d(meshes.add(mesh)),
+    commands.entity(entity).insert((
+        Mesh3d(meshes.add(mesh.unwrap_or_else(empty_mesh))),
         MeshMaterial3d(materials.add(Color::from(css::SADDLE_BROWN))),
-        Transform::from_xyz(-2., height / 2. + FLOOR_HEIGHT / 2. + 0.5, 0.),
+        transform,
+        GeneratedObject(GeneratorKind::Staff),
+        StaffName::default(),
+        sockets,
+        config.clone(),
     ));
+    spawn_world_label(commands, entity, offset, 16.);
 }
 
-pub fn generate_staff_mesh(
-    radius: f32,
-    radial_variance: f32,
-    height: f32,
-    resolution: u32,
-    segments: u32,
-    horizontal_variance: f32,
-    rand: &mut ChaCha8Rng,
-) -> Mesh {
-    let half_height = height / 2.;
-    debug_assert!(resolution > 2);
-    debug_assert!(resolution > 0);
-
-    let num_rings = segments + 1;
-    let num_vertices = resolution * 2 + num_rings * (resolution + 1);
-    let num_faces = resolution * (num_rings - 2);
-    let num_indices = (2 * num_faces + 2 * (resolution - 1) * 2) * 3;
-
-    let mut positions = Vec::with_capacity(num_vertices as usize);
-    let mut normals = Vec::with_capacity(num_vertices as usize);
-    let mut uvs = Vec::with_capacity(num_vertices as usize);
-    let mut indices = Vec::with_capacity(num_indices as usize);
-
-    let step_theta = TAU / resolution as f32;
-    let step_y = 2.0 * half_height / segments as f32;
-
-    // Bottom and Top variance X and Z must be known for cap placement
-
-    // Bottom variance
-    // Bottom radius should be a little smaller than top
-    let bvr = rand.random_range((radius / 2. - radial_variance)..(radius / 2.));
-    let bvx = rand.random::<f32>();
-    let bvz = rand.random::<f32>();
-    // Top variance
-    let tvr = rand.random_range((radius - radial_variance)..radius);
-    let tvx = rand.random::<f32>();
-    let tvz = rand.random::<f32>();
-    info!(
-        "bvx: {:?}, bvz: {:?}, tvx: {:?}, tvy: {:?}",
-        bvx, bvz, tvx, tvz
-    );
-
-    // rings
-
-    for ring in 0..num_rings {
-        // Radius with variance
-
-        let (vr, vx, vz) = if ring == 0 {
-            // Bottom variance radial, X, Z
-            (bvr, bvx, bvz)
-        } else if ring == num_rings - 1 {
-            // Top variance radial, X, Z
-            (tvr, tvx, tvz)
-        } else {
-            // New random variances X and Z
-            (
-                rand.random_range((radius - radial_variance)..radius),
-                rand.random::<f32>(),
-                rand.random::<f32>(),
-            )
-        };
-
-        let offset = (horizontal_variance * vx, horizontal_variance * vz);
-        let y = -half_height + ring as f32 * step_y;
+fn spawn_head_parts(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    style: &StyleConfig,
+    staff: Entity,
+    head: Option<Head>,
+) {
+    let Some(head) = head else {
+        return;
+    };
+    if let Some(gem) = head.gem {
+        commands.spawn((
+            Name::new("StaffGem"),
+            StaffHeadPart,
+            StaffGem,
+            Mesh3d(meshes.add(style.apply(gem))),
+            MeshMaterial3d(materials.add(Color::from(css::SKY_BLUE))),
+            ChildOf(staff),
+        ));
+    }
+    if let Some(trim) = head.trim {
+        commands.spawn((
+            Name::new("StaffTrim"),
+            StaffHeadPart,
+            Mesh3d(meshes.add(style.apply(trim))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: Color::from(css::GOLD),
+                metallic: 1.,
+                perceptual_roughness: 0.3,
+                ..default()
+            })),
+            ChildOf(staff),
+        ));
+    }
+}
 
-        for segment in 0..=resolution {
-            let theta = segment as f32 * step_theta;
-            let (sin, cos) = sin_cos(theta);
+type HeadedStaff = (
+    Entity,
+    Ref<'static, StaffConfig>,
+    &'static Mesh3d,
+    &'static mut Sockets,
+    Option<&'static Children>,
+);
 
-            positions.push([vr * cos + offset.0, y, vr * sin + offset.1]);
-            normals.push([cos, 0., sin]);
-            uvs.push([
-                segment as f32 / resolution as f32,
-                ring as f32 / segment as f32,
-            ]);
+/// Regenerates staffs and their heads when their [`StaffConfig`] was edited after spawning, as
+/// paced by the [`RebuildScheduler`]. The morphed [`Staff`] follows
+/// [`StaffMorph`](crate::morph::StaffMorph) instead.
+#[allow(clippy::too_many_arguments)]
+pub fn rebuild_changed_staffs(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut staffs: Query<HeadedStaff, Without<Staff>>,
+    head_parts: Query<(), With<StaffHeadPart>>,
+    heads: Res<HeadRegistry>,
+    mut scheduler: ResMut<RebuildScheduler>,
+    mut mesh_gen: MeshGenMessages,
+) {
+    for (entity, config, mesh3d, mut sockets, children) in &mut staffs {
+        let changed = config.is_changed() && !config.is_added();
+        let Some(rebuild) = scheduler.rebuild(entity, changed) else {
+            continue;
+        };
+        let styled = mesh_gen.style().staff(&rebuild.staff(&config));
+        let head = styled.try_generate_head(&heads).unwrap_or_default();
+        let buffers = mesh_gen.buffers();
+        let Some(new_mesh) = mesh_gen.try_rebuild(entity, GeneratorKind::Staff, rebuild, || {
+            styled.try_generate_mesh_with_head_in(head.as_ref(), buffers)
+        }) else {
+            // An invalid staff keeps its last mesh, head and sockets until it's fixed
+            continue;
+        };
+        if let Some(mesh) = meshes.get_mut(&mesh3d.0) {
+            mesh_gen.replace(mesh, new_mesh);
         }
-    }
-
-    // barrel skin
-
-    for i in 0..segments {
-        let ring = i * (resolution + 1);
-        let next_ring = (i + 1) * (resolution + 1);
-
-        for j in 0..resolution {
-            indices.extend_from_slice(&[
-                ring + j,
-                next_ring + j,
-                ring + j + 1,
-                next_ring + j,
-                next_ring + j + 1,
-                ring + j + 1,
-            ]);
+        *sockets = config.sockets_with_head(head.as_ref());
+        for &child in children.into_iter().flatten() {
+            if head_parts.contains(child) {
+                commands.entity(child).despawn();
+            }
         }
+        spawn_head_parts(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            mesh_gen.style(),
+            entity,
+            head,
+        );
     }
+}
 
-    // caps
-    let mut build_cap = |top: bool| {
-        let offset = positions.len() as u32;
-        let (y, normal_y, winding, radial_variance, variance_offset) = if top {
-            (
-                half_height,
-                1.,
-                (1, 0),
-                tvr,
-                (horizontal_variance * tvx, horizontal_variance * tvz),
-            )
-        } else {
-            (
-                -half_height,
-                -1.,
-                (0, 1),
-                bvr,
-                (horizontal_variance * bvx, horizontal_variance * bvz),
-            )
+pub fn handle_generate_staff(
+    mut commands: Commands,
+    mut requests: MessageReader<GenerateStaff>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    heads: Res<HeadRegistry>,
+    mut mesh_gen: MeshGenMessages,
+) {
+    for request in requests.read() {
+        let Some(entity) = generation_target(&mut commands, request.entity) else {
+            continue;
         };
+        let config = StaffConfig {
+            seed: request.seed.unwrap_or(request.config.seed),
+            ..request.config.clone()
+        };
+        insert_generated_staff(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &mut mesh_gen,
+            &heads,
+            entity,
+            config,
+            request.transform,
+        );
+    }
+}
 
-        for i in 0..resolution {
-            let theta = i as f32 * step_theta;
-            let (sin, cos) = sin_cos(theta);
-
-            positions.push([
-                cos * radial_variance + variance_offset.0,
-                y,
-                sin * radial_variance + variance_offset.1,
-            ]);
-            normals.push([0.0, normal_y, 0.0]);
-            uvs.push([0.5 * (cos + 1.0), 1.0 - 0.5 * (sin + 1.0)]);
-        }
+/// Just above the tip of a staff, its [`sockets::TOP`] socket.
+pub fn label_offset(sockets: &Sockets) -> Vec3 {
+    sockets.get(sockets::TOP).unwrap_or_default().translation + Vec3::Y * 0.1
+}
 
-        for i in 1..(resolution - 1) {
-            indices.extend_from_slice(&[offset, offset + i + winding.0, offset + i + winding.1]);
+/// Recomputes [`StaffStats`] whenever a staff's mesh has been regenerated.
+pub fn update_staff_stats(
+    mut commands: Commands,
+    mut completed: MessageReader<MeshGenCompleted>,
+    staffs: Query<(&StaffConfig, &Mesh3d)>,
+    meshes: Res<Assets<Mesh>>,
+    units: Res<UnitsConfig>,
+) {
+    for generation in completed.read() {
+        if generation.stats.kind != GeneratorKind::Staff {
+            continue;
         }
-    };
-
-    build_cap(true);
-    build_cap(false);
-
-    // Assume anchor is at midpoint. No need for vertex position offsets
-
-    Mesh::new(
-        PrimitiveTopology::TriangleList,
-        RenderAssetUsages::default(),
-    )
-    .with_inserted_indices(Indices::U32(indices))
-    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
-    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
-    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        let Ok((config, mesh)) = staffs.get(generation.entity) else {
+            continue;
+        };
+        let Some(mesh) = meshes.get(&mesh.0) else {
+            continue;
+        };
+        commands.entity(generation.entity).insert(StaffStats::new(
+            config,
+            mesh,
+            units.meters_per_unit,
+        ));
+    }
 }
@@ -0,0 +1,329 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use staff_gen::sockets::{self, Sockets};
+
+use crate::environment::Floor;
+use crate::pickup::Held;
+use crate::staff::StaffGem;
+use crate::state::AppState;
+use crate::units::UnitsConfig;
+
+/// Seconds the staff takes to strike the ground and rise again
+const PLANT_DURATION: f32 = 0.35;
+/// Furthest below the staff's base the ground is looked for, in meters
+const GROUND_REACH: f32 = 2.;
+/// Seconds the shockwave ring takes to spread out and fade
+const SHOCKWAVE_DURATION: f32 = 0.8;
+/// In meters
+const SHOCKWAVE_RADIUS: f32 = 1.5;
+/// Width of the wave front grass is pushed by, in meters
+const SHOCKWAVE_FRONT: f32 = 0.3;
+/// Push at the front, in the units of wind speed grass bends with
+const SHOCKWAVE_PUSH: f32 = 5.;
+const DUST_MOTES: u32 = 12;
+/// Seconds a dust mote lasts
+const DUST_LIFETIME: f32 = 0.7;
+/// Meters per second
+const DUST_SPEED: f32 = 1.2;
+/// Seconds the gem glows for
+const PULSE_DURATION: f32 = 0.6;
+const PULSE_INTENSITY: f32 = 20.;
+/// Meters per second squared
+const GRAVITY: f32 = 9.81;
+
+/// A held staff striking the ground and rising back into the hand.
+#[derive(Component, Debug)]
+struct Planting {
+    elapsed: f32,
+    /// How far the staff moves down to reach the ground
+    depth: f32,
+    contact: Vec3,
+    /// The staff's transform in the hand
+    rest: Transform,
+    struck: bool,
+}
+
+/// A ring spreading from where a staff struck the ground, pushing grass outwards as it passes.
+#[derive(Component, Debug)]
+pub struct Shockwave {
+    elapsed: f32,
+    /// Radius the ring reaches, in scene units
+    radius: f32,
+}
+
+impl Shockwave {
+    fn progress(&self) -> f32 {
+        (self.elapsed / SHOCKWAVE_DURATION).min(1.)
+    }
+
+    /// Outward push at `position` from a wave centered on `origin`, strongest where the front
+    /// is passing and weakening as the ring fades.
+    pub fn push(&self, origin: Vec3, position: Vec3) -> Vec3 {
+        let offset = (position - origin).with_y(0.);
+        let front = self.radius * self.progress();
+        let width = SHOCKWAVE_FRONT * self.radius / SHOCKWAVE_RADIUS;
+        let closeness = 1. - ((offset.length() - front).abs() / width).min(1.);
+        offset.normalize_or_zero() * SHOCKWAVE_PUSH * closeness * (1. - self.progress())
+    }
+}
+
+#[derive(Component, Debug)]
+struct DustMote {
+    velocity: Vec3,
+    age: f32,
+}
+
+#[derive(Component, Debug)]
+struct GemPulse {
+    elapsed: f32,
+}
+
+#[derive(Resource, Debug)]
+struct PlantAssets {
+    ring: Handle<Mesh>,
+    dust: Handle<Mesh>,
+    dust_material: Handle<StandardMaterial>,
+}
+
+/// Space plants the staff Laura is holding on the ground: a ring spreads from where it strikes,
+/// bending the grass outwards, dust flies up and the staff's gem pulses.
+pub struct StaffPlantPlugin;
+
+impl Plugin for StaffPlantPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_plant_assets).add_systems(
+            Update,
+            (
+                plant_staff.run_if(in_state(AppState::Walking)).run_if(
+                    |keyboard_input: Res<ButtonInput<KeyCode>>| {
+                        keyboard_input.just_pressed(KeyCode::Space)
+                    },
+                ),
+                strike_ground,
+                spread_shockwaves,
+                settle_dust,
+                pulse_gems,
+            ),
+        );
+    }
+}
+
+fn setup_plant_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(PlantAssets {
+        // Scaled to the shockwave's radius as it spreads
+        ring: meshes.add(Annulus::new(0.9, 1.)),
+        dust: meshes.add(Sphere::new(0.02).mesh().ico(0).expect("ico 0 is valid")),
+        dust_material: materials.add(Color::from(css::TAN)),
+    });
+}
+
+type HeldStaff = (
+    Entity,
+    &'static Transform,
+    &'static GlobalTransform,
+    &'static Sockets,
+);
+
+/// The ground is found by casting down from the staff's base, so it's struck wherever the
+/// floor is.
+fn plant_staff(
+    mut commands: Commands,
+    units: Res<UnitsConfig>,
+    mut ray_cast: MeshRayCast,
+    floors: Query<(), With<Floor>>,
+    staffs: Query<HeldStaff, (With<Held>, Without<Planting>)>,
+) {
+    let Some((staff, transform, global, sockets)) = staffs.iter().next() else {
+        return;
+    };
+    let bottom = sockets.get(sockets::BOTTOM).unwrap_or_default();
+    let base = global.transform_point(bottom.translation);
+    let is_floor = |entity| floors.contains(entity);
+    let settings = MeshRayCastSettings::default().with_filter(&is_floor);
+    let Some((_, hit)) = ray_cast
+        .cast_ray(Ray3d::new(base, Dir3::NEG_Y), &settings)
+        .first()
+    else {
+        return;
+    };
+    if hit.distance > GROUND_REACH * units.from_meters() {
+        return;
+    }
+    commands.entity(staff).insert(Planting {
+        elapsed: 0.,
+        depth: hit.distance,
+        contact: hit.point,
+        rest: *transform,
+        struck: false,
+    });
+}
+
+type PlantingStaff = (
+    Entity,
+    &'static mut Transform,
+    &'static mut Planting,
+    Has<Held>,
+    Option<&'static Children>,
+);
+
+/// Moves planting staffs down and up again. Halfway, when the base touches the ground, the
+/// shockwave, dust and gem pulse start.
+fn strike_ground(
+    mut commands: Commands,
+    time: Res<Time>,
+    units: Res<UnitsConfig>,
+    assets: Res<PlantAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut staffs: Query<PlantingStaff>,
+    gems: Query<(), With<StaffGem>>,
+) {
+    for (staff, mut transform, mut planting, held, children) in &mut staffs {
+        // Dropped halfway, so it falls from where it is
+        if !held {
+            commands.entity(staff).remove::<Planting>();
+            continue;
+        }
+        planting.elapsed += time.delta_secs();
+        let t = (planting.elapsed / PLANT_DURATION).min(1.);
+        // The hand only turns about the vertical, so down in the hand is down in the world
+        transform.translation =
+            planting.rest.translation - Vec3::Y * planting.depth * (t * PI).sin();
+        if t >= 0.5 && !planting.struck {
+            planting.struck = true;
+            spawn_strike(
+                &mut commands,
+                &assets,
+                &mut materials,
+                planting.contact,
+                units.from_meters(),
+            );
+            for &child in children.into_iter().flatten() {
+                if gems.contains(child) {
+                    commands.entity(child).insert(GemPulse { elapsed: 0. });
+                }
+            }
+        }
+        if t >= 1. {
+            *transform = planting.rest;
+            commands.entity(staff).remove::<Planting>();
+        }
+    }
+}
+
+fn spawn_strike(
+    commands: &mut Commands,
+    assets: &PlantAssets,
+    materials: &mut Assets<StandardMaterial>,
+    contact: Vec3,
+    from_meters: f32,
+) {
+    // Each ring fades on its own
+    let ring_material = materials.add(StandardMaterial {
+        base_color: Color::from(css::LIGHT_CYAN).with_alpha(0.8),
+        emissive: LinearRgba::from(css::LIGHT_CYAN) * 2.,
+        alpha_mode: AlphaMode::Add,
+        unlit: true,
+        ..default()
+    });
+    commands.spawn((
+        Name::new("Shockwave"),
+        Shockwave {
+            elapsed: 0.,
+            radius: SHOCKWAVE_RADIUS * from_meters,
+        },
+        Mesh3d(assets.ring.clone()),
+        MeshMaterial3d(ring_material),
+        // Lying on the ground, just above it so it doesn't flicker
+        Transform::from_translation(contact + Vec3::Y * 0.005)
+            .with_rotation(Quat::from_rotation_x(-FRAC_PI_2))
+            .with_scale(Vec3::ZERO),
+    ));
+    for i in 0..DUST_MOTES {
+        let angle = i as f32 / DUST_MOTES as f32 * TAU;
+        let outward = Vec2::from_angle(angle);
+        // Alternate steep and shallow throws so the puff has some depth
+        let rise = if i % 2 == 0 { 1.2 } else { 0.6 };
+        commands.spawn((
+            Name::new("DustMote"),
+            DustMote {
+                velocity: vec3(outward.x, rise, outward.y) * DUST_SPEED * from_meters,
+                age: 0.,
+            },
+            Mesh3d(assets.dust.clone()),
+            MeshMaterial3d(assets.dust_material.clone()),
+            Transform::from_translation(contact).with_scale(Vec3::splat(from_meters)),
+        ));
+    }
+}
+
+fn spread_shockwaves(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut waves: Query<(
+        Entity,
+        &mut Shockwave,
+        &mut Transform,
+        &MeshMaterial3d<StandardMaterial>,
+    )>,
+) {
+    for (entity, mut wave, mut transform, material) in &mut waves {
+        wave.elapsed += time.delta_secs();
+        let progress = wave.progress();
+        if progress >= 1. {
+            materials.remove(&material.0);
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.scale = Vec3::splat(wave.radius * progress);
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color.set_alpha(0.8 * (1. - progress));
+        }
+    }
+}
+
+/// Motes fly out, fall back and shrink away.
+fn settle_dust(
+    mut commands: Commands,
+    time: Res<Time>,
+    units: Res<UnitsConfig>,
+    mut motes: Query<(Entity, &mut DustMote, &mut Transform)>,
+) {
+    let delta = time.delta_secs();
+    for (entity, mut mote, mut transform) in &mut motes {
+        mote.age += delta;
+        if mote.age >= DUST_LIFETIME {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        mote.velocity.y -= GRAVITY * units.from_meters() * delta;
+        transform.translation += mote.velocity * delta;
+        transform.scale = Vec3::splat(units.from_meters() * (1. - mote.age / DUST_LIFETIME));
+    }
+}
+
+/// Gems flash in their own color and fade back to unlit.
+fn pulse_gems(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut gems: Query<(Entity, &mut GemPulse, &MeshMaterial3d<StandardMaterial>)>,
+) {
+    for (entity, mut pulse, material) in &mut gems {
+        pulse.elapsed += time.delta_secs();
+        let t = (pulse.elapsed / PULSE_DURATION).min(1.);
+        let Some(material) = materials.get_mut(&material.0) else {
+            continue;
+        };
+        material.emissive = material.base_color.to_linear() * PULSE_INTENSITY * (1. - t).powi(2);
+        if t >= 1. {
+            commands.entity(entity).remove::<GemPulse>();
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use bevy::asset::RecursiveDependencyLoadState;
+use bevy::prelude::*;
+
+use crate::actions::RegisterAction;
+use crate::asset_loader::SceneAssets;
+
+/// Top-level mode of the viewer.
+/// E toggles editing, B opens the gallery and Escape returns to viewing. While walking, E picks
+/// up and drops staffs instead.
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AppState {
+    /// Waiting for scene assets
+    #[default]
+    Loading,
+    Viewing,
+    /// Parameter panel and normal gizmos are shown
+    Editing,
+    /// A batch of staff variants is laid out behind the scene
+    Gallery,
+    /// A grid of variants bred from a favorite staff
+    Exploring,
+    /// Walking Laura around the floor, followed by the camera
+    Walking,
+}
+
+pub struct AppStatePlugin;
+
+impl Plugin for AppStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<AppState>()
+            .register_action(
+                "Toggle editing and normal gizmos",
+                |state: Res<State<AppState>>, mut next_state: ResMut<NextState<AppState>>| {
+                    next_state.set(match state.get() {
+                        AppState::Editing => AppState::Viewing,
+                        _ => AppState::Editing,
+                    });
+                },
+            )
+            .register_action(
+                "Open gallery",
+                |mut next_state: ResMut<NextState<AppState>>| next_state.set(AppState::Gallery),
+            )
+            .register_action(
+                "Return to viewing",
+                |mut next_state: ResMut<NextState<AppState>>| next_state.set(AppState::Viewing),
+            )
+            .add_systems(Update, finish_loading.run_if(in_state(AppState::Loading)))
+            .add_systems(
+                Update,
+                switch_app_state.run_if(not(in_state(AppState::Loading))),
+            );
+    }
+}
+
+fn finish_loading(
+    scene_assets: Res<SceneAssets>,
+    asset_server: Res<AssetServer>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    match asset_server.get_recursive_dependency_load_state(&scene_assets.laura) {
+        Some(RecursiveDependencyLoadState::Loaded) => next_state.set(AppState::Viewing),
+        // The generated meshes are still worth looking at without the scene
+        Some(RecursiveDependencyLoadState::Failed(error)) => {
+            warn!("Scene assets failed to load: {error}");
+            next_state.set(AppState::Viewing);
+        }
+        _ => {}
+    }
+}
+
+fn switch_app_state(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Viewing);
+    } else if keyboard_input.just_pressed(KeyCode::KeyE) && *state.get() != AppState::Walking {
+        next_state.set(match state.get() {
+            AppState::Editing => AppState::Viewing,
+            _ => AppState::Editing,
+        });
+    } else if keyboard_input.just_pressed(KeyCode::KeyB) {
+        next_state.set(AppState::Gallery);
+    }
+}
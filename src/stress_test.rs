@@ -0,0 +1,172 @@
+use bevy::color::palettes::css;
+use bevy::mesh::MeshTag;
+use bevy::prelude::*;
+use staff_gen::crystal::generate_crystal_mesh;
+
+use crate::environment::{EnvironmentConfig, FLOOR_HEIGHT};
+use crate::foliage::{grass_blade_mesh, grass_material};
+use crate::grass_material::GrassMaterial;
+use crate::wind::Wind;
+
+const INSTANCES_PER_SIDE: u32 = 100;
+const INSTANCE_SCALE: f32 = 0.2;
+
+/// One mesh and one material shared by every stress test crystal, and another pair by every
+/// grass blade. Entities sharing both handles are batched into instanced draws by Bevy's
+/// renderer, the blades with their [`MeshTag`] as per-instance data for the grass shader.
+#[derive(Resource, Debug)]
+struct StressTestAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    blade: Handle<Mesh>,
+    grass: Handle<GrassMaterial>,
+}
+
+#[derive(Component)]
+struct StressTestInstance;
+
+pub struct StressTestPlugin;
+
+impl Plugin for StressTestPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_stress_test_assets)
+            .add_systems(Update, toggle_stress_test);
+    }
+}
+
+fn setup_stress_test_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut grass_materials: ResMut<Assets<GrassMaterial>>,
+    wind: Res<Wind>,
+) {
+    commands.insert_resource(StressTestAssets {
+        mesh: meshes.add(generate_crystal_mesh(0.5, 1., 6)),
+        material: materials.add(Color::from(css::SKY_BLUE)),
+        blade: meshes.add(grass_blade_mesh()),
+        grass: grass_materials.add(grass_material(&wind)),
+    });
+}
+
+/// F9 spawns a grid of 10k crystals with a blade of grass between every four, pressing it again
+/// despawns them.
+fn toggle_stress_test(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    assets: Res<StressTestAssets>,
+    environment: Res<EnvironmentConfig>,
+    instances: Query<Entity, With<StressTestInstance>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F9) {
+        return;
+    }
+
+    if !instances.is_empty() {
+        for entity in &instances {
+            commands.entity(entity).despawn();
+        }
+        info!("Despawned stress test instances");
+        return;
+    }
+
+    let spacing = environment.floor_length / INSTANCES_PER_SIDE as f32;
+    let start = -environment.floor_length / 2. + spacing / 2.;
+    let y = FLOOR_HEIGHT / 2. + INSTANCE_SCALE / 2.;
+    let crystals: Vec<_> = (0..INSTANCES_PER_SIDE * INSTANCES_PER_SIDE)
+        .map(|i| {
+            let x = start + (i % INSTANCES_PER_SIDE) as f32 * spacing;
+            let z = start + (i / INSTANCES_PER_SIDE) as f32 * spacing;
+            (
+                Name::new(format!("StressTestInstance {i}")),
+                StressTestInstance,
+                Mesh3d(assets.mesh.clone()),
+                MeshMaterial3d(assets.material.clone()),
+                Transform::from_xyz(x, y, z).with_scale(Vec3::splat(INSTANCE_SCALE)),
+            )
+        })
+        .collect();
+    let blades: Vec<_> = (0..INSTANCES_PER_SIDE * INSTANCES_PER_SIDE)
+        .map(|i| {
+            let x = start + ((i % INSTANCES_PER_SIDE) as f32 + 0.5) * spacing;
+            let z = start + ((i / INSTANCES_PER_SIDE) as f32 + 0.5) * spacing;
+            (
+                Name::new(format!("StressTestBlade {i}")),
+                StressTestInstance,
+                Mesh3d(assets.blade.clone()),
+                MeshMaterial3d(assets.grass.clone()),
+                MeshTag(i),
+                Transform::from_xyz(x, FLOOR_HEIGHT / 2., z),
+            )
+        })
+        .collect();
+    let (crystal_count, blade_count) = (crystals.len(), blades.len());
+    commands.spawn_batch(crystals);
+    commands.spawn_batch(blades);
+    info!(
+        "Spawned {crystal_count} stress test crystals and {blade_count} grass blades, each sharing \
+         one mesh and material"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use bevy::asset::AssetPlugin;
+
+    use super::*;
+
+    /// Bevy only batches entities into one instanced draw when they share their mesh and
+    /// material, so every crystal must share one pair and every blade another.
+    #[test]
+    fn instances_share_their_mesh_and_material() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<StandardMaterial>()
+            .init_asset::<GrassMaterial>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<EnvironmentConfig>()
+            .init_resource::<Wind>()
+            .add_plugins(StressTestPlugin);
+        app.update();
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::F9);
+        app.update();
+
+        let world = app.world_mut();
+        let crystals: Vec<_> = world
+            .query_filtered::<(&Mesh3d, &MeshMaterial3d<StandardMaterial>), With<StressTestInstance>>()
+            .iter(world)
+            .map(|(mesh, material)| (mesh.id(), material.id()))
+            .collect();
+        assert_eq!(crystals.len(), 10_000);
+        assert_eq!(crystals.iter().collect::<HashSet<_>>().len(), 1);
+
+        let blades: Vec<_> = world
+            .query_filtered::<(&Mesh3d, &MeshMaterial3d<GrassMaterial>, &MeshTag), With<StressTestInstance>>()
+            .iter(world)
+            .map(|(mesh, material, tag)| ((mesh.id(), material.id()), tag.0))
+            .collect();
+        assert_eq!(blades.len(), 10_000);
+        assert_eq!(
+            blades
+                .iter()
+                .map(|(handles, _)| handles)
+                .collect::<HashSet<_>>()
+                .len(),
+            1
+        );
+        // The per-instance data differs even though the draw is shared
+        assert_eq!(
+            blades
+                .iter()
+                .map(|(_, tag)| tag)
+                .collect::<HashSet<_>>()
+                .len(),
+            10_000
+        );
+    }
+}
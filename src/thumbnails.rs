@@ -0,0 +1,328 @@
+use std::f32::consts::FRAC_PI_8;
+
+use bevy::camera::RenderTarget;
+use bevy::camera::primitives::MeshAabb;
+use bevy::camera::visibility::RenderLayers;
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+use staff_gen::staff::StaffConfig;
+use staff_gen::style::StyleConfig;
+
+use crate::locale::Locale;
+use crate::pedestal::{PedestalDisplay, PedestalMaterial};
+use crate::staff::GenerateStaff;
+use crate::state::AppState;
+use crate::units::UnitsConfig;
+
+/// Render layer the thumbnail staffs live on, so the main camera never sees them
+const THUMBNAIL_LAYER: usize = 1;
+const THUMBNAIL_COUNT: usize = 8;
+const THUMBNAIL_SIZE: u32 = 96;
+/// Thumbnail staffs stand far below the scene, spaced so each camera only frames its own
+const THUMBNAIL_STAGE: Vec3 = vec3(0., -50., 0.);
+const THUMBNAIL_SPACING: f32 = 4.;
+/// Frames a thumbnail camera stays active for after its staff changes
+const THUMBNAIL_RENDER_FRAMES: u32 = 2;
+
+/// Which seeds the gallery's thumbnail strip shows. Comma and period page through them.
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct ThumbnailBrowser {
+    pub first_seed: u64,
+}
+
+#[derive(Component, Debug)]
+struct ThumbnailPanel;
+
+/// Offscreen staff rendered into the thumbnail of the same slot.
+#[derive(Component, Debug)]
+struct ThumbnailStaff(usize);
+
+/// Pedestal under a thumbnail staff, shown while [`PedestalDisplay`] is enabled.
+#[derive(Component, Debug)]
+struct ThumbnailPedestal;
+
+#[derive(Component, Debug)]
+struct ThumbnailCamera {
+    slot: usize,
+    frames_left: u32,
+}
+
+/// A thumbnail in the strip. Clicking it spawns its staff beside the viewer's own.
+#[derive(Component, Debug)]
+struct Thumbnail {
+    slot: usize,
+    config: Option<StaffConfig>,
+}
+
+#[derive(Resource, Debug)]
+struct ThumbnailMaterial(Handle<StandardMaterial>);
+
+/// Renders small previews of staff seeds to textures shown while the gallery is open, so
+/// variants can be browsed without spawning each one into the scene.
+pub struct ThumbnailPlugin;
+
+impl Plugin for ThumbnailPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ThumbnailBrowser>()
+            .init_resource::<ThumbnailBrowser>()
+            .add_systems(Startup, setup_thumbnails)
+            .add_systems(OnEnter(AppState::Gallery), show_thumbnail_panel)
+            .add_systems(OnExit(AppState::Gallery), hide_thumbnail_panel)
+            .add_systems(
+                Update,
+                (
+                    page_thumbnails_on_key,
+                    render_thumbnails.run_if(
+                        resource_changed::<ThumbnailBrowser>
+                            .or(resource_changed::<Locale>)
+                            .or(resource_changed::<PedestalDisplay>),
+                    ),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Gallery)),
+            )
+            .add_systems(Update, finish_thumbnail_renders);
+    }
+}
+
+fn setup_thumbnails(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(ThumbnailMaterial(
+        materials.add(Color::from(css::SADDLE_BROWN)),
+    ));
+    commands.spawn((
+        Name::new("ThumbnailLight"),
+        DirectionalLight::default(),
+        Transform::from_xyz(1., 2., 2.).looking_at(Vec3::ZERO, Dir3::Y),
+        RenderLayers::layer(THUMBNAIL_LAYER),
+    ));
+
+    let panel = commands
+        .spawn((
+            Name::new("ThumbnailPanel"),
+            ThumbnailPanel,
+            Visibility::Hidden,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.),
+                left: Val::Px(12.),
+                column_gap: Val::Px(4.),
+                ..default()
+            },
+        ))
+        .id();
+
+    for slot in 0..THUMBNAIL_COUNT {
+        let image = images.add(Image::new_target_texture(
+            THUMBNAIL_SIZE,
+            THUMBNAIL_SIZE,
+            TextureFormat::bevy_default(),
+        ));
+        commands.spawn((
+            Name::new(format!("ThumbnailStaff {slot}")),
+            ThumbnailStaff(slot),
+            Transform::from_translation(
+                THUMBNAIL_STAGE + Vec3::X * slot as f32 * THUMBNAIL_SPACING,
+            ),
+            Visibility::default(),
+            RenderLayers::layer(THUMBNAIL_LAYER),
+            children![(
+                Name::new(format!("ThumbnailPedestal {slot}")),
+                ThumbnailPedestal,
+                RenderLayers::layer(THUMBNAIL_LAYER),
+            )],
+        ));
+        commands.spawn((
+            Name::new(format!("ThumbnailCamera {slot}")),
+            ThumbnailCamera {
+                slot,
+                frames_left: 0,
+            },
+            Camera3d::default(),
+            Camera {
+                target: RenderTarget::Image(image.clone().into()),
+                clear_color: ClearColorConfig::Custom(Color::from(css::DARK_SLATE_GRAY)),
+                is_active: false,
+                ..default()
+            },
+            RenderLayers::layer(THUMBNAIL_LAYER),
+        ));
+        commands
+            .spawn((
+                Name::new(format!("Thumbnail {slot}")),
+                Thumbnail { slot, config: None },
+                ChildOf(panel),
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                children![
+                    (
+                        ImageNode::new(image),
+                        Node {
+                            width: Val::Px(THUMBNAIL_SIZE as f32),
+                            height: Val::Px(THUMBNAIL_SIZE as f32),
+                            ..default()
+                        },
+                    ),
+                    (Text::default(), TextFont::from_font_size(10.)),
+                ],
+            ))
+            .observe(spawn_thumbnail_staff);
+    }
+}
+
+fn show_thumbnail_panel(
+    mut panel: Single<&mut Visibility, With<ThumbnailPanel>>,
+    mut browser: ResMut<ThumbnailBrowser>,
+) {
+    **panel = Visibility::Inherited;
+    // Rerender in case the unit defaults changed while the gallery was closed
+    browser.set_changed();
+}
+
+fn hide_thumbnail_panel(mut panel: Single<&mut Visibility, With<ThumbnailPanel>>) {
+    **panel = Visibility::Hidden;
+}
+
+/// Period shows the next page of seeds, comma the previous one.
+fn page_thumbnails_on_key(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut browser: ResMut<ThumbnailBrowser>,
+) {
+    let page = THUMBNAIL_COUNT as u64;
+    if keyboard_input.just_pressed(KeyCode::Period) {
+        browser.first_seed += page;
+    }
+    if keyboard_input.just_pressed(KeyCode::Comma) {
+        browser.first_seed = browser.first_seed.saturating_sub(page);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_thumbnails(
+    mut commands: Commands,
+    browser: Res<ThumbnailBrowser>,
+    locale: Res<Locale>,
+    units: Res<UnitsConfig>,
+    style: Res<StyleConfig>,
+    material: Res<ThumbnailMaterial>,
+    display: Res<PedestalDisplay>,
+    pedestal_material: Res<PedestalMaterial>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    staffs: Query<(
+        Entity,
+        &ThumbnailStaff,
+        &Transform,
+        Option<&Mesh3d>,
+        &Children,
+    )>,
+    pedestals: Query<(Entity, Option<&Mesh3d>), With<ThumbnailPedestal>>,
+    mut cameras: Query<
+        (&mut ThumbnailCamera, &mut Camera, &mut Transform),
+        Without<ThumbnailStaff>,
+    >,
+    mut thumbnails: Query<(&mut Thumbnail, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (entity, staff, transform, old_mesh, children) in &staffs {
+        let config = StaffConfig {
+            seed: browser.first_seed + staff.0 as u64,
+            ..units.staff_defaults()
+        };
+        let mesh = style.apply(style.staff(&config).generate_mesh());
+        let Some(aabb) = mesh.compute_aabb() else {
+            continue;
+        };
+        if let Some(old_mesh) = old_mesh {
+            meshes.remove(&old_mesh.0);
+        }
+        commands
+            .entity(entity)
+            .insert((Mesh3d(meshes.add(mesh)), MeshMaterial3d(material.0.clone())));
+
+        let (mut min, mut max) = (aabb.min(), aabb.max());
+        for (pedestal, old_mesh) in pedestals.iter_many(children) {
+            let Some(under) = display.under(&config) else {
+                commands.entity(pedestal).insert(Visibility::Hidden);
+                continue;
+            };
+            let mesh = display.pedestal.generate_mesh();
+            if let Some(pedestal_aabb) = mesh.compute_aabb() {
+                min = min.min(pedestal_aabb.min() + Vec3A::from(under.translation));
+                max = max.max(pedestal_aabb.max() + Vec3A::from(under.translation));
+            }
+            if let Some(old_mesh) = old_mesh {
+                meshes.remove(&old_mesh.0);
+            }
+            commands.entity(pedestal).insert((
+                Mesh3d(meshes.add(mesh)),
+                MeshMaterial3d(pedestal_material.0.clone()),
+                under,
+                Visibility::Inherited,
+            ));
+        }
+        let half_extents = (max - min) / 2.;
+
+        // Fit the staff and pedestal in a 45° field of view, seen slightly from above and the side
+        let center = transform.transform_point(((min + max) / 2.).into());
+        let distance = half_extents.max_element() * 1.2 / FRAC_PI_8.tan() + half_extents.z;
+        for (mut thumbnail_camera, mut camera, mut camera_transform) in &mut cameras {
+            if thumbnail_camera.slot != staff.0 {
+                continue;
+            }
+            *camera_transform =
+                Transform::from_translation(center + vec3(0.3, 0.2, 1.).normalize() * distance)
+                    .looking_at(center, Dir3::Y);
+            camera.is_active = true;
+            thumbnail_camera.frames_left = THUMBNAIL_RENDER_FRAMES;
+        }
+        for (mut thumbnail, children) in &mut thumbnails {
+            if thumbnail.slot != staff.0 {
+                continue;
+            }
+            let mut text = texts.iter_many_mut(children);
+            if let Some(mut text) = text.fetch_next() {
+                text.0 = locale.staff_name(&config);
+            }
+            thumbnail.config = Some(config.clone());
+        }
+    }
+}
+
+/// Thumbnails only change with their seed, so cameras stop rendering once they're drawn.
+fn finish_thumbnail_renders(mut cameras: Query<(&mut ThumbnailCamera, &mut Camera)>) {
+    for (mut thumbnail_camera, mut camera) in &mut cameras {
+        if !camera.is_active {
+            continue;
+        }
+        if thumbnail_camera.frames_left == 0 {
+            camera.is_active = false;
+        } else {
+            thumbnail_camera.frames_left -= 1;
+        }
+    }
+}
+
+fn spawn_thumbnail_staff(
+    click: On<Pointer<Click>>,
+    thumbnails: Query<&Thumbnail>,
+    mut requests: MessageWriter<GenerateStaff>,
+) {
+    let Some(config) = thumbnails
+        .get(click.entity)
+        .ok()
+        .and_then(|thumbnail| thumbnail.config.clone())
+    else {
+        return;
+    };
+    let mut request = GenerateStaff::new(config);
+    request.transform.translation.x += 0.6;
+    requests.write(request);
+}
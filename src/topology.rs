@@ -0,0 +1,68 @@
+use bevy::prelude::*;
+use staff_gen::topology::TopologyReport;
+
+use crate::actions::RegisterAction;
+use crate::generation::{GeneratedObject, is_generated};
+
+/// "Analyze topology" attaches a [`TopologyReport`] to every generated mesh, shown in the
+/// inspector and logged. Reports follow their meshes from then on, so regenerating a staff keeps
+/// its report current.
+pub struct TopologyPlugin;
+
+impl Plugin for TopologyPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<TopologyReport>()
+            .register_action("Analyze topology", analyze_topology)
+            .add_systems(Update, refresh_topology_reports);
+    }
+}
+
+fn analyze_topology(
+    mut commands: Commands,
+    meshes: Res<Assets<Mesh>>,
+    objects: Query<(Entity, &Mesh3d, NameOrEntity)>,
+    generated: Query<(), With<GeneratedObject>>,
+    parents: Query<&ChildOf>,
+) {
+    let mut analyzed = 0;
+    for (entity, mesh, name) in &objects {
+        if !is_generated(entity, &generated, &parents) {
+            continue;
+        }
+        let Some(mesh) = meshes.get(mesh) else {
+            continue;
+        };
+        let report = TopologyReport::new(mesh);
+        if report.is_closed_manifold() {
+            info!("{name}: {report:?}");
+        } else {
+            warn!("{name} is not a closed manifold: {report:?}");
+        }
+        commands.entity(entity).insert(report);
+        analyzed += 1;
+    }
+    info!("Analyzed the topology of {analyzed} meshes");
+}
+
+/// Reanalyzes meshes that were regenerated in place or swapped for another.
+fn refresh_topology_reports(
+    mut events: MessageReader<AssetEvent<Mesh>>,
+    meshes: Res<Assets<Mesh>>,
+    mut reports: Query<(&mut TopologyReport, Ref<Mesh3d>)>,
+) {
+    let modified: Vec<AssetId<Mesh>> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    for (mut report, mesh) in &mut reports {
+        if !mesh.is_changed() && !modified.contains(&mesh.id()) {
+            continue;
+        }
+        if let Some(mesh) = meshes.get(&*mesh) {
+            report.set_if_neq(TopologyReport::new(mesh));
+        }
+    }
+}
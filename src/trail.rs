@@ -0,0 +1,236 @@
+use std::collections::VecDeque;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::camera::visibility::NoFrustumCulling;
+use bevy::light::NotShadowCaster;
+use bevy::mesh::skinning::SkinnedMesh;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use bevy::transform::TransformSystems;
+use staff_gen::sockets::{self, Sockets};
+use staff_gen::staff::StaffConfig;
+
+use crate::camera::MainCamera;
+use crate::dynamic_mesh::{DynamicMesh, DynamicMeshSync};
+use crate::skinning::StaffSwing;
+use crate::units::UnitsConfig;
+
+/// Points closer than this to the last one recorded are skipped, in meters
+const MIN_SPACING: f32 = 0.01;
+
+/// Leaves a ribbon behind a point on the entity as it moves, fading out and narrowing towards
+/// its oldest end.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct Trail {
+    /// Point the trail follows, in the entity's own space
+    pub offset: Vec3,
+    /// Seconds a point stays on the trail
+    pub lifetime: f32,
+    /// Width at the newest point, in meters
+    pub width: f32,
+    pub color: Color,
+}
+
+impl Default for Trail {
+    fn default() -> Self {
+        Self {
+            offset: Vec3::ZERO,
+            lifetime: 0.4,
+            width: 0.05,
+            color: Color::srgb(1., 0.85, 0.55),
+        }
+    }
+}
+
+/// The ribbon drawing a [`Trail`], in world space, and the points it's built from along with
+/// when each was recorded.
+#[derive(Component, Debug)]
+struct TrailRibbon {
+    source: Entity,
+    points: VecDeque<(Vec3, f32)>,
+}
+
+/// Trails behind staff tips, visible while a staff swings, is dragged around or is carried.
+/// Each trail's [`DynamicMesh`] is rewritten every frame from the points it has recorded,
+/// turned to face the camera.
+pub struct TrailPlugin;
+
+impl Plugin for TrailPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Trail>()
+            .add_systems(Update, (add_staff_trails, spawn_trail_ribbons).chain())
+            .add_systems(
+                PostUpdate,
+                (record_trails, stream_trail_meshes)
+                    .chain()
+                    .after(TransformSystems::Propagate)
+                    .before(DynamicMeshSync),
+            );
+    }
+}
+
+type StaffTip = (Entity, &'static Sockets, Option<&'static mut Trail>);
+
+/// Staffs trail from their top socket, which moves when they're regenerated, and swinging
+/// staffs from their tip joint.
+fn add_staff_trails(
+    mut commands: Commands,
+    mut staffs: Query<StaffTip, (With<StaffConfig>, Changed<Sockets>)>,
+    swinging: Query<&SkinnedMesh, Added<StaffSwing>>,
+) {
+    for (staff, sockets, trail) in &mut staffs {
+        let offset = sockets.get(sockets::TOP).unwrap_or_default().translation;
+        match trail {
+            Some(mut trail) => trail.offset = offset,
+            None => {
+                commands.entity(staff).insert(Trail {
+                    offset,
+                    ..default()
+                });
+            }
+        }
+    }
+    for skinned_mesh in &swinging {
+        if let Some(&tip) = skinned_mesh.joints.last() {
+            commands.entity(tip).insert(Trail::default());
+        }
+    }
+}
+
+fn spawn_trail_ribbons(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    trails: Query<Entity, Added<Trail>>,
+) {
+    for source in &trails {
+        // A collapsed strip until there are points to draw
+        let mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_indices(Indices::U32(vec![0, 1, 2, 1, 3, 2]))
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.; 3]; 4])
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, vec![[0.; 4]; 4]);
+        commands.spawn((
+            Name::new("TrailRibbon"),
+            TrailRibbon {
+                source,
+                points: VecDeque::new(),
+            },
+            Mesh3d(meshes.add(mesh)),
+            DynamicMesh::default(),
+            // The trail's color is in the vertex colors, which also fade it out
+            MeshMaterial3d(materials.add(StandardMaterial {
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                cull_mode: None,
+                ..default()
+            })),
+            // Points are in world space
+            Transform::IDENTITY,
+            // Hidden until there are two points, and culling would go by the first mesh's bounds
+            Visibility::Hidden,
+            NoFrustumCulling,
+            NotShadowCaster,
+        ));
+    }
+}
+
+/// Records where each trail's point has moved to since last frame and forgets points older
+/// than its lifetime. Ribbons whose trail is gone are removed.
+fn record_trails(
+    mut commands: Commands,
+    time: Res<Time>,
+    units: Res<UnitsConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut ribbons: Query<(
+        Entity,
+        &mut TrailRibbon,
+        &Mesh3d,
+        &MeshMaterial3d<StandardMaterial>,
+    )>,
+    trails: Query<(&Trail, &GlobalTransform)>,
+) {
+    let now = time.elapsed_secs();
+    for (entity, mut ribbon, mesh, material) in &mut ribbons {
+        let Ok((trail, transform)) = trails.get(ribbon.source) else {
+            meshes.remove(&mesh.0);
+            materials.remove(&material.0);
+            commands.entity(entity).despawn();
+            continue;
+        };
+        let point = transform.transform_point(trail.offset);
+        let moved = ribbon
+            .points
+            .back()
+            .is_none_or(|&(last, _)| last.distance(point) >= MIN_SPACING * units.from_meters());
+        if moved {
+            ribbon.points.push_back((point, now));
+        }
+        while ribbon
+            .points
+            .front()
+            .is_some_and(|&(_, recorded)| now - recorded > trail.lifetime)
+        {
+            ribbon.points.pop_front();
+        }
+    }
+}
+
+/// Rebuilds each ribbon from its points: a strip whose sides are spread across the direction
+/// it runs in and the direction to the camera, narrowing and fading with each point's age.
+fn stream_trail_meshes(
+    time: Res<Time>,
+    units: Res<UnitsConfig>,
+    camera: Single<&GlobalTransform, With<MainCamera>>,
+    mut ribbons: Query<(&TrailRibbon, &mut DynamicMesh, &mut Visibility)>,
+    trails: Query<&Trail>,
+) {
+    let now = time.elapsed_secs();
+    let camera = camera.translation();
+    for (ribbon, mut mesh, mut visibility) in &mut ribbons {
+        let Ok(trail) = trails.get(ribbon.source) else {
+            continue;
+        };
+        if ribbon.points.len() < 2 {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+
+        mesh.positions.clear();
+        mesh.colors.clear();
+        let color = trail.color.to_linear();
+        let half_width = trail.width / 2. * units.from_meters();
+        let last = ribbon.points.len() - 1;
+        for (i, &(point, recorded)) in ribbon.points.iter().enumerate() {
+            let before = ribbon.points[i.saturating_sub(1)].0;
+            let after = ribbon.points[(i + 1).min(last)].0;
+            let fade = (1. - (now - recorded) / trail.lifetime.max(f32::EPSILON)).clamp(0., 1.);
+            let side =
+                (after - before).cross(camera - point).normalize_or_zero() * half_width * fade;
+            mesh.positions.push((point - side).to_array());
+            mesh.positions.push((point + side).to_array());
+            let faded = color.with_alpha(color.alpha * fade).to_f32_array();
+            mesh.colors.extend_from_slice(&[faded, faded]);
+        }
+        // The strip's triangles only change when points are added or forgotten
+        if mesh.indices.len() != last * 6 {
+            mesh.indices.clear();
+            for segment in 0..last as u32 {
+                let (left, right) = (segment * 2, segment * 2 + 1);
+                mesh.indices.extend_from_slice(&[
+                    left,
+                    right,
+                    left + 2,
+                    right,
+                    right + 2,
+                    left + 2,
+                ]);
+            }
+        }
+    }
+}
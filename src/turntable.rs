@@ -0,0 +1,242 @@
+use std::f32::consts::TAU;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bevy::camera::RenderTarget;
+use bevy::camera::primitives::Aabb;
+use bevy::math::bounding::BoundingVolume;
+use bevy::prelude::*;
+use bevy::render::render_resource::{TextureFormat, TextureUsages};
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured, save_to_disk};
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::{PresentMode, PrimaryWindow};
+
+use crate::graphics::{GraphicsSettings, apply_post_processing};
+use crate::selection::{Selected, selected_or_staff, world_aabb};
+use crate::staff::Staff;
+
+const TURNTABLE_DIR: &str = "exports";
+
+/// Size and length of turntable renders. V records one.
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct TurntableSettings {
+    /// Frames for one full orbit
+    pub frames: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Playback rate of the encoded video
+    pub framerate: u32,
+    /// Camera distance as a multiple of the framed object's radius
+    pub distance: f32,
+    /// Camera height above the object's center as a multiple of its radius
+    pub elevation: f32,
+}
+
+impl Default for TurntableSettings {
+    fn default() -> Self {
+        Self {
+            frames: 120,
+            width: 1280,
+            height: 720,
+            framerate: 30,
+            distance: 2.5,
+            elevation: 0.4,
+        }
+    }
+}
+
+/// A turntable being rendered one frame per app update, as fast as the GPU allows. Time steps
+/// by exactly one video frame per update meanwhile, so wind, particles and other animation play
+/// back at the right speed however long each frame takes to render.
+#[derive(Resource)]
+struct TurntableRecording {
+    camera: Entity,
+    dir: PathBuf,
+    center: Vec3,
+    radius: f32,
+    frame: u32,
+    saved: u32,
+    present_mode: PresentMode,
+    time_strategy: TimeUpdateStrategy,
+}
+
+/// Renders generated objects offline into numbered PNGs, then encodes them with ffmpeg when it
+/// is installed. Native only.
+pub struct TurntablePlugin;
+
+impl Plugin for TurntablePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<TurntableSettings>()
+            .init_resource::<TurntableSettings>()
+            .add_systems(
+                Update,
+                (
+                    start_turntable_on_key.run_if(not(resource_exists::<TurntableRecording>)),
+                    record_turntable_frame.run_if(resource_exists::<TurntableRecording>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// V renders a turntable of the selected object, or the viewer's staff.
+#[allow(clippy::too_many_arguments)]
+fn start_turntable_on_key(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    settings: Res<TurntableSettings>,
+    graphics: Res<GraphicsSettings>,
+    mut time_strategy: ResMut<TimeUpdateStrategy>,
+    mut images: ResMut<Assets<Image>>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+    selected: Query<Entity, With<Selected>>,
+    staffs: Query<Entity, With<Staff>>,
+    bounds: Query<(&Aabb, &GlobalTransform)>,
+    children: Query<&Children>,
+) {
+    // Ctrl+V pastes parameters instead
+    if !keyboard_input.just_pressed(KeyCode::KeyV)
+        || keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+    {
+        return;
+    }
+    let Some(aabb) = selected_or_staff(&selected, &staffs)
+        .and_then(|entity| world_aabb(entity, &bounds, &children))
+    else {
+        warn!("Nothing to render a turntable of");
+        return;
+    };
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let dir = PathBuf::from(TURNTABLE_DIR).join(format!("turntable_{seconds}"));
+    if let Err(error) = fs::create_dir_all(&dir) {
+        error!("Failed to create {}: {error}", dir.display());
+        return;
+    }
+
+    let mut image = Image::new_target_texture(
+        settings.width,
+        settings.height,
+        TextureFormat::bevy_default(),
+    );
+    // Screenshots copy out of the target
+    image.texture_descriptor.usage |= TextureUsages::COPY_SRC;
+    let mut camera = commands.spawn((
+        Name::new("TurntableCamera"),
+        Camera3d::default(),
+        Camera {
+            target: RenderTarget::Image(images.add(image).into()),
+            order: -1,
+            ..default()
+        },
+    ));
+    // Match the viewport's look, but not its depth of field, which focuses for the main camera
+    apply_post_processing(&mut camera, &graphics);
+    let camera = camera.id();
+
+    info!(
+        "Rendering a {} frame turntable to {}",
+        settings.frames,
+        dir.display()
+    );
+    // Don't wait for the display between frames
+    let present_mode = window.present_mode;
+    window.present_mode = PresentMode::AutoNoVsync;
+    let frame_time = Duration::from_secs_f64(1. / f64::from(settings.framerate.max(1)));
+    let time_strategy = std::mem::replace(
+        &mut *time_strategy,
+        TimeUpdateStrategy::ManualDuration(frame_time),
+    );
+    commands.insert_resource(TurntableRecording {
+        camera,
+        dir,
+        center: aabb.center().into(),
+        radius: aabb.half_size().length(),
+        frame: 0,
+        saved: 0,
+        present_mode,
+        time_strategy,
+    });
+}
+
+fn record_turntable_frame(
+    mut commands: Commands,
+    settings: Res<TurntableSettings>,
+    mut recording: ResMut<TurntableRecording>,
+    mut time_strategy: ResMut<TimeUpdateStrategy>,
+    mut cameras: Query<(&mut Transform, &Camera)>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+) {
+    if recording.frame < settings.frames {
+        let Ok((mut transform, camera)) = cameras.get_mut(recording.camera) else {
+            return;
+        };
+        // Steps by frame rather than by time, so every frame is exactly one step around
+        let angle = TAU * recording.frame as f32 / settings.frames as f32;
+        let offset = Quat::from_rotation_y(angle)
+            * vec3(0., settings.elevation, settings.distance)
+            * recording.radius;
+        *transform = Transform::from_translation(recording.center + offset)
+            .looking_at(recording.center, Dir3::Y);
+
+        let RenderTarget::Image(target) = &camera.target else {
+            return;
+        };
+        let path = recording
+            .dir
+            .join(format!("frame_{:04}.png", recording.frame));
+        commands
+            .spawn(Screenshot::image(target.handle.clone()))
+            .observe(save_to_disk(path))
+            .observe(count_saved_frame);
+        recording.frame += 1;
+        return;
+    }
+    if recording.saved < settings.frames {
+        return;
+    }
+
+    commands.entity(recording.camera).despawn();
+    window.present_mode = recording.present_mode;
+    *time_strategy = std::mem::take(&mut recording.time_strategy);
+    info!("Turntable frames saved to {}", recording.dir.display());
+    encode_with_ffmpeg(recording.dir.clone(), settings.framerate);
+    commands.remove_resource::<TurntableRecording>();
+}
+
+fn count_saved_frame(_: On<ScreenshotCaptured>, mut recording: ResMut<TurntableRecording>) {
+    recording.saved += 1;
+}
+
+/// Encodes the frames into `turntable.mp4` on a background thread, if ffmpeg is installed.
+fn encode_with_ffmpeg(dir: PathBuf, framerate: u32) {
+    std::thread::spawn(move || {
+        let result = Command::new("ffmpeg")
+            .current_dir(&dir)
+            .args(["-y", "-loglevel", "error", "-framerate"])
+            .arg(framerate.to_string())
+            .args([
+                "-i",
+                "frame_%04d.png",
+                "-pix_fmt",
+                "yuv420p",
+                "turntable.mp4",
+            ])
+            .status();
+        match result {
+            Ok(status) if status.success() => {
+                info!("Encoded {}", dir.join("turntable.mp4").display());
+            }
+            Ok(status) => warn!("ffmpeg failed with {status}, the PNG frames are kept"),
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                info!("ffmpeg not found, the turntable is left as PNG frames");
+            }
+            Err(error) => warn!("Failed to run ffmpeg: {error}"),
+        }
+    });
+}
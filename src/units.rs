@@ -0,0 +1,30 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use staff_gen::staff::StaffConfig;
+
+/// Real-world size of one scene unit.
+/// Generator defaults are authored in meters and converted into scene units with this.
+#[derive(Resource, Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct UnitsConfig {
+    pub meters_per_unit: f32,
+}
+
+impl Default for UnitsConfig {
+    fn default() -> Self {
+        Self {
+            meters_per_unit: 1.,
+        }
+    }
+}
+
+impl UnitsConfig {
+    /// Scale from meters to scene units.
+    pub fn from_meters(&self) -> f32 {
+        1. / self.meters_per_unit
+    }
+
+    pub fn staff_defaults(&self) -> StaffConfig {
+        StaffConfig::default().scaled(self.from_meters())
+    }
+}
@@ -0,0 +1,179 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use staff_gen::assembly::{PartShape, StaffAssembly};
+use staff_gen::sockets;
+
+use crate::assembly::AssemblyPartId;
+use crate::foliage::GrassBlade;
+use crate::grass_material::GrassMaterial;
+use crate::skinning::{StaffBend, bend_staffs};
+use crate::staff_plant::Shockwave;
+use crate::state::AppState;
+
+/// Where the gizmo arrow showing the wind is drawn
+const WIND_GIZMO_ORIGIN: Vec3 = vec3(0., 3.5, 0.);
+
+/// A steady breeze with gusts that roll across the scene along its direction,
+/// so nearby staffs, banners and grass sway together rather than each on its own beat.
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct Wind {
+    /// Horizontal direction the wind blows towards, in world XZ
+    pub direction: Vec2,
+    pub strength: f32,
+    /// Extra strength at the peak of a gust
+    pub gust_strength: f32,
+    /// Gusts per second passing any one point
+    pub gust_frequency: f32,
+    /// Distance between gust fronts
+    pub gust_length: f32,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self {
+            direction: vec2(1., 0.3),
+            strength: 1.,
+            gust_strength: 1.5,
+            gust_frequency: 0.3,
+            gust_length: 4.,
+        }
+    }
+}
+
+impl Wind {
+    /// Wind velocity at `position` and `time`, always horizontal.
+    pub fn at(&self, position: Vec3, time: f32) -> Vec3 {
+        let direction = self.direction.normalize_or_zero();
+        let along = position.xz().dot(direction) / self.gust_length.max(f32::EPSILON);
+        let phase = (time * self.gust_frequency - along) * TAU;
+        // Two incommensurate waves stand in for noise, so gusts never settle into a fixed beat
+        let gust = 0.6 * (0.5 + 0.5 * phase.sin()) + 0.4 * (0.5 + 0.5 * (phase * 2.3 + 1.7).sin());
+        let speed = self.strength + self.gust_strength * gust;
+        vec3(direction.x, 0., direction.y) * speed
+    }
+}
+
+/// Lets the wind bend a skinned staff, by `response` radians per unit of wind speed.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct WindSway {
+    pub response: f32,
+}
+
+impl Default for WindSway {
+    fn default() -> Self {
+        Self { response: 0.06 }
+    }
+}
+
+pub struct WindPlugin;
+
+impl Plugin for WindPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Wind>()
+            .register_type::<WindSway>()
+            .init_resource::<Wind>()
+            .add_systems(
+                Update,
+                (
+                    sway_skinned_staffs.before(bend_staffs),
+                    sway_banners,
+                    blow_grass.run_if(resource_changed::<Wind>),
+                    push_grass,
+                    draw_wind_gizmo.run_if(in_state(AppState::Editing)),
+                ),
+            );
+    }
+}
+
+fn sway_skinned_staffs(
+    wind: Res<Wind>,
+    time: Res<Time>,
+    mut staffs: Query<(&WindSway, &mut StaffBend, &GlobalTransform)>,
+) {
+    for (sway, mut bend, transform) in &mut staffs {
+        let local =
+            transform.rotation().inverse() * wind.at(transform.translation(), time.elapsed_secs());
+        // Rotating around X tips the staff towards +Z, around Z towards -X
+        bend.sway = vec2(local.z, -local.x) * sway.response;
+    }
+}
+
+/// Lifts banners on assemblies away from the wind, pivoting around their top edge.
+fn sway_banners(
+    wind: Res<Wind>,
+    time: Res<Time>,
+    assemblies: Query<(&StaffAssembly, &GlobalTransform)>,
+    mut banners: Query<(&AssemblyPartId, &ChildOf, &mut Transform)>,
+) {
+    let time = time.elapsed_secs();
+    for (part_id, child_of, mut transform) in &mut banners {
+        let Ok((assembly, assembly_transform)) = assemblies.get(child_of.parent()) else {
+            continue;
+        };
+        let Some(part) = assembly.part(part_id.0) else {
+            continue;
+        };
+        if !matches!(part.shape, PartShape::Banner { .. }) {
+            continue;
+        }
+        let Some(rest) = assembly.part_transforms()[part_id.0] else {
+            continue;
+        };
+        let pivot = rest.transform_point(part.shape.socket(sockets::TOP).unwrap_or_default());
+        let local = assembly_transform.rotation().inverse()
+            * wind.at(assembly_transform.transform_point(pivot), time);
+        let speed = local.length();
+        // The bottom edge swings downwind, and a light flutter keeps the cloth moving
+        let flutter = 0.08 * (time * 7. + pivot.y * 3.).sin() * speed.min(1.);
+        let angle = ((speed * 0.4).atan() + flutter).clamp(0., FRAC_PI_2 - 0.1);
+        let Ok(axis) = Dir3::new(Vec3::NEG_Y.cross(local)) else {
+            *transform = rest;
+            continue;
+        };
+        let mut swayed = rest;
+        swayed.rotate_around(pivot, Quat::from_axis_angle(*axis, angle));
+        *transform = swayed;
+    }
+}
+
+/// Hands the wind to the grass shader, which sways every blade on the GPU.
+fn blow_grass(wind: Res<Wind>, mut materials: ResMut<Assets<GrassMaterial>>) {
+    for (_, material) in materials.iter_mut() {
+        material.extension.wind = (&*wind).into();
+    }
+}
+
+/// Shockwaves from planted staffs push grass outwards, on top of the shader's wind. Blades the
+/// waves don't reach are left alone, so the patch isn't touched between waves.
+fn push_grass(
+    shockwaves: Query<(&Shockwave, &GlobalTransform)>,
+    mut blades: Query<(&GrassBlade, &mut Transform, &GlobalTransform)>,
+) {
+    for (blade, mut transform, global) in &mut blades {
+        let position = global.translation();
+        let push: Vec3 = shockwaves
+            .iter()
+            .map(|(wave, origin)| wave.push(origin.translation(), position))
+            .sum();
+        let lean = Dir3::new(Vec3::Y.cross(push)).map_or(Quat::IDENTITY, |axis| {
+            Quat::from_axis_angle(*axis, (push.length() * 0.2).min(1.))
+        });
+        let rotation = lean * blade.rest;
+        if transform.rotation != rotation {
+            transform.rotation = rotation;
+        }
+    }
+}
+
+fn draw_wind_gizmo(mut gizmos: Gizmos, wind: Res<Wind>, time: Res<Time>) {
+    let wind = wind.at(WIND_GIZMO_ORIGIN, time.elapsed_secs());
+    gizmos.arrow(
+        WIND_GIZMO_ORIGIN,
+        WIND_GIZMO_ORIGIN + wind * 0.5,
+        css::LIGHT_CYAN,
+    );
+}
@@ -0,0 +1,56 @@
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use staff_gen::staff::StaffConfig;
+use staff_gen::wrapping::WrappingConfig;
+
+use crate::generation::{GeneratorKind, MeshGenMessages, empty_mesh};
+
+/// Winds a cord around `staff`, generated from `staff_config`, as a child so it moves with it.
+pub fn spawn_wrapping(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    mesh_gen: &mut MeshGenMessages,
+    staff: Entity,
+    staff_config: &StaffConfig,
+    config: WrappingConfig,
+) {
+    let entity = commands.spawn(Name::new("Wrapping")).id();
+    let mesh = mesh_gen
+        .try_generate(entity, GeneratorKind::Wrapping, || {
+            config.try_generate_mesh(staff_config)
+        })
+        .unwrap_or_else(empty_mesh);
+    commands.entity(entity).insert((
+        Mesh3d(meshes.add(mesh)),
+        MeshMaterial3d(materials.add(Color::from(css::TAN))),
+        Transform::default(),
+        ChildOf(staff),
+        config,
+    ));
+}
+
+/// Regenerates wrappings whose [`WrappingConfig`] was edited, or whose staff changed shape.
+pub fn rebuild_changed_wrappings(
+    mut meshes: ResMut<Assets<Mesh>>,
+    wrappings: Query<(Entity, Ref<WrappingConfig>, &Mesh3d, &ChildOf)>,
+    staffs: Query<Ref<StaffConfig>>,
+    mut mesh_gen: MeshGenMessages,
+) {
+    for (entity, config, mesh3d, child_of) in &wrappings {
+        let Ok(staff_config) = staffs.get(child_of.parent()) else {
+            continue;
+        };
+        if !(config.is_changed() || staff_config.is_changed()) || config.is_added() {
+            continue;
+        }
+        let Some(new_mesh) = mesh_gen.try_generate(entity, GeneratorKind::Wrapping, || {
+            config.try_generate_mesh(&staff_config)
+        }) else {
+            continue;
+        };
+        if let Some(mesh) = meshes.get_mut(&mesh3d.0) {
+            *mesh = new_mesh;
+        }
+    }
+}
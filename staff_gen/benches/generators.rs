@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+use staff_gen::crystal::generate_crystal_mesh;
+use staff_gen::cylinder::{CylinderNormals, generate_cylinder_mesh};
+use staff_gen::mesh_util::{Bvh, MeshSourceData, raycast};
+use staff_gen::staff::StaffConfig;
+
+const RESOLUTIONS: [u32; 4] = [6, 32, 128, 256];
+const SEGMENTS: [u32; 4] = [1, 4, 16, 64];
+
+fn staff(c: &mut Criterion) {
+    let mut group = c.benchmark_group("staff");
+    for resolution in RESOLUTIONS {
+        for segments in SEGMENTS {
+            let config = StaffConfig {
+                resolution,
+                segments,
+                ..StaffConfig::default()
+            };
+            group.bench_with_input(
+                BenchmarkId::new(format!("res{resolution}"), segments),
+                &config,
+                |b, config| b.iter(|| black_box(config.generate_mesh())),
+            );
+        }
+    }
+    group.finish();
+}
+
+fn crystal(c: &mut Criterion) {
+    let mut group = c.benchmark_group("crystal");
+    for resolution in RESOLUTIONS {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(resolution),
+            &resolution,
+            |b, &resolution| b.iter(|| black_box(generate_crystal_mesh(0.5, 1., resolution))),
+        );
+    }
+    group.finish();
+}
+
+fn cylinder(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cylinder");
+    for resolution in RESOLUTIONS {
+        for segments in SEGMENTS {
+            group.bench_with_input(
+                BenchmarkId::new(format!("res{resolution}"), segments),
+                &(resolution, segments),
+                |b, &(resolution, segments)| {
+                    b.iter(|| {
+                        // A fresh normals buffer each time, otherwise it grows without bound
+                        let mut normals = CylinderNormals::default();
+                        black_box(generate_cylinder_mesh(
+                            0.5,
+                            1.,
+                            resolution,
+                            segments,
+                            &mut normals,
+                        ))
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn staff_raycast(c: &mut Criterion) {
+    let mut group = c.benchmark_group("staff_raycast");
+    let ray = Ray3d::new(vec3(0., 0., 5.), Dir3::NEG_Z);
+    for resolution in RESOLUTIONS {
+        let source = MeshSourceData::from_mesh(
+            &StaffConfig {
+                resolution,
+                segments: 64,
+                ..StaffConfig::default()
+            }
+            .generate_mesh(),
+        );
+        let bvh = Bvh::new(&source);
+        group.bench_with_input(
+            BenchmarkId::new("brute", resolution),
+            &source,
+            |b, source| b.iter(|| black_box(raycast(source, ray))),
+        );
+        group.bench_with_input(BenchmarkId::new("bvh", resolution), &source, |b, source| {
+            b.iter(|| black_box(bvh.raycast(source, ray)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, staff, crystal, cylinder, staff_raycast);
+criterion_main!(benches);
@@ -0,0 +1,19 @@
+//! Prints a staff as Wavefront OBJ, without starting a Bevy app.
+//!
+//! `cargo run -p staff_gen --example staff_obj -- 1234 > staff.obj`
+
+use staff_gen::mesh_util::to_obj;
+use staff_gen::staff::StaffConfig;
+
+fn main() {
+    let seed = std::env::args()
+        .nth(1)
+        .map(|seed| seed.parse().expect("seed must be a number"))
+        .unwrap_or(StaffConfig::default().seed);
+    let mesh = StaffConfig {
+        seed,
+        ..Default::default()
+    }
+    .generate_mesh();
+    print!("{}", to_obj(&mesh));
+}
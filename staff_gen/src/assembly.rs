@@ -0,0 +1,506 @@
+//! Staffs built from several generated parts attached to each other.
+
+use bevy::prelude::*;
+
+use crate::cone::ConeConfig;
+use crate::crystal::CrystalConfig;
+use crate::cylinder::{CylinderNormals, generate_cylinder_mesh};
+use crate::error::{GenError, check_length, check_resolution};
+use crate::ferrule::FerruleConfig;
+use crate::formation::FormationConfig;
+use crate::sockets::{self, Sockets};
+use crate::staff::StaffConfig;
+
+/// How thick a bed of embers is
+const EMBERS_THICKNESS: f32 = 0.02;
+
+/// Index of a part in its [`StaffAssembly`]. Stays valid until that part is removed.
+pub type PartId = usize;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PartShape {
+    Shaft(StaffConfig),
+    Crystal(CrystalConfig),
+    /// A pointed cap or finial
+    Head(ConeConfig),
+    /// A short band wrapped around the shaft
+    Ring {
+        radius: f32,
+        thickness: f32,
+        resolution: u32,
+    },
+    /// A thin flag hanging from its top edge
+    Banner {
+        width: f32,
+        height: f32,
+    },
+    /// A metal sleeve over the base of a shaft
+    Ferrule(FerruleConfig),
+    /// A lumpy, faceted stone
+    Rock {
+        radius: f32,
+        seed: u64,
+    },
+    /// A flat bed of glowing coals, lying on the ground
+    Embers {
+        radius: f32,
+    },
+}
+
+impl PartShape {
+    pub fn height(&self) -> f32 {
+        match self {
+            Self::Shaft(config) => config.height,
+            Self::Crystal(config) => config.height,
+            Self::Head(config) => config.height,
+            Self::Ring { thickness, .. } => *thickness,
+            Self::Banner { height, .. } => *height,
+            Self::Ferrule(config) => config.length,
+            Self::Rock { radius, .. } => radius * 2. * FormationConfig::default().rock_squash,
+            Self::Embers { .. } => EMBERS_THICKNESS,
+        }
+    }
+
+    /// Named points other parts attach to, relative to the part's origin.
+    pub fn sockets(&self) -> Sockets {
+        match self {
+            Self::Shaft(config) => config.sockets(),
+            Self::Crystal(config) => config.sockets(),
+            Self::Head(config) => config.sockets(),
+            Self::Ferrule(config) => config.sockets(),
+            Self::Ring { .. } | Self::Banner { .. } | Self::Rock { .. } | Self::Embers { .. } => {
+                Sockets::upright(self.height())
+            }
+        }
+    }
+
+    /// Position of the socket called `name`, or `None` if the part has no such socket.
+    pub fn socket(&self, name: &str) -> Option<Vec3> {
+        self.sockets().get(name).map(|socket| socket.translation)
+    }
+
+    pub fn generate_mesh(&self) -> Mesh {
+        match self {
+            Self::Shaft(config) => config.generate_mesh(),
+            Self::Crystal(config) => config.generate_mesh(),
+            Self::Head(config) => config.generate_mesh(),
+            Self::Ring {
+                radius,
+                thickness,
+                resolution,
+            } => generate_cylinder_mesh(
+                *radius,
+                *thickness,
+                *resolution,
+                1,
+                &mut CylinderNormals::default(),
+            ),
+            Self::Banner { width, height } => Cuboid::new(*width, *height, 0.01).mesh().build(),
+            Self::Ferrule(config) => config.generate_mesh(),
+            Self::Rock { radius, seed } => FormationConfig {
+                seed: *seed,
+                rock_radius: *radius,
+                ..default()
+            }
+            .generate_rock_mesh(),
+            Self::Embers { radius } => generate_cylinder_mesh(
+                *radius,
+                EMBERS_THICKNESS,
+                16,
+                1,
+                &mut CylinderNormals::default(),
+            ),
+        }
+    }
+
+    /// [`Self::generate_mesh`], unless [`Self::validate`] finds a parameter it can't build.
+    pub fn try_generate_mesh(&self) -> Result<Mesh, GenError> {
+        self.validate()?;
+        Ok(self.generate_mesh())
+    }
+
+    pub fn validate(&self) -> Result<(), GenError> {
+        match self {
+            Self::Shaft(config) => config.validate(),
+            Self::Crystal(config) => config.validate(),
+            Self::Head(config) => config.validate(),
+            Self::Ring {
+                radius,
+                thickness,
+                resolution,
+            } => {
+                check_length("ring radius", *radius)?;
+                check_length("ring thickness", *thickness)?;
+                check_resolution(*resolution)
+            }
+            Self::Banner { width, height } => {
+                check_length("banner width", *width)?;
+                check_length("banner height", *height)
+            }
+            Self::Ferrule(config) => config.validate(),
+            Self::Rock { radius, .. } => check_length("rock radius", *radius),
+            Self::Embers { radius } => check_length("embers radius", *radius),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssemblyPart {
+    pub name: String,
+    pub shape: PartShape,
+    /// The part and socket this part hangs from, `None` for the root
+    pub parent: Option<(PartId, String)>,
+    /// Name of this part's own socket that sits on the parent socket
+    pub anchor: String,
+    /// Offset and rotation around the anchor, relative to the parent socket. Ignored for the root.
+    pub transform: Transform,
+}
+
+impl AssemblyPart {
+    pub fn new(name: impl Into<String>, shape: PartShape) -> Self {
+        Self {
+            name: name.into(),
+            shape,
+            parent: None,
+            anchor: sockets::CENTER.into(),
+            transform: Transform::IDENTITY,
+        }
+    }
+
+    /// Puts this part's `anchor` socket on the `socket` of `parent`.
+    pub fn attached_to(
+        mut self,
+        parent: PartId,
+        socket: impl Into<String>,
+        anchor: impl Into<String>,
+    ) -> Self {
+        self.parent = Some((parent, socket.into()));
+        self.anchor = anchor.into();
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+}
+
+/// An ordered graph of parts, each attached to a socket of an earlier part.
+/// The first part is the root and stays at the assembly's origin.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct StaffAssembly {
+    parts: Vec<Option<AssemblyPart>>,
+}
+
+impl StaffAssembly {
+    pub fn new(root: AssemblyPart) -> Self {
+        Self {
+            parts: vec![Some(AssemblyPart {
+                parent: None,
+                ..root
+            })],
+        }
+    }
+
+    /// A shaft with a crystal held on top, two bands below it, a banner and a spiked ferrule.
+    pub fn wizard_staff(shaft: StaffConfig) -> Self {
+        let radius = shaft.radius;
+        let ferrule = FerruleConfig {
+            spike_length: radius * 2.,
+            ..FerruleConfig::fitted(&shaft, shaft.height * 0.06)
+        };
+        let mut assembly = Self::new(AssemblyPart::new("shaft", PartShape::Shaft(shaft)));
+        assembly.add_part(
+            AssemblyPart::new(
+                "crystal",
+                PartShape::Crystal(CrystalConfig {
+                    radius: radius * 2.,
+                    height: radius * 4.,
+                    resolution: 6,
+                }),
+            )
+            .attached_to(0, sockets::TOP, sockets::BOTTOM),
+        );
+        for (i, drop) in [0.15, 0.25].into_iter().enumerate() {
+            assembly.add_part(
+                AssemblyPart::new(
+                    format!("ring {i}"),
+                    PartShape::Ring {
+                        radius: radius * 1.3,
+                        thickness: radius * 0.6,
+                        resolution: 12,
+                    },
+                )
+                .attached_to(0, sockets::TOP, sockets::CENTER)
+                .with_transform(Transform::from_xyz(0., -drop, 0.)),
+            );
+        }
+        assembly.add_part(
+            AssemblyPart::new(
+                "banner",
+                PartShape::Banner {
+                    width: radius * 5.,
+                    height: radius * 8.,
+                },
+            )
+            .attached_to(0, sockets::TOP, sockets::TOP)
+            .with_transform(Transform::from_xyz(radius * 3.5, -0.3, 0.)),
+        );
+        assembly.add_part(
+            AssemblyPart::new("ferrule", PartShape::Ferrule(ferrule)).attached_to(
+                0,
+                sockets::BOTTOM,
+                sockets::BOTTOM,
+            ),
+        );
+        assembly
+    }
+
+    /// [`Self::wizard_staff`], unless the shaft's [`StaffConfig::validate`] finds a parameter
+    /// it can't build. Fitting the other parts already generates the shaft's rings.
+    pub fn try_wizard_staff(shaft: StaffConfig) -> Result<Self, GenError> {
+        shaft.validate()?;
+        Ok(Self::wizard_staff(shaft))
+    }
+
+    /// Adds `part`, returning its id, or `None` when its parent or either socket doesn't exist.
+    pub fn add_part(&mut self, part: AssemblyPart) -> Option<PartId> {
+        let (parent, socket) = part.parent.as_ref()?;
+        self.part(*parent)?.shape.socket(socket)?;
+        part.shape.socket(&part.anchor)?;
+        self.parts.push(Some(part));
+        Some(self.parts.len() - 1)
+    }
+
+    /// Removes a part and everything attached to it, returning how many parts were removed.
+    /// The root can't be removed.
+    pub fn remove_part(&mut self, id: PartId) -> usize {
+        if id == 0 || self.part(id).is_none() {
+            return 0;
+        }
+        self.parts[id] = None;
+        let mut removed = 1;
+        // Children always come after their parents, so one pass finds every descendant
+        for child in id + 1..self.parts.len() {
+            let orphaned = self.parts[child]
+                .as_ref()
+                .and_then(|part| part.parent.as_ref())
+                .is_some_and(|(parent, _)| self.parts[*parent].is_none());
+            if orphaned {
+                self.parts[child] = None;
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    pub fn part(&self, id: PartId) -> Option<&AssemblyPart> {
+        self.parts.get(id)?.as_ref()
+    }
+
+    /// A part's `parent` must stay pointed at an earlier part.
+    pub fn part_mut(&mut self, id: PartId) -> Option<&mut AssemblyPart> {
+        self.parts.get_mut(id)?.as_mut()
+    }
+
+    pub fn parts(&self) -> impl Iterator<Item = (PartId, &AssemblyPart)> {
+        self.parts
+            .iter()
+            .enumerate()
+            .filter_map(|(id, part)| Some((id, part.as_ref()?)))
+    }
+
+    /// Transform of every part relative to the assembly, indexed by [`PartId`].
+    pub fn part_transforms(&self) -> Vec<Option<Transform>> {
+        let mut transforms: Vec<Option<Transform>> = Vec::with_capacity(self.parts.len());
+        for part in &self.parts {
+            let transform = part.as_ref().map(|part| {
+                let Some((parent, socket)) = &part.parent else {
+                    return Transform::IDENTITY;
+                };
+                let parent_transform = transforms[*parent].unwrap_or_default();
+                let socket = self.parts[*parent]
+                    .as_ref()
+                    .and_then(|parent| parent.shape.socket(socket))
+                    .unwrap_or_default();
+                let anchor = part.shape.socket(&part.anchor).unwrap_or_default();
+                parent_transform
+                    * Transform::from_translation(socket)
+                    * part.transform
+                    * Transform::from_translation(-anchor)
+            });
+            transforms.push(transform);
+        }
+        transforms
+    }
+
+    /// One mesh per part with its transform relative to the assembly.
+    pub fn generate_meshes(&self) -> Vec<(PartId, Mesh, Transform)> {
+        let transforms = self.part_transforms();
+        self.parts()
+            .map(|(id, part)| {
+                (
+                    id,
+                    part.shape.generate_mesh(),
+                    transforms[id].unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+
+    /// Every part merged into a single mesh, for export or a single draw call.
+    pub fn generate_combined_mesh(&self) -> Mesh {
+        let mut parts = self.generate_meshes().into_iter();
+        let (_, root, transform) = parts.next().expect("the root part can't be removed");
+        let mut combined = root.transformed_by(transform);
+        for (id, mesh, transform) in parts {
+            if let Err(error) = combined.merge(&mesh.transformed_by(transform)) {
+                warn!("Skipping assembly part {id}: {error}");
+            }
+        }
+        combined
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_util::positions;
+
+    #[test]
+    fn crystal_sits_on_top_of_shaft() {
+        let assembly = StaffAssembly::wizard_staff(StaffConfig::default());
+        let transforms = assembly.part_transforms();
+        let crystal = transforms[1].unwrap();
+        let crystal_bottom = crystal.transform_point(
+            assembly
+                .part(1)
+                .unwrap()
+                .shape
+                .socket(sockets::BOTTOM)
+                .unwrap(),
+        );
+        let shaft_top = StaffConfig::default()
+            .sockets()
+            .get(sockets::TOP)
+            .unwrap()
+            .translation;
+        assert!(
+            crystal_bottom.abs_diff_eq(shaft_top, 1e-6),
+            "{crystal_bottom} != {shaft_top}"
+        );
+    }
+
+    #[test]
+    fn root_stays_at_the_origin() {
+        let root = AssemblyPart::new("shaft", PartShape::Shaft(StaffConfig::default()))
+            .with_transform(Transform::from_xyz(1., 2., 3.).with_scale(Vec3::splat(2.)));
+        let mut assembly = StaffAssembly::new(root);
+        assembly.add_part(
+            AssemblyPart::new("crystal", PartShape::Crystal(CrystalConfig::default())).attached_to(
+                0,
+                sockets::TOP,
+                sockets::BOTTOM,
+            ),
+        );
+        let transforms = assembly.part_transforms();
+        assert_eq!(transforms[0], Some(Transform::IDENTITY));
+        let shaft_top = StaffConfig::default()
+            .sockets()
+            .get(sockets::TOP)
+            .unwrap()
+            .translation;
+        let crystal_bottom = transforms[1].unwrap().transform_point(
+            CrystalConfig::default()
+                .sockets()
+                .get(sockets::BOTTOM)
+                .unwrap()
+                .translation,
+        );
+        assert!(
+            crystal_bottom.abs_diff_eq(shaft_top, 1e-6),
+            "{crystal_bottom} != {shaft_top}"
+        );
+    }
+
+    #[test]
+    fn oversized_shafts_are_reported() {
+        let shaft = StaffConfig {
+            segments: 3_000_000_000,
+            ..default()
+        };
+        assert!(matches!(
+            StaffAssembly::try_wizard_staff(shaft),
+            Err(GenError::TooMany {
+                parameter: "segments",
+                ..
+            })
+        ));
+        let ring = PartShape::Ring {
+            radius: 0.1,
+            thickness: 0.05,
+            resolution: u32::MAX,
+        };
+        assert!(ring.try_generate_mesh().is_err());
+    }
+
+    #[test]
+    fn removing_a_part_removes_its_children() {
+        let mut assembly = StaffAssembly::wizard_staff(StaffConfig::default());
+        let count = assembly.parts().count();
+        let holder =
+            assembly
+                .add_part(
+                    AssemblyPart::new("holder", PartShape::Head(ConeConfig::default()))
+                        .attached_to(1, sockets::TOP, sockets::BOTTOM),
+                )
+                .unwrap();
+        assembly
+            .add_part(
+                AssemblyPart::new("tip", PartShape::Head(ConeConfig::default())).attached_to(
+                    holder,
+                    sockets::TOP,
+                    sockets::BOTTOM,
+                ),
+            )
+            .unwrap();
+        assert!(
+            assembly
+                .add_part(
+                    AssemblyPart::new("orphan", PartShape::Head(ConeConfig::default()))
+                        .attached_to(99, sockets::TOP, sockets::BOTTOM)
+                )
+                .is_none()
+        );
+        assert!(
+            assembly
+                .add_part(
+                    AssemblyPart::new("misplaced", PartShape::Head(ConeConfig::default()))
+                        .attached_to(0, "no such socket", sockets::BOTTOM)
+                )
+                .is_none()
+        );
+
+        assert_eq!(assembly.remove_part(1), 3);
+        assert_eq!(assembly.parts().count(), count - 1);
+        assert_eq!(assembly.remove_part(0), 0);
+    }
+
+    #[test]
+    fn combined_mesh_includes_every_part() {
+        let assembly = StaffAssembly::wizard_staff(StaffConfig::default());
+        let vertices: usize = assembly
+            .generate_meshes()
+            .iter()
+            .map(|(_, mesh, _)| mesh.count_vertices())
+            .sum();
+        let combined = assembly.generate_combined_mesh();
+        assert_eq!(combined.count_vertices(), vertices);
+        // The crystal reaches above the shaft
+        let top = positions(&combined)
+            .iter()
+            .map(|p| p[1])
+            .fold(f32::MIN, f32::max);
+        assert!(top > StaffConfig::default().height / 2.);
+    }
+}
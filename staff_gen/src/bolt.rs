@@ -0,0 +1,284 @@
+//! Jagged lightning and magic bolts: a line split again and again at its midpoints, each pushed
+//! aside at random, then swept into a thin tube or a flat ribbon.
+
+use std::f32::consts::TAU;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{
+    GenError, MAX_COUNT, check_at_most, check_finite, check_length, check_resolution,
+};
+use crate::mesh_util::unit_circle;
+
+/// More would split a bolt into millions of points
+const MAX_SUBDIVISIONS: u32 = 12;
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BoltStyle {
+    /// Round, so it looks the same from every side
+    #[default]
+    Tube,
+    /// A flat strip turned towards the viewer, with fewer triangles and a crisper outline
+    Ribbon,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct BoltConfig {
+    pub seed: u64,
+    /// Straight-line distance from one end to the other
+    pub length: f32,
+    /// Times every segment is split at its midpoint, doubling the segments each time
+    pub subdivisions: u32,
+    /// How far each midpoint is pushed aside, relative to the length of the segment it splits
+    pub jaggedness: f32,
+    /// Thickness at the start
+    pub width: f32,
+    /// Thickness at the end, relative to the start
+    pub taper: f32,
+    pub style: BoltStyle,
+    /// Sides of a tube
+    pub resolution: u32,
+}
+
+impl Default for BoltConfig {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            length: 1.5,
+            subdivisions: 5,
+            jaggedness: 0.35,
+            width: 0.02,
+            taper: 0.3,
+            style: BoltStyle::default(),
+            resolution: 5,
+        }
+    }
+}
+
+impl BoltConfig {
+    /// A bolt from the origin to `length` along +Y. Ribbons face `view`, the direction the
+    /// viewer looks at it from, while tubes ignore it.
+    pub fn generate_mesh(&self, view: Vec3) -> Mesh {
+        let path = self.path();
+        match self.style {
+            BoltStyle::Tube => generate_bolt_tube(&path, self.width, self.taper, self.resolution),
+            BoltStyle::Ribbon => generate_bolt_ribbon(&path, self.width, self.taper, view),
+        }
+    }
+
+    /// [`Self::generate_mesh`], unless [`Self::validate`] finds a parameter it can't build.
+    pub fn try_generate_mesh(&self, view: Vec3) -> Result<Mesh, GenError> {
+        self.validate()?;
+        Ok(self.generate_mesh(view))
+    }
+
+    pub fn validate(&self) -> Result<(), GenError> {
+        check_length("length", self.length)?;
+        check_finite("jaggedness", self.jaggedness)?;
+        check_length("width", self.width)?;
+        check_finite("taper", self.taper)?;
+        // Every subdivision doubles the segments
+        check_at_most("subdivisions", self.subdivisions, MAX_COUNT.ilog2())?;
+        if self.style == BoltStyle::Tube {
+            check_resolution(self.resolution)?;
+        }
+        Ok(())
+    }
+
+    pub fn path(&self) -> Vec<Vec3> {
+        bolt_path(self.seed, self.length, self.subdivisions, self.jaggedness)
+    }
+}
+
+/// Points along a bolt from the origin to `length` along +Y, `2^subdivisions + 1` of them.
+/// Midpoints are pushed aside across the segment they split, so the ends never move.
+pub fn bolt_path(seed: u64, length: f32, subdivisions: u32, jaggedness: f32) -> Vec<Vec3> {
+    let mut rand = ChaCha8Rng::seed_from_u64(seed);
+    let jaggedness = jaggedness.abs();
+    let mut points = vec![Vec3::ZERO, Vec3::Y * length];
+    for _ in 0..subdivisions.min(MAX_SUBDIVISIONS) {
+        let mut split = Vec::with_capacity(points.len() * 2 - 1);
+        for pair in points.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let along = to - from;
+            let (side, up) = along.normalize_or(Vec3::Y).any_orthonormal_pair();
+            let (sin, cos) = rand.random_range(0. ..TAU).sin_cos();
+            let push = rand.random_range(0. ..=jaggedness) * along.length();
+            split.push(from);
+            split.push(from.midpoint(to) + (side * cos + up * sin) * push);
+        }
+        split.push(points[points.len() - 1]);
+        points = split;
+    }
+    points
+}
+
+/// Direction along `path` at each point, halfway between the segments meeting there.
+fn tangents(path: &[Vec3]) -> Vec<Vec3> {
+    (0..path.len())
+        .map(|i| {
+            let before = path[i.saturating_sub(1)];
+            let after = path[(i + 1).min(path.len() - 1)];
+            (after - before).normalize_or(Vec3::Y)
+        })
+        .collect()
+}
+
+/// Sweeps a ring along `path`, thinning from `width` to `width * taper`. Its rings are carried
+/// from one point to the next by the smallest turn, so the tube doesn't twist. The ends are
+/// left open, being too thin to see into.
+pub fn generate_bolt_tube(path: &[Vec3], width: f32, taper: f32, resolution: u32) -> Mesh {
+    let circle = unit_circle(resolution);
+    let ring_size = resolution + 1;
+    let num_vertices = path.len() * ring_size as usize;
+    let mut positions = Vec::with_capacity(num_vertices);
+    let mut normals = Vec::with_capacity(num_vertices);
+    let mut uvs = Vec::with_capacity(num_vertices);
+    let mut indices = Vec::new();
+
+    let last = path.len().saturating_sub(1).max(1) as f32;
+    let mut rotation = Quat::IDENTITY;
+    let mut previous = Vec3::Y;
+    for (i, (&center, tangent)) in path.iter().zip(tangents(path)).enumerate() {
+        rotation = Quat::from_rotation_arc(previous, tangent) * rotation;
+        previous = tangent;
+        let t = i as f32 / last;
+        let radius = width / 2. * 1f32.lerp(taper, t);
+        for (segment, &(sin, cos)) in circle.iter().enumerate() {
+            let normal = rotation * vec3(cos, 0., sin);
+            positions.push((center + normal * radius).to_array());
+            normals.push(normal.to_array());
+            uvs.push([segment as f32 / resolution as f32, t]);
+        }
+    }
+    for ring in 0..path.len().saturating_sub(1) as u32 {
+        let (lower, upper) = (ring * ring_size, (ring + 1) * ring_size);
+        for j in 0..resolution {
+            indices.extend_from_slice(&[
+                lower + j,
+                upper + j,
+                lower + j + 1,
+                upper + j,
+                upper + j + 1,
+                lower + j + 1,
+            ]);
+        }
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_indices(Indices::U32(indices))
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+}
+
+/// A strip along `path` as wide as `width`, thinning to `width * taper`, turned to face back
+/// along `view` as far as it can while following the path.
+pub fn generate_bolt_ribbon(path: &[Vec3], width: f32, taper: f32, view: Vec3) -> Mesh {
+    let facing = -view.normalize_or(Vec3::NEG_Z);
+    let mut positions = Vec::with_capacity(path.len() * 2);
+    let mut uvs = Vec::with_capacity(path.len() * 2);
+    let mut indices = Vec::new();
+
+    let last = path.len().saturating_sub(1).max(1) as f32;
+    for (i, (&center, tangent)) in path.iter().zip(tangents(path)).enumerate() {
+        let t = i as f32 / last;
+        let side = tangent
+            .cross(facing)
+            .try_normalize()
+            .unwrap_or_else(|| tangent.any_orthonormal_vector());
+        let half_width = width / 2. * 1f32.lerp(taper, t);
+        positions.push((center - side * half_width).to_array());
+        positions.push((center + side * half_width).to_array());
+        uvs.extend_from_slice(&[[0., t], [1., t]]);
+    }
+    for segment in 0..path.len().saturating_sub(1) as u32 {
+        let (left, right) = (segment * 2, segment * 2 + 1);
+        let (next_left, next_right) = (left + 2, right + 2);
+        indices.extend_from_slice(&[left, right, next_left, right, next_right, next_left]);
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_indices(Indices::U32(indices))
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        vec![facing.to_array(); positions.len()],
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_util::{compare, positions, triangles};
+
+    #[test]
+    fn same_seed_strikes_the_same_way() {
+        let config = BoltConfig::default();
+        let path = config.path();
+        assert_eq!(path, config.path());
+        assert_eq!(path.len(), 2usize.pow(config.subdivisions) + 1);
+        assert_eq!(path[0], Vec3::ZERO);
+        assert_eq!(path[path.len() - 1], Vec3::Y * config.length);
+        assert!(path.windows(2).all(|pair| pair[0] != pair[1]));
+        let reseeded = BoltConfig {
+            seed: 2,
+            ..config.clone()
+        };
+        assert_ne!(path, reseeded.path());
+        assert!(
+            compare(
+                &config.generate_mesh(Vec3::NEG_Z),
+                &config.generate_mesh(Vec3::NEG_Z)
+            )
+            .is_identical()
+        );
+    }
+
+    #[test]
+    fn ribbon_faces_the_viewer() {
+        let view = vec3(0.3, -0.2, -1.).normalize();
+        let config = BoltConfig {
+            style: BoltStyle::Ribbon,
+            ..default()
+        };
+        let mesh = config.generate_mesh(view);
+        assert_eq!(positions(&mesh).len(), config.path().len() * 2);
+        for [a, b, c] in triangles(&mesh) {
+            assert!((b - a).cross(c - a).dot(-view) >= 0.);
+        }
+    }
+
+    #[test]
+    fn tube_thins_towards_the_end() {
+        let config = BoltConfig {
+            jaggedness: 0.,
+            ..default()
+        };
+        let mesh = config.generate_mesh(Vec3::NEG_Z);
+        let positions = positions(&mesh);
+        let ring = config.resolution as usize + 1;
+        assert_eq!(positions.len(), config.path().len() * ring);
+        // A straight bolt is a cone along Y
+        for &position in &positions[..ring] {
+            assert!((Vec3::from(position).xz().length() - config.width / 2.).abs() < 1e-5);
+        }
+        for &position in &positions[positions.len() - ring..] {
+            let radius = Vec3::from(position).xz().length();
+            assert!((radius - config.width / 2. * config.taper).abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! A campfire built as a [`StaffAssembly`]: a bed of embers ringed by stones, with logs leaning
+//! in over it. The logs are staffs with their variance turned off.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::assembly::{AssemblyPart, PartShape, StaffAssembly};
+use crate::sockets;
+use crate::staff::StaffConfig;
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct CampfireConfig {
+    pub seed: u64,
+    /// Radius of the ring of stones
+    pub radius: f32,
+    pub rocks: u32,
+    pub rock_radius: f32,
+    pub logs: u32,
+    pub log_length: f32,
+    pub log_radius: f32,
+    /// How far the logs lean in from upright, in radians
+    pub log_lean: f32,
+}
+
+impl Default for CampfireConfig {
+    fn default() -> Self {
+        Self {
+            seed: 3,
+            radius: 0.35,
+            rocks: 9,
+            rock_radius: 0.09,
+            logs: 4,
+            log_length: 0.45,
+            log_radius: 0.03,
+            log_lean: 0.9,
+        }
+    }
+}
+
+impl CampfireConfig {
+    /// The embers are the root, so the fire stands on the assembly's origin. Stones are placed
+    /// around them with a little jitter and the logs stand on the embers, crossing above the
+    /// middle.
+    pub fn assembly(&self) -> StaffAssembly {
+        let mut rand = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut assembly = StaffAssembly::new(AssemblyPart::new(
+            "embers",
+            PartShape::Embers {
+                radius: self.radius - self.rock_radius,
+            },
+        ));
+
+        for i in 0..self.rocks {
+            let angle = (i as f32 + rand.random_range(-0.2..0.2)) * TAU / self.rocks as f32;
+            let distance = self.radius * rand.random_range(0.95..1.05);
+            assembly.add_part(
+                AssemblyPart::new(
+                    format!("rock {i}"),
+                    PartShape::Rock {
+                        radius: self.rock_radius * rand.random_range(0.75..1.1),
+                        seed: rand.random(),
+                    },
+                )
+                .attached_to(0, sockets::BOTTOM, sockets::CENTER)
+                .with_transform(
+                    Transform::from_xyz(angle.cos() * distance, 0., angle.sin() * distance)
+                        .with_rotation(Quat::from_rotation_y(rand.random_range(0. ..TAU))),
+                ),
+            );
+        }
+
+        let log = StaffConfig {
+            radius: self.log_radius,
+            radial_variance: 0.,
+            height: self.log_length,
+            segments: 1,
+            horizontal_variance: 0.,
+            ..default()
+        };
+        // Logs stand this far out, so they lean across the middle
+        let footing = self.log_length * self.log_lean.sin() * 0.35;
+        for i in 0..self.logs {
+            let angle = (i as f32 + 0.5) * TAU / self.logs as f32;
+            let outward = vec3(angle.cos(), 0., angle.sin());
+            // Tilting about the horizontal axis across `outward` leans the top inwards
+            let lean = Quat::from_axis_angle(outward.cross(Vec3::Y), self.log_lean);
+            assembly.add_part(
+                AssemblyPart::new(
+                    format!("log {i}"),
+                    PartShape::Shaft(StaffConfig {
+                        seed: rand.random(),
+                        ..log.clone()
+                    }),
+                )
+                .attached_to(0, sockets::TOP, sockets::BOTTOM)
+                .with_transform(Transform::from_translation(outward * footing).with_rotation(lean)),
+            );
+        }
+        assembly
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stones_ring_the_fire_and_logs_lean_across_it() {
+        let config = CampfireConfig::default();
+        let assembly = config.assembly();
+        assert_eq!(
+            assembly.parts().count(),
+            1 + (config.rocks + config.logs) as usize
+        );
+        assert_eq!(assembly, config.assembly());
+
+        let transforms = assembly.part_transforms();
+        for (id, part) in assembly.parts() {
+            let transform = transforms[id].unwrap();
+            match &part.shape {
+                PartShape::Rock { .. } => {
+                    let distance = transform.translation.xz().length();
+                    assert!((distance - config.radius).abs() <= config.radius * 0.05 + 1e-5);
+                }
+                PartShape::Shaft(log) => {
+                    let socket = |name| {
+                        transform.transform_point(log.sockets().get(name).unwrap().translation)
+                    };
+                    let (bottom, top) = (socket(sockets::BOTTOM), socket(sockets::TOP));
+                    assert!(top.y > bottom.y);
+                    // The top has crossed over to the far side of the middle
+                    assert!(
+                        top.xz().dot(bottom.xz()) < 0.,
+                        "{} {top} {bottom}",
+                        part.name
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+}
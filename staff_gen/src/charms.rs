@@ -0,0 +1,198 @@
+//! Feathers, beads and bones hung from a staff on short cords.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::sockets;
+use crate::staff::StaffConfig;
+
+const CORD_RADIUS: f32 = 0.002;
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CharmKind {
+    Feather,
+    Bead,
+    Bone,
+}
+
+/// How many charms hang from a staff and how likely each kind is. Weights don't need to add up
+/// to anything, a kind with weight 0 never appears.
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Component, Default, Serialize, Deserialize)]
+pub struct CharmConfig {
+    pub count: u32,
+    pub feather_weight: f32,
+    pub bead_weight: f32,
+    pub bone_weight: f32,
+    pub cord_length: f32,
+    pub seed: u64,
+}
+
+impl Default for CharmConfig {
+    fn default() -> Self {
+        Self {
+            count: 3,
+            feather_weight: 2.,
+            bead_weight: 1.,
+            bone_weight: 1.,
+            cord_length: 0.12,
+            seed: 7,
+        }
+    }
+}
+
+/// One charm, hanging from `anchor` on the staff's surface next to the ring socket `socket`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Charm {
+    pub kind: CharmKind,
+    pub socket: String,
+    pub anchor: Vec3,
+    /// Horizontal direction from the ring's center out through `anchor`
+    pub outward: Vec3,
+}
+
+impl CharmConfig {
+    /// Picks a kind and a ring for every charm. Charms hang from the rings in the upper half of
+    /// the staff, below its tip, on the side of the ring given by the seed.
+    pub fn charms(&self, staff: &StaffConfig) -> Vec<Charm> {
+        let mut rand = ChaCha8Rng::seed_from_u64(self.seed);
+        let rings = staff.generate_rings();
+        let upper = (rings.len() / 2).min(rings.len() - 2)..rings.len() - 1;
+        let weights = [
+            (CharmKind::Feather, self.feather_weight.max(0.)),
+            (CharmKind::Bead, self.bead_weight.max(0.)),
+            (CharmKind::Bone, self.bone_weight.max(0.)),
+        ];
+        let total: f32 = weights.iter().map(|(_, weight)| weight).sum();
+        if total <= 0. || upper.is_empty() {
+            return Vec::new();
+        }
+
+        (0..self.count)
+            .map(|_| {
+                let mut pick = rand.random_range(0. ..total);
+                let kind = weights
+                    .iter()
+                    .find(|(_, weight)| {
+                        pick -= weight;
+                        pick < 0.
+                    })
+                    .map_or(CharmKind::Bone, |(kind, _)| *kind);
+                let ring_index = rand.random_range(upper.clone());
+                let ring = rings[ring_index];
+                let (sin, cos) = (rand.random::<f32>() * TAU).sin_cos();
+                let outward = vec3(cos, 0., sin);
+                Charm {
+                    kind,
+                    socket: sockets::ring_socket(ring_index),
+                    anchor: vec3(ring.offset.x, ring.y, ring.offset.y) + outward * ring.radius,
+                    outward,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Charm {
+    /// The cord and charm hanging below the mesh origin, which is the point they swing around.
+    pub fn generate_mesh(&self, cord_length: f32) -> Mesh {
+        let _span = info_span!("decorations").entered();
+        generate_charm_mesh(self.kind, cord_length)
+    }
+}
+
+pub fn generate_charm_mesh(kind: CharmKind, cord_length: f32) -> Mesh {
+    let mut mesh = Cylinder::new(CORD_RADIUS, cord_length)
+        .mesh()
+        .resolution(4)
+        .build()
+        .translated_by(Vec3::NEG_Y * cord_length / 2.);
+    let bottom = Vec3::NEG_Y * cord_length;
+
+    let parts = match kind {
+        CharmKind::Feather => vec![
+            Sphere::new(1.)
+                .mesh()
+                .uv(8, 6)
+                .transformed_by(Transform::from_scale(vec3(0.012, 0.05, 0.003)))
+                .translated_by(bottom + Vec3::NEG_Y * 0.045),
+        ],
+        CharmKind::Bead => vec![
+            Sphere::new(0.016)
+                .mesh()
+                .uv(10, 6)
+                .translated_by(bottom + Vec3::NEG_Y * 0.014),
+        ],
+        CharmKind::Bone => {
+            let center = bottom + Vec3::NEG_Y * 0.035;
+            let mut parts = vec![
+                Capsule3d::new(0.005, 0.04)
+                    .mesh()
+                    .build()
+                    .translated_by(center),
+            ];
+            // Two knuckles at each end
+            for end in [-1., 1.] {
+                for side in [-1., 1.] {
+                    parts.push(
+                        Sphere::new(0.007)
+                            .mesh()
+                            .uv(6, 4)
+                            .translated_by(center + vec3(side * 0.005, end * 0.022, 0.)),
+                    );
+                }
+            }
+            parts
+        }
+    };
+    for part in parts {
+        mesh.merge(&part)
+            .expect("primitive meshes share the same attributes");
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn charms_are_seeded_and_hang_from_the_upper_rings() {
+        let staff = StaffConfig::default();
+        let config = CharmConfig {
+            count: 8,
+            ..default()
+        };
+        let charms = config.charms(&staff);
+        assert_eq!(charms, config.charms(&staff));
+        assert_eq!(charms.len(), 8);
+        let rings = staff.generate_rings();
+        for charm in &charms {
+            assert!(charm.anchor.y >= 0. && charm.anchor.y < rings[rings.len() - 1].y);
+            let ring = rings.iter().find(|ring| ring.y == charm.anchor.y).unwrap();
+            let from_axis = (charm.anchor.xz() - ring.offset).length();
+            assert!((from_axis - ring.radius).abs() < 1e-5, "{charm:?}");
+        }
+    }
+
+    #[test]
+    fn zero_weight_kinds_never_appear() {
+        let config = CharmConfig {
+            count: 32,
+            feather_weight: 0.,
+            bone_weight: 0.,
+            ..default()
+        };
+        let charms = config.charms(&StaffConfig::default());
+        assert!(charms.iter().all(|charm| charm.kind == CharmKind::Bead));
+        let none = CharmConfig {
+            bead_weight: 0.,
+            ..config
+        };
+        assert!(none.charms(&StaffConfig::default()).is_empty());
+    }
+}
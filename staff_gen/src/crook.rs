@@ -0,0 +1,227 @@
+//! A shepherd's crook or spiral curl carved from the top of a staff.
+
+use std::f32::consts::TAU;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::head::{Head, HeadFrame, HeadGenerator};
+use crate::mesh_util::unit_circle;
+use crate::sockets::{self, Sockets};
+use crate::staff::StaffRing;
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct CrookConfig {
+    /// Radius of the bend where the curl leaves the shaft
+    pub curl_radius: f32,
+    /// Half a turn is a hook, more rolls it into a spiral
+    pub turns: f32,
+    /// Thickness and curl radius at the tip, relative to where the curl starts
+    pub taper: f32,
+    /// Direction the curl bends towards, in radians around Y from +X
+    pub heading: f32,
+    pub steps_per_turn: u32,
+}
+
+impl Default for CrookConfig {
+    fn default() -> Self {
+        Self {
+            curl_radius: 0.12,
+            turns: 0.8,
+            taper: 0.5,
+            heading: 0.,
+            steps_per_turn: 24,
+        }
+    }
+}
+
+/// The curl starts on the staff's top ring, so the shaft continues into it without a seam, and
+/// [`sockets::TOP`] moves to its highest point.
+impl HeadGenerator for CrookConfig {
+    fn generate(&self, frame: &HeadFrame) -> Head {
+        let top = crook_path(
+            frame.ring,
+            self.curl_radius,
+            self.turns,
+            self.taper,
+            self.heading,
+            self.steps_per_turn,
+        )
+        .into_iter()
+        .map(|(center, _, _)| center)
+        .max_by(|a, b| a.y.total_cmp(&b.y))
+        .unwrap_or_default();
+        let mut sockets = Sockets::default();
+        sockets.insert(sockets::TOP, Transform::from_translation(top));
+        Head {
+            mesh: Some(generate_crook_mesh(
+                frame.ring,
+                frame.resolution,
+                self.curl_radius,
+                self.turns,
+                self.taper,
+                self.heading,
+                self.steps_per_turn,
+            )),
+            sockets,
+            open: true,
+            ..default()
+        }
+    }
+}
+
+/// Center, rotation and progress from 0 to 1 of every ring along the curl, starting at `top`.
+/// The rotation turns the staff's flat rings to face along the curl.
+fn crook_path(
+    top: StaffRing,
+    curl_radius: f32,
+    turns: f32,
+    taper: f32,
+    heading: f32,
+    steps_per_turn: u32,
+) -> Vec<(Vec3, Quat, f32)> {
+    let steps = ((turns.abs() * steps_per_turn as f32).ceil() as u32).max(1);
+    let angle = TAU * turns;
+    let (sin, cos) = heading.sin_cos();
+    // Rotating Y around this axis swings it towards the heading
+    let axis = Vec3::Y.cross(vec3(cos, 0., sin));
+    let step_angle = angle / steps as f32;
+
+    let mut center = vec3(top.offset.x, top.y, top.offset.y);
+    let mut path = Vec::with_capacity(steps as usize + 1);
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32;
+        path.push((center, Quat::from_axis_angle(axis, angle * t), t));
+        // Midpoint rule, so the curl closes up the way a spiral should
+        let mid = (step as f32 + 0.5) / steps as f32;
+        let radius = curl_radius * 1f32.lerp(taper, mid);
+        center += Quat::from_axis_angle(axis, angle * mid) * Vec3::Y * radius * step_angle.abs();
+    }
+    path
+}
+
+/// Sweeps the staff's `top` ring along a curl that bends towards `heading` and tightens and thins
+/// by `taper` towards its capped tip.
+pub fn generate_crook_mesh(
+    top: StaffRing,
+    resolution: u32,
+    curl_radius: f32,
+    turns: f32,
+    taper: f32,
+    heading: f32,
+    steps_per_turn: u32,
+) -> Mesh {
+    let path = crook_path(top, curl_radius, turns, taper, heading, steps_per_turn);
+    let num_vertices = path.len() * (resolution as usize + 1) + resolution as usize;
+    let mut positions = Vec::with_capacity(num_vertices);
+    let mut normals = Vec::with_capacity(num_vertices);
+    let mut uvs = Vec::with_capacity(num_vertices);
+    let mut indices = Vec::new();
+
+    let circle = unit_circle(resolution);
+
+    // curl
+
+    for &(center, rotation, t) in &path {
+        let radius = top.radius * 1f32.lerp(taper, t);
+        for (segment, &(sin, cos)) in circle.iter().enumerate() {
+            let normal = rotation * vec3(cos, 0., sin);
+            positions.push((center + normal * radius).to_array());
+            normals.push(normal.to_array());
+            uvs.push([segment as f32 / resolution as f32, t]);
+        }
+    }
+    for i in 0..path.len() as u32 - 1 {
+        let ring = i * (resolution + 1);
+        let next_ring = (i + 1) * (resolution + 1);
+        for j in 0..resolution {
+            indices.extend_from_slice(&[
+                ring + j,
+                next_ring + j,
+                ring + j + 1,
+                next_ring + j,
+                next_ring + j + 1,
+                ring + j + 1,
+            ]);
+        }
+    }
+
+    // tip
+
+    let (center, rotation, _) = path[path.len() - 1];
+    let radius = top.radius * taper;
+    let offset = positions.len() as u32;
+    for i in 0..resolution {
+        let (sin, cos) = circle[i as usize];
+        positions.push((center + rotation * vec3(cos, 0., sin) * radius).to_array());
+        normals.push((rotation * Vec3::Y).to_array());
+        uvs.push([0.5 * (cos + 1.), 1. - 0.5 * (sin + 1.)]);
+    }
+    for i in 1..(resolution - 1) {
+        indices.extend_from_slice(&[offset, offset + i + 1, offset + i]);
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_indices(Indices::U32(indices))
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::head::HeadStyle;
+    use crate::mesh_util::{assert_closed_and_outward, positions};
+    use crate::staff::{StaffConfig, staff_ring_vertices};
+
+    #[test]
+    fn curl_starts_on_the_staff_top_ring() {
+        let staff = StaffConfig::default();
+        let config = CrookConfig::default();
+        let rings = staff.generate_rings();
+        let curl = generate_crook_mesh(
+            rings[rings.len() - 1],
+            staff.resolution,
+            config.curl_radius,
+            config.turns,
+            config.taper,
+            config.heading,
+            config.steps_per_turn,
+        );
+        let (staff_positions, _) = staff_ring_vertices(&rings, staff.resolution);
+        let ring_len = staff.resolution as usize + 1;
+        let top_ring = &staff_positions[staff_positions.len() - ring_len..];
+        for (curl, staff) in positions(&curl)[..ring_len].iter().zip(top_ring) {
+            assert!(
+                Vec3::from(*curl).abs_diff_eq(Vec3::from(*staff), 1e-6),
+                "{curl:?} != {staff:?}"
+            );
+        }
+        // A hook reaches above the shaft
+        let crook = StaffConfig {
+            head: HeadStyle::Crook(config),
+            ..staff
+        };
+        let top = crook.sockets().get(sockets::TOP).unwrap().translation;
+        assert!(top.y > rings[rings.len() - 1].y, "{top}");
+    }
+
+    #[test]
+    fn crook_staff_is_closed_and_faces_outwards() {
+        for turns in [0.5, 1.5] {
+            let mesh = StaffConfig {
+                head: HeadStyle::Crook(CrookConfig { turns, ..default() }),
+                ..default()
+            }
+            .generate_mesh();
+            assert_closed_and_outward(&mesh);
+        }
+    }
+}
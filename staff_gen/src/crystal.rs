@@ -0,0 +1,139 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{GenError, check_length, check_resolution};
+use crate::mesh_util::unit_circle;
+use crate::sockets::Sockets;
+
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Component, Default, Serialize, Deserialize)]
+pub struct CrystalConfig {
+    pub radius: f32,
+    pub height: f32,
+    pub resolution: u32,
+}
+
+impl Default for CrystalConfig {
+    fn default() -> Self {
+        let radius = 0.5;
+        Self {
+            radius,
+            // The crystal has always been generated with its radial variance as its height
+            height: radius * 0.5,
+            resolution: 6,
+        }
+    }
+}
+
+impl CrystalConfig {
+    pub fn generate_mesh(&self) -> Mesh {
+        generate_crystal_mesh(self.radius, self.height, self.resolution)
+    }
+
+    /// [`Self::generate_mesh`], unless [`Self::validate`] finds a parameter it can't build.
+    pub fn try_generate_mesh(&self) -> Result<Mesh, GenError> {
+        self.validate()?;
+        Ok(self.generate_mesh())
+    }
+
+    pub fn validate(&self) -> Result<(), GenError> {
+        check_length("radius", self.radius)?;
+        check_length("height", self.height)?;
+        check_resolution(self.resolution)
+    }
+
+    pub fn sockets(&self) -> Sockets {
+        Sockets::upright(self.height)
+    }
+}
+
+pub fn generate_crystal_mesh(radius: f32, height: f32, resolution: u32) -> Mesh {
+    let segments = 1;
+    let half_height = height / 2.;
+
+    let num_rings = segments + 1;
+    let num_vertices = resolution * 2 + num_rings * (resolution + 1);
+    let num_faces = resolution * (num_rings - 2);
+    let num_indices = (2 * num_faces + 2 * (resolution - 1) * 2) * 3;
+
+    let mut positions = Vec::with_capacity(num_vertices as usize);
+    let mut normals = Vec::with_capacity(num_vertices as usize);
+    let mut uvs = Vec::with_capacity(num_vertices as usize);
+    let mut indices = Vec::with_capacity(num_indices as usize);
+
+    let circle = unit_circle(resolution);
+    let step_y = 2.0 * half_height / segments as f32;
+
+    // rings
+
+    for ring in 0..num_rings {
+        let y = -half_height + ring as f32 * step_y;
+
+        for segment in 0..=resolution {
+            let (sin, cos) = circle[segment as usize];
+
+            positions.push([radius * cos, y, radius * sin]);
+            normals.push([cos, 0., sin]);
+            uvs.push([
+                segment as f32 / resolution as f32,
+                ring as f32 / segments as f32,
+            ]);
+        }
+    }
+
+    // barrel skin
+
+    for i in 0..segments {
+        let ring = i * (resolution + 1);
+        let next_ring = (i + 1) * (resolution + 1);
+
+        for j in 0..resolution {
+            indices.extend_from_slice(&[
+                ring + j,
+                next_ring + j,
+                ring + j + 1,
+                next_ring + j,
+                next_ring + j + 1,
+                ring + j + 1,
+            ]);
+        }
+    }
+
+    // caps
+    let mut build_cap = |top: bool| {
+        let offset = positions.len() as u32;
+        let (y, normal_y, winding) = if top {
+            (half_height, 1., (1, 0))
+        } else {
+            (-half_height, -1., (0, 1))
+        };
+
+        for i in 0..resolution {
+            let (sin, cos) = circle[i as usize];
+
+            positions.push([cos * radius, y, sin * radius]);
+            normals.push([0.0, normal_y, 0.0]);
+            uvs.push([0.5 * (cos + 1.0), 1.0 - 0.5 * (sin + 1.0)]);
+        }
+
+        for i in 1..(resolution - 1) {
+            indices.extend_from_slice(&[offset, offset + i + winding.0, offset + i + winding.1]);
+        }
+    };
+
+    build_cap(true);
+    build_cap(false);
+
+    // Assume anchor is at midpoint. No need for vertex position offsets
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_indices(Indices::U32(indices))
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+}
@@ -0,0 +1,204 @@
+//! Stalagmites and stalactites: stacked cones that narrow like melting wax towards a tip.
+
+use std::f32::consts::TAU;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{GenError, check_count, check_finite, check_length, check_resolution};
+use crate::mesh_util::unit_circle;
+
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Component, Default, Serialize, Deserialize)]
+pub struct DripstoneConfig {
+    pub seed: u64,
+    pub length: f32,
+    /// Radius at the base
+    pub radius: f32,
+    /// Number of cones stacked along the length, each a little narrower than the one below
+    pub segments: u32,
+    /// How quickly it narrows towards the tip. 1 is a straight cone, below 1 stays thick and
+    /// drips to a sudden point, above 1 thins out early.
+    pub taper: f32,
+    /// How much each cone's width and position stray, relative to its radius
+    pub noise: f32,
+    pub resolution: u32,
+}
+
+impl Default for DripstoneConfig {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            length: 0.8,
+            radius: 0.12,
+            segments: 5,
+            taper: 0.6,
+            noise: 0.15,
+            resolution: 8,
+        }
+    }
+}
+
+impl DripstoneConfig {
+    /// A stalagmite rising from the origin to its tip at `length`. Turned upside down it's a
+    /// stalactite. The base is open, since it's meant to be sunk into the floor or ceiling.
+    pub fn generate_mesh(&self) -> Mesh {
+        generate_dripstone_mesh(
+            self.seed,
+            self.length,
+            self.radius,
+            self.segments,
+            self.taper,
+            self.noise,
+            self.resolution,
+        )
+    }
+
+    /// [`Self::generate_mesh`], unless [`Self::validate`] finds a parameter it can't build.
+    pub fn try_generate_mesh(&self) -> Result<Mesh, GenError> {
+        self.validate()?;
+        Ok(self.generate_mesh())
+    }
+
+    pub fn validate(&self) -> Result<(), GenError> {
+        check_length("length", self.length)?;
+        check_length("radius", self.radius)?;
+        check_count("segments", self.segments, 1)?;
+        check_finite("taper", self.taper)?;
+        check_finite("noise", self.noise)?;
+        check_resolution(self.resolution)
+    }
+}
+
+pub fn generate_dripstone_mesh(
+    seed: u64,
+    length: f32,
+    radius: f32,
+    segments: u32,
+    taper: f32,
+    noise: f32,
+    resolution: u32,
+) -> Mesh {
+    let segments = segments.max(1);
+    let noise = noise.abs();
+    let mut rand = ChaCha8Rng::seed_from_u64(seed);
+    let envelope = |t: f32| radius * (1. - t).max(0.).powf(taper);
+
+    // Each cone's base ring sits on the narrower top ring of the cone below, leaving a ledge
+    let mut rings: Vec<(Vec3, f32)> = Vec::with_capacity(segments as usize * 2);
+    let mut center = Vec3::ZERO;
+    for segment in 0..segments {
+        let (t0, t1) = (
+            segment as f32 / segments as f32,
+            (segment + 1) as f32 / segments as f32,
+        );
+        center.y = t0 * length;
+        let base_radius = envelope(t0) * (1. + rand.random_range(-noise..=noise));
+        rings.push((center, base_radius));
+        if segment + 1 == segments {
+            center.y = length;
+            break;
+        }
+        let drift = Vec2::from_angle(rand.random_range(0. ..TAU));
+        center += vec3(drift.x, 0., drift.y) * noise * envelope(t1) * 0.5;
+        center.y = t1 * length;
+        rings.push((center, envelope(t1) * (1. - noise)));
+    }
+    let tip = center;
+
+    let circle = unit_circle(resolution);
+    let ring_size = resolution + 1;
+    let num_vertices = rings.len() * ring_size as usize + 1;
+    let mut positions = Vec::with_capacity(num_vertices);
+    let mut uvs = Vec::with_capacity(num_vertices);
+    let mut indices = Vec::new();
+
+    for &(center, radius) in &rings {
+        for segment in 0..=resolution {
+            let (sin, cos) = circle[segment as usize];
+            positions.push((center + vec3(cos, 0., sin) * radius).to_array());
+            uvs.push([segment as f32 / resolution as f32, center.y / length]);
+        }
+    }
+    for ring in 0..rings.len() as u32 - 1 {
+        let (lower, upper) = (ring * ring_size, (ring + 1) * ring_size);
+        for j in 0..resolution {
+            indices.extend_from_slice(&[
+                lower + j,
+                upper + j,
+                lower + j + 1,
+                upper + j,
+                upper + j + 1,
+                lower + j + 1,
+            ]);
+        }
+    }
+    let tip_index = positions.len() as u32;
+    positions.push(tip.to_array());
+    uvs.push([0.5, 1.]);
+    let last = (rings.len() as u32 - 1) * ring_size;
+    for j in 0..resolution {
+        indices.extend_from_slice(&[last + j, tip_index, last + j + 1]);
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_indices(Indices::U32(indices))
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_computed_smooth_normals()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_util::{compare, positions};
+
+    #[test]
+    fn same_seed_drips_the_same_way() {
+        let config = DripstoneConfig::default();
+        let mesh = config.generate_mesh();
+        assert!(compare(&mesh, &config.generate_mesh()).is_identical());
+        let reseeded = DripstoneConfig {
+            seed: 2,
+            ..config.clone()
+        };
+        assert!(!compare(&mesh, &reseeded.generate_mesh()).is_identical());
+    }
+
+    #[test]
+    fn narrows_from_the_base_to_a_tip() {
+        let config = DripstoneConfig::default();
+        let mesh = config.generate_mesh();
+        let positions = positions(&mesh);
+        let ring = config.resolution as usize + 1;
+        assert_eq!(
+            positions.len(),
+            config.segments as usize * 2 * ring - ring + 1
+        );
+
+        let tip = Vec3::from(positions[positions.len() - 1]);
+        assert_eq!(tip.y, config.length);
+        let widest = config.radius * (1. + config.noise);
+        for &position in positions {
+            let position = Vec3::from(position);
+            assert!((0. ..=config.length).contains(&position.y));
+            assert!(position.xz().length() <= widest + config.radius * config.noise * 2.);
+        }
+        // Each cone's base is wider than the last cone's top
+        let ring_radius = |ring_index: usize| {
+            let first = Vec3::from(positions[ring_index * ring]);
+            let second = Vec3::from(positions[ring_index * ring + ring / 2]);
+            first.distance(second) / 2.
+        };
+        for segment in 1..config.segments as usize {
+            assert!(ring_radius(segment * 2) > ring_radius(segment * 2 - 1));
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Why a config can't be generated. Every config with a `try_generate_mesh` checks itself with
+//! `validate` first, so a bad value from a slider or a file is reported instead of building a
+//! broken mesh.
+
+use std::error::Error;
+use std::fmt;
+
+/// A config parameter no mesh can be built from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenError {
+    /// A count below the smallest that makes a closed shape, e.g. a ring with two sides
+    TooFew {
+        parameter: &'static str,
+        value: u32,
+        min: u32,
+    },
+    /// A count above the largest that can be built without running out of memory, see
+    /// [`MAX_COUNT`]
+    TooMany {
+        parameter: &'static str,
+        value: u32,
+        max: u32,
+    },
+    /// A length that has to be above zero, e.g. a zero height
+    NotPositive { parameter: &'static str, value: f32 },
+    /// A NaN or infinite parameter
+    NotFinite { parameter: &'static str, value: f32 },
+}
+
+impl fmt::Display for GenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooFew {
+                parameter,
+                value,
+                min,
+            } => write!(f, "{parameter} is {value}, needs at least {min}"),
+            Self::TooMany {
+                parameter,
+                value,
+                max,
+            } => write!(f, "{parameter} is {value}, can be at most {max}"),
+            Self::NotPositive { parameter, value } => {
+                write!(f, "{parameter} is {value}, needs to be above 0")
+            }
+            Self::NotFinite { parameter, value } => write!(f, "{parameter} is {value}"),
+        }
+    }
+}
+
+impl Error for GenError {}
+
+/// The most sides a ring, or segments or steps along a shape, can have. A staff at the limit
+/// already has a million vertices, and far larger counts abort on allocation.
+pub const MAX_COUNT: u32 = 1024;
+
+/// Rings need three sides to enclose anything.
+pub fn check_resolution(resolution: u32) -> Result<(), GenError> {
+    check_count("resolution", resolution, 3)
+}
+
+/// A count from `min` up to [`MAX_COUNT`].
+pub fn check_count(parameter: &'static str, value: u32, min: u32) -> Result<(), GenError> {
+    if value < min {
+        return Err(GenError::TooFew {
+            parameter,
+            value,
+            min,
+        });
+    }
+    check_at_most(parameter, value, MAX_COUNT)
+}
+
+/// A count no larger than `max`, for counts whose cost grows faster than linearly.
+pub fn check_at_most(parameter: &'static str, value: u32, max: u32) -> Result<(), GenError> {
+    if value > max {
+        return Err(GenError::TooMany {
+            parameter,
+            value,
+            max,
+        });
+    }
+    Ok(())
+}
+
+pub fn check_finite(parameter: &'static str, value: f32) -> Result<(), GenError> {
+    if !value.is_finite() {
+        return Err(GenError::NotFinite { parameter, value });
+    }
+    Ok(())
+}
+
+/// A finite length above zero.
+pub fn check_length(parameter: &'static str, value: f32) -> Result<(), GenError> {
+    check_finite(parameter, value)?;
+    if value <= 0. {
+        return Err(GenError::NotPositive { parameter, value });
+    }
+    Ok(())
+}
@@ -0,0 +1,248 @@
+//! A metal sleeve capping the base of a staff, optionally ending in a spike.
+
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{GenError, check_finite, check_length, check_resolution};
+use crate::mesh_util::unit_circle;
+use crate::sockets::{self, Sockets};
+use crate::staff::{StaffConfig, ring_axis, ring_radius};
+
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Component, Default, Serialize, Deserialize)]
+pub struct FerruleConfig {
+    /// Radius of the staff the sleeve fits over, at its bottom and top edges
+    pub bottom_radius: f32,
+    pub top_radius: f32,
+    pub length: f32,
+    /// Wall thickness of the sleeve
+    pub thickness: f32,
+    /// Horizontal shift of the top edge relative to the bottom, so the sleeve follows a
+    /// leaning staff
+    pub lean: Vec2,
+    /// Length of the spike below the sleeve, 0 for a flat cap
+    pub spike_length: f32,
+    pub resolution: u32,
+}
+
+impl Default for FerruleConfig {
+    fn default() -> Self {
+        Self {
+            bottom_radius: 0.03,
+            top_radius: 0.035,
+            length: 0.12,
+            thickness: 0.006,
+            lean: Vec2::ZERO,
+            spike_length: 0.,
+            resolution: 12,
+        }
+    }
+}
+
+impl FerruleConfig {
+    /// A sleeve `length` long fitted around the base of a staff generated from `staff`,
+    /// following its bottom ring's radius and the lean of its axis.
+    pub fn fitted(staff: &StaffConfig, length: f32) -> Self {
+        let rings = staff.generate_rings();
+        let bottom = rings[0];
+        let top_y = bottom.y + length;
+        let top_center = ring_axis(&rings, top_y);
+        Self {
+            bottom_radius: bottom.radius,
+            top_radius: ring_radius(&rings, top_y),
+            length,
+            thickness: bottom.radius * 0.15,
+            lean: top_center.xz() - bottom.offset,
+            ..default()
+        }
+    }
+
+    pub fn generate_mesh(&self) -> Mesh {
+        generate_ferrule_mesh(
+            self.bottom_radius + self.thickness,
+            self.top_radius + self.thickness,
+            self.length,
+            self.lean,
+            self.spike_length,
+            self.resolution,
+        )
+    }
+
+    /// [`Self::generate_mesh`], unless [`Self::validate`] finds a parameter it can't build.
+    pub fn try_generate_mesh(&self) -> Result<Mesh, GenError> {
+        self.validate()?;
+        Ok(self.generate_mesh())
+    }
+
+    /// The thickness and spike may be 0, the radii and length can't.
+    pub fn validate(&self) -> Result<(), GenError> {
+        check_length("bottom_radius", self.bottom_radius)?;
+        check_length("top_radius", self.top_radius)?;
+        check_length("length", self.length)?;
+        check_finite("thickness", self.thickness)?;
+        check_finite("lean", self.lean.x)?;
+        check_finite("lean", self.lean.y)?;
+        check_finite("spike_length", self.spike_length)?;
+        check_resolution(self.resolution)
+    }
+
+    /// [`sockets::BOTTOM`] and [`sockets::TOP`] at the center of the sleeve's edges, so
+    /// [`sockets::BOTTOM`] goes on the staff's own. The spike hangs below it.
+    pub fn sockets(&self) -> Sockets {
+        let half_length = self.length / 2.;
+        let mut sockets = Sockets::default();
+        sockets.insert(
+            sockets::TOP,
+            Transform::from_xyz(self.lean.x, half_length, self.lean.y),
+        );
+        sockets.insert(
+            sockets::CENTER,
+            Transform::from_xyz(self.lean.x / 2., 0., self.lean.y / 2.),
+        );
+        sockets.insert(sockets::BOTTOM, Transform::from_xyz(0., -half_length, 0.));
+        sockets
+    }
+}
+
+/// A closed truncated cone with its bottom centered below the origin and its top shifted by
+/// `lean`. With a `spike_length` the bottom comes to a point instead of a flat cap.
+pub fn generate_ferrule_mesh(
+    bottom_radius: f32,
+    top_radius: f32,
+    length: f32,
+    lean: Vec2,
+    spike_length: f32,
+    resolution: u32,
+) -> Mesh {
+    let half_length = length / 2.;
+    let num_vertices = 2 * (resolution + 1) + 2 * resolution + 1;
+    let mut positions = Vec::with_capacity(num_vertices as usize);
+    let mut normals = Vec::with_capacity(num_vertices as usize);
+    let mut uvs = Vec::with_capacity(num_vertices as usize);
+    let mut indices = Vec::with_capacity((resolution * 6 + resolution * 6) as usize);
+
+    let circle = unit_circle(resolution);
+    let edge = |top: bool, sin: f32, cos: f32| {
+        if top {
+            vec3(
+                top_radius * cos + lean.x,
+                half_length,
+                top_radius * sin + lean.y,
+            )
+        } else {
+            vec3(bottom_radius * cos, -half_length, bottom_radius * sin)
+        }
+    };
+
+    // sleeve
+
+    let slope = (bottom_radius - top_radius) / length;
+    for (ring, top) in [false, true].into_iter().enumerate() {
+        for segment in 0..=resolution {
+            let (sin, cos) = circle[segment as usize];
+            positions.push(edge(top, sin, cos).to_array());
+            normals.push(vec3(cos, slope, sin).normalize().to_array());
+            uvs.push([segment as f32 / resolution as f32, ring as f32]);
+        }
+    }
+    for j in 0..resolution {
+        let next_ring = resolution + 1;
+        indices.extend_from_slice(&[
+            j,
+            next_ring + j,
+            j + 1,
+            next_ring + j,
+            next_ring + j + 1,
+            j + 1,
+        ]);
+    }
+
+    // top cap
+
+    let offset = positions.len() as u32;
+    for i in 0..resolution {
+        let (sin, cos) = circle[i as usize];
+        positions.push(edge(true, sin, cos).to_array());
+        normals.push([0., 1., 0.]);
+        uvs.push([0.5 * (cos + 1.), 1. - 0.5 * (sin + 1.)]);
+    }
+    for i in 1..(resolution - 1) {
+        indices.extend_from_slice(&[offset, offset + i + 1, offset + i]);
+    }
+
+    // bottom cap or spike
+
+    let offset = positions.len() as u32;
+    let spike_slope = bottom_radius / spike_length.max(f32::EPSILON);
+    for i in 0..resolution {
+        let (sin, cos) = circle[i as usize];
+        positions.push(edge(false, sin, cos).to_array());
+        let normal = if spike_length > 0. {
+            vec3(cos * spike_slope, -1., sin * spike_slope).normalize()
+        } else {
+            Vec3::NEG_Y
+        };
+        normals.push(normal.to_array());
+        uvs.push([0.5 * (cos + 1.), 0.5 * (sin + 1.)]);
+    }
+    if spike_length > 0. {
+        let tip = positions.len() as u32;
+        positions.push([0., -half_length - spike_length, 0.]);
+        normals.push([0., -1., 0.]);
+        uvs.push([0.5, 0.5]);
+        for i in 0..resolution {
+            indices.extend_from_slice(&[tip, offset + i, offset + (i + 1) % resolution]);
+        }
+    } else {
+        for i in 1..(resolution - 1) {
+            indices.extend_from_slice(&[offset, offset + i, offset + i + 1]);
+        }
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_indices(Indices::U32(indices))
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_util::assert_closed_and_outward;
+
+    #[test]
+    fn fitted_ferrule_wraps_the_staff_base() {
+        let staff = StaffConfig::default();
+        let config = FerruleConfig::fitted(&staff, 0.1);
+        let rings = staff.generate_rings();
+        assert_eq!(config.bottom_radius, rings[0].radius);
+        let bottom = config.sockets().get(sockets::BOTTOM).unwrap().translation;
+        let top = config.sockets().get(sockets::TOP).unwrap().translation;
+        // Moved onto the staff's bottom socket, the sleeve's top follows the staff's axis
+        let staff_bottom = staff.sockets().get(sockets::BOTTOM).unwrap().translation;
+        let expected = ring_axis(&rings, rings[0].y + 0.1);
+        assert!(
+            (top - bottom + staff_bottom).abs_diff_eq(expected, 1e-5),
+            "{top} {expected}"
+        );
+    }
+
+    #[test]
+    fn ferrule_is_closed_and_faces_outwards() {
+        for spike_length in [0., 0.05] {
+            let mesh = FerruleConfig {
+                lean: vec2(0.01, -0.005),
+                spike_length,
+                ..default()
+            }
+            .generate_mesh();
+            assert_closed_and_outward(&mesh);
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! A staff head that splits into prongs curving out and back in, cradling a crystal.
+
+use std::f32::consts::PI;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::crystal::CrystalConfig;
+use crate::head::{Head, HeadFrame, HeadGenerator};
+use crate::mesh_util::unit_circle;
+use crate::sockets::{self, Sockets};
+use crate::staff::StaffRing;
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct ForkConfig {
+    /// Number of prongs the top ring splits into, at least 2
+    pub prongs: u32,
+    pub length: f32,
+    /// How far the prongs lean outwards halfway along, in radians
+    pub spread: f32,
+    /// How far the tips lean back inwards, in radians
+    pub tip_curl: f32,
+    /// Thickness of the tips, relative to where the prongs split
+    pub taper: f32,
+    pub steps: u32,
+    /// Whether a crystal sits between the tips
+    pub crystal: bool,
+}
+
+impl Default for ForkConfig {
+    fn default() -> Self {
+        Self {
+            prongs: 3,
+            length: 0.3,
+            spread: 0.6,
+            tip_curl: 0.5,
+            taper: 0.4,
+            steps: 12,
+            crystal: true,
+        }
+    }
+}
+
+impl ForkConfig {
+    /// A crystal sized to fit between the tips and its transform relative to the staff, or `None`
+    /// when [`Self::crystal`] is off or the tips close up too far to hold one.
+    pub fn crystal(&self, frame: &HeadFrame) -> Option<(CrystalConfig, Transform)> {
+        if !self.crystal {
+            return None;
+        }
+        let (cradle, gap) = self.cradle(frame);
+        let radius = (gap - frame.ring.radius * self.taper) * 0.9;
+        (radius > 0.).then(|| {
+            (
+                CrystalConfig {
+                    radius,
+                    height: radius * 2.5,
+                    resolution: 6,
+                },
+                Transform::from_translation(cradle),
+            )
+        })
+    }
+
+    /// The point between the prong tips and its horizontal distance to the nearest tip.
+    fn cradle(&self, frame: &HeadFrame) -> (Vec3, f32) {
+        let prongs = self.prongs.clamp(2, frame.resolution);
+        let tips: Vec<Vec3> = prong_sectors(frame.resolution, prongs)
+            .map(|(first, last)| {
+                let (start, outward) = prong_start(frame.ring, frame.resolution, first, last);
+                let path = prong_path(
+                    start,
+                    outward,
+                    self.length,
+                    self.spread,
+                    self.tip_curl,
+                    self.steps,
+                );
+                path[path.len() - 1].0
+            })
+            .collect();
+        let cradle = tips.iter().sum::<Vec3>() / tips.len() as f32;
+        let gap = tips
+            .iter()
+            .map(|tip| (tip - cradle).xz().length())
+            .fold(f32::MAX, f32::min);
+        (cradle, gap)
+    }
+}
+
+/// Each prong starts on its own arc of the staff's top ring, so the prongs share their inner
+/// walls where they split. [`sockets::TOP`] moves between the tips, where the crystal sits.
+impl HeadGenerator for ForkConfig {
+    fn generate(&self, frame: &HeadFrame) -> Head {
+        let mut sockets = Sockets::default();
+        sockets.insert(
+            sockets::TOP,
+            Transform::from_translation(self.cradle(frame).0),
+        );
+        Head {
+            mesh: Some(generate_fork_mesh(
+                frame.ring,
+                frame.resolution,
+                self.prongs,
+                self.length,
+                self.spread,
+                self.tip_curl,
+                self.taper,
+                self.steps,
+            )),
+            gem: self
+                .crystal(frame)
+                .map(|(crystal, transform)| crystal.generate_mesh().transformed_by(transform)),
+            sockets,
+            open: true,
+            ..default()
+        }
+    }
+}
+
+/// First and last top ring vertex of each prong's arc.
+fn prong_sectors(resolution: u32, prongs: u32) -> impl Iterator<Item = (u32, u32)> {
+    (0..prongs).map(move |prong| {
+        (
+            prong * resolution / prongs,
+            (prong + 1) * resolution / prongs,
+        )
+    })
+}
+
+/// A prong's cross-section relative to the center of the `top` ring: its arc, closed through the
+/// center.
+fn prong_outline(top: StaffRing, resolution: u32, first: u32, last: u32) -> Vec<Vec3> {
+    let circle = unit_circle(resolution);
+    (first..=last)
+        .map(|j| {
+            let (sin, cos) = circle[j as usize];
+            vec3(cos, 0., sin) * top.radius
+        })
+        .chain([Vec3::ZERO])
+        .collect()
+}
+
+/// Centroid of a prong's cross-section and the horizontal direction it leans towards.
+fn prong_start(top: StaffRing, resolution: u32, first: u32, last: u32) -> (Vec3, Vec3) {
+    let outline = prong_outline(top, resolution, first, last);
+    let centroid = outline.iter().sum::<Vec3>() / outline.len() as f32;
+    (
+        vec3(top.offset.x, top.y, top.offset.y) + centroid,
+        centroid.normalize(),
+    )
+}
+
+/// Center, rotation and progress from 0 to 1 of every ring along a prong starting at `start`.
+/// The prong leans `outward` by up to `spread` and ends leaning back in by `tip_curl`.
+fn prong_path(
+    start: Vec3,
+    outward: Vec3,
+    length: f32,
+    spread: f32,
+    tip_curl: f32,
+    steps: u32,
+) -> Vec<(Vec3, Quat, f32)> {
+    let steps = steps.max(1);
+    // Rotating Y around this axis swings it outwards
+    let axis = Vec3::Y.cross(outward);
+    let lean = |t: f32| Quat::from_axis_angle(axis, spread * (PI * t).sin() - tip_curl * t);
+    let step_length = length / steps as f32;
+
+    let mut center = start;
+    let mut path = Vec::with_capacity(steps as usize + 1);
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32;
+        path.push((center, lean(t), t));
+        let mid = (step as f32 + 0.5) / steps as f32;
+        center += lean(mid) * Vec3::Y * step_length;
+    }
+    path
+}
+
+/// Splits the staff's top ring into `prongs` arcs and sweeps each, closed through the ring's
+/// center, along a prong that leans out by `spread`, back in by `tip_curl` and thins by `taper`
+/// towards its capped tip.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_fork_mesh(
+    top: StaffRing,
+    resolution: u32,
+    prongs: u32,
+    length: f32,
+    spread: f32,
+    tip_curl: f32,
+    taper: f32,
+    steps: u32,
+) -> Mesh {
+    let prongs = prongs.clamp(2, resolution);
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut indices = Vec::new();
+
+    for (first, last) in prong_sectors(resolution, prongs) {
+        let outline = prong_outline(top, resolution, first, last);
+        let (start, outward) = prong_start(top, resolution, first, last);
+        let centroid = start - vec3(top.offset.x, top.y, top.offset.y);
+        let path = prong_path(start, outward, length, spread, tip_curl, steps);
+
+        // Sweeps an open strip of the outline, given as offset, normal and u per vertex
+        let mut sweep = |strip: &[(Vec3, Vec3, f32)]| {
+            let offset = positions.len() as u32;
+            for &(center, rotation, t) in &path {
+                let scale = 1f32.lerp(taper, t);
+                for &(point, normal, u) in strip {
+                    positions.push((center + rotation * ((point - centroid) * scale)).to_array());
+                    normals.push((rotation * normal).to_array());
+                    uvs.push([u, t]);
+                }
+            }
+            let width = strip.len() as u32;
+            for i in 0..path.len() as u32 - 1 {
+                let ring = offset + i * width;
+                let next_ring = offset + (i + 1) * width;
+                for j in 0..width - 1 {
+                    indices.extend_from_slice(&[
+                        ring + j,
+                        next_ring + j,
+                        ring + j + 1,
+                        next_ring + j,
+                        next_ring + j + 1,
+                        ring + j + 1,
+                    ]);
+                }
+            }
+        };
+
+        // outer arc
+        let arc: Vec<_> = (first..=last)
+            .zip(&outline)
+            .map(|(j, &point)| (point, point.normalize(), j as f32 / resolution as f32))
+            .collect();
+        sweep(&arc);
+
+        // inner walls, shared with the neighbouring prongs where they split
+        let center = outline[outline.len() - 1];
+        for (from, to) in [(outline[outline.len() - 2], center), (center, outline[0])] {
+            let edge = to - from;
+            let normal = vec3(edge.z, 0., -edge.x).normalize();
+            sweep(&[(from, normal, 0.), (to, normal, 1.)]);
+        }
+
+        // tip
+        let (tip, rotation, _) = path[path.len() - 1];
+        let offset = positions.len() as u32;
+        for &point in &outline {
+            positions.push((tip + rotation * ((point - centroid) * taper)).to_array());
+            normals.push((rotation * Vec3::Y).to_array());
+            uvs.push([
+                0.5 + point.x / top.radius / 2.,
+                0.5 - point.z / top.radius / 2.,
+            ]);
+        }
+        let center = offset + outline.len() as u32 - 1;
+        for i in 0..outline.len() as u32 - 2 {
+            indices.extend_from_slice(&[center, offset + i + 1, offset + i]);
+        }
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_indices(Indices::U32(indices))
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::head::HeadStyle;
+    use crate::mesh_util::assert_closed_and_outward;
+    use crate::staff::StaffConfig;
+
+    #[test]
+    fn forked_staff_is_closed_and_faces_outwards() {
+        for prongs in [2, 3] {
+            let mesh = StaffConfig {
+                head: HeadStyle::Forked(ForkConfig {
+                    prongs,
+                    ..default()
+                }),
+                ..default()
+            }
+            .generate_mesh();
+            assert_closed_and_outward(&mesh);
+        }
+    }
+
+    #[test]
+    fn crystal_sits_between_the_tips() {
+        let plain = StaffConfig::default();
+        let rings = plain.generate_rings();
+        let frame = HeadFrame {
+            ring: rings[rings.len() - 1],
+            resolution: plain.resolution,
+        };
+        let config = ForkConfig::default();
+        let (crystal, transform) = config.crystal(&frame).unwrap();
+        let staff = StaffConfig {
+            head: HeadStyle::Forked(config),
+            ..plain.clone()
+        };
+        let top = staff.sockets().get(sockets::TOP).unwrap().translation;
+        assert_eq!(transform.translation, top);
+        let staff_top = plain.sockets().get(sockets::TOP).unwrap().translation;
+        assert!(
+            top.y - crystal.height / 2. > staff_top.y,
+            "{top} {crystal:?}"
+        );
+
+        let closed = ForkConfig {
+            crystal: false,
+            ..default()
+        };
+        assert!(closed.crystal(&frame).is_none());
+    }
+}
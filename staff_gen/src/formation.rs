@@ -0,0 +1,192 @@
+//! Crystal formations: crystals of varying sizes and tilts growing out of a rock, for dressing
+//! caves and as standalone geode props.
+
+use std::f32::consts::TAU;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::crystal::CrystalConfig;
+use crate::mesh_util::triangles;
+
+/// How much of each crystal is sunk into the rock, so its base doesn't hang over the face edges
+const ROOT_DEPTH: f32 = 0.15;
+
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Component, Default, Serialize, Deserialize)]
+pub struct FormationConfig {
+    pub seed: u64,
+    pub rock_radius: f32,
+    /// Height of the rock relative to its width
+    pub rock_squash: f32,
+    /// Number of crystals, at most one per upward facing face of the rock
+    pub crystals: u32,
+    /// Length of the shortest and longest crystals, relative to the rock's radius
+    pub min_length: f32,
+    pub max_length: f32,
+    /// Crystal radius relative to its length
+    pub thickness: f32,
+    /// Most a crystal leans away from the face it grows out of, in radians
+    pub max_tilt: f32,
+    pub resolution: u32,
+}
+
+impl Default for FormationConfig {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            rock_radius: 0.3,
+            rock_squash: 0.6,
+            crystals: 9,
+            min_length: 0.5,
+            max_length: 1.4,
+            thickness: 0.15,
+            max_tilt: 0.5,
+            resolution: 6,
+        }
+    }
+}
+
+impl FormationConfig {
+    /// A lumpy, faceted rock centered on the origin.
+    pub fn generate_rock_mesh(&self) -> Mesh {
+        self.rock(&mut ChaCha8Rng::seed_from_u64(self.seed))
+    }
+
+    /// Every crystal and its transform relative to the rock. Crystals grow out of the rock's
+    /// upper faces, along the face normal give or take [`Self::max_tilt`], so the rock can rest
+    /// half sunk into the ground.
+    pub fn crystals(&self) -> Vec<(CrystalConfig, Transform)> {
+        let mut rand = ChaCha8Rng::seed_from_u64(self.seed);
+        let rock = self.rock(&mut rand);
+        let mut faces: Vec<[Vec3; 3]> = triangles(&rock)
+            .filter(|[a, b, c]| (b - a).cross(c - a).y > 0.)
+            .collect();
+        faces.shuffle(&mut rand);
+        faces.truncate(self.crystals as usize);
+
+        let max_length = self.max_length.max(self.min_length);
+        faces
+            .into_iter()
+            .map(|[a, b, c]| {
+                let normal = (b - a).cross(c - a).normalize();
+                // A random point on the face
+                let (mut u, mut v) = (rand.random::<f32>(), rand.random::<f32>());
+                if u + v > 1. {
+                    (u, v) = (1. - u, 1. - v);
+                }
+                let root = a + (b - a) * u + (c - a) * v;
+                let lean = Quat::from_axis_angle(
+                    normal.any_orthonormal_vector(),
+                    rand.random_range(0. ..=self.max_tilt.abs()),
+                );
+                let direction =
+                    Quat::from_axis_angle(normal, rand.random_range(0. ..TAU)) * lean * normal;
+
+                let length = self.rock_radius * rand.random_range(self.min_length..=max_length);
+                let crystal = CrystalConfig {
+                    radius: length * self.thickness,
+                    height: length,
+                    resolution: self.resolution,
+                };
+                let transform =
+                    Transform::from_translation(root + direction * length * (0.5 - ROOT_DEPTH))
+                        .with_rotation(
+                            Quat::from_rotation_arc(Vec3::Y, direction)
+                                * Quat::from_rotation_y(rand.random_range(0. ..TAU)),
+                        );
+                (crystal, transform)
+            })
+            .collect()
+    }
+
+    /// All the crystals in one mesh, so they can share a material apart from the rock.
+    pub fn generate_crystals_mesh(&self) -> Mesh {
+        let mut crystals = self
+            .crystals()
+            .into_iter()
+            .map(|(crystal, transform)| crystal.generate_mesh().transformed_by(transform));
+        let Some(mut mesh) = crystals.next() else {
+            return Mesh::new(
+                PrimitiveTopology::TriangleList,
+                RenderAssetUsages::default(),
+            )
+            .with_inserted_indices(Indices::U32(Vec::new()))
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, Vec::<[f32; 3]>::new())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, Vec::<[f32; 3]>::new())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, Vec::<[f32; 2]>::new());
+        };
+        for crystal in crystals {
+            mesh.merge(&crystal)
+                .expect("crystal meshes share the same attributes");
+        }
+        mesh
+    }
+
+    fn rock(&self, rand: &mut impl Rng) -> Mesh {
+        let mut rock = Sphere::new(self.rock_radius)
+            .mesh()
+            .ico(1)
+            .expect("subdivision 1 is within the icosphere limit");
+        // Pushing the corners in and out before they're split into faces keeps the rock closed
+        if let Some(VertexAttributeValues::Float32x3(positions)) =
+            rock.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+        {
+            for position in positions {
+                let bump = rand.random_range(0.85..1.15);
+                *position = (Vec3::from(*position) * bump).to_array();
+            }
+        }
+        rock.with_duplicated_vertices()
+            .transformed_by(Transform::from_scale(vec3(1., self.rock_squash, 1.)))
+            .with_computed_flat_normals()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_util::compare;
+
+    #[test]
+    fn same_seed_grows_the_same_formation() {
+        let config = FormationConfig::default();
+        let crystals = config.crystals();
+        assert_eq!(crystals.len(), config.crystals as usize);
+        assert_eq!(crystals, config.crystals());
+        assert!(compare(&config.generate_rock_mesh(), &config.generate_rock_mesh()).is_identical());
+
+        let reseeded = FormationConfig {
+            seed: 2,
+            ..config.clone()
+        };
+        assert_ne!(crystals, reseeded.crystals());
+    }
+
+    #[test]
+    fn crystals_grow_outward_from_the_upper_faces() {
+        let config = FormationConfig {
+            crystals: 1000,
+            ..default()
+        };
+        let crystals = config.crystals();
+        // An icosphere with one subdivision has 80 faces, half of them facing up
+        assert!(
+            crystals.len() < 80 && crystals.len() >= 30,
+            "{}",
+            crystals.len()
+        );
+        for (crystal, transform) in &crystals {
+            let direction = transform.rotation * Vec3::Y;
+            let root = transform.translation - direction * crystal.height * (0.5 - ROOT_DEPTH);
+            assert!(direction.dot(root) > 0., "{crystal:?} {transform:?}");
+            let length = crystal.height / config.rock_radius;
+            assert!(length > config.min_length - 1e-5 && length < config.max_length + 1e-5);
+        }
+    }
+}
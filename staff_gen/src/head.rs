@@ -0,0 +1,224 @@
+//! Interchangeable heads on top of a staff, including user-registered generators.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::crook::CrookConfig;
+use crate::crystal::CrystalConfig;
+use crate::error::{GenError, check_count, check_length, check_resolution};
+use crate::fork::ForkConfig;
+use crate::orb::OrbConfig;
+use crate::sockets::{self, Sockets};
+use crate::staff::StaffRing;
+
+/// The staff's top ring, which a head is built on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeadFrame {
+    pub ring: StaffRing,
+    /// Vertices around the ring, not counting the seam
+    pub resolution: u32,
+}
+
+impl HeadFrame {
+    /// Center of the top ring, relative to the staff's origin.
+    pub fn center(&self) -> Vec3 {
+        vec3(self.ring.offset.x, self.ring.y, self.ring.offset.y)
+    }
+}
+
+/// A generated head, relative to the staff's origin.
+#[derive(Debug, Default)]
+pub struct Head {
+    /// Merged into the staff's own mesh
+    pub mesh: Option<Mesh>,
+    /// A separate part drawn in its own material, like a crystal or an orb
+    pub gem: Option<Mesh>,
+    /// Fittings drawn in a material of their own, like the claws holding an orb
+    pub trim: Option<Mesh>,
+    /// Replace the staff's sockets of the same name, usually [`sockets::TOP`]
+    pub sockets: Sockets,
+    /// Whether `mesh` carries on from the top ring, so the staff's top cap is left out
+    pub open: bool,
+}
+
+/// Builds a head on a staff's top ring. Closures taking a [`HeadFrame`] are generators too.
+pub trait HeadGenerator: Send + Sync {
+    fn generate(&self, frame: &HeadFrame) -> Head;
+}
+
+impl<F: Fn(&HeadFrame) -> Head + Send + Sync> HeadGenerator for F {
+    fn generate(&self, frame: &HeadFrame) -> Head {
+        self(frame)
+    }
+}
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum HeadStyle {
+    /// Just the staff's flat top cap
+    #[default]
+    Plain,
+    /// A crystal standing on the top ring
+    Crystal(CrystalConfig),
+    Crook(CrookConfig),
+    Forked(ForkConfig),
+    /// An orb held up by claws
+    Orb(OrbConfig),
+    /// A generator registered under this name in a [`HeadRegistry`]
+    Custom(String),
+}
+
+impl HeadStyle {
+    /// The head built on `frame`, or `None` for [`HeadStyle::Plain`] or an unregistered
+    /// [`HeadStyle::Custom`] generator.
+    pub fn generate(&self, frame: &HeadFrame, heads: &HeadRegistry) -> Option<Head> {
+        match self {
+            Self::Plain => None,
+            Self::Crystal(config) => Some(config.generate(frame)),
+            Self::Crook(config) => Some(config.generate(frame)),
+            Self::Forked(config) => Some(config.generate(frame)),
+            Self::Orb(config) => Some(config.generate(frame)),
+            Self::Custom(name) => {
+                let generator = heads.get(name);
+                if generator.is_none() {
+                    warn!("No head generator registered as {name:?}, leaving the staff plain");
+                }
+                Some(generator?.generate(frame))
+            }
+        }
+    }
+
+    /// Parameters of the head's own that no head can be built from. Crooks and forks only
+    /// follow the staff's top ring, which the staff checks.
+    pub fn validate(&self) -> Result<(), GenError> {
+        match self {
+            Self::Crystal(config) => config.validate(),
+            Self::Orb(config) => {
+                check_length("orb radius", config.radius)?;
+                check_count("claws", config.claws, 0)?;
+                check_count("claw steps", config.steps, 1)?;
+                check_resolution(config.resolution)
+            }
+            Self::Crook(config) => check_count("crook steps_per_turn", config.steps_per_turn, 1),
+            Self::Forked(config) => {
+                check_count("prongs", config.prongs, 0)?;
+                check_count("prong steps", config.steps, 1)
+            }
+            Self::Plain | Self::Custom(_) => Ok(()),
+        }
+    }
+}
+
+/// Custom head generators by name, for [`HeadStyle::Custom`].
+#[derive(Resource, Default)]
+pub struct HeadRegistry {
+    generators: HashMap<String, Box<dyn HeadGenerator>>,
+}
+
+impl HeadRegistry {
+    /// Adds a generator, replacing any generator of the same name.
+    pub fn register(&mut self, name: impl Into<String>, generator: impl HeadGenerator + 'static) {
+        self.generators.insert(name.into(), Box::new(generator));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn HeadGenerator> {
+        self.generators.get(name).map(|generator| &**generator)
+    }
+}
+
+impl HeadGenerator for CrystalConfig {
+    fn generate(&self, frame: &HeadFrame) -> Head {
+        let bottom = frame.center();
+        let mut sockets = Sockets::default();
+        sockets.insert(
+            sockets::TOP,
+            Transform::from_translation(bottom + Vec3::Y * self.height),
+        );
+        Head {
+            gem: Some(
+                self.generate_mesh()
+                    .translated_by(bottom + Vec3::Y * self.height / 2.),
+            ),
+            sockets,
+            ..default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_util::positions;
+    use crate::staff::StaffConfig;
+
+    #[test]
+    fn custom_heads_come_from_the_registry() {
+        let staff = StaffConfig {
+            head: HeadStyle::Custom("spike".into()),
+            ..default()
+        };
+        let mut heads = HeadRegistry::default();
+        // Unregistered heads leave the staff plain
+        assert_eq!(
+            staff
+                .generate_mesh_with_head(staff.generate_head(&heads).as_ref())
+                .count_vertices(),
+            StaffConfig::default().generate_mesh().count_vertices()
+        );
+
+        heads.register("spike", |frame: &HeadFrame| {
+            let tip = frame.center() + Vec3::Y;
+            let mut sockets = Sockets::default();
+            sockets.insert(sockets::TOP, Transform::from_translation(tip));
+            Head {
+                mesh: Some(
+                    Cone::new(frame.ring.radius, 1.)
+                        .mesh()
+                        .build()
+                        .translated_by(tip - Vec3::Y / 2.),
+                ),
+                sockets,
+                ..default()
+            }
+        });
+        let head = staff.generate_head(&heads);
+        let top = staff
+            .sockets_with_head(head.as_ref())
+            .get(sockets::TOP)
+            .unwrap()
+            .translation;
+        let highest = positions(&staff.generate_mesh_with_head(head.as_ref()))
+            .iter()
+            .map(|p| p[1])
+            .fold(f32::MIN, f32::max);
+        assert!((top.y - highest).abs() < 1e-5, "{top} {highest}");
+    }
+
+    #[test]
+    fn crystal_head_sits_on_the_top_ring() {
+        let plain = StaffConfig::default();
+        let staff_top = plain.sockets().get(sockets::TOP).unwrap().translation;
+        let staff = StaffConfig {
+            head: HeadStyle::Crystal(CrystalConfig {
+                radius: 0.08,
+                height: 0.2,
+                resolution: 6,
+            }),
+            ..default()
+        };
+        let head = staff.generate_head(&HeadRegistry::default()).unwrap();
+        let lowest = positions(head.gem.as_ref().unwrap())
+            .iter()
+            .map(|p| p[1])
+            .fold(f32::MAX, f32::min);
+        assert!((lowest - staff_top.y).abs() < 1e-5, "{lowest}");
+        // The staff itself keeps its cap under a gem
+        assert_eq!(
+            staff.generate_mesh().count_vertices(),
+            plain.generate_mesh().count_vertices()
+        );
+        let top = staff.sockets().get(sockets::TOP).unwrap().translation;
+        assert!((top.y - staff_top.y - 0.2).abs() < 1e-5, "{top}");
+    }
+}
@@ -0,0 +1,56 @@
+//! Procedural mesh generators for staffs, crystals and simple primitives.
+//!
+//! Every generator returns a plain Bevy [`Mesh`](bevy::prelude::Mesh) and needs no window,
+//! renderer or running app, so the meshes can be built in tests, tools or another game.
+//! Each shape has a serializable config with a `generate_mesh` method:
+//!
+//! ```
+//! use staff_gen::staff::StaffConfig;
+//!
+//! let config = StaffConfig {
+//!     seed: 42,
+//!     ..Default::default()
+//! };
+//! let mesh = config.generate_mesh();
+//! assert_eq!(mesh.count_vertices(), 47);
+//! ```
+//!
+//! Configs from users or files can be built with `try_generate_mesh` instead, which checks
+//! them first and returns a [`GenError`](error::GenError) rather than a broken mesh.
+//!
+//! The lower level `generate_*_mesh` functions take the parameters directly and expect them to
+//! be valid.
+
+pub mod assembly;
+pub mod bolt;
+pub mod campfire;
+pub mod charms;
+pub mod cone;
+pub mod crook;
+pub mod crystal;
+pub mod cube;
+pub mod cylinder;
+pub mod dripstone;
+pub mod error;
+pub mod ferrule;
+pub mod fork;
+pub mod formation;
+pub mod head;
+pub mod mesh_util;
+pub mod morph_targets;
+pub mod naming;
+pub mod orb;
+pub mod pedestal;
+pub mod pool;
+pub mod randomize;
+pub mod repair;
+pub mod scatter;
+pub mod skinning;
+#[cfg(test)]
+mod snapshot_tests;
+pub mod sockets;
+pub mod staff;
+pub mod stats;
+pub mod style;
+pub mod topology;
+pub mod wrapping;
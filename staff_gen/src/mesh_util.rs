@@ -0,0 +1,767 @@
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use bevy::asset::RenderAssetUsages;
+use bevy::math::ops::sin_cos;
+use bevy::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
+use bevy::prelude::*;
+
+mod atlas;
+mod bake;
+mod bvh;
+mod gltf;
+mod query;
+mod vertex_cache;
+
+pub use atlas::pack_uv_atlas;
+pub use bake::{AtlasTexel, bake_atlas, wood_grain};
+pub use bvh::Bvh;
+pub use gltf::to_glb;
+pub use query::{MeshSourceData, RayHit, SurfaceHit, closest_point, raycast, raycast_mesh};
+pub use vertex_cache::{
+    ACMR_CACHE_SIZE, acmr, optimize_vertex_cache, optimize_vertex_cache_in, to_triangle_strip,
+};
+
+/// Number of vertices sampled from each mesh when estimating the distance between them.
+const DISTANCE_SAMPLES: usize = 256;
+
+type UnitCircle = Arc<[(f32, f32)]>;
+
+static UNIT_CIRCLES: LazyLock<Mutex<HashMap<u32, UnitCircle>>> = LazyLock::new(Default::default);
+
+/// `(sin, cos)` for `resolution + 1` evenly spaced angles around the unit circle.
+/// The last entry is at `TAU` so rings can duplicate their seam vertex.
+/// Tables are cached per resolution and shared by all generators.
+pub fn unit_circle(resolution: u32) -> UnitCircle {
+    let mut circles = UNIT_CIRCLES.lock().unwrap();
+    circles
+        .entry(resolution)
+        .or_insert_with(|| {
+            let step_theta = TAU / resolution as f32;
+            (0..=resolution)
+                .map(|segment| sin_cos(segment as f32 * step_theta))
+                .collect()
+        })
+        .clone()
+}
+
+/// Differences between two meshes, as reported by [`compare`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MeshDiff {
+    /// `b` vertex count minus `a` vertex count
+    pub vertex_count_delta: i64,
+    /// `b` index count minus `a` index count
+    pub index_count_delta: i64,
+    /// Hausdorff-style distance: the furthest any sampled vertex is from the other mesh's vertices
+    pub max_distance: f32,
+    /// Names of attributes missing from one mesh or holding different data
+    pub attribute_mismatches: Vec<String>,
+    pub indices_match: bool,
+}
+
+impl MeshDiff {
+    pub fn is_identical(&self) -> bool {
+        self.vertex_count_delta == 0
+            && self.index_count_delta == 0
+            && self.max_distance == 0.
+            && self.attribute_mismatches.is_empty()
+            && self.indices_match
+    }
+}
+
+pub fn compare(a: &Mesh, b: &Mesh) -> MeshDiff {
+    let index_count = |mesh: &Mesh| mesh.indices().map_or(0, |indices| indices.len()) as i64;
+
+    let mut attribute_mismatches = Vec::new();
+    for (attribute, values) in a.attributes() {
+        match b.attribute(attribute.id) {
+            None => attribute_mismatches.push(format!("{} missing from b", attribute.name)),
+            // Compare bytes so NaNs in identical meshes don't count as a mismatch
+            Some(other) if other.get_bytes() != values.get_bytes() => {
+                attribute_mismatches.push(attribute.name.to_string())
+            }
+            Some(_) => {}
+        }
+    }
+    for (attribute, _) in b.attributes() {
+        if !a.contains_attribute(attribute.id) {
+            attribute_mismatches.push(format!("{} missing from a", attribute.name));
+        }
+    }
+
+    let indices_match = match (a.indices(), b.indices()) {
+        (Some(a), Some(b)) => a.iter().eq(b.iter()),
+        (None, None) => true,
+        _ => false,
+    };
+
+    let a_positions = positions(a);
+    let b_positions = positions(b);
+    let max_distance = directed_distance(a_positions, b_positions)
+        .max(directed_distance(b_positions, a_positions));
+
+    MeshDiff {
+        vertex_count_delta: b.count_vertices() as i64 - a.count_vertices() as i64,
+        index_count_delta: index_count(b) - index_count(a),
+        max_distance,
+        attribute_mismatches,
+        indices_match,
+    }
+}
+
+/// A fingerprint of the mesh's vertex data and indices, for checking that a generator makes
+/// exactly the same mesh across runs and platforms. It's FNV-1a over the attribute names and
+/// their little-endian bytes, so it doesn't depend on the standard library's hasher. Indices are
+/// hashed as `u32`, so narrowing them to `u16` keeps the hash.
+pub fn mesh_hash(mesh: &Mesh) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    let mut hash = OFFSET_BASIS;
+    let mut write = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash = (hash ^ byte as u64).wrapping_mul(PRIME);
+        }
+    };
+    // Attributes iterate in id order, the same everywhere
+    for (attribute, values) in mesh.attributes() {
+        write(attribute.name.as_bytes());
+        match values {
+            VertexAttributeValues::Float32(values) => {
+                values.iter().for_each(|value| write(&value.to_le_bytes()));
+            }
+            VertexAttributeValues::Float32x2(values) => values
+                .iter()
+                .flatten()
+                .for_each(|value| write(&value.to_le_bytes())),
+            VertexAttributeValues::Float32x3(values) => values
+                .iter()
+                .flatten()
+                .for_each(|value| write(&value.to_le_bytes())),
+            VertexAttributeValues::Float32x4(values) => values
+                .iter()
+                .flatten()
+                .for_each(|value| write(&value.to_le_bytes())),
+            // Integer and packed attributes are byte arrays already on every target we build for
+            values => write(values.get_bytes()),
+        }
+    }
+    if let Some(indices) = mesh.indices() {
+        write(b"indices");
+        indices
+            .iter()
+            .for_each(|index| write(&(index as u32).to_le_bytes()));
+    }
+    hash
+}
+
+/// Serializes a triangle list mesh to Wavefront OBJ.
+/// Floats are written with their shortest exact representation so the output round-trips.
+pub fn to_obj(mesh: &Mesh) -> String {
+    let mut obj = String::new();
+    for [x, y, z] in positions(mesh) {
+        obj.push_str(&format!("v {x:?} {y:?} {z:?}\n"));
+    }
+    let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+        Some(VertexAttributeValues::Float32x2(uvs)) => uvs.as_slice(),
+        _ => &[],
+    };
+    for [u, v] in uvs {
+        obj.push_str(&format!("vt {u:?} {v:?}\n"));
+    }
+    let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+        Some(VertexAttributeValues::Float32x3(normals)) => normals.as_slice(),
+        _ => &[],
+    };
+    for [x, y, z] in normals {
+        obj.push_str(&format!("vn {x:?} {y:?} {z:?}\n"));
+    }
+
+    // OBJ indices are 1-based
+    let vertex = |i: usize| match (uvs.is_empty(), normals.is_empty()) {
+        (true, true) => format!("{}", i + 1),
+        (false, true) => format!("{0}/{0}", i + 1),
+        (true, false) => format!("{0}//{0}", i + 1),
+        (false, false) => format!("{0}/{0}/{0}", i + 1),
+    };
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(indices) => indices.iter().collect(),
+        None => (0..mesh.count_vertices()).collect(),
+    };
+    for triangle in indices.chunks_exact(3) {
+        obj.push_str(&format!(
+            "f {} {} {}\n",
+            vertex(triangle[0]),
+            vertex(triangle[1]),
+            vertex(triangle[2])
+        ));
+    }
+    obj
+}
+
+/// Signed volume enclosed by a closed triangle mesh, positive when triangles wind
+/// counter-clockwise seen from outside.
+pub fn volume(mesh: &Mesh) -> f32 {
+    triangles(mesh)
+        .map(|[a, b, c]| a.dot(b.cross(c)) / 6.)
+        .sum()
+}
+
+/// Total area of every triangle in the mesh.
+pub fn surface_area(mesh: &Mesh) -> f32 {
+    triangles(mesh)
+        .map(|[a, b, c]| (b - a).cross(c - a).length() / 2.)
+        .sum()
+}
+
+/// Indices of the triangles with no more area than `min_area`, in the order [`triangles`] yields
+/// them. These are collapsed corners or repeated indices, invisible but breaking normals and
+/// tangents.
+pub fn degenerate_triangles(mesh: &Mesh, min_area: f32) -> Vec<usize> {
+    triangles(mesh)
+        .enumerate()
+        .filter(|(_, [a, b, c])| (*b - *a).cross(*c - *a).length() / 2. <= min_area)
+        .map(|(triangle, _)| triangle)
+        .collect()
+}
+
+/// Vertex colors along the mesh's height, from `color_at(0.)` at its lowest vertex to
+/// `color_at(1.)` at its highest, ready for [`Mesh::ATTRIBUTE_COLOR`]. Colors are linear RGBA.
+pub fn vertical_gradient(mesh: &Mesh, color_at: impl Fn(f32) -> [f32; 4]) -> Vec<[f32; 4]> {
+    let positions = positions(mesh);
+    let (bottom, top) = positions
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(bottom, top), p| {
+            (bottom.min(p[1]), top.max(p[1]))
+        });
+    let height = (top - bottom).max(f32::EPSILON);
+    positions
+        .iter()
+        .map(|p| color_at((p[1] - bottom) / height))
+        .collect()
+}
+
+/// Center of mass of a closed triangle mesh of uniform density.
+/// Returns `None` for meshes that enclose no volume.
+pub fn center_of_mass(mesh: &Mesh) -> Option<Vec3> {
+    // Sum of the tetrahedra formed by each triangle and the origin, weighted by signed volume
+    let (weighted, volume) =
+        triangles(mesh).fold((Vec3::ZERO, 0.), |(weighted, volume), [a, b, c]| {
+            let tetrahedron = a.dot(b.cross(c)) / 6.;
+            (
+                weighted + tetrahedron * (a + b + c) / 4.,
+                volume + tetrahedron,
+            )
+        });
+    (volume.abs() > f32::EPSILON).then(|| weighted / volume)
+}
+
+/// Where the mesh's surface crosses the plane through `origin` facing `normal`, as one line
+/// segment per triangle it cuts. A closed mesh cuts into closed outlines.
+pub fn plane_section(mesh: &Mesh, origin: Vec3, normal: Vec3) -> Vec<[Vec3; 2]> {
+    triangles(mesh)
+        .filter_map(|corners| {
+            let distances = corners.map(|corner| normal.dot(corner - origin));
+            // Corners on the plane count as behind it, so each cut crosses exactly two edges
+            let mut crossings = (0..3).filter_map(|i| {
+                let j = (i + 1) % 3;
+                let (a, b) = (distances[i], distances[j]);
+                ((a > 0.) != (b > 0.)).then(|| corners[i].lerp(corners[j], a / (a - b)))
+            });
+            Some([crossings.next()?, crossings.next()?])
+        })
+        .collect()
+}
+
+/// Every triangle of a triangle list mesh, indexed or not.
+pub fn triangles(mesh: &Mesh) -> impl Iterator<Item = [Vec3; 3]> + '_ {
+    let positions = positions(mesh);
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(indices) => indices.iter().collect(),
+        None => (0..positions.len()).collect(),
+    };
+    (0..indices.len() / 3).map(move |triangle| {
+        let vertex = |corner: usize| Vec3::from(positions[indices[triangle * 3 + corner]]);
+        [vertex(0), vertex(1), vertex(2)]
+    })
+}
+
+/// Stores the indices of `mesh` as `u16` when it has few enough vertices, halving its index
+/// buffer. Index 65535 is left out, as strips read it as a restart. Merging more vertices in
+/// later widens the indices back to `u32` by itself.
+pub fn compact_indices(mesh: &mut Mesh) {
+    compact_indices_in(mesh, &mut Vec::new());
+}
+
+/// [`compact_indices`] narrowing into `compact`, which the mesh takes when it fits. Gives back
+/// the `u32` indices it had then, see [`BufferPool`](crate::pool::BufferPool).
+pub fn compact_indices_in(mesh: &mut Mesh, compact: &mut Vec<u16>) -> Option<Vec<u32>> {
+    if mesh.count_vertices() > u16::MAX as usize {
+        return None;
+    }
+    let Some(Indices::U32(indices)) = mesh.indices() else {
+        return None;
+    };
+    compact.clear();
+    compact.reserve(indices.len());
+    for &index in indices {
+        compact.push(u16::try_from(index).ok()?);
+    }
+    let wide = mesh.remove_indices();
+    mesh.insert_indices(Indices::U16(std::mem::take(compact)));
+    match wide {
+        Some(Indices::U32(indices)) => Some(indices),
+        _ => None,
+    }
+}
+
+/// Merges vertices within `position_epsilon` of each other and returns how many were removed.
+/// Positions are snapped to a grid of that size, so near neighbours straddling a grid line can
+/// survive. With `preserve_seams`, vertices are only merged when every other attribute matches
+/// too, keeping UV and hard normal seams intact. Without it, merged vertices keep the first
+/// vertex's attributes but average their normals, smoothing shading across seams.
+/// Triangles collapsed by the merge are dropped.
+pub fn weld_vertices(mesh: &mut Mesh, position_epsilon: f32, preserve_seams: bool) -> usize {
+    let vertex_count = mesh.count_vertices();
+    // Per vertex bytes of every attribute besides the position, for seam comparisons
+    let other_attributes: Vec<(Vec<u8>, usize)> = mesh
+        .attributes()
+        .filter(|(attribute, _)| preserve_seams && attribute.id != Mesh::ATTRIBUTE_POSITION.id)
+        .map(|(_, values)| {
+            let bytes = values.get_bytes().to_vec();
+            let size = bytes.len() / vertex_count.max(1);
+            (bytes, size)
+        })
+        .collect();
+
+    let mut welded: HashMap<(IVec3, Vec<u8>), u32> = HashMap::new();
+    let mut kept = Vec::new();
+    let remap: Vec<u32> = positions(mesh)
+        .iter()
+        .enumerate()
+        .map(|(i, &position)| {
+            let cell = (Vec3::from(position) / position_epsilon).round().as_ivec3();
+            let seam = other_attributes
+                .iter()
+                .flat_map(|(bytes, size)| &bytes[i * size..(i + 1) * size])
+                .copied()
+                .collect();
+            *welded.entry((cell, seam)).or_insert_with(|| {
+                kept.push(i as u32);
+                kept.len() as u32 - 1
+            })
+        })
+        .collect();
+    if kept.len() == vertex_count {
+        return 0;
+    }
+
+    let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+        Some(VertexAttributeValues::Float32x3(normals)) if !preserve_seams => {
+            let mut summed = vec![Vec3::ZERO; kept.len()];
+            for (i, normal) in normals.iter().enumerate() {
+                summed[remap[i] as usize] += Vec3::from(*normal);
+            }
+            let normals: Vec<[f32; 3]> = summed
+                .into_iter()
+                .map(|normal| normal.normalize_or_zero().to_array())
+                .collect();
+            Some(normals)
+        }
+        _ => None,
+    };
+    let indices: Vec<u32> = match mesh.indices() {
+        Some(indices) => indices.iter().map(|i| remap[i]).collect(),
+        None => remap.clone(),
+    };
+    let indices = indices
+        .chunks_exact(3)
+        .filter(|t| t[0] != t[1] && t[1] != t[2] && t[2] != t[0])
+        .flatten()
+        .copied()
+        .collect();
+
+    // Indexing by the kept vertices and duplicating them selects those from every attribute
+    mesh.insert_indices(Indices::U32(kept));
+    mesh.duplicate_vertices();
+    if let Some(normals) = normals {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    }
+    mesh.insert_indices(Indices::U32(indices));
+    vertex_count - mesh.count_vertices()
+}
+
+/// Convex hull of every vertex of `mesh`, see [`convex_hull`].
+pub fn convex_hull_mesh(mesh: &Mesh) -> Option<Mesh> {
+    let points: Vec<Vec3> = positions(mesh).iter().copied().map(Vec3::from).collect();
+    convex_hull(&points)
+}
+
+/// Flat shaded convex hull of `points`, with outward facing triangles.
+/// Returns `None` when the points are all on one plane and enclose no volume.
+pub fn convex_hull(points: &[Vec3]) -> Option<Mesh> {
+    let faces = hull_faces(points)?;
+
+    let mut positions = Vec::with_capacity(faces.len() * 3);
+    let mut normals = Vec::with_capacity(faces.len() * 3);
+    for face in &faces {
+        let [a, b, c] = face.map(|i| points[i]);
+        let normal = (b - a).cross(c - a).normalize_or_zero();
+        positions.extend([a, b, c].map(|p| p.to_array()));
+        normals.extend([normal.to_array(); 3]);
+    }
+    let indices = (0..positions.len() as u32).collect();
+
+    Some(
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_indices(Indices::U32(indices))
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals),
+    )
+}
+
+/// Incremental hull: start from a tetrahedron of extreme points, then for each point outside
+/// the hull replace the faces it can see with a fan from their horizon to the point.
+fn hull_faces(points: &[Vec3]) -> Option<Vec<[usize; 3]>> {
+    let extent = points
+        .iter()
+        .fold(0_f32, |extent, p| extent.max(p.abs().max_element()));
+    let epsilon = extent.max(f32::MIN_POSITIVE) * 1e-5;
+
+    let farthest = |score: &dyn Fn(Vec3) -> f32| {
+        (0..points.len()).max_by(|&a, &b| score(points[a]).total_cmp(&score(points[b])))
+    };
+    let a = farthest(&|p| -p.x)?;
+    let b = farthest(&|p| p.distance(points[a]))?;
+    let line = (points[b] - points[a]).normalize_or_zero();
+    let c = farthest(&|p| (p - points[a]).reject_from_normalized(line).length())?;
+    let plane = (points[b] - points[a])
+        .cross(points[c] - points[a])
+        .normalize_or_zero();
+    let d = farthest(&|p| (p - points[a]).dot(plane).abs())?;
+    if (points[d] - points[a]).dot(plane).abs() <= epsilon {
+        return None;
+    }
+
+    let distance = |[a, b, c]: [usize; 3], p: Vec3| {
+        let normal = (points[b] - points[a])
+            .cross(points[c] - points[a])
+            .normalize_or_zero();
+        normal.dot(p - points[a])
+    };
+    let mut faces = vec![[a, b, c], [a, c, d], [a, d, b], [b, d, c]];
+    // Flip all faces if the first tetrahedron came out inside out
+    if distance(faces[0], points[d]) > 0. {
+        for face in &mut faces {
+            face.swap(1, 2);
+        }
+    }
+
+    for (i, &point) in points.iter().enumerate() {
+        let (visible, hidden): (Vec<[usize; 3]>, Vec<[usize; 3]>) = faces
+            .iter()
+            .partition(|&&face| distance(face, point) > epsilon);
+        if visible.is_empty() {
+            continue;
+        }
+        // Edges of the visible region that no other visible face shares
+        let edges: Vec<(usize, usize)> = visible
+            .iter()
+            .flat_map(|&[a, b, c]| [(a, b), (b, c), (c, a)])
+            .collect();
+        let horizon = edges
+            .iter()
+            .filter(|&&(a, b)| !edges.contains(&(b, a)))
+            .map(|&(a, b)| [a, b, i]);
+        faces = hidden.into_iter().chain(horizon).collect();
+    }
+    Some(faces)
+}
+
+pub fn positions(mesh: &Mesh) -> &[[f32; 3]] {
+    match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(positions)) => positions,
+        _ => &[],
+    }
+}
+
+/// Largest distance from a sample of `from` to its nearest point in `to`.
+fn directed_distance(from: &[[f32; 3]], to: &[[f32; 3]]) -> f32 {
+    if from.is_empty() || to.is_empty() {
+        return if from.len() == to.len() {
+            0.
+        } else {
+            f32::INFINITY
+        };
+    }
+    let step = from.len().div_ceil(DISTANCE_SAMPLES);
+    from.iter()
+        .step_by(step)
+        .map(|&p| {
+            let p = Vec3::from(p);
+            to.iter()
+                .map(|&q| p.distance(Vec3::from(q)))
+                .fold(f32::INFINITY, f32::min)
+        })
+        .fold(0., f32::max)
+}
+
+/// Asserts `mesh` has no open edges and that every triangle winds towards its vertex normals,
+/// for generators whose closed shapes mustn't be drawn inside out.
+#[cfg(test)]
+#[track_caller]
+pub(crate) fn assert_closed_and_outward(mesh: &Mesh) {
+    let report = crate::repair::check_watertight(mesh);
+    assert!(report.is_watertight(), "{report:?}");
+
+    let Some(Indices::U32(indices)) = mesh.indices() else {
+        panic!("missing indices");
+    };
+    let Some(normals) = mesh
+        .attribute(Mesh::ATTRIBUTE_NORMAL)
+        .and_then(|normals| normals.as_float3())
+    else {
+        panic!("missing normals");
+    };
+    for (triangle, corners) in triangles(mesh).zip(indices.chunks_exact(3)) {
+        let [a, b, c] = triangle;
+        let vertex_normals: Vec3 = corners
+            .iter()
+            .map(|&i| Vec3::from(normals[i as usize]))
+            .sum();
+        assert!(
+            (b - a).cross(c - a).dot(vertex_normals) > 0.,
+            "{corners:?} is inside out"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cone::generate_cone_mesh;
+    use crate::cube::{CubeNormals, generate_cube_mesh};
+    use crate::cylinder::{CylinderNormals, generate_cylinder_mesh};
+    use crate::staff::StaffConfig;
+
+    const EPSILON: f32 = 1e-5;
+
+    /// Area of a regular polygon with `sides` corners on a circle of `radius`.
+    fn polygon_area(radius: f32, sides: u32) -> f32 {
+        let sides = sides as f32;
+        sides * radius * radius * (TAU / sides).sin() / 2.
+    }
+
+    /// Side length of a regular polygon with `sides` corners on a circle of `radius`.
+    fn polygon_side(radius: f32, sides: u32) -> f32 {
+        2. * radius * (TAU / sides as f32 / 2.).sin()
+    }
+
+    #[test]
+    fn hash_follows_the_mesh_data() {
+        let config = StaffConfig::default();
+        let mesh = config.generate_mesh();
+        assert_eq!(mesh_hash(&mesh), mesh_hash(&config.generate_mesh()));
+        let reseeded = StaffConfig { seed: 1, ..config };
+        assert_ne!(mesh_hash(&mesh), mesh_hash(&reseeded.generate_mesh()));
+
+        // One ulp anywhere changes the hash, the index width doesn't
+        let mut nudged = mesh.clone();
+        if let Some(VertexAttributeValues::Float32x3(positions)) =
+            nudged.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+        {
+            positions[3][1] = f32::from_bits(positions[3][1].to_bits() + 1);
+        }
+        assert_ne!(mesh_hash(&mesh), mesh_hash(&nudged));
+        let narrowed = mesh.clone().with_inserted_indices(Indices::U16(
+            mesh.indices().unwrap().iter().map(|i| i as u16).collect(),
+        ));
+        assert_eq!(mesh_hash(&mesh), mesh_hash(&narrowed));
+    }
+
+    #[test]
+    fn small_meshes_get_u16_indices() {
+        let mut staff = StaffConfig::default().generate_mesh();
+        let hash = mesh_hash(&staff);
+        compact_indices(&mut staff);
+        assert!(matches!(staff.indices(), Some(Indices::U16(_))));
+        assert_eq!(mesh_hash(&staff), hash);
+
+        // One vertex too many for a u16 that isn't the strip restart index
+        let positions = vec![[0.; 3]; u16::MAX as usize + 1];
+        let mut large = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_indices(Indices::U32(vec![0, 1, 2]));
+        compact_indices(&mut large);
+        assert!(matches!(large.indices(), Some(Indices::U32(_))));
+    }
+
+    #[test]
+    fn degenerate_triangles_are_found() {
+        let staff = StaffConfig::default().generate_mesh();
+        assert!(degenerate_triangles(&staff, 1e-9).is_empty());
+
+        // Point the first corner of the second triangle at its second corner
+        let mut indices: Vec<u32> = staff.indices().unwrap().iter().map(|i| i as u32).collect();
+        indices[3] = indices[4];
+        let collapsed = staff.with_inserted_indices(Indices::U32(indices));
+        assert_eq!(degenerate_triangles(&collapsed, 1e-9), [1]);
+    }
+
+    #[test]
+    fn gradient_runs_from_base_to_tip() {
+        let cone = generate_cone_mesh(2., 1., 8);
+        let colors = vertical_gradient(&cone, |t| [t, 0., 0., 1.]);
+        assert_eq!(colors.len(), cone.count_vertices());
+        for (color, p) in colors.iter().zip(positions(&cone)) {
+            assert!((color[0] - (p[1] + 1.) / 2.).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn weld_merges_duplicates() {
+        let mut cube = generate_cube_mesh(&mut CubeNormals::default());
+        assert_eq!(weld_vertices(&mut cube, 1e-5, true), 0);
+        assert_eq!(weld_vertices(&mut cube, 1e-5, false), 16);
+        assert_eq!(cube.count_vertices(), 8);
+        assert!((volume(&cube) - 1.).abs() < EPSILON);
+
+        let config = StaffConfig::default();
+        let mut staff = config.generate_mesh();
+        let before = staff.count_vertices();
+        // Seam and cap vertices carry their own UVs and normals
+        assert_eq!(weld_vertices(&mut staff, 1e-5, true), 0);
+        weld_vertices(&mut staff, 1e-5, false);
+        let rings = config.segments + 1;
+        assert_eq!(staff.count_vertices(), (rings * config.resolution) as usize);
+        assert!(staff.count_vertices() < before);
+        assert!((volume(&staff) - volume(&config.generate_mesh())).abs() < EPSILON);
+    }
+
+    #[test]
+    fn hull_of_closed_shapes() {
+        let cube = generate_cube_mesh(&mut CubeNormals::default());
+        let hull = convex_hull_mesh(&cube).unwrap();
+        assert_eq!(hull.indices().unwrap().len(), 12 * 3);
+        assert!((volume(&hull) - 1.).abs() < EPSILON);
+
+        let staff = StaffConfig::default().generate_mesh();
+        let hull = convex_hull_mesh(&staff).unwrap();
+        assert!(volume(&hull) >= volume(&staff) - EPSILON);
+        // Every staff vertex is inside or on the hull
+        for point in positions(&staff).iter().copied().map(Vec3::from) {
+            for [a, b, c] in triangles(&hull) {
+                let outside = (b - a).cross(c - a).normalize().dot(point - a);
+                assert!(outside < 1e-4, "{point} is outside the hull by {outside}");
+            }
+        }
+    }
+
+    #[test]
+    fn flat_points_have_no_hull() {
+        let square = [Vec3::ZERO, Vec3::X, Vec3::Z, vec3(1., 0., 1.)];
+        assert!(convex_hull(&square).is_none());
+    }
+
+    #[test]
+    fn cube_mass_properties() {
+        let mesh = generate_cube_mesh(&mut CubeNormals::default());
+        assert!((volume(&mesh) - 1.).abs() < EPSILON);
+        assert!((surface_area(&mesh) - 6.).abs() < EPSILON);
+        let center = center_of_mass(&mesh).unwrap();
+        assert!(center.abs_diff_eq(Vec3::ZERO, EPSILON), "{center}");
+    }
+
+    #[test]
+    fn cube_section_is_a_square() {
+        let mesh = generate_cube_mesh(&mut CubeNormals::default());
+        let section = plane_section(&mesh, vec3(0., 0.2, 0.), Vec3::Y);
+        let perimeter: f32 = section.iter().map(|[a, b]| a.distance(*b)).sum();
+        assert!((perimeter - 4.).abs() < EPSILON);
+        for point in section.as_flattened() {
+            assert!((point.y - 0.2).abs() < EPSILON);
+            assert!(point.xz().abs().max_element() <= 0.5 + EPSILON);
+        }
+        assert!(plane_section(&mesh, Vec3::Y, Vec3::Y).is_empty());
+    }
+
+    #[test]
+    fn cylinder_mass_properties() {
+        let (radius, height, resolution) = (0.5, 2., 8);
+        let mesh = generate_cylinder_mesh(
+            radius,
+            height,
+            resolution,
+            3,
+            &mut CylinderNormals::default(),
+        );
+        // A prism with a regular polygon cross-section
+        let cap = polygon_area(radius, resolution);
+        let sides = resolution as f32 * polygon_side(radius, resolution) * height;
+        assert!((volume(&mesh) - cap * height).abs() < EPSILON);
+        assert!((surface_area(&mesh) - (2. * cap + sides)).abs() < EPSILON);
+        let center = center_of_mass(&mesh).unwrap();
+        assert!(center.abs_diff_eq(Vec3::ZERO, EPSILON), "{center}");
+    }
+
+    #[test]
+    fn cone_mass_properties() {
+        let (height, radius, resolution) = (1.5, 0.5, 6);
+        let mesh = generate_cone_mesh(height, radius, resolution);
+        // A pyramid with a regular polygon base
+        let base = polygon_area(radius, resolution);
+        let side = polygon_side(radius, resolution);
+        let apothem = radius * (TAU / resolution as f32 / 2.).cos();
+        let slant = (height * height + apothem * apothem).sqrt();
+        let lateral = resolution as f32 * side * slant / 2.;
+        assert!((volume(&mesh) - base * height / 3.).abs() < EPSILON);
+        assert!((surface_area(&mesh) - (base + lateral)).abs() < EPSILON);
+        // A quarter of the way up from the base, which sits at -height / 2
+        let center = center_of_mass(&mesh).unwrap();
+        assert!(
+            center.abs_diff_eq(Vec3::Y * -height / 4., EPSILON),
+            "{center}"
+        );
+    }
+
+    #[test]
+    fn identical_meshes_have_no_diff() {
+        let diff = compare(
+            &generate_cone_mesh(1., 0.5, 6),
+            &generate_cone_mesh(1., 0.5, 6),
+        );
+        assert!(diff.is_identical(), "{diff:?}");
+    }
+
+    #[test]
+    fn resolution_change_is_reported() {
+        let diff = compare(
+            &generate_cone_mesh(1., 0.5, 6),
+            &generate_cone_mesh(1., 0.5, 8),
+        );
+        assert_eq!(diff.vertex_count_delta, 4);
+        assert_eq!(diff.index_count_delta, 12);
+        assert!(!diff.indices_match);
+        assert!(diff.max_distance > 0.);
+    }
+
+    #[test]
+    fn scaled_mesh_distance() {
+        let a = generate_cone_mesh(1., 0.5, 6);
+        let b = generate_cone_mesh(1., 0.6, 6);
+        let diff = compare(&a, &b);
+        assert_eq!(diff.vertex_count_delta, 0);
+        assert!(diff.indices_match);
+        assert!((diff.max_distance - 0.1).abs() < 1e-5, "{diff:?}");
+        assert!(
+            diff.attribute_mismatches
+                .contains(&Mesh::ATTRIBUTE_POSITION.name.to_string())
+        );
+    }
+}
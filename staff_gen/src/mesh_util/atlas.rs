@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+use bevy::mesh::Indices;
+use bevy::prelude::*;
+
+use super::{triangles, weld_vertices};
+
+/// Corners closer than this count as one when finding which triangles touch.
+const CHART_WELD_EPSILON: f32 = 1e-5;
+
+/// Packs non-overlapping UVs into [`Mesh::ATTRIBUTE_UV_1`], the channel lightmaps are read from.
+/// Connected triangles facing the same way along their nearest axis form a chart, which is
+/// projected flat onto that axis's plane. Charts keep their size relative to each other and are
+/// packed in rows into the unit square, roughly `padding` apart. Vertices on the border between
+/// two charts are split, so each chart has its own.
+pub fn pack_uv_atlas(mesh: &mut Mesh, padding: f32) {
+    let triangles: Vec<[Vec3; 3]> = triangles(mesh).collect();
+    let (charts, chart_count) = find_charts(&triangles);
+
+    // Each chart projected onto the plane of its axis
+    let mut corners: Vec<[Vec2; 3]> = triangles
+        .iter()
+        .map(|triangle| {
+            let (u, v) = dominant_axis(triangle).any_orthonormal_pair();
+            triangle.map(|corner| vec2(corner.dot(u), corner.dot(v)))
+        })
+        .collect();
+    let bounds = |corners: &[[Vec2; 3]]| {
+        let mut bounds = vec![(Vec2::MAX, Vec2::MIN); chart_count];
+        for (triangle, &chart) in corners.iter().zip(&charts) {
+            for corner in triangle {
+                bounds[chart] = (bounds[chart].0.min(*corner), bounds[chart].1.max(*corner));
+            }
+        }
+        bounds
+    };
+
+    // Stand charts upright, so long ones sit side by side in a row
+    let wide: Vec<bool> = bounds(&corners)
+        .iter()
+        .map(|(min, max)| max.x - min.x > max.y - min.y)
+        .collect();
+    for (triangle, &chart) in corners.iter_mut().zip(&charts) {
+        if wide[chart] {
+            *triangle = triangle.map(Vec2::perp);
+        }
+    }
+    let bounds = bounds(&corners);
+
+    // Shelf packing, tallest charts first, into rows about as wide as the atlas is tall
+    let sizes: Vec<Vec2> = bounds.iter().map(|(min, max)| *max - *min).collect();
+    let area: f32 = sizes.iter().map(|size| size.x * size.y).sum();
+    let gap = padding * area.sqrt();
+    let row_width = sizes.iter().map(|size| size.x).fold(area.sqrt(), f32::max);
+    let mut order: Vec<usize> = (0..chart_count).collect();
+    order.sort_by(|&a, &b| sizes[b].y.total_cmp(&sizes[a].y));
+
+    let mut offsets = vec![Vec2::ZERO; chart_count];
+    let mut cursor = Vec2::splat(gap);
+    let mut row_height = 0f32;
+    let mut extent = Vec2::ZERO;
+    for chart in order {
+        let size = sizes[chart];
+        if cursor.x > gap && cursor.x + size.x > row_width + gap {
+            cursor = vec2(gap, cursor.y + row_height + gap);
+            row_height = 0.;
+        }
+        offsets[chart] = cursor - bounds[chart].0;
+        cursor.x += size.x + gap;
+        row_height = row_height.max(size.y);
+        extent = extent.max(vec2(cursor.x, cursor.y + size.y + gap));
+    }
+    let scale = 1. / extent.max_element().max(f32::EPSILON);
+
+    // One vertex per corner with its chart's UVs, welded back together within each chart
+    mesh.duplicate_vertices();
+    let uvs: Vec<[f32; 2]> = corners
+        .iter()
+        .zip(&charts)
+        .flat_map(|(triangle, &chart)| {
+            triangle.map(|corner| ((corner + offsets[chart]) * scale).to_array())
+        })
+        .collect();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_1, uvs);
+    weld_vertices(mesh, CHART_WELD_EPSILON, true);
+    if mesh.indices().is_none() {
+        let vertices = mesh.count_vertices() as u32;
+        mesh.insert_indices(Indices::U32((0..vertices).collect()));
+    }
+}
+
+/// Signed axis closest to a triangle's normal.
+fn dominant_axis(triangle: &[Vec3; 3]) -> Vec3 {
+    let [a, b, c] = *triangle;
+    let normal = (b - a).cross(c - a);
+    let axis = normal.abs().max_position();
+    let mut direction = Vec3::ZERO;
+    direction[axis] = normal[axis].signum();
+    direction
+}
+
+/// Chart of every triangle and the number of charts.
+fn find_charts(triangles: &[[Vec3; 3]]) -> (Vec<usize>, usize) {
+    fn root(parents: &mut [usize], mut triangle: usize) -> usize {
+        while parents[triangle] != triangle {
+            parents[triangle] = parents[parents[triangle]];
+            triangle = parents[triangle];
+        }
+        triangle
+    }
+
+    let axes: Vec<Vec3> = triangles.iter().map(dominant_axis).collect();
+    let mut parents: Vec<usize> = (0..triangles.len()).collect();
+    let mut edges: HashMap<([i32; 3], [i32; 3]), usize> = HashMap::new();
+    for (triangle, corners) in triangles.iter().enumerate() {
+        let cells =
+            corners.map(|corner| (corner / CHART_WELD_EPSILON).round().as_ivec3().to_array());
+        for k in 0..3 {
+            let (a, b) = (cells[k], cells[(k + 1) % 3]);
+            let edge = if a < b { (a, b) } else { (b, a) };
+            match edges.entry(edge) {
+                Entry::Occupied(entry) => {
+                    let neighbour = *entry.get();
+                    if axes[neighbour] == axes[triangle] {
+                        let (a, b) = (root(&mut parents, neighbour), root(&mut parents, triangle));
+                        parents[a] = b;
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(triangle);
+                }
+            }
+        }
+    }
+
+    let mut chart_ids = HashMap::new();
+    let charts = (0..triangles.len())
+        .map(|triangle| {
+            let root = root(&mut parents, triangle);
+            let next = chart_ids.len();
+            *chart_ids.entry(root).or_insert(next)
+        })
+        .collect();
+    (charts, chart_ids.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::mesh::VertexAttributeValues;
+
+    use super::*;
+    use crate::repair::check_watertight;
+    use crate::staff::StaffConfig;
+
+    fn uvs(mesh: &Mesh, attribute: bevy::mesh::MeshVertexAttribute) -> &[[f32; 2]] {
+        match mesh.attribute(attribute) {
+            Some(VertexAttributeValues::Float32x2(uvs)) => uvs,
+            _ => panic!("missing {}", attribute.name),
+        }
+    }
+
+    /// Whether the insides of two triangles overlap, by the separating axis theorem.
+    fn overlap(a: [Vec2; 3], b: [Vec2; 3]) -> bool {
+        [a, b].iter().all(|triangle| {
+            (0..3).all(|k| {
+                let axis = (triangle[(k + 1) % 3] - triangle[k]).perp();
+                let project = |t: [Vec2; 3]| {
+                    let [x, y, z] = t.map(|corner| corner.dot(axis));
+                    (x.min(y).min(z), x.max(y).max(z))
+                };
+                let ((a_min, a_max), (b_min, b_max)) = (project(a), project(b));
+                a_max > b_min + 1e-6 && b_max > a_min + 1e-6
+            })
+        })
+    }
+
+    #[test]
+    fn atlas_charts_do_not_overlap() {
+        let mut mesh = StaffConfig {
+            resolution: 6,
+            segments: 6,
+            ..default()
+        }
+        .generate_mesh();
+        pack_uv_atlas(&mut mesh, 0.01);
+        let report = check_watertight(&mesh);
+        assert!(report.is_watertight(), "{report:?}");
+
+        let uvs = uvs(&mesh, Mesh::ATTRIBUTE_UV_1);
+        let triangles: Vec<[Vec2; 3]> = mesh
+            .indices()
+            .unwrap()
+            .iter()
+            .collect::<Vec<_>>()
+            .chunks_exact(3)
+            .map(|corners| [0, 1, 2].map(|k| Vec2::from(uvs[corners[k]])))
+            .collect();
+        for (i, &a) in triangles.iter().enumerate() {
+            for &b in &triangles[i + 1..] {
+                assert!(!overlap(a, b), "{a:?} overlaps {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn staff_uvs_stay_in_the_unit_square() {
+        let mut mesh = StaffConfig::default().generate_mesh();
+        let in_unit_square = |uv: &[f32; 2]| uv.iter().all(|x| (0. ..=1.).contains(x));
+        assert!(uvs(&mesh, Mesh::ATTRIBUTE_UV_0).iter().all(in_unit_square));
+
+        pack_uv_atlas(&mut mesh, 0.01);
+        let atlas = uvs(&mesh, Mesh::ATTRIBUTE_UV_1);
+        assert!(atlas.iter().all(in_unit_square));
+        // The charts fill the atlas up to its padding
+        let highest = atlas.iter().flatten().fold(0f32, |a, &b| a.max(b));
+        assert!(highest > 0.9, "{highest}");
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::mesh::VertexAttributeValues;
+use bevy::prelude::*;
+
+use super::positions;
+
+/// How many texels the baked charts grow past their edges, so filtering doesn't sample past them.
+const BAKE_DILATION: u32 = 2;
+/// Growth rings of [`wood_grain`] per meter out from the axis
+const RINGS_PER_METER: f32 = 150.;
+/// Frequencies of the noise bending the rings, and of the streaks along the grain. Both are
+/// stretched along Y, the way fibres run up a branch.
+const RING_WOBBLE_FREQUENCY: Vec3 = vec3(40., 3., 40.);
+const STREAK_FREQUENCY: Vec3 = vec3(400., 8., 400.);
+
+/// The surface under one texel of a baked texture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasTexel {
+    pub position: Vec3,
+    pub normal: Vec3,
+}
+
+/// Rasterizes every triangle at its [`Mesh::ATTRIBUTE_UV_1`] atlas UVs, as packed by
+/// [`super::pack_uv_atlas`], into `size` by `size` texels in rows from the top. Texels no chart
+/// covers are `None`, apart from a small border around each chart. Without atlas UVs every texel
+/// is `None`.
+pub fn bake_atlas(mesh: &Mesh, size: u32) -> Vec<Option<AtlasTexel>> {
+    let size = size as usize;
+    let mut texels = vec![None; size * size];
+    let Some(VertexAttributeValues::Float32x2(atlas)) = mesh.attribute(Mesh::ATTRIBUTE_UV_1) else {
+        return texels;
+    };
+    let positions = positions(mesh);
+    let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+        Some(VertexAttributeValues::Float32x3(normals)) => normals.as_slice(),
+        _ => &[],
+    };
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(indices) => indices.iter().collect(),
+        None => (0..positions.len()).collect(),
+    };
+
+    for corners in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|k| Vec2::from(atlas[corners[k]]) * size as f32);
+        let area = (b - a).perp_dot(c - a);
+        if area.abs() < f32::EPSILON {
+            continue;
+        }
+        let min = a.min(b).min(c).floor().max(Vec2::ZERO).as_uvec2();
+        let max = a
+            .max(b)
+            .max(c)
+            .ceil()
+            .min(Vec2::splat(size as f32))
+            .as_uvec2();
+        for y in min.y..max.y {
+            for x in min.x..max.x {
+                let center = vec2(x as f32, y as f32) + 0.5;
+                let weights = vec3(
+                    (c - b).perp_dot(center - b),
+                    (a - c).perp_dot(center - c),
+                    (b - a).perp_dot(center - a),
+                ) / area;
+                if weights.min_element() < -1e-4 {
+                    continue;
+                }
+                let attribute = |values: &[[f32; 3]]| {
+                    let [a, b, c] = [0, 1, 2].map(|k| {
+                        values
+                            .get(corners[k])
+                            .copied()
+                            .map_or(Vec3::ZERO, Vec3::from)
+                    });
+                    a * weights.x + b * weights.y + c * weights.z
+                };
+                texels[y as usize * size + x as usize] = Some(AtlasTexel {
+                    position: attribute(positions),
+                    normal: attribute(normals).normalize_or_zero(),
+                });
+            }
+        }
+    }
+
+    // Grow every chart outwards, copying its edge texels
+    for _ in 0..BAKE_DILATION {
+        let previous = texels.clone();
+        for y in 0..size {
+            for x in 0..size {
+                if previous[y * size + x].is_some() {
+                    continue;
+                }
+                let neighbours = [
+                    (x > 0).then(|| y * size + x - 1),
+                    (x + 1 < size).then(|| y * size + x + 1),
+                    (y > 0).then(|| (y - 1) * size + x),
+                    (y + 1 < size).then(|| (y + 1) * size + x),
+                ];
+                texels[y * size + x] = neighbours.into_iter().flatten().find_map(|i| previous[i]);
+            }
+        }
+    }
+    texels
+}
+
+/// Procedural wood at `position`, in meters from a staff's origin: 0 in the pale wood grown early
+/// in a year, up to 1 in the thin dark band grown late in it. Rings circle the Y axis, bent by
+/// noise and broken up by fine streaks along the grain.
+pub fn wood_grain(position: Vec3) -> f32 {
+    let wobble = value_noise(position * RING_WOBBLE_FREQUENCY);
+    let ring = (position.xz().length() * RINGS_PER_METER + wobble * 1.5).fract();
+    let latewood = ((ring - 0.7) / 0.3).clamp(0., 1.);
+    let latewood = latewood * latewood * (3. - 2. * latewood);
+    let streaks = value_noise(position * STREAK_FREQUENCY);
+    (latewood * 0.8 + streaks * 0.2).clamp(0., 1.)
+}
+
+/// Smoothly interpolated noise from 0 to 1, with a new random value at every integer point.
+fn value_noise(point: Vec3) -> f32 {
+    let cell = point.floor();
+    let t = point - cell;
+    let t = t * t * (3. - 2. * t);
+    let cell = cell.as_ivec3();
+    let corner = |x, y, z| lattice_value(cell + IVec3::new(x, y, z));
+    let along_x = |y, z| corner(0, y, z).lerp(corner(1, y, z), t.x);
+    let along_y = |z| along_x(0, z).lerp(along_x(1, z), t.y);
+    along_y(0).lerp(along_y(1), t.z)
+}
+
+fn lattice_value(point: IVec3) -> f32 {
+    let mut hash = (point.x as u32).wrapping_mul(0x8da6_b343)
+        ^ (point.y as u32).wrapping_mul(0xd816_3841)
+        ^ (point.z as u32).wrapping_mul(0xcb1a_b31f);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2c1b_3c6d);
+    hash ^= hash >> 12;
+    hash as f32 / u32::MAX as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_util::{MeshSourceData, closest_point, pack_uv_atlas};
+    use crate::staff::StaffConfig;
+
+    #[test]
+    fn baked_texels_lie_on_the_surface() {
+        let mut mesh = StaffConfig::default().generate_mesh();
+        pack_uv_atlas(&mut mesh, 0.02);
+        let texels = bake_atlas(&mesh, 64);
+        let source = MeshSourceData::from_mesh(&mesh);
+        let baked: Vec<_> = texels.iter().flatten().collect();
+        assert!(baked.len() > texels.len() / 4, "{} baked", baked.len());
+        for texel in baked {
+            let hit = closest_point(&source, texel.position).unwrap();
+            assert!(
+                hit.position.distance(texel.position) < 1e-4,
+                "{texel:?} is off the surface"
+            );
+            assert!((texel.normal.length() - 1.).abs() < 1e-4, "{texel:?}");
+        }
+    }
+
+    #[test]
+    fn wood_grain_has_rings() {
+        // A line out from the axis crosses several rings, each with pale and dark wood
+        let grain: Vec<f32> = (0..500)
+            .map(|i| wood_grain(vec3(i as f32 * 1e-4, 0.3, 0.)))
+            .collect();
+        assert!(grain.iter().all(|g| (0. ..=1.).contains(g)));
+        let dark = grain.iter().filter(|&&g| g > 0.6).count();
+        let pale = grain.iter().filter(|&&g| g < 0.3).count();
+        assert!(dark > 20 && pale > 100, "{dark} dark, {pale} pale");
+        assert_eq!(
+            wood_grain(vec3(0.01, 0.2, 0.03)),
+            wood_grain(vec3(0.01, 0.2, 0.03))
+        );
+    }
+
+    #[test]
+    fn nothing_is_baked_without_an_atlas() {
+        let mesh = StaffConfig::default().generate_mesh();
+        assert!(bake_atlas(&mesh, 16).iter().all(Option::is_none));
+    }
+}
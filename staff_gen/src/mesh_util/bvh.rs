@@ -0,0 +1,287 @@
+use bevy::math::bounding::{Aabb3d, RayCast3d};
+use bevy::prelude::*;
+
+use super::query::{
+    MeshSourceData, RayHit, SurfaceHit, closest_point_on_triangle, raycast_triangle,
+};
+
+/// Leaves are split until they hold at most this many triangles.
+const MAX_LEAF_TRIANGLES: usize = 4;
+
+#[derive(Debug, Clone)]
+enum BvhNodeKind {
+    /// Range into [`Bvh::triangles`]
+    Leaf {
+        first: usize,
+        count: usize,
+    },
+    Branch {
+        left: usize,
+        right: usize,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct BvhNode {
+    bounds: Aabb3d,
+    kind: BvhNodeKind,
+}
+
+/// Bounding volume hierarchy over the triangles of a [`MeshSourceData`].
+///
+/// The tree only stores triangle indices, so every query takes the source it was built from.
+/// After moving vertices without changing the triangles, [`Bvh::refit`] updates the bounds
+/// in place, which is much cheaper than a rebuild but gets slower to query the further
+/// the vertices move.
+#[derive(Debug, Clone)]
+pub struct Bvh {
+    /// Children always come after their parent
+    nodes: Vec<BvhNode>,
+    /// Triangle indices, grouped by leaf
+    triangles: Vec<usize>,
+}
+
+impl Bvh {
+    /// Splits triangles at the median centroid along the longest axis.
+    pub fn new(source: &MeshSourceData) -> Self {
+        let centroids: Vec<Vec3> = (0..source.triangle_count())
+            .map(|triangle| source.triangle(triangle).iter().sum::<Vec3>() / 3.)
+            .collect();
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            triangles: (0..source.triangle_count()).collect(),
+        };
+        if !bvh.triangles.is_empty() {
+            bvh.build(source, &centroids, 0, bvh.triangles.len());
+        }
+        bvh
+    }
+
+    fn build(
+        &mut self,
+        source: &MeshSourceData,
+        centroids: &[Vec3],
+        start: usize,
+        end: usize,
+    ) -> usize {
+        let index = self.nodes.len();
+        let bounds = self.leaf_bounds(source, start, end);
+        self.nodes.push(BvhNode {
+            bounds,
+            kind: BvhNodeKind::Leaf {
+                first: start,
+                count: end - start,
+            },
+        });
+        if end - start <= MAX_LEAF_TRIANGLES {
+            return index;
+        }
+
+        let (min, max) = self.triangles[start..end].iter().fold(
+            (Vec3::INFINITY, Vec3::NEG_INFINITY),
+            |(min, max), &triangle| (min.min(centroids[triangle]), max.max(centroids[triangle])),
+        );
+        let axis = (max - min).max_position();
+        let middle = (start + end) / 2;
+        self.triangles[start..end].select_nth_unstable_by(middle - start, |&a, &b| {
+            centroids[a][axis].total_cmp(&centroids[b][axis])
+        });
+
+        let left = self.build(source, centroids, start, middle);
+        let right = self.build(source, centroids, middle, end);
+        self.nodes[index].kind = BvhNodeKind::Branch { left, right };
+        index
+    }
+
+    fn leaf_bounds(&self, source: &MeshSourceData, start: usize, end: usize) -> Aabb3d {
+        let (min, max) = self.triangles[start..end]
+            .iter()
+            .flat_map(|&triangle| source.triangle(triangle))
+            .fold(
+                (Vec3::INFINITY, Vec3::NEG_INFINITY),
+                |(min, max), corner| (min.min(corner), max.max(corner)),
+            );
+        Aabb3d {
+            min: min.into(),
+            max: max.into(),
+        }
+    }
+
+    /// Recomputes every bound from the current vertex positions of `source`, which must
+    /// still have the triangles the tree was built from.
+    pub fn refit(&mut self, source: &MeshSourceData) {
+        for index in (0..self.nodes.len()).rev() {
+            self.nodes[index].bounds = match self.nodes[index].kind {
+                BvhNodeKind::Leaf { first, count } => {
+                    self.leaf_bounds(source, first, first + count)
+                }
+                BvhNodeKind::Branch { left, right } => {
+                    let (left, right) = (&self.nodes[left].bounds, &self.nodes[right].bounds);
+                    Aabb3d {
+                        min: left.min.min(right.min),
+                        max: left.max.max(right.max),
+                    }
+                }
+            };
+        }
+    }
+
+    /// Same result as [`raycast`](super::raycast), skipping subtrees the ray misses or
+    /// only reaches beyond the closest hit so far.
+    pub fn raycast(&self, source: &MeshSourceData, ray: Ray3d) -> Option<RayHit> {
+        let mut closest: Option<RayHit> = None;
+        let mut stack = Vec::from_iter((!self.nodes.is_empty()).then_some(0));
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let max = closest.map_or(f32::MAX, |hit| hit.distance);
+            if RayCast3d::from_ray(ray, max)
+                .aabb_intersection_at(&node.bounds)
+                .is_none()
+            {
+                continue;
+            }
+            match node.kind {
+                BvhNodeKind::Leaf { first, count } => {
+                    for &triangle in &self.triangles[first..first + count] {
+                        let hit = raycast_triangle(ray, source.triangle(triangle), triangle);
+                        if let Some(hit) = hit
+                            && closest.is_none_or(|closest| hit.distance < closest.distance)
+                        {
+                            closest = Some(hit);
+                        }
+                    }
+                }
+                BvhNodeKind::Branch { left, right } => stack.extend([left, right]),
+            }
+        }
+        closest
+    }
+
+    /// Same result as [`closest_point`](super::closest_point), skipping subtrees whose
+    /// bounds are further away than the nearest point so far.
+    pub fn closest_point(&self, source: &MeshSourceData, point: Vec3) -> Option<SurfaceHit> {
+        let distance_squared = |index: usize| {
+            let bounds = &self.nodes[index].bounds;
+            Vec3::from(bounds.closest_point(point)).distance_squared(point)
+        };
+        let mut closest: Option<(SurfaceHit, f32)> = None;
+        let mut stack = Vec::from_iter((!self.nodes.is_empty()).then_some(0));
+        while let Some(index) = stack.pop() {
+            if closest.is_some_and(|(_, best)| distance_squared(index) >= best) {
+                continue;
+            }
+            match self.nodes[index].kind {
+                BvhNodeKind::Leaf { first, count } => {
+                    for &triangle in &self.triangles[first..first + count] {
+                        let hit =
+                            closest_point_on_triangle(point, source.triangle(triangle), triangle);
+                        let distance = hit.position.distance_squared(point);
+                        if closest.is_none_or(|(_, best)| distance < best) {
+                            closest = Some((hit, distance));
+                        }
+                    }
+                }
+                // Visit the nearer child first so it can prune the other
+                BvhNodeKind::Branch { left, right } => {
+                    if distance_squared(left) < distance_squared(right) {
+                        stack.extend([right, left]);
+                    } else {
+                        stack.extend([left, right]);
+                    }
+                }
+            }
+        }
+        closest.map(|(hit, _)| hit)
+    }
+
+    /// Bounds of every triangle in the tree, `None` when it is empty.
+    pub fn bounds(&self) -> Option<Aabb3d> {
+        self.nodes.first().map(|node| node.bounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_util::{closest_point, raycast};
+    use crate::staff::StaffConfig;
+
+    fn rays() -> impl Iterator<Item = Ray3d> {
+        // A fan of horizontal rays at several heights, aimed roughly at the staff
+        (0..20).flat_map(|i| {
+            (0..16).map(move |j| {
+                let angle = j as f32 / 16. * std::f32::consts::TAU;
+                let origin = vec3(angle.cos() * 3., i as f32 / 10. - 1., angle.sin() * 3.);
+                let target = vec3(0.05 * (j % 3) as f32, origin.y, 0.);
+                Ray3d::new(origin, Dir3::new(target - origin).unwrap())
+            })
+        })
+    }
+
+    fn staff() -> MeshSourceData {
+        MeshSourceData::from_mesh(
+            &StaffConfig {
+                resolution: 16,
+                segments: 24,
+                ..default()
+            }
+            .generate_mesh(),
+        )
+    }
+
+    #[test]
+    fn bvh_matches_brute_force() {
+        let source = staff();
+        let bvh = Bvh::new(&source);
+        let mut hits = 0;
+        for ray in rays() {
+            let expected = raycast(&source, ray);
+            assert_eq!(
+                bvh.raycast(&source, ray).map(|hit| hit.distance),
+                expected.map(|hit| hit.distance)
+            );
+            hits += expected.is_some() as usize;
+        }
+        assert!(hits > 0);
+    }
+
+    #[test]
+    fn bvh_closest_point_matches_brute_force() {
+        let source = staff();
+        let bvh = Bvh::new(&source);
+        for ray in rays() {
+            // Points scattered around and inside the staff
+            for distance in [0.5, 2.9, 3.5] {
+                let point = ray.get_point(distance);
+                let expected = closest_point(&source, point).unwrap();
+                let hit = bvh.closest_point(&source, point).unwrap();
+                assert!(
+                    (hit.position.distance(point) - expected.position.distance(point)).abs() < 1e-6,
+                    "{point}: {hit:?} != {expected:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn refit_follows_moved_vertices() {
+        let mut source = staff();
+        let mut bvh = Bvh::new(&source);
+        for position in &mut source.positions {
+            *position += vec3(0.02, 0., 0.) * position.y;
+        }
+        bvh.refit(&source);
+        for ray in rays() {
+            assert_eq!(
+                bvh.raycast(&source, ray).map(|hit| hit.distance),
+                raycast(&source, ray).map(|hit| hit.distance)
+            );
+        }
+        let bounds = bvh.bounds().unwrap();
+        for &position in &source.positions {
+            assert!(
+                position.cmpge(bounds.min.into()).all() && position.cmple(bounds.max.into()).all()
+            );
+        }
+    }
+}
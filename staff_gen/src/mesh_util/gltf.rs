@@ -0,0 +1,159 @@
+use bevy::mesh::{PrimitiveTopology, VertexAttributeValues};
+use bevy::prelude::*;
+
+use super::positions;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const TRIANGLES: u32 = 4;
+const TRIANGLE_STRIP: u32 = 5;
+
+/// Serializes a triangle list or strip mesh to binary glTF 2.0 (GLB), as a single node holding
+/// positions, plus normals and UVs when the mesh has them.
+pub fn to_glb(mesh: &Mesh) -> Vec<u8> {
+    let positions = positions(mesh);
+    let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+        Some(VertexAttributeValues::Float32x3(normals)) => normals.as_slice(),
+        _ => &[],
+    };
+    let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+        Some(VertexAttributeValues::Float32x2(uvs)) => uvs.as_slice(),
+        _ => &[],
+    };
+    let indices: Vec<u32> = match mesh.indices() {
+        Some(indices) => indices.iter().map(|i| i as u32).collect(),
+        None => (0..positions.len() as u32).collect(),
+    };
+
+    let mut bin: Vec<u8> = Vec::new();
+    let mut buffer_views = Vec::new();
+    let mut accessors = Vec::new();
+    let mut attributes = Vec::new();
+    // Every component is four bytes, so the views stay aligned without padding
+    let mut push_view = |bin: &mut Vec<u8>, bytes: Vec<u8>, target: u32| {
+        buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{target}}}"#,
+            bin.len(),
+            bytes.len()
+        ));
+        bin.extend(bytes);
+        buffer_views.len() - 1
+    };
+    let floats =
+        |values: &[f32]| -> Vec<u8> { values.iter().flat_map(|v| v.to_le_bytes()).collect() };
+
+    let view = push_view(&mut bin, floats(positions.as_flattened()), ARRAY_BUFFER);
+    let (min, max) = positions.iter().fold(
+        (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+        |(min, max), &position| (min.min(position.into()), max.max(position.into())),
+    );
+    let (min, max) = if positions.is_empty() {
+        (Vec3::ZERO, Vec3::ZERO)
+    } else {
+        (min, max)
+    };
+    accessors.push(format!(
+        r#"{{"bufferView":{view},"componentType":{FLOAT},"count":{},"type":"VEC3","min":[{:?},{:?},{:?}],"max":[{:?},{:?},{:?}]}}"#,
+        positions.len(),
+        min.x,
+        min.y,
+        min.z,
+        max.x,
+        max.y,
+        max.z
+    ));
+    attributes.push(format!(r#""POSITION":{}"#, accessors.len() - 1));
+    if normals.len() == positions.len() && !normals.is_empty() {
+        let view = push_view(&mut bin, floats(normals.as_flattened()), ARRAY_BUFFER);
+        accessors.push(format!(
+            r#"{{"bufferView":{view},"componentType":{FLOAT},"count":{},"type":"VEC3"}}"#,
+            normals.len()
+        ));
+        attributes.push(format!(r#""NORMAL":{}"#, accessors.len() - 1));
+    }
+    if uvs.len() == positions.len() && !uvs.is_empty() {
+        let view = push_view(&mut bin, floats(uvs.as_flattened()), ARRAY_BUFFER);
+        accessors.push(format!(
+            r#"{{"bufferView":{view},"componentType":{FLOAT},"count":{},"type":"VEC2"}}"#,
+            uvs.len()
+        ));
+        attributes.push(format!(r#""TEXCOORD_0":{}"#, accessors.len() - 1));
+    }
+    let index_bytes = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+    let view = push_view(&mut bin, index_bytes, ELEMENT_ARRAY_BUFFER);
+    accessors.push(format!(
+        r#"{{"bufferView":{view},"componentType":{UNSIGNED_INT},"count":{},"type":"SCALAR"}}"#,
+        indices.len()
+    ));
+
+    let mode = match mesh.primitive_topology() {
+        PrimitiveTopology::TriangleStrip => TRIANGLE_STRIP,
+        _ => TRIANGLES,
+    };
+
+    let json = format!(
+        r#"{{"asset":{{"version":"2.0","generator":"staff_gen"}},"scene":0,"scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0}}],"meshes":[{{"primitives":[{{"attributes":{{{}}},"indices":{},"mode":{mode}}}]}}],"accessors":[{}],"bufferViews":[{}],"buffers":[{{"byteLength":{}}}]}}"#,
+        attributes.join(","),
+        accessors.len() - 1,
+        accessors.join(","),
+        buffer_views.join(","),
+        bin.len()
+    );
+    // Chunks are padded to four bytes, JSON with spaces
+    let mut json = json.into_bytes();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    bin.resize(bin.len().next_multiple_of(4), 0);
+
+    let length = 12 + 8 + json.len() + 8 + bin.len();
+    let mut glb = Vec::with_capacity(length);
+    for word in [GLB_MAGIC, GLB_VERSION, length as u32] {
+        glb.extend(word.to_le_bytes());
+    }
+    for (chunk_type, chunk) in [(CHUNK_JSON, json), (CHUNK_BIN, bin)] {
+        glb.extend((chunk.len() as u32).to_le_bytes());
+        glb.extend(chunk_type.to_le_bytes());
+        glb.extend(chunk);
+    }
+    glb
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::staff::StaffConfig;
+
+    fn word(glb: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(glb[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn glb_chunks_cover_the_whole_file() {
+        let glb = to_glb(&StaffConfig::default().generate_mesh());
+        assert_eq!(word(&glb, 0), GLB_MAGIC);
+        assert_eq!(word(&glb, 8) as usize, glb.len());
+        let json_length = word(&glb, 12) as usize;
+        assert_eq!(word(&glb, 16), CHUNK_JSON);
+        let bin_offset = 20 + json_length;
+        assert_eq!(word(&glb, bin_offset + 4), CHUNK_BIN);
+        assert_eq!(bin_offset + 8 + word(&glb, bin_offset) as usize, glb.len());
+        assert!(json_length.is_multiple_of(4));
+    }
+
+    #[test]
+    fn glb_ends_with_the_mesh_indices() {
+        let mesh = StaffConfig::default().generate_mesh();
+        let glb = to_glb(&mesh);
+        let indices: Vec<u32> = mesh.indices().unwrap().iter().map(|i| i as u32).collect();
+        // Four byte indices need no padding, so they end the binary chunk
+        let stored: Vec<u32> = (0..indices.len())
+            .map(|i| word(&glb, glb.len() - 4 * (indices.len() - i)))
+            .collect();
+        assert_eq!(stored, indices);
+    }
+}
@@ -0,0 +1,225 @@
+use bevy::prelude::*;
+
+use super::{positions, triangles};
+
+/// CPU copy of a mesh's triangles for geometric queries, independent of the render asset.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MeshSourceData {
+    pub positions: Vec<Vec3>,
+    /// Three per triangle
+    pub indices: Vec<u32>,
+}
+
+impl MeshSourceData {
+    pub fn from_mesh(mesh: &Mesh) -> Self {
+        let positions = positions(mesh).iter().copied().map(Vec3::from).collect();
+        let indices = match mesh.indices() {
+            Some(indices) => indices.iter().map(|i| i as u32).collect(),
+            None => (0..mesh.count_vertices() as u32).collect(),
+        };
+        Self { positions, indices }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn triangle(&self, triangle: usize) -> [Vec3; 3] {
+        let corner = |i: usize| self.positions[self.indices[triangle * 3 + i] as usize];
+        [corner(0), corner(1), corner(2)]
+    }
+}
+
+impl From<&Mesh> for MeshSourceData {
+    fn from(mesh: &Mesh) -> Self {
+        Self::from_mesh(mesh)
+    }
+}
+
+/// Where a ray first meets a mesh.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub distance: f32,
+    pub position: Vec3,
+    /// Geometric normal of the hit triangle, facing the way the triangle winds
+    pub normal: Vec3,
+    pub triangle: usize,
+}
+
+/// Closest intersection of `ray` with any triangle of `source`, hitting both faces.
+pub fn raycast(source: &MeshSourceData, ray: Ray3d) -> Option<RayHit> {
+    (0..source.triangle_count())
+        .filter_map(|triangle| raycast_triangle(ray, source.triangle(triangle), triangle))
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+}
+
+/// Möller–Trumbore ray-triangle intersection.
+pub(crate) fn raycast_triangle(
+    ray: Ray3d,
+    [a, b, c]: [Vec3; 3],
+    triangle: usize,
+) -> Option<RayHit> {
+    let ab = b - a;
+    let ac = c - a;
+    let p = ray.direction.cross(ac);
+    let determinant = ab.dot(p);
+    // Parallel to the triangle plane
+    if determinant.abs() < f32::EPSILON {
+        return None;
+    }
+    let inverse = 1. / determinant;
+    let to_origin = ray.origin - a;
+    let u = to_origin.dot(p) * inverse;
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+    let q = to_origin.cross(ab);
+    let v = ray.direction.dot(q) * inverse;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+    let distance = ac.dot(q) * inverse;
+    (distance >= 0.).then(|| RayHit {
+        distance,
+        position: ray.get_point(distance),
+        normal: ab.cross(ac).normalize_or_zero(),
+        triangle,
+    })
+}
+
+/// Point on a mesh surface nearest to a query point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceHit {
+    pub position: Vec3,
+    /// Geometric normal of the triangle, facing the way it winds
+    pub normal: Vec3,
+    pub triangle: usize,
+}
+
+/// Nearest point to `point` on any triangle of `source`, `None` for an empty mesh.
+pub fn closest_point(source: &MeshSourceData, point: Vec3) -> Option<SurfaceHit> {
+    (0..source.triangle_count())
+        .map(|triangle| closest_point_on_triangle(point, source.triangle(triangle), triangle))
+        .min_by(|a, b| {
+            a.position
+                .distance_squared(point)
+                .total_cmp(&b.position.distance_squared(point))
+        })
+}
+
+/// Closest point by Voronoi region of the triangle, from Ericson's Real-Time Collision Detection.
+pub(crate) fn closest_point_on_triangle(
+    point: Vec3,
+    [a, b, c]: [Vec3; 3],
+    triangle: usize,
+) -> SurfaceHit {
+    let hit = |position| SurfaceHit {
+        position,
+        normal: (b - a).cross(c - a).normalize_or_zero(),
+        triangle,
+    };
+    let ab = b - a;
+    let ac = c - a;
+    let ap = point - a;
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= 0. && d2 <= 0. {
+        return hit(a);
+    }
+    let bp = point - b;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= 0. && d4 <= d3 {
+        return hit(b);
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0. && d1 >= 0. && d3 <= 0. {
+        return hit(a + ab * (d1 / (d1 - d3)));
+    }
+    let cp = point - c;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= 0. && d5 <= d6 {
+        return hit(c);
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0. && d2 >= 0. && d6 <= 0. {
+        return hit(a + ac * (d2 / (d2 - d6)));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0. && d4 - d3 >= 0. && d5 - d6 >= 0. {
+        return hit(b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6))));
+    }
+    // Inside the face
+    let denominator = 1. / (va + vb + vc);
+    hit(a + ab * (vb * denominator) + ac * (vc * denominator))
+}
+
+/// Same as [`raycast`], straight from a mesh without keeping its [`MeshSourceData`].
+pub fn raycast_mesh(mesh: &Mesh, ray: Ray3d) -> Option<RayHit> {
+    triangles(mesh)
+        .enumerate()
+        .filter_map(|(triangle, corners)| raycast_triangle(ray, corners, triangle))
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::{CubeNormals, generate_cube_mesh};
+    use crate::staff::StaffConfig;
+
+    #[test]
+    fn ray_hits_cube_face() {
+        let source = MeshSourceData::from_mesh(&generate_cube_mesh(&mut CubeNormals::default()));
+        let ray = Ray3d::new(vec3(0.1, 5., 0.2), Dir3::NEG_Y);
+        let hit = raycast(&source, ray).unwrap();
+        assert!((hit.distance - 4.5).abs() < 1e-5);
+        assert!(hit.position.abs_diff_eq(vec3(0.1, 0.5, 0.2), 1e-5));
+        assert!(hit.normal.abs_diff_eq(Vec3::Y, 1e-5));
+
+        let miss = Ray3d::new(vec3(2., 5., 0.), Dir3::NEG_Y);
+        assert!(raycast(&source, miss).is_none());
+        let away = Ray3d::new(vec3(0., 5., 0.), Dir3::Y);
+        assert!(raycast(&source, away).is_none());
+    }
+
+    #[test]
+    fn closest_point_on_cube() {
+        let source = MeshSourceData::from_mesh(&generate_cube_mesh(&mut CubeNormals::default()));
+        // Above a face, beyond an edge and beyond a corner
+        let cases = [
+            (vec3(0.1, 3., 0.2), vec3(0.1, 0.5, 0.2)),
+            (vec3(2., 2., 0.), vec3(0.5, 0.5, 0.)),
+            (vec3(2., -2., 2.), vec3(0.5, -0.5, 0.5)),
+        ];
+        for (point, expected) in cases {
+            let hit = closest_point(&source, point).unwrap();
+            assert!(hit.position.abs_diff_eq(expected, 1e-5), "{point}: {hit:?}");
+        }
+        let hit = closest_point(&source, vec3(0.1, 0.4, 0.)).unwrap();
+        assert!(hit.normal.abs_diff_eq(Vec3::Y, 1e-5), "{hit:?}");
+        assert!(closest_point(&MeshSourceData::default(), Vec3::ZERO).is_none());
+    }
+
+    #[test]
+    fn ray_hits_staff_surface() {
+        let config = StaffConfig::default();
+        let mesh = config.generate_mesh();
+        let source = MeshSourceData::from_mesh(&mesh);
+        // From outside towards the center of the middle ring, which wanders off the Y axis
+        let ring = config.segments as usize / 2 * (config.resolution as usize + 1);
+        let center = source.positions[ring..ring + config.resolution as usize]
+            .iter()
+            .sum::<Vec3>()
+            / config.resolution as f32;
+        let ray = Ray3d::new(center + Vec3::Z * 5., Dir3::NEG_Z);
+        let hit = raycast(&source, ray).unwrap();
+        assert!(
+            hit.position.z > center.z && hit.position.z < center.z + 0.2,
+            "{hit:?}"
+        );
+        assert!(hit.normal.z > 0., "{hit:?}");
+        assert_eq!(raycast_mesh(&mesh, ray), Some(hit));
+    }
+}
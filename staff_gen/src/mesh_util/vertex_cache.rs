@@ -0,0 +1,351 @@
+//! Triangle orders that make the most of the GPU's post-transform vertex cache, and the
+//! triangle strips some engines still prefer.
+
+use std::collections::{HashMap, VecDeque};
+
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+
+/// Entries in the cache [`acmr`] simulates, a common size for the FIFO caches of real GPUs
+pub const ACMR_CACHE_SIZE: usize = 16;
+
+/// Size of the least recently used cache Forsyth's scoring models
+const CACHE_SIZE: usize = 32;
+const CACHE_DECAY_POWER: f32 = 1.5;
+/// Score of the vertices of the last triangle. Lower than the vertex after them, so the next
+/// triangle doesn't just turn back on the same edge.
+const LAST_TRIANGLE_SCORE: f32 = 0.75;
+const VALENCE_BOOST_SCALE: f32 = 2.;
+const VALENCE_BOOST_POWER: f32 = 0.5;
+
+/// Average cache misses per triangle of a triangle list mesh, drawn through a FIFO cache of
+/// [`ACMR_CACHE_SIZE`] vertices. 3 is the worst, and a regular grid can't do much better than
+/// 0.5 even in the best order.
+pub fn acmr(mesh: &Mesh) -> f32 {
+    let indices: Vec<u32> = match mesh.indices() {
+        Some(indices) => indices.iter().map(|i| i as u32).collect(),
+        None => (0..mesh.count_vertices() as u32).collect(),
+    };
+    let triangles = indices.len() / 3;
+    if triangles == 0 {
+        return 0.;
+    }
+    let mut cache = VecDeque::with_capacity(ACMR_CACHE_SIZE + 1);
+    let mut misses = 0;
+    for &index in &indices[..triangles * 3] {
+        if !cache.contains(&index) {
+            misses += 1;
+            cache.push_back(index);
+            if cache.len() > ACMR_CACHE_SIZE {
+                cache.pop_front();
+            }
+        }
+    }
+    misses as f32 / triangles as f32
+}
+
+/// Reorders the triangles of an indexed triangle list mesh so neighbours are drawn one after
+/// another, with Tom Forsyth's linear-speed vertex cache optimisation. Triangles keep their
+/// winding and vertices aren't touched, so the mesh looks exactly the same.
+pub fn optimize_vertex_cache(mesh: &mut Mesh) {
+    optimize_vertex_cache_in(mesh, Vec::new());
+}
+
+/// [`optimize_vertex_cache`] writing the new order into `order`, see
+/// [`BufferPool`](crate::pool::BufferPool). Gives back the vector the old order was in, or
+/// `order` itself when the mesh is left as it is.
+pub fn optimize_vertex_cache_in(mesh: &mut Mesh, mut order: Vec<u32>) -> Vec<u32> {
+    let vertex_count = mesh.count_vertices();
+    let reorderable = mesh
+        .indices()
+        .is_some_and(|indices| indices.len() >= 3 && indices.iter().all(|i| i < vertex_count));
+    if !reorderable {
+        return order;
+    }
+    let indices = match mesh.remove_indices() {
+        Some(Indices::U32(indices)) => indices,
+        Some(indices) => indices.iter().map(|i| i as u32).collect(),
+        None => return order,
+    };
+    let triangles = indices.len() / 3;
+
+    // Triangles using each vertex, packed one vertex after another
+    let mut remaining = vec![0u32; vertex_count];
+    for &index in &indices[..triangles * 3] {
+        remaining[index as usize] += 1;
+    }
+    let mut offsets = Vec::with_capacity(vertex_count + 1);
+    offsets.push(0);
+    for &count in &remaining {
+        offsets.push(offsets[offsets.len() - 1] + count as usize);
+    }
+    let mut adjacency = vec![0u32; triangles * 3];
+    let mut filled = offsets.clone();
+    for triangle in 0..triangles {
+        for &vertex in &indices[triangle * 3..triangle * 3 + 3] {
+            adjacency[filled[vertex as usize]] = triangle as u32;
+            filled[vertex as usize] += 1;
+        }
+    }
+
+    let mut cache_position: Vec<Option<usize>> = vec![None; vertex_count];
+    let mut vertex_scores: Vec<f32> = remaining.iter().map(|&r| vertex_score(None, r)).collect();
+    let mut triangle_scores: Vec<f32> = (0..triangles)
+        .map(|t| {
+            indices[t * 3..t * 3 + 3]
+                .iter()
+                .map(|&v| vertex_scores[v as usize])
+                .sum()
+        })
+        .collect();
+    let mut emitted = vec![false; triangles];
+    order.clear();
+    order.reserve(triangles * 3);
+    let mut cache: Vec<u32> = Vec::with_capacity(CACHE_SIZE + 3);
+    // Where to look for a triangle to restart from once the cache has nothing left to draw
+    let mut cursor = 0;
+    let mut best = best_triangle(&triangle_scores, &emitted, 0..triangles);
+
+    while let Some(triangle) = best {
+        emitted[triangle] = true;
+        let corners = &indices[triangle * 3..triangle * 3 + 3];
+        order.extend_from_slice(corners);
+        for &vertex in corners {
+            let vertex = vertex as usize;
+            remaining[vertex] -= 1;
+            // Moves the drawn triangle to the end of the vertex's list, out of the live range
+            let start = offsets[vertex];
+            let live = start + remaining[vertex] as usize;
+            let slot = (start..=live)
+                .find(|&slot| adjacency[slot] == triangle as u32)
+                .expect("every triangle is listed by its vertices");
+            adjacency.swap(slot, live);
+        }
+
+        // The drawn triangle's vertices move to the front of the cache, pushing the rest back
+        let mut next_cache: Vec<u32> = corners.to_vec();
+        next_cache.extend(cache.iter().filter(|vertex| !corners.contains(vertex)));
+        for &vertex in next_cache.iter().skip(CACHE_SIZE) {
+            cache_position[vertex as usize] = None;
+        }
+        for (position, &vertex) in next_cache.iter().enumerate().take(CACHE_SIZE) {
+            cache_position[vertex as usize] = Some(position);
+        }
+
+        // Rescore everything whose cache position changed, and pick the best triangle that
+        // touches it
+        best = None;
+        let mut best_score = f32::NEG_INFINITY;
+        for &vertex in &next_cache {
+            let vertex = vertex as usize;
+            let score = vertex_score(cache_position[vertex], remaining[vertex]);
+            let change = score - vertex_scores[vertex];
+            vertex_scores[vertex] = score;
+            let live = offsets[vertex]..offsets[vertex] + remaining[vertex] as usize;
+            for &neighbour in &adjacency[live] {
+                triangle_scores[neighbour as usize] += change;
+            }
+        }
+        for &vertex in next_cache.iter().take(CACHE_SIZE) {
+            let vertex = vertex as usize;
+            let live = offsets[vertex]..offsets[vertex] + remaining[vertex] as usize;
+            for &neighbour in &adjacency[live] {
+                if triangle_scores[neighbour as usize] > best_score {
+                    best_score = triangle_scores[neighbour as usize];
+                    best = Some(neighbour as usize);
+                }
+            }
+        }
+        next_cache.truncate(CACHE_SIZE);
+        cache = next_cache;
+
+        if best.is_none() {
+            while cursor < triangles && emitted[cursor] {
+                cursor += 1;
+            }
+            best = best_triangle(&triangle_scores, &emitted, cursor..triangles);
+        }
+    }
+
+    // Indices past the last whole triangle are dropped, as they draw nothing
+    mesh.insert_indices(Indices::U32(order));
+    indices
+}
+
+/// Forsyth's score of a vertex at `cache_position` that `remaining` triangles still use.
+/// Vertices in the cache score higher the more recently they were used, and vertices with few
+/// triangles left get a boost, so lone triangles are finished off instead of left behind.
+fn vertex_score(cache_position: Option<usize>, remaining: u32) -> f32 {
+    if remaining == 0 {
+        return -1.;
+    }
+    let cache_score = match cache_position {
+        None => 0.,
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) => {
+            let scaler = 1. / (CACHE_SIZE - 3) as f32;
+            (1. - (position - 3) as f32 * scaler).powf(CACHE_DECAY_POWER)
+        }
+    };
+    cache_score + VALENCE_BOOST_SCALE * (remaining as f32).powf(-VALENCE_BOOST_POWER)
+}
+
+fn best_triangle(scores: &[f32], emitted: &[bool], range: std::ops::Range<usize>) -> Option<usize> {
+    range
+        .filter(|&triangle| !emitted[triangle])
+        .max_by(|&a, &b| scores[a].total_cmp(&scores[b]))
+}
+
+/// The triangles of an indexed triangle list mesh as a single triangle strip. Each run walks on
+/// across shared edges for as long as it can, and new runs start in the list's order, so running
+/// [`optimize_vertex_cache`] first keeps the strip cache friendly. Runs are stitched together
+/// with zero-area triangles, which the GPU skips. Vertices are shared with `mesh`.
+pub fn to_triangle_strip(mesh: &Mesh) -> Mesh {
+    let indices: Vec<u32> = match mesh.indices() {
+        Some(indices) => indices.iter().map(|i| i as u32).collect(),
+        None => (0..mesh.count_vertices() as u32).collect(),
+    };
+    let triangles: Vec<[u32; 3]> = indices
+        .chunks_exact(3)
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+        .collect();
+    // Triangles by the edges of their winding, so the triangle on the other side of an edge is
+    // found by reversing it
+    let mut edges: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for (index, &[a, b, c]) in triangles.iter().enumerate() {
+        for edge in [(a, b), (b, c), (c, a)] {
+            edges.entry(edge).or_default().push(index);
+        }
+    }
+    let mut drawn = vec![false; triangles.len()];
+    // The corner of an undrawn triangle opposite `edge`, in its winding
+    let next_across = |edge: (u32, u32), drawn: &[bool]| {
+        edges.get(&edge).and_then(|candidates| {
+            candidates.iter().find(|&&t| !drawn[t]).map(|&t| {
+                let [a, b, c] = triangles[t];
+                let opposite = [(a, b, c), (b, c, a), (c, a, b)]
+                    .into_iter()
+                    .find(|&(first, second, _)| (first, second) == edge)
+                    .map_or(c, |(_, _, opposite)| opposite);
+                (t, opposite)
+            })
+        })
+    };
+
+    let mut strip: Vec<u32> = Vec::with_capacity(indices.len());
+    for start in 0..triangles.len() {
+        if drawn[start] {
+            continue;
+        }
+        drawn[start] = true;
+        let [a, b, c] = triangles[start];
+        // Starts on the corner that lets the run carry on, if any does
+        let [a, b, c] = [[a, b, c], [b, c, a], [c, a, b]]
+            .into_iter()
+            .find(|&[_, b, c]| next_across((c, b), &drawn).is_some())
+            .unwrap_or([a, b, c]);
+        if let Some(&last) = strip.last() {
+            // Repeat the last corner and the next one, plus one more to start on an even
+            // triangle so the winding stays the same
+            strip.push(last);
+            strip.push(a);
+            if !strip.len().is_multiple_of(2) {
+                strip.push(a);
+            }
+        }
+        strip.extend_from_slice(&[a, b, c]);
+
+        // Every other triangle of a strip is drawn with its first two corners swapped, so it
+        // continues across the opposite edge
+        while let [.., p, q] = strip[..] {
+            let edge = if (strip.len() - 2).is_multiple_of(2) {
+                (p, q)
+            } else {
+                (q, p)
+            };
+            let Some((triangle, next)) = next_across(edge, &drawn) else {
+                break;
+            };
+            drawn[triangle] = true;
+            strip.push(next);
+        }
+    }
+
+    let mut strip_mesh = Mesh::new(PrimitiveTopology::TriangleStrip, mesh.asset_usage);
+    for (attribute, values) in mesh.attributes() {
+        strip_mesh.insert_attribute(*attribute, values.clone());
+    }
+    strip_mesh.with_inserted_indices(Indices::U32(strip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::staff::StaffConfig;
+
+    /// Triangles turned to start at their smallest index and sorted, so two orders of the same
+    /// triangles are equal.
+    fn canonical(triangles: impl Iterator<Item = [u32; 3]>) -> Vec<[u32; 3]> {
+        let mut triangles: Vec<[u32; 3]> = triangles
+            .map(|[a, b, c]| {
+                let rotations = [[a, b, c], [b, c, a], [c, a, b]];
+                rotations.into_iter().min().unwrap_or_default()
+            })
+            .collect();
+        triangles.sort_unstable();
+        triangles
+    }
+
+    fn list_triangles(mesh: &Mesh) -> Vec<[u32; 3]> {
+        let indices: Vec<u32> = mesh.indices().unwrap().iter().map(|i| i as u32).collect();
+        canonical(indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]))
+    }
+
+    /// A staff with its triangles shuffled, as a worst case for the cache.
+    fn shuffled_staff() -> Mesh {
+        let mut mesh = StaffConfig::default().generate_mesh();
+        let indices: Vec<u32> = mesh.indices().unwrap().iter().map(|i| i as u32).collect();
+        let mut triangles: Vec<&[u32]> = indices.chunks_exact(3).collect();
+        let count = triangles.len();
+        for i in 0..count {
+            triangles.swap(i, (i * 7919 + 13) % count);
+        }
+        mesh.insert_indices(Indices::U32(triangles.concat()));
+        mesh
+    }
+
+    #[test]
+    fn reordering_keeps_every_triangle() {
+        let mut mesh = shuffled_staff();
+        let before = acmr(&mesh);
+        let triangles = list_triangles(&mesh);
+        optimize_vertex_cache(&mut mesh);
+        assert_eq!(list_triangles(&mesh), triangles);
+        let after = acmr(&mesh);
+        assert!(after < before * 0.75, "{before} -> {after}");
+        assert!(after <= 1., "{after}");
+    }
+
+    #[test]
+    fn strips_draw_the_same_triangles() {
+        let mut mesh = StaffConfig::default().generate_mesh();
+        optimize_vertex_cache(&mut mesh);
+        let strip = to_triangle_strip(&mesh);
+        assert_eq!(strip.primitive_topology(), PrimitiveTopology::TriangleStrip);
+        let indices: Vec<u32> = strip.indices().unwrap().iter().map(|i| i as u32).collect();
+        let drawn = indices
+            .windows(3)
+            .enumerate()
+            .map(|(k, t)| {
+                if k.is_multiple_of(2) {
+                    [t[0], t[1], t[2]]
+                } else {
+                    [t[1], t[0], t[2]]
+                }
+            })
+            .filter(|[a, b, c]| a != b && b != c && c != a);
+        assert_eq!(canonical(drawn), list_triangles(&mesh));
+        // Fewer indices than the list, or strips wouldn't be worth it
+        assert!(indices.len() < mesh.indices().unwrap().len());
+    }
+}
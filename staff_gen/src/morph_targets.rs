@@ -0,0 +1,169 @@
+//! Staff variants on one shared topology, blended at runtime as Bevy morph targets.
+
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::morph::{MorphAttributes, MorphBuildError, MorphTargetImage};
+use bevy::prelude::*;
+
+use crate::error::GenError;
+use crate::mesh_util::positions;
+use crate::staff::StaffConfig;
+
+/// A base staff mesh and the per vertex offsets towards each named variant.
+#[derive(Clone)]
+pub struct StaffMorphTargets {
+    pub base: Mesh,
+    pub names: Vec<String>,
+    /// One offset per base vertex for every target, in the order of `names`
+    pub targets: Vec<Vec<MorphAttributes>>,
+}
+
+impl StaffMorphTargets {
+    /// Generates `base` and the offsets to every target. Targets are generated with the base's
+    /// seed, so every ring wanders in the same direction and only by a different amount.
+    /// Returns `None` when a target has a different `resolution` or `segments`, as its
+    /// vertices wouldn't line up with the base.
+    pub fn new(base: &StaffConfig, targets: &[(&str, StaffConfig)]) -> Option<Self> {
+        let base_mesh = base.generate_mesh();
+        let base_positions = positions(&base_mesh);
+        let base_normals = normals(&base_mesh);
+
+        let mut names = Vec::with_capacity(targets.len());
+        let mut offsets = Vec::with_capacity(targets.len());
+        for (name, target) in targets {
+            if target.resolution != base.resolution || target.segments != base.segments {
+                return None;
+            }
+            let mesh = StaffConfig {
+                seed: base.seed,
+                ..target.clone()
+            }
+            .generate_mesh();
+            let attributes = positions(&mesh)
+                .iter()
+                .zip(base_positions.iter())
+                .zip(normals(&mesh).iter().zip(base_normals.iter()))
+                .map(|((position, base_position), (normal, base_normal))| {
+                    MorphAttributes::new(
+                        Vec3::from(*position) - Vec3::from(*base_position),
+                        *normal - *base_normal,
+                        Vec3::ZERO,
+                    )
+                })
+                .collect();
+            names.push(name.to_string());
+            offsets.push(attributes);
+        }
+        Some(Self {
+            base: base_mesh,
+            names,
+            targets: offsets,
+        })
+    }
+
+    /// Blends from a perfectly straight version of `config` to a "gnarled" one that wanders
+    /// three times as far.
+    pub fn straight_and_gnarled(config: &StaffConfig) -> Self {
+        let straight = StaffConfig {
+            horizontal_variance: 0.,
+            ..config.clone()
+        };
+        let gnarled = StaffConfig {
+            horizontal_variance: config.horizontal_variance.max(config.height * 0.05) * 3.,
+            ..config.clone()
+        };
+        Self::new(&straight, &[("gnarled", gnarled)]).expect("both share the base topology")
+    }
+
+    /// [`Self::straight_and_gnarled`], unless [`StaffConfig::validate`] finds a parameter it
+    /// can't build.
+    pub fn try_straight_and_gnarled(config: &StaffConfig) -> Result<Self, GenError> {
+        config.validate()?;
+        Ok(Self::straight_and_gnarled(config))
+    }
+
+    /// Texture holding every target, for [`Self::into_mesh`].
+    pub fn image(&self) -> Result<MorphTargetImage, MorphBuildError> {
+        MorphTargetImage::new(
+            self.targets.iter().map(|target| target.iter().copied()),
+            self.base.count_vertices(),
+            RenderAssetUsages::RENDER_WORLD,
+        )
+    }
+
+    /// The base mesh with `morph_targets`, the handle of [`Self::image`], and target names set.
+    pub fn into_mesh(self, morph_targets: Handle<Image>) -> Mesh {
+        let mut mesh = self.base.with_morph_targets(morph_targets);
+        mesh.set_morph_target_names(self.names);
+        mesh
+    }
+
+    /// Positions with every target applied at its weight, as the GPU would blend them.
+    pub fn blended_positions(&self, weights: &[f32]) -> Vec<Vec3> {
+        let mut blended: Vec<Vec3> = positions(&self.base)
+            .iter()
+            .copied()
+            .map(Vec3::from)
+            .collect();
+        for (target, weight) in self.targets.iter().zip(weights) {
+            for (position, offset) in blended.iter_mut().zip(target) {
+                *position += offset.position * *weight;
+            }
+        }
+        blended
+    }
+}
+
+fn normals(mesh: &Mesh) -> Vec<Vec3> {
+    mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+        .and_then(|normals| normals.as_float3())
+        .map_or_else(
+            || vec![Vec3::ZERO; mesh.count_vertices()],
+            |normals| normals.iter().copied().map(Vec3::from).collect(),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_weight_matches_the_target_mesh() {
+        let config = StaffConfig::default();
+        let morph = StaffMorphTargets::straight_and_gnarled(&config);
+        let gnarled = StaffConfig {
+            horizontal_variance: config.horizontal_variance * 3.,
+            ..config.clone()
+        }
+        .generate_mesh();
+
+        let straight = morph.blended_positions(&[0.]);
+        assert!(straight.iter().all(|p| p.x.abs() <= config.radius + 1e-6));
+        for (blended, expected) in morph
+            .blended_positions(&[1.])
+            .iter()
+            .zip(positions(&gnarled))
+        {
+            assert!(
+                blended.abs_diff_eq(Vec3::from(*expected), 1e-5),
+                "{blended}"
+            );
+        }
+    }
+
+    #[test]
+    fn targets_need_the_base_topology() {
+        let config = StaffConfig::default();
+        let finer = StaffConfig {
+            segments: config.segments * 2,
+            ..config.clone()
+        };
+        assert!(StaffMorphTargets::new(&config, &[("finer", finer)]).is_none());
+    }
+
+    #[test]
+    fn image_holds_every_target() {
+        let morph = StaffMorphTargets::straight_and_gnarled(&StaffConfig::default());
+        let image = morph.image().unwrap();
+        assert_eq!(image.0.texture_descriptor.size.depth_or_array_layers, 1);
+    }
+}
@@ -0,0 +1,150 @@
+use rand::SeedableRng;
+use rand::seq::IndexedRandom;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::staff::StaffConfig;
+
+const WOODS: [&str; 10] = [
+    "Oak",
+    "Ash",
+    "Yew",
+    "Rowan",
+    "Willow",
+    "Hawthorn",
+    "Elder",
+    "Birch",
+    "Blackthorn",
+    "Ironwood",
+];
+const ORIGINS: [&str; 12] = [
+    "Embers",
+    "the Tides",
+    "Whispers",
+    "the North Wind",
+    "Ashes",
+    "the Deep Wood",
+    "Dusk",
+    "the Old Kings",
+    "Thunder",
+    "Frost",
+    "the Wanderer",
+    "Starlight",
+];
+
+/// The words staff names are made of, so they can be translated. Defaults to English.
+/// Lists as long as the English ones keep each seed's pick at the same position.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct NameWords {
+    /// Where the words go, with `{shape}`, `{wood}` and `{origin}` standing in for them
+    pub template: String,
+    pub shapes: ShapeWords,
+    pub woods: Vec<String>,
+    pub origins: Vec<String>,
+}
+
+impl Default for NameWords {
+    fn default() -> Self {
+        Self {
+            template: "{shape} {wood} Staff of {origin}".into(),
+            shapes: ShapeWords::default(),
+            woods: WOODS.map(String::from).into(),
+            origins: ORIGINS.map(String::from).into(),
+        }
+    }
+}
+
+/// Adjectives describing a staff's shape, from most to least crooked.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ShapeWords {
+    pub gnarled: String,
+    pub twisted: String,
+    pub slender: String,
+    pub stout: String,
+    pub straight: String,
+}
+
+impl Default for ShapeWords {
+    fn default() -> Self {
+        Self {
+            gnarled: "Gnarled".into(),
+            twisted: "Twisted".into(),
+            slender: "Slender".into(),
+            stout: "Stout".into(),
+            straight: "Straight".into(),
+        }
+    }
+}
+
+impl NameWords {
+    /// Fills in the template for `config`, like [`staff_name`] does in English.
+    pub fn staff_name(&self, config: &StaffConfig) -> String {
+        let mut rand = ChaCha8Rng::seed_from_u64(config.seed);
+        let wood = self.woods.choose(&mut rand).map_or("", String::as_str);
+        let origin = self.origins.choose(&mut rand).map_or("", String::as_str);
+        self.template
+            .replace("{shape}", shape_adjective(config, &self.shapes))
+            .replace("{wood}", wood)
+            .replace("{origin}", origin)
+    }
+}
+
+/// A name like "Gnarled Oak Staff of Embers".
+/// The adjective describes the shape, the wood and origin are picked by the seed,
+/// so the same config always gets the same name.
+pub fn staff_name(config: &StaffConfig) -> String {
+    NameWords::default().staff_name(config)
+}
+
+fn shape_adjective<'a>(config: &StaffConfig, shapes: &'a ShapeWords) -> &'a str {
+    let crookedness = config.horizontal_variance / config.height;
+    let slenderness = config.height / (config.radius * 2.);
+    if crookedness > 0.08 {
+        &shapes.gnarled
+    } else if crookedness > 0.04 {
+        &shapes.twisted
+    } else if slenderness > 30. {
+        &shapes.slender
+    } else if slenderness < 12. {
+        &shapes.stout
+    } else {
+        &shapes.straight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_is_deterministic_for_config() {
+        let config = StaffConfig::default();
+        assert_eq!(staff_name(&config), staff_name(&config.clone()));
+    }
+
+    #[test]
+    fn name_describes_shape() {
+        let crooked = StaffConfig {
+            horizontal_variance: 0.5,
+            ..StaffConfig::default()
+        };
+        assert!(staff_name(&crooked).starts_with("Gnarled "));
+        assert!(staff_name(&crooked).contains(" Staff of "));
+    }
+
+    #[test]
+    fn translated_words_follow_their_template() {
+        let words = NameWords {
+            template: "{wood}stab {origin}".into(),
+            woods: vec!["Eichen".into()],
+            origins: vec!["der Glut".into()],
+            ..NameWords::default()
+        };
+        assert_eq!(
+            words.staff_name(&StaffConfig::default()),
+            "Eichenstab der Glut"
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! An orb held above the staff by claws bent around it.
+
+use std::f32::consts::TAU;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::head::{Head, HeadFrame, HeadGenerator};
+use crate::mesh_util::unit_circle;
+use crate::sockets::{self, Sockets};
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct OrbConfig {
+    pub radius: f32,
+    /// How far the orb hovers above the claws, 0 to have them clasp it
+    pub float: f32,
+    /// A faceted icosphere instead of a smooth sphere
+    pub gem: bool,
+    /// 3 to 5 hold an orb best
+    pub claws: u32,
+    pub claw_thickness: f32,
+    /// How far the claws bow out past the orb, relative to its radius
+    pub bend: f32,
+    /// Latitude the claw tips reach on the orb, in radians above its equator
+    pub grip: f32,
+    /// Thickness of the claw tips, relative to their base
+    pub taper: f32,
+    /// Direction of the first claw, in radians around Y from +X
+    pub heading: f32,
+    pub resolution: u32,
+    pub steps: u32,
+}
+
+impl Default for OrbConfig {
+    fn default() -> Self {
+        Self {
+            radius: 0.08,
+            float: 0.,
+            gem: false,
+            claws: 4,
+            claw_thickness: 0.012,
+            bend: 0.4,
+            grip: 0.6,
+            taper: 0.4,
+            heading: 0.,
+            resolution: 6,
+            steps: 12,
+        }
+    }
+}
+
+impl OrbConfig {
+    /// Center of the orb when the claws clasp it, relative to the center of the top ring.
+    fn clasp_offset(&self) -> Vec3 {
+        Vec3::Y * (self.radius + self.claw_thickness)
+    }
+
+    /// The orb centered on the origin.
+    pub fn generate_orb_mesh(&self) -> Mesh {
+        let sphere = Sphere::new(self.radius);
+        if self.gem {
+            sphere
+                .mesh()
+                .ico(1)
+                .expect("one subdivision is within the icosphere's limits")
+                .with_duplicated_vertices()
+                .with_computed_flat_normals()
+        } else {
+            sphere.mesh().uv(24, 16)
+        }
+    }
+
+    /// Every claw as one mesh, relative to the center of the top ring.
+    pub fn generate_claws_mesh(&self, ring_radius: f32) -> Mesh {
+        let clasp = self.clasp_offset();
+        let paths: Vec<_> = (0..self.claws)
+            .map(|claw| {
+                let angle = self.heading + claw as f32 * TAU / self.claws as f32;
+                let outward = vec3(angle.cos(), 0., angle.sin());
+                // Sunk into the staff's top so the claw grows out of it
+                let base = outward * ring_radius * 0.5 - Vec3::Y * self.claw_thickness;
+                let bow =
+                    clasp - Vec3::Y * self.radius * 0.3 + outward * self.radius * (1. + self.bend);
+                let tip = clasp
+                    + (outward * self.grip.cos() + Vec3::Y * self.grip.sin())
+                        * (self.radius + self.claw_thickness * self.taper);
+                (outward, [base, bow, tip])
+            })
+            .collect();
+        generate_claws_mesh(
+            &paths,
+            self.claw_thickness,
+            self.taper,
+            self.resolution,
+            self.steps,
+        )
+    }
+}
+
+/// The orb floats [`OrbConfig::float`] above where the claws would clasp it, and
+/// [`sockets::TOP`] moves to its top.
+impl HeadGenerator for OrbConfig {
+    fn generate(&self, frame: &HeadFrame) -> Head {
+        let center = frame.center() + self.clasp_offset() + Vec3::Y * self.float;
+        let mut sockets = Sockets::default();
+        sockets.insert(
+            sockets::TOP,
+            Transform::from_translation(center + Vec3::Y * self.radius),
+        );
+        Head {
+            gem: Some(self.generate_orb_mesh().translated_by(center)),
+            trim: (self.claws > 0).then(|| {
+                self.generate_claws_mesh(frame.ring.radius)
+                    .translated_by(frame.center())
+            }),
+            sockets,
+            ..default()
+        }
+    }
+}
+
+/// Sweeps a closed tube of `thickness` tapering by `taper` along each quadratic Bézier curve in
+/// `paths`, given with the horizontal direction its claw bends towards.
+pub fn generate_claws_mesh(
+    paths: &[(Vec3, [Vec3; 3])],
+    thickness: f32,
+    taper: f32,
+    resolution: u32,
+    steps: u32,
+) -> Mesh {
+    let steps = steps.max(1);
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+
+    let circle = unit_circle(resolution);
+
+    for &(outward, [base, bow, tip]) in paths {
+        // Perpendicular to the plane the claw bends in
+        let side = Vec3::Y.cross(outward);
+        let frame = |t: f32| {
+            let point = base * (1. - t).powi(2) + bow * 2. * (1. - t) * t + tip * t.powi(2);
+            let tangent = ((bow - base) * (1. - t) + (tip - bow) * t).normalize();
+            (point, tangent, side.cross(tangent))
+        };
+
+        // tube
+
+        let offset = positions.len() as u32;
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let (point, _, up) = frame(t);
+            let radius = thickness * 1f32.lerp(taper, t);
+            for (segment, &(sin, cos)) in circle.iter().enumerate() {
+                let normal = side * cos + up * sin;
+                positions.push((point + normal * radius).to_array());
+                normals.push(normal.to_array());
+                uvs.push([segment as f32 / resolution as f32, t]);
+            }
+        }
+        for i in 0..steps {
+            let ring = offset + i * (resolution + 1);
+            let next_ring = offset + (i + 1) * (resolution + 1);
+            for j in 0..resolution {
+                indices.extend_from_slice(&[
+                    ring + j,
+                    next_ring + j,
+                    ring + j + 1,
+                    next_ring + j,
+                    next_ring + j + 1,
+                    ring + j + 1,
+                ]);
+            }
+        }
+
+        // base and tip caps
+
+        for (t, radius) in [(0., thickness), (1., thickness * taper)] {
+            let (point, tangent, up) = frame(t);
+            let normal = if t == 0. { -tangent } else { tangent };
+            let offset = positions.len() as u32;
+            for i in 0..resolution {
+                let (sin, cos) = circle[i as usize];
+                positions.push((point + (side * cos + up * sin) * radius).to_array());
+                normals.push(normal.to_array());
+                uvs.push([0.5 * (cos + 1.), 0.5 * (sin + 1.)]);
+            }
+            for i in 1..(resolution - 1) {
+                if t == 0. {
+                    indices.extend_from_slice(&[offset, offset + i, offset + i + 1]);
+                } else {
+                    indices.extend_from_slice(&[offset, offset + i + 1, offset + i]);
+                }
+            }
+        }
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_indices(Indices::U32(indices))
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_util::{assert_closed_and_outward, positions};
+
+    #[test]
+    fn claws_are_closed_and_face_outwards() {
+        for claws in [3, 5] {
+            let mesh = OrbConfig { claws, ..default() }.generate_claws_mesh(0.05);
+            assert_closed_and_outward(&mesh);
+        }
+    }
+
+    #[test]
+    fn claws_clasp_the_orb_until_it_floats() {
+        let config = OrbConfig::default();
+        let clasp = config.clasp_offset();
+        // The claw tips close over the orb, touching its surface
+        let closest = positions(&config.generate_claws_mesh(0.05))
+            .iter()
+            .map(|&p| Vec3::from(p).distance(clasp))
+            .fold(f32::MAX, f32::min);
+        assert!(
+            (closest - config.radius).abs() < config.claw_thickness,
+            "{closest}"
+        );
+
+        let floating = OrbConfig {
+            float: 0.1,
+            gem: true,
+            ..config
+        };
+        let frame = HeadFrame {
+            ring: crate::staff::StaffRing {
+                radius: 0.05,
+                offset: Vec2::ZERO,
+                y: 1.,
+            },
+            resolution: 6,
+        };
+        let head = floating.generate(&frame);
+        let top = head.sockets.get(sockets::TOP).unwrap().translation;
+        assert!(
+            top.abs_diff_eq(
+                frame.center() + clasp + Vec3::Y * (0.1 + floating.radius),
+                1e-6
+            ),
+            "{top}"
+        );
+        assert!(head.trim.is_some() && head.mesh.is_none());
+    }
+}
@@ -0,0 +1,204 @@
+//! A stone stand to show a staff on: a plinth, a column and a cap, each with chamfered edges.
+
+use std::f32::consts::{FRAC_PI_4, SQRT_2};
+
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::mesh_util::unit_circle;
+use crate::sockets::{self, Sockets};
+use crate::staff::StaffConfig;
+
+/// Width and height of each tier stacked from the bottom up, relative to the whole pedestal
+const TIERS: [(f32, f32); 5] = [
+    (1., 0.15),
+    (0.8, 0.07),
+    (0.55, 0.56),
+    (0.8, 0.07),
+    (0.9, 0.15),
+];
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PedestalShape {
+    #[default]
+    Round,
+    Square,
+}
+
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Component, Default, Serialize, Deserialize)]
+pub struct PedestalConfig {
+    pub height: f32,
+    /// Width of the plinth at the bottom, the widest tier
+    pub width: f32,
+    pub shape: PedestalShape,
+    /// How much of each tier's edges is bevelled off, relative to the width
+    pub chamfer: f32,
+    /// Sides of round tiers. Square tiers always have 4.
+    pub resolution: u32,
+}
+
+impl Default for PedestalConfig {
+    fn default() -> Self {
+        Self {
+            height: 0.5,
+            width: 0.3,
+            shape: PedestalShape::default(),
+            chamfer: 0.04,
+            resolution: 24,
+        }
+    }
+}
+
+impl PedestalConfig {
+    pub fn generate_mesh(&self) -> Mesh {
+        generate_pedestal_mesh(
+            self.height,
+            self.width,
+            self.shape,
+            self.chamfer,
+            self.resolution,
+        )
+    }
+
+    /// [`sockets::TOP`] is the middle of the cap, where a staff stands.
+    pub fn sockets(&self) -> Sockets {
+        Sockets::upright(self.height)
+    }
+
+    /// Where `staff` stands upright on top, with its [`sockets::BOTTOM`] on the pedestal's
+    /// [`sockets::TOP`], relative to the pedestal. It's only ever a translation.
+    pub fn staff_transform(&self, staff: &StaffConfig) -> Transform {
+        let top = self.sockets().get(sockets::TOP).unwrap_or_default();
+        let bottom = staff.sockets().get(sockets::BOTTOM).unwrap_or_default();
+        Transform::from_translation(top.translation - bottom.translation)
+    }
+}
+
+/// A pedestal standing on `y = -height / 2`, with flat shading so the chamfers catch the light.
+pub fn generate_pedestal_mesh(
+    height: f32,
+    width: f32,
+    shape: PedestalShape,
+    chamfer: f32,
+    resolution: u32,
+) -> Mesh {
+    // A square is a circle of 4 sides turned by 45°, reaching out to the corners
+    let (sides, corner, turn) = match shape {
+        PedestalShape::Round => (resolution.max(3), 1., 0.),
+        PedestalShape::Square => (4, SQRT_2, FRAC_PI_4),
+    };
+    let circle = unit_circle(sides);
+    let rotation = Vec2::from_angle(turn);
+
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    let mut y = -height / 2.;
+    for (tier_width, tier_height) in TIERS {
+        let radius = width * tier_width / 2.;
+        let tier_height = height * tier_height;
+        let bevel = (width * chamfer).min(tier_height / 2.).min(radius / 2.);
+        // Outline of the tier's side, from the bottom edge up
+        let outline = [
+            (radius - bevel, y),
+            (radius, y + bevel),
+            (radius, y + tier_height - bevel),
+            (radius - bevel, y + tier_height),
+        ];
+
+        let first_ring = positions.len() as u32;
+        for (ring_radius, ring_y) in outline {
+            for (i, &(sin, cos)) in circle.iter().enumerate() {
+                let around = rotation.rotate(vec2(cos, sin)) * ring_radius * corner;
+                positions.push([around.x, ring_y, around.y]);
+                uvs.push([i as f32 / sides as f32, (ring_y + height / 2.) / height]);
+            }
+        }
+        let ring_size = sides + 1;
+        for ring in 0..outline.len() as u32 - 1 {
+            let lower = first_ring + ring * ring_size;
+            let upper = lower + ring_size;
+            for j in 0..sides {
+                indices.extend_from_slice(&[
+                    lower + j,
+                    upper + j,
+                    lower + j + 1,
+                    upper + j,
+                    upper + j + 1,
+                    lower + j + 1,
+                ]);
+            }
+        }
+
+        // Caps, fanned from the middle
+        for (ring, cap_y, up) in [(0, y, false), (3, y + tier_height, true)] {
+            let center = positions.len() as u32;
+            positions.push([0., cap_y, 0.]);
+            uvs.push([0.5, 0.5]);
+            let ring = first_ring + ring * ring_size;
+            for j in 0..sides {
+                let (a, b) = (ring + j, ring + j + 1);
+                if up {
+                    indices.extend_from_slice(&[center, b, a]);
+                } else {
+                    indices.extend_from_slice(&[center, a, b]);
+                }
+            }
+        }
+        y += tier_height;
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_indices(Indices::U32(indices))
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_duplicated_vertices()
+    .with_computed_flat_normals()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_util::{positions, triangles};
+
+    #[test]
+    fn pedestal_fills_its_bounds_and_faces_outwards() {
+        for shape in [PedestalShape::Round, PedestalShape::Square] {
+            let config = PedestalConfig { shape, ..default() };
+            let mesh = config.generate_mesh();
+            let half = vec3(config.width / 2., config.height / 2., config.width / 2.);
+            let (mut min, mut max) = (Vec3::MAX, Vec3::MIN);
+            for &position in positions(&mesh) {
+                min = min.min(position.into());
+                max = max.max(position.into());
+            }
+            assert!(max.abs_diff_eq(half, 1e-5), "{shape:?} {max}");
+            assert!(min.abs_diff_eq(-half, 1e-5), "{shape:?} {min}");
+
+            // Every face points away from the pedestal's axis or straight up or down
+            for [a, b, c] in triangles(&mesh) {
+                let normal = (b - a).cross(c - a);
+                let center = (a + b + c) / 3.;
+                let outward = center.xz().dot(normal.xz()) > 0.;
+                assert!(outward || normal.xz().length() < 1e-6, "{shape:?} {center}");
+            }
+        }
+    }
+
+    #[test]
+    fn staff_stands_on_top() {
+        let pedestal = PedestalConfig::default();
+        let staff = StaffConfig::default();
+        let transform = pedestal.staff_transform(&staff);
+        let bottom = staff.sockets().get(sockets::BOTTOM).unwrap().translation;
+        let foot = transform.transform_point(bottom);
+        assert!(foot.abs_diff_eq(Vec3::Y * pedestal.height / 2., 1e-6));
+    }
+}
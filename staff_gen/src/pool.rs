@@ -0,0 +1,161 @@
+//! Scratch buffers for generators, so a mesh rebuilt every frame reuses the vertex and index
+//! vectors of the mesh it replaces instead of allocating new ones.
+
+use bevy::mesh::{Indices, VertexAttributeValues};
+use bevy::prelude::*;
+
+use crate::mesh_util::{compact_indices_in, optimize_vertex_cache_in};
+
+/// Most vectors of each kind kept for reuse, enough for a few objects rebuilt every frame
+const MAX_POOLED: usize = 8;
+
+/// Vectors taken back from meshes that were replaced, handed out again by [`Self::take`].
+#[derive(Resource, Debug, Default)]
+pub struct BufferPool {
+    positions: Vec<Vec<[f32; 3]>>,
+    normals: Vec<Vec<[f32; 3]>>,
+    uvs: Vec<Vec<[f32; 2]>>,
+    indices: Vec<Vec<u32>>,
+    compact_indices: Vec<Vec<u16>>,
+}
+
+impl BufferPool {
+    /// Empty buffers for the next mesh, with the room of the last ones recycled.
+    pub fn take(&mut self) -> MeshBuffers {
+        MeshBuffers {
+            positions: self.positions.pop().unwrap_or_default(),
+            normals: self.normals.pop().unwrap_or_default(),
+            uvs: self.uvs.pop().unwrap_or_default(),
+            indices: self.indices.pop().unwrap_or_default(),
+        }
+    }
+
+    /// Keeps the position, normal, UV and index vectors of a mesh that's no longer needed.
+    /// Other attributes are dropped.
+    pub fn recycle(&mut self, mut mesh: Mesh) {
+        if let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.remove_attribute(Mesh::ATTRIBUTE_POSITION)
+        {
+            keep(&mut self.positions, positions);
+        }
+        if let Some(VertexAttributeValues::Float32x3(normals)) =
+            mesh.remove_attribute(Mesh::ATTRIBUTE_NORMAL)
+        {
+            keep(&mut self.normals, normals);
+        }
+        if let Some(VertexAttributeValues::Float32x2(uvs)) =
+            mesh.remove_attribute(Mesh::ATTRIBUTE_UV_0)
+        {
+            keep(&mut self.uvs, uvs);
+        }
+        match mesh.remove_indices() {
+            Some(Indices::U32(indices)) => keep(&mut self.indices, indices),
+            Some(Indices::U16(indices)) => keep(&mut self.compact_indices, indices),
+            None => {}
+        }
+    }
+
+    /// [`optimize_vertex_cache`](crate::mesh_util::optimize_vertex_cache) reordering into a
+    /// pooled vector, keeping the one the old order was in.
+    pub fn optimize_vertex_cache(&mut self, mesh: &mut Mesh) {
+        let order = self.indices.pop().unwrap_or_default();
+        let old = optimize_vertex_cache_in(mesh, order);
+        keep(&mut self.indices, old);
+    }
+
+    /// [`compact_indices`](crate::mesh_util::compact_indices) narrowing into a pooled vector,
+    /// keeping the `u32` one it replaces.
+    pub fn compact_indices(&mut self, mesh: &mut Mesh) {
+        let mut compact = self.compact_indices.pop().unwrap_or_default();
+        match compact_indices_in(mesh, &mut compact) {
+            Some(wide) => keep(&mut self.indices, wide),
+            None => keep(&mut self.compact_indices, compact),
+        }
+    }
+}
+
+fn keep<T>(pooled: &mut Vec<Vec<T>>, mut buffer: Vec<T>) {
+    if pooled.len() < MAX_POOLED && buffer.capacity() > 0 {
+        buffer.clear();
+        pooled.push(buffer);
+    }
+}
+
+/// Empty vectors for a generator to build a mesh in, from [`BufferPool::take`]. Generators
+/// without a `_in` variant allocate their own.
+#[derive(Debug, Default)]
+pub struct MeshBuffers {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_util::{compact_indices, compare, optimize_vertex_cache};
+    use crate::staff::StaffConfig;
+
+    fn positions_address(mesh: &Mesh) -> *const [f32; 3] {
+        match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float32x3(positions)) => positions.as_ptr(),
+            _ => panic!("missing positions"),
+        }
+    }
+
+    #[test]
+    fn rebuilds_reuse_the_replaced_mesh() {
+        let config = StaffConfig::default();
+        let mut pool = BufferPool::default();
+        let first = config.generate_mesh_in(pool.take());
+        let address = positions_address(&first);
+        pool.recycle(first);
+        let second = config.generate_mesh_in(pool.take());
+        assert_eq!(positions_address(&second), address);
+        assert!(compare(&second, &config.generate_mesh()).is_identical());
+    }
+
+    fn indices_address(mesh: &Mesh) -> *const u8 {
+        match mesh.indices() {
+            Some(Indices::U32(indices)) => indices.as_ptr().cast(),
+            Some(Indices::U16(indices)) => indices.as_ptr().cast(),
+            None => panic!("missing indices"),
+        }
+    }
+
+    #[test]
+    fn optimized_rebuilds_reuse_the_index_buffers() {
+        let config = StaffConfig::default();
+        let mut pool = BufferPool::default();
+        let build = |pool: &mut BufferPool| {
+            let mut mesh = config.generate_mesh_in(pool.take());
+            pool.optimize_vertex_cache(&mut mesh);
+            pool.compact_indices(&mut mesh);
+            mesh
+        };
+        let first = build(&mut pool);
+        let address = indices_address(&first);
+        pool.recycle(first);
+        let second = build(&mut pool);
+        assert!(matches!(second.indices(), Some(Indices::U16(_))));
+        assert_eq!(indices_address(&second), address);
+        let mut expected = config.generate_mesh();
+        optimize_vertex_cache(&mut expected);
+        compact_indices(&mut expected);
+        assert!(compare(&second, &expected).is_identical());
+    }
+
+    #[test]
+    fn pool_is_bounded() {
+        let mut pool = BufferPool::default();
+        for _ in 0..MAX_POOLED * 2 {
+            pool.recycle(StaffConfig::default().generate_mesh());
+        }
+        assert_eq!(pool.positions.len(), MAX_POOLED);
+        // Only what was recycled comes back
+        assert!(pool.take().uvs.capacity() > 0);
+        let mut empty = BufferPool::default();
+        assert_eq!(empty.take().positions.capacity(), 0);
+    }
+}
@@ -0,0 +1,350 @@
+//! "I'm feeling lucky" for staffs: every parameter is picked at random, not just the seed, but
+//! within bounds that keep the parameters plausible together. Thickness follows the length, the
+//! shaft never wobbles wider than it is thick and heads are sized to the shaft they sit on.
+
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::crook::CrookConfig;
+use crate::crystal::CrystalConfig;
+use crate::fork::ForkConfig;
+use crate::head::HeadStyle;
+use crate::orb::OrbConfig;
+use crate::staff::StaffConfig;
+
+/// How likely each head style is to be picked, relative to each other. Custom heads are never
+/// picked, since the randomizer can't know which generators are registered.
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct HeadWeights {
+    pub plain: f32,
+    pub crystal: f32,
+    pub crook: f32,
+    pub forked: f32,
+    pub orb: f32,
+}
+
+impl Default for HeadWeights {
+    fn default() -> Self {
+        Self {
+            plain: 3.,
+            crystal: 2.,
+            crook: 2.,
+            forked: 1.,
+            orb: 1.,
+        }
+    }
+}
+
+/// Bounds for [`StaffRandomizer::randomize`]. Lengths are in meters.
+#[derive(Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct StaffRandomizer {
+    pub height: (f32, f32),
+    /// Radius as a fraction of the height, so long staffs come out thicker
+    pub thickness: (f32, f32),
+    /// Largest radial variance as a fraction of the radius
+    pub radial_variance: f32,
+    /// Largest horizontal variance as a fraction of the height
+    pub horizontal_variance: f32,
+    pub resolution: (u32, u32),
+    pub segments: (u32, u32),
+    pub heads: HeadWeights,
+}
+
+impl Default for StaffRandomizer {
+    fn default() -> Self {
+        Self {
+            height: (1.2, 2.4),
+            thickness: (0.018, 0.035),
+            radial_variance: 0.6,
+            horizontal_variance: 0.1,
+            resolution: (5, 10),
+            segments: (2, 10),
+            heads: HeadWeights::default(),
+        }
+    }
+}
+
+impl StaffRandomizer {
+    /// A new staff with every field of `current` named in `locked` kept as it is. The free
+    /// fields are still picked to go with the locked ones, e.g. a locked radius draws a height
+    /// it's plausible for.
+    pub fn randomize(
+        &self,
+        current: &StaffConfig,
+        locked: &HashSet<String>,
+        rand: &mut impl Rng,
+    ) -> StaffConfig {
+        let free = |field: &str| !locked.contains(field);
+        let thickness = rand.random_range(span(self.thickness));
+
+        let height = match (free("height"), free("radius")) {
+            (false, _) => current.height,
+            (true, false) => clamp_to(current.radius / thickness, self.height),
+            (true, true) => rand.random_range(span(self.height)),
+        };
+        let radius = if free("radius") {
+            height * thickness
+        } else {
+            current.radius
+        };
+        let radial_variance = if free("radial_variance") {
+            radius * rand.random_range(0. ..=self.radial_variance.abs())
+        } else {
+            current.radial_variance
+        };
+        let horizontal_variance = if free("horizontal_variance") {
+            height * rand.random_range(0. ..=self.horizontal_variance.abs())
+        } else {
+            current.horizontal_variance
+        };
+        let mut count = |field: &str, (min, max): (u32, u32), current: u32, floor: u32| {
+            if free(field) {
+                rand.random_range(min.max(floor)..=max.max(min).max(floor))
+            } else {
+                current
+            }
+        };
+        let resolution = count("resolution", self.resolution, current.resolution, 3);
+        let segments = count("segments", self.segments, current.segments, 1);
+        let seed = if free("seed") {
+            rand.random()
+        } else {
+            current.seed
+        };
+        let head = if free("head") {
+            self.random_head(radius, rand)
+        } else {
+            current.head.clone()
+        };
+
+        StaffConfig {
+            radius,
+            radial_variance,
+            height,
+            resolution,
+            segments,
+            horizontal_variance,
+            seed,
+            head,
+        }
+    }
+
+    /// A child of `parent` for evolving staffs. Unlocked lengths move by up to `strength` of
+    /// their own size and variances by up to `strength` of their bound, staying within the
+    /// bounds. With a chance of `strength` each, counts step by one, the seed is rerolled and
+    /// the head is swapped for a random one.
+    pub fn mutate(
+        &self,
+        parent: &StaffConfig,
+        locked: &HashSet<String>,
+        strength: f32,
+        rand: &mut impl Rng,
+    ) -> StaffConfig {
+        let free = |field: &str| !locked.contains(field);
+        let strength = strength.clamp(0., 1.);
+        let thickness = clamp_to(
+            parent.radius / parent.height * (1. + rand.random_range(-strength..=strength)),
+            self.thickness,
+        );
+        let height = match (free("height"), free("radius")) {
+            (false, _) => parent.height,
+            (true, false) => clamp_to(parent.radius / thickness, self.height),
+            (true, true) => clamp_to(
+                parent.height * (1. + rand.random_range(-strength..=strength)),
+                self.height,
+            ),
+        };
+        let radius = if free("radius") {
+            height * thickness
+        } else {
+            parent.radius
+        };
+        // Variances move as fractions of their bound, so a straight staff can start to wobble
+        let mut nudge = |fraction: f32, max: f32| {
+            let max = max.abs();
+            (fraction + rand.random_range(-strength..=strength) * max).clamp(0., max)
+        };
+        let radial_variance = if free("radial_variance") {
+            radius * nudge(parent.radial_variance / parent.radius, self.radial_variance)
+        } else {
+            parent.radial_variance
+        };
+        let horizontal_variance = if free("horizontal_variance") {
+            height
+                * nudge(
+                    parent.horizontal_variance / parent.height,
+                    self.horizontal_variance,
+                )
+        } else {
+            parent.horizontal_variance
+        };
+
+        let mut step = |field: &str, (min, max): (u32, u32), value: u32, floor: u32| {
+            if !free(field) || !rand.random_bool(strength as f64) {
+                return value;
+            }
+            let value = if rand.random_bool(0.5) {
+                value.saturating_add(1)
+            } else {
+                value.saturating_sub(1)
+            };
+            value.clamp(min.max(floor), max.max(min).max(floor))
+        };
+        let resolution = step("resolution", self.resolution, parent.resolution, 3);
+        let segments = step("segments", self.segments, parent.segments, 1);
+        let seed = if free("seed") && rand.random_bool(strength as f64) {
+            rand.random()
+        } else {
+            parent.seed
+        };
+        let head = if free("head") && rand.random_bool(strength as f64) {
+            self.random_head(radius, rand)
+        } else {
+            parent.head.clone()
+        };
+
+        StaffConfig {
+            radius,
+            radial_variance,
+            height,
+            resolution,
+            segments,
+            horizontal_variance,
+            seed,
+            head,
+        }
+    }
+
+    /// A head style picked by [`Self::heads`], sized relative to the shaft's `radius`.
+    fn random_head(&self, radius: f32, rand: &mut impl Rng) -> HeadStyle {
+        let weights = &self.heads;
+        let styles = [
+            (0, weights.plain),
+            (1, weights.crystal),
+            (2, weights.crook),
+            (3, weights.forked),
+            (4, weights.orb),
+        ];
+        let style = styles
+            .choose_weighted(rand, |&(_, weight)| weight.max(0.))
+            .map_or(0, |&(style, _)| style);
+        match style {
+            1 => HeadStyle::Crystal(CrystalConfig {
+                radius: radius * rand.random_range(1.2..2.),
+                height: radius * rand.random_range(3. ..5.),
+                ..default()
+            }),
+            2 => HeadStyle::Crook(CrookConfig {
+                curl_radius: radius * rand.random_range(2. ..3.),
+                turns: rand.random_range(0.6..1.),
+                ..default()
+            }),
+            3 => HeadStyle::Forked(ForkConfig {
+                prongs: rand.random_range(2..=4),
+                length: radius * rand.random_range(5. ..7.),
+                ..default()
+            }),
+            4 => HeadStyle::Orb(OrbConfig {
+                radius: radius * rand.random_range(1.3..2.),
+                claws: rand.random_range(3..=5),
+                ..default()
+            }),
+            _ => HeadStyle::Plain,
+        }
+    }
+}
+
+fn span((min, max): (f32, f32)) -> RangeInclusive<f32> {
+    min.min(max)..=max.max(min)
+}
+
+fn clamp_to(value: f32, bounds: (f32, f32)) -> f32 {
+    let span = span(bounds);
+    value.clamp(*span.start(), *span.end())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    #[test]
+    fn random_staffs_stay_in_proportion() {
+        let randomizer = StaffRandomizer::default();
+        let mut rand = ChaCha8Rng::seed_from_u64(1);
+        let current = StaffConfig::default();
+        for _ in 0..200 {
+            let staff = randomizer.randomize(&current, &HashSet::new(), &mut rand);
+            assert!((1.2..=2.4).contains(&staff.height), "{staff:?}");
+            let thickness = staff.radius / staff.height;
+            assert!(
+                (0.018 - 1e-6..=0.035 + 1e-6).contains(&thickness),
+                "{staff:?}"
+            );
+            assert!(staff.radial_variance <= staff.radius * 0.6 + 1e-6);
+            assert!(staff.horizontal_variance <= staff.height * 0.1 + 1e-6);
+            assert!(staff.resolution >= 5 && staff.segments >= 2);
+        }
+    }
+
+    #[test]
+    fn locked_fields_are_kept() {
+        let randomizer = StaffRandomizer {
+            heads: HeadWeights {
+                plain: 0.,
+                ..default()
+            },
+            ..default()
+        };
+        let mut rand = ChaCha8Rng::seed_from_u64(2);
+        let current = StaffConfig {
+            radius: 0.06,
+            seed: 7,
+            ..default()
+        };
+        let locked: HashSet<String> = ["radius", "seed", "head"].map(String::from).into();
+        for _ in 0..50 {
+            let staff = randomizer.randomize(&current, &locked, &mut rand);
+            assert_eq!(staff.radius, current.radius);
+            assert_eq!(staff.seed, current.seed);
+            assert_eq!(staff.head, HeadStyle::Plain);
+            // The height is drawn to suit the locked radius
+            let thickness = staff.radius / staff.height;
+            assert!(
+                (0.018 - 1e-6..=0.035 + 1e-6).contains(&thickness),
+                "{staff:?}"
+            );
+        }
+        let unlocked = randomizer.randomize(&current, &HashSet::new(), &mut rand);
+        assert_ne!(unlocked.head, HeadStyle::Plain);
+    }
+
+    #[test]
+    fn mutants_stay_close_to_their_parent() {
+        let randomizer = StaffRandomizer::default();
+        let mut rand = ChaCha8Rng::seed_from_u64(3);
+        let parent = StaffConfig::default();
+        let locked: HashSet<String> = ["segments".into()].into();
+        for _ in 0..100 {
+            let child = randomizer.mutate(&parent, &locked, 0.2, &mut rand);
+            assert!((child.height / parent.height - 1.).abs() <= 0.2 + 1e-5);
+            assert!(child.radial_variance <= child.radius * 0.6 + 1e-6);
+            assert!(child.resolution.abs_diff(parent.resolution) <= 1);
+            assert_eq!(child.segments, parent.segments);
+        }
+        // No mutation leaves the parent as it was
+        assert_eq!(
+            randomizer.mutate(&parent, &HashSet::new(), 0., &mut rand),
+            parent
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Watertightness checks and hole capping for generated meshes.
+//!
+//! Generators duplicate vertices along UV and normal seams, so edges are matched by
+//! position rather than by index: two triangles sharing an edge only count as joined when
+//! they use it in opposite directions.
+
+use std::collections::HashMap;
+
+use bevy::mesh::Indices;
+use bevy::prelude::*;
+
+use crate::mesh_util::positions;
+
+/// Vertices closer than this are treated as the same point when matching edges.
+pub const WELD_EPSILON: f32 = 1e-5;
+
+/// Open edges of a mesh, as found by [`check_watertight`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct WatertightReport {
+    /// Directed edges, as vertex indices, that no other triangle uses in reverse
+    pub boundary_edges: Vec<[u32; 2]>,
+    /// Boundary edges chained into closed loops, wound so they can be capped directly
+    pub holes: Vec<Vec<u32>>,
+}
+
+impl WatertightReport {
+    pub fn is_watertight(&self) -> bool {
+        self.boundary_edges.is_empty()
+    }
+}
+
+pub fn check_watertight(mesh: &Mesh) -> WatertightReport {
+    let Some(indices) = mesh.indices() else {
+        return WatertightReport::default();
+    };
+    let indices: Vec<u32> = indices.iter().map(|index| index as u32).collect();
+    let welded = weld_ids(positions(mesh));
+
+    // Count each welded edge by direction, keeping one vertex index pair to report
+    let mut edges: HashMap<(u32, u32), ([u32; 2], i32)> = HashMap::new();
+    for triangle in indices.chunks_exact(3) {
+        for corner in 0..3 {
+            let (a, b) = (triangle[corner], triangle[(corner + 1) % 3]);
+            let key = (welded[a as usize], welded[b as usize]);
+            edges.entry(key).or_insert(([a, b], 0)).1 += 1;
+        }
+    }
+    let mut boundary_edges: Vec<[u32; 2]> = edges
+        .iter()
+        .filter(|((a, b), (_, count))| {
+            let reverse = edges.get(&(*b, *a)).map_or(0, |(_, count)| *count);
+            count > &reverse
+        })
+        .map(|(_, (edge, _))| *edge)
+        .collect();
+    boundary_edges.sort_unstable();
+
+    WatertightReport {
+        holes: chain_holes(&boundary_edges, &welded),
+        boundary_edges,
+    }
+}
+
+/// Closes every hole found by [`check_watertight`] with an ear-clipped cap and returns
+/// how many were capped. Caps reuse the rim vertices, so they take on the rim's normals and UVs.
+pub fn cap_holes(mesh: &mut Mesh) -> usize {
+    let report = check_watertight(mesh);
+    if report.holes.is_empty() {
+        return 0;
+    }
+    let positions: Vec<Vec3> = positions(mesh).iter().copied().map(Vec3::from).collect();
+    let mut indices: Vec<u32> = match mesh.indices() {
+        Some(indices) => indices.iter().map(|index| index as u32).collect(),
+        None => return 0,
+    };
+    for hole in &report.holes {
+        indices.extend(ear_clip(hole, &positions));
+    }
+    mesh.insert_indices(Indices::U32(indices));
+    report.holes.len()
+}
+
+/// Maps every vertex to the first vertex at the same position.
+pub(crate) fn weld_ids(positions: &[[f32; 3]]) -> Vec<u32> {
+    let mut first_at: HashMap<[i64; 3], u32> = HashMap::new();
+    positions
+        .iter()
+        .enumerate()
+        .map(|(i, position)| {
+            let key = position.map(|x| (x / WELD_EPSILON).round() as i64);
+            *first_at.entry(key).or_insert(i as u32)
+        })
+        .collect()
+}
+
+/// Walks boundary edges backwards so each loop winds the way its cap must face.
+fn chain_holes(boundary_edges: &[[u32; 2]], welded: &[u32]) -> Vec<Vec<u32>> {
+    let mut next: HashMap<u32, Vec<u32>> = HashMap::new();
+    for &[a, b] in boundary_edges {
+        next.entry(welded[b as usize]).or_default().push(a);
+    }
+
+    let mut holes = Vec::new();
+    for &[_, start] in boundary_edges {
+        // Already walked as part of an earlier hole
+        if next.get(&welded[start as usize]).is_none_or(Vec::is_empty) {
+            continue;
+        }
+        let mut hole = vec![start];
+        let mut current = welded[start as usize];
+        while let Some(vertex) = next.get_mut(&current).and_then(Vec::pop) {
+            current = welded[vertex as usize];
+            if current == welded[start as usize] {
+                break;
+            }
+            hole.push(vertex);
+        }
+        if hole.len() >= 3 {
+            holes.push(hole);
+        }
+    }
+    holes
+}
+
+/// Triangulates a simple, roughly planar polygon by clipping convex corners.
+fn ear_clip(polygon: &[u32], positions: &[Vec3]) -> Vec<u32> {
+    // Newell's method, robust for non-convex and slightly non-planar loops
+    let normal = polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .fold(Vec3::ZERO, |normal, (&a, &b)| {
+            let (a, b) = (positions[a as usize], positions[b as usize]);
+            normal + (a - b).cross(a + b)
+        })
+        .normalize_or(Vec3::Y);
+    let (u, v) = normal.any_orthonormal_pair();
+    let project = |i: u32| {
+        let p = positions[i as usize];
+        vec2(p.dot(u), p.dot(v))
+    };
+    let is_convex = |a: Vec2, b: Vec2, c: Vec2| (b - a).perp_dot(c - b) > 0.;
+    let contains = |[a, b, c]: [Vec2; 3], p: Vec2| {
+        is_convex(a, b, p) && is_convex(b, c, p) && is_convex(c, a, p)
+    };
+
+    let mut remaining = polygon.to_vec();
+    let mut triangles = Vec::with_capacity((polygon.len() - 2) * 3);
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        let ear = (0..len).find(|&i| {
+            let corner = [
+                remaining[(i + len - 1) % len],
+                remaining[i],
+                remaining[(i + 1) % len],
+            ];
+            let points = corner.map(project);
+            is_convex(points[0], points[1], points[2])
+                && remaining
+                    .iter()
+                    .filter(|vertex| !corner.contains(vertex))
+                    .all(|&vertex| !contains(points, project(vertex)))
+        });
+        // Degenerate or self-intersecting loops have no ear, fan out whatever is left
+        let i = ear.unwrap_or(1);
+        triangles.extend([
+            remaining[(i + len - 1) % len],
+            remaining[i],
+            remaining[(i + 1) % len],
+        ]);
+        remaining.remove(i);
+    }
+    triangles.extend(remaining);
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cone::generate_cone_mesh;
+    use crate::crystal::generate_crystal_mesh;
+    use crate::cube::{CubeNormals, generate_cube_mesh};
+    use crate::cylinder::{CylinderNormals, generate_cylinder_mesh};
+    use crate::mesh_util::volume;
+    use crate::staff::StaffConfig;
+
+    #[test]
+    fn generators_are_watertight() {
+        let meshes = [
+            ("cube", generate_cube_mesh(&mut CubeNormals::default())),
+            ("cone", generate_cone_mesh(1., 0.5, 6)),
+            (
+                "cylinder",
+                generate_cylinder_mesh(0.5, 1., 6, 3, &mut CylinderNormals::default()),
+            ),
+            ("crystal", generate_crystal_mesh(0.5, 0.25, 6)),
+            ("staff", StaffConfig::default().generate_mesh()),
+        ];
+        for (name, mesh) in meshes {
+            let report = check_watertight(&mesh);
+            assert!(report.is_watertight(), "{name}: {report:?}");
+        }
+    }
+
+    #[test]
+    fn caps_missing_cylinder_ends() {
+        let resolution = 8;
+        let mut mesh =
+            generate_cylinder_mesh(0.5, 1., resolution, 2, &mut CylinderNormals::default());
+        let expected_volume = volume(&mesh);
+
+        // The caps are the last triangles, resolution - 2 per end
+        let mut indices: Vec<u32> = mesh.indices().unwrap().iter().map(|i| i as u32).collect();
+        indices.truncate(indices.len() - 2 * (resolution as usize - 2) * 3);
+        mesh.insert_indices(Indices::U32(indices));
+
+        let report = check_watertight(&mesh);
+        assert_eq!(report.boundary_edges.len(), 2 * resolution as usize);
+        assert_eq!(report.holes.len(), 2);
+
+        assert_eq!(cap_holes(&mut mesh), 2);
+        assert!(check_watertight(&mesh).is_watertight());
+        assert!((volume(&mesh) - expected_volume).abs() < 1e-5);
+    }
+}
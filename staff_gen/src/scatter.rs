@@ -0,0 +1,233 @@
+//! Poisson disk sampling, for scattering props that keep their distance from each other.
+
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+/// Candidates tried around each placed point before it stops growing the sample
+const CANDIDATES: u32 = 30;
+
+/// One kind of object to scatter. Two objects stay at least the sum of their radii apart, and
+/// `density` objects are placed per square unit, or as many as fit if that's fewer.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ScatterKind {
+    pub radius: f32,
+    pub density: f32,
+}
+
+/// Where on the ground objects are scattered.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ScatterArea {
+    Disc { center: Vec2, radius: f32 },
+    Rect { min: Vec2, max: Vec2 },
+}
+
+impl ScatterArea {
+    pub fn contains(&self, point: Vec2) -> bool {
+        match *self {
+            Self::Disc { center, radius } => point.distance_squared(center) <= radius * radius,
+            Self::Rect { min, max } => point.cmpge(min).all() && point.cmple(max).all(),
+        }
+    }
+
+    pub fn area(&self) -> f32 {
+        match *self {
+            Self::Disc { radius, .. } => std::f32::consts::PI * radius * radius,
+            Self::Rect { min, max } => (max - min).max(Vec2::ZERO).element_product(),
+        }
+    }
+
+    fn random_point(&self, rand: &mut impl Rng) -> Vec2 {
+        match *self {
+            Self::Disc { center, radius } => {
+                let angle = rand.random_range(0. ..TAU);
+                let distance = radius * rand.random::<f32>().sqrt();
+                center + Vec2::from_angle(angle) * distance
+            }
+            Self::Rect { min, max } => vec2(
+                rand.random_range(min.x..=max.x),
+                rand.random_range(min.y..=max.y),
+            ),
+        }
+    }
+}
+
+/// Scatters points that never crowd the ones placed before, whichever kind they were, so rocks
+/// can be scattered first and grass around them. The same seed and calls give the same points.
+pub struct PoissonDisk {
+    rand: ChaCha8Rng,
+    /// Points and radii placed so far
+    placed: Vec<(Vec2, f32)>,
+    /// Indices into `placed` by grid cell, for finding neighbors
+    cells: HashMap<IVec2, Vec<usize>>,
+    cell_size: f32,
+    /// Largest radius placed so far, which bounds how far away a crowding point can be
+    max_radius: f32,
+}
+
+impl PoissonDisk {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rand: ChaCha8Rng::seed_from_u64(seed),
+            placed: Vec::new(),
+            cells: HashMap::new(),
+            cell_size: 0.,
+            max_radius: 0.,
+        }
+    }
+
+    /// Every point placed so far, with its radius.
+    pub fn placed(&self) -> &[(Vec2, f32)] {
+        &self.placed
+    }
+
+    /// Places objects of `kind` in `area` with Bridson's algorithm, then keeps a random pick of
+    /// them to match the density. Returns the kept points, which later calls avoid.
+    pub fn scatter(&mut self, kind: ScatterKind, area: ScatterArea) -> Vec<Vec2> {
+        if kind.radius <= 0. || kind.density <= 0. {
+            return Vec::new();
+        }
+        self.max_radius = self.max_radius.max(kind.radius);
+        if self.cell_size < kind.radius {
+            self.rebuild_cells(kind.radius * 2.);
+        }
+
+        let mut candidates: Vec<Vec2> = Vec::new();
+        let mut active: Vec<Vec2> = Vec::new();
+        // Start a front wherever there's room, since earlier kinds may already cover the area
+        for _ in 0..CANDIDATES {
+            let point = area.random_point(&mut self.rand);
+            if self.has_room(point, kind.radius, &candidates) {
+                candidates.push(point);
+                active.push(point);
+            }
+        }
+        let spacing = kind.radius * 2.;
+        while !active.is_empty() {
+            let index = self.rand.random_range(0..active.len());
+            let origin = active[index];
+            let found = (0..CANDIDATES).find_map(|_| {
+                let angle = self.rand.random_range(0. ..TAU);
+                let distance = self.rand.random_range(spacing..spacing * 2.);
+                let point = origin + Vec2::from_angle(angle) * distance;
+                (area.contains(point) && self.has_room(point, kind.radius, &candidates))
+                    .then_some(point)
+            });
+            match found {
+                Some(point) => {
+                    candidates.push(point);
+                    active.push(point);
+                }
+                None => {
+                    active.swap_remove(index);
+                }
+            }
+        }
+
+        let count = (kind.density * area.area()).round() as usize;
+        candidates.shuffle(&mut self.rand);
+        candidates.truncate(count);
+        for &point in &candidates {
+            self.insert(point, kind.radius);
+        }
+        candidates
+    }
+
+    /// Whether a point of `radius` clears every placed point and the kind's own `candidates`,
+    /// which all share its radius.
+    fn has_room(&self, point: Vec2, radius: f32, candidates: &[Vec2]) -> bool {
+        let spacing = radius * 2.;
+        if candidates
+            .iter()
+            .any(|candidate| candidate.distance_squared(point) < spacing * spacing)
+        {
+            return false;
+        }
+        if self.placed.is_empty() {
+            return true;
+        }
+        let reach = radius + self.max_radius;
+        let min = self.cell(point - reach);
+        let max = self.cell(point + reach);
+        (min.x..=max.x)
+            .flat_map(|x| (min.y..=max.y).map(move |y| ivec2(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .all(|&i| {
+                let (other, other_radius) = self.placed[i];
+                other.distance(point) >= radius + other_radius
+            })
+    }
+
+    fn cell(&self, point: Vec2) -> IVec2 {
+        (point / self.cell_size).floor().as_ivec2()
+    }
+
+    fn insert(&mut self, point: Vec2, radius: f32) {
+        let cell = self.cell(point);
+        self.cells.entry(cell).or_default().push(self.placed.len());
+        self.placed.push((point, radius));
+    }
+
+    fn rebuild_cells(&mut self, cell_size: f32) {
+        self.cell_size = cell_size;
+        self.cells.clear();
+        for (i, &(point, _)) in self.placed.iter().enumerate() {
+            let cell = self.cell(point);
+            self.cells.entry(cell).or_default().push(i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: ScatterArea = ScatterArea::Rect {
+        min: Vec2::ZERO,
+        max: vec2(4., 4.),
+    };
+
+    #[test]
+    fn kinds_keep_their_distance() {
+        let mut sampler = PoissonDisk::new(3);
+        let rocks = ScatterKind {
+            radius: 0.3,
+            density: 0.5,
+        };
+        let grass = ScatterKind {
+            radius: 0.05,
+            density: 40.,
+        };
+        assert_eq!(sampler.scatter(rocks, AREA).len(), 8);
+        assert!(!sampler.scatter(grass, AREA).is_empty());
+
+        let placed = sampler.placed();
+        for (i, &(a, a_radius)) in placed.iter().enumerate() {
+            assert!(AREA.contains(a));
+            for &(b, b_radius) in &placed[i + 1..] {
+                assert!(a.distance(b) >= a_radius + b_radius - 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_scatters_the_same_points() {
+        let kind = ScatterKind {
+            radius: 0.1,
+            density: 10.,
+        };
+        let area = ScatterArea::Disc {
+            center: vec2(1., -1.),
+            radius: 1.5,
+        };
+        let points = PoissonDisk::new(9).scatter(kind, area);
+        assert_eq!(points, PoissonDisk::new(9).scatter(kind, area));
+        assert_ne!(points, PoissonDisk::new(10).scatter(kind, area));
+    }
+}
@@ -0,0 +1,126 @@
+//! A joint chain along a staff's spine, so it can bend at runtime without regenerating the mesh.
+
+use std::ops::RangeInclusive;
+
+use bevy::mesh::VertexAttributeValues;
+use bevy::prelude::*;
+
+use crate::mesh_util::positions;
+use crate::staff::{StaffConfig, ring_axis};
+
+/// Enough joints for a smooth bend while staying cheap to animate.
+pub const JOINT_COUNT: RangeInclusive<usize> = 3..=5;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StaffSkeleton {
+    /// Rest position of every joint relative to the mesh origin, bottom to top
+    pub joints: Vec<Vec3>,
+}
+
+impl StaffSkeleton {
+    /// Evenly spaced joints on the staff's axis from its base to its tip.
+    /// `joint_count` is clamped to [`JOINT_COUNT`].
+    pub fn new(config: &StaffConfig, joint_count: usize) -> Self {
+        let joint_count = joint_count.clamp(*JOINT_COUNT.start(), *JOINT_COUNT.end());
+        let rings = config.generate_rings();
+        let (bottom, top) = (rings[0].y, rings[rings.len() - 1].y);
+        let joints = (0..joint_count)
+            .map(|i| {
+                let t = i as f32 / (joint_count - 1) as f32;
+                ring_axis(&rings, bottom.lerp(top, t))
+            })
+            .collect();
+        Self { joints }
+    }
+
+    /// Rest transform of every joint relative to its parent, the joint below it.
+    /// The first joint is relative to the mesh.
+    pub fn local_transforms(&self) -> Vec<Transform> {
+        let mut parent = Vec3::ZERO;
+        self.joints
+            .iter()
+            .map(|&joint| {
+                let local = Transform::from_translation(joint - parent);
+                parent = joint;
+                local
+            })
+            .collect()
+    }
+
+    /// Moves vertices from mesh space into each joint's rest space.
+    pub fn inverse_bindposes(&self) -> Vec<Mat4> {
+        self.joints
+            .iter()
+            .map(|&joint| Mat4::from_translation(-joint))
+            .collect()
+    }
+
+    /// Joint indices and weights for a vertex at height `y`, split between the two joints
+    /// around it.
+    pub fn weights_at(&self, y: f32) -> ([u16; 4], [f32; 4]) {
+        let last = self.joints.len() - 1;
+        let upper = self
+            .joints
+            .partition_point(|joint| joint.y < y)
+            .clamp(1, last);
+        let (below, above) = (self.joints[upper - 1].y, self.joints[upper].y);
+        let t = ((y - below) / (above - below)).clamp(0., 1.);
+        ([upper as u16 - 1, upper as u16, 0, 0], [1. - t, t, 0., 0.])
+    }
+
+    /// Adds joint indices and weights to a mesh generated from the same config.
+    /// Every vertex of a ring sits at the ring's height, so each ring shares one set of weights.
+    pub fn skin(&self, mesh: &mut Mesh) {
+        let (indices, weights): (Vec<_>, Vec<_>) = positions(mesh)
+            .iter()
+            .map(|position| self.weights_at(position[1]))
+            .unzip();
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_JOINT_INDEX,
+            VertexAttributeValues::Uint16x4(indices),
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT, weights);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joints_run_from_base_to_tip() {
+        let config = StaffConfig::default();
+        let skeleton = StaffSkeleton::new(&config, 10);
+        assert_eq!(skeleton.joints.len(), *JOINT_COUNT.end());
+        assert_eq!(skeleton.joints[0].y, -config.height / 2.);
+        assert_eq!(skeleton.joints[4].y, config.height / 2.);
+
+        // Chaining the local transforms puts every joint back at its rest position
+        let mut global = Transform::IDENTITY;
+        for (local, joint) in skeleton.local_transforms().iter().zip(&skeleton.joints) {
+            global = global * *local;
+            assert!(global.translation.abs_diff_eq(*joint, 1e-6));
+        }
+    }
+
+    #[test]
+    fn skin_weights_are_normalized() {
+        let config = StaffConfig::default();
+        let skeleton = StaffSkeleton::new(&config, 4);
+        let mut mesh = config.generate_mesh();
+        skeleton.skin(&mut mesh);
+
+        let Some(VertexAttributeValues::Float32x4(weights)) =
+            mesh.attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT)
+        else {
+            panic!("missing joint weights");
+        };
+        assert_eq!(weights.len(), mesh.count_vertices());
+        for weight in weights {
+            assert!((weight.iter().sum::<f32>() - 1.).abs() < 1e-6, "{weight:?}");
+        }
+        // The tip follows the top joint only
+        let (indices, weights) = skeleton.weights_at(config.height / 2.);
+        assert_eq!((indices[1], weights[1]), (3, 1.));
+    }
+}
@@ -0,0 +1,92 @@
+//! Golden-file tests pinning the output of every generator.
+//!
+//! Run with `UPDATE_SNAPSHOTS=1 cargo test` to regenerate the files in `tests/snapshots/`
+//! after an intentional change to generated geometry.
+
+use std::path::PathBuf;
+
+use bevy::mesh::VertexAttributeValues;
+use bevy::prelude::*;
+
+use crate::cone::generate_cone_mesh;
+use crate::crystal::generate_crystal_mesh;
+use crate::cube::{CubeNormals, generate_cube_mesh};
+use crate::cylinder::{CylinderNormals, generate_cylinder_mesh};
+use crate::mesh_util::to_obj;
+use crate::staff::StaffConfig;
+
+/// Snapshots pin whatever they're given, so broken values are refused before they're written
+/// or compared.
+fn assert_finite(name: &str, mesh: &Mesh) {
+    for (attribute, values) in mesh.attributes() {
+        let floats = match values {
+            VertexAttributeValues::Float32(values) => values.as_slice(),
+            VertexAttributeValues::Float32x2(values) => values.as_flattened(),
+            VertexAttributeValues::Float32x3(values) => values.as_flattened(),
+            VertexAttributeValues::Float32x4(values) => values.as_flattened(),
+            _ => continue,
+        };
+        if let Some(value) = floats.iter().find(|value| !value.is_finite()) {
+            panic!("{name} has a {value} in {}", attribute.name);
+        }
+    }
+}
+
+fn assert_snapshot(name: &str, mesh: &Mesh) {
+    assert_finite(name, mesh);
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{name}.obj"));
+    let obj = to_obj(mesh);
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, obj).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing snapshot {}, run with UPDATE_SNAPSHOTS=1 to create it",
+            path.display()
+        )
+    });
+    if obj != expected {
+        let line = obj
+            .lines()
+            .zip(expected.lines())
+            .position(|(a, b)| a != b)
+            .map_or(obj.lines().count().min(expected.lines().count()), |i| i);
+        panic!(
+            "{name} differs from {} at line {}, run with UPDATE_SNAPSHOTS=1 if this is intended",
+            path.display(),
+            line + 1
+        );
+    }
+}
+
+#[test]
+fn cone_snapshot() {
+    assert_snapshot("cone", &generate_cone_mesh(1., 0.5, 6));
+}
+
+#[test]
+fn crystal_snapshot() {
+    assert_snapshot("crystal", &generate_crystal_mesh(0.5, 1., 6));
+}
+
+#[test]
+fn cube_snapshot() {
+    assert_snapshot("cube", &generate_cube_mesh(&mut CubeNormals::default()));
+}
+
+#[test]
+fn cylinder_snapshot() {
+    let mesh = generate_cylinder_mesh(0.5, 1., 8, 3, &mut CylinderNormals::default());
+    assert_snapshot("cylinder", &mesh);
+}
+
+#[test]
+fn staff_snapshot() {
+    assert_snapshot("staff", &StaffConfig::default().generate_mesh());
+}
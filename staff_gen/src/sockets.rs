@@ -0,0 +1,55 @@
+//! Named attachment points on generated meshes.
+
+use bevy::prelude::*;
+
+/// The highest point on a part's axis. The tip of a staff.
+pub const TOP: &str = "top";
+pub const CENTER: &str = "center";
+/// The lowest point on a part's axis. The base of a staff.
+pub const BOTTOM: &str = "bottom";
+/// Where a hand holds a staff, a little above its middle.
+pub const GRIP: &str = "grip";
+
+/// Name of the socket at the center of a staff's `ring`, counting from the bottom.
+pub fn ring_socket(ring: usize) -> String {
+    format!("ring_{ring}")
+}
+
+/// Attachment points of a generated mesh, relative to its origin.
+/// Attach things by name rather than recomputing positions from generator parameters.
+#[derive(Component, Reflect, Clone, Debug, Default, PartialEq)]
+#[reflect(Component)]
+pub struct Sockets(Vec<(String, Transform)>);
+
+impl Sockets {
+    /// [`TOP`], [`CENTER`] and [`BOTTOM`] of a shape centered on its origin, standing along Y.
+    pub fn upright(height: f32) -> Self {
+        let mut sockets = Self::default();
+        sockets.insert(TOP, Transform::from_xyz(0., height / 2., 0.));
+        sockets.insert(CENTER, Transform::IDENTITY);
+        sockets.insert(BOTTOM, Transform::from_xyz(0., -height / 2., 0.));
+        sockets
+    }
+
+    /// Adds a socket, replacing any socket of the same name.
+    pub fn insert(&mut self, name: impl Into<String>, transform: Transform) {
+        let name = name.into();
+        match self.0.iter_mut().find(|(existing, _)| *existing == name) {
+            Some((_, existing)) => *existing = transform,
+            None => self.0.push((name, transform)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Transform> {
+        self.0
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|(_, transform)| *transform)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Transform)> {
+        self.0
+            .iter()
+            .map(|(name, transform)| (name.as_str(), *transform))
+    }
+}
@@ -0,0 +1,662 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{GenError, check_count, check_finite, check_length, check_resolution};
+use crate::head::{Head, HeadFrame, HeadRegistry, HeadStyle};
+use crate::mesh_util::unit_circle;
+use crate::pool::MeshBuffers;
+use crate::sockets::{self, Sockets};
+
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Component, Default, Serialize, Deserialize)]
+pub struct StaffConfig {
+    pub radius: f32,
+    pub radial_variance: f32,
+    pub height: f32,
+    pub resolution: u32,
+    pub segments: u32,
+    pub horizontal_variance: f32,
+    pub seed: u64,
+    #[serde(default)]
+    pub head: HeadStyle,
+}
+
+impl Default for StaffConfig {
+    fn default() -> Self {
+        let radius = 0.05;
+        let height = 2.;
+        Self {
+            radius,
+            radial_variance: radius * 0.5,
+            height,
+            resolution: 6,
+            segments: 4,
+            horizontal_variance: height * 0.05,
+            seed: 19878367467713,
+            head: HeadStyle::Plain,
+        }
+    }
+}
+
+impl StaffConfig {
+    /// Multiplies every length parameter by `factor`, e.g. to convert between units.
+    pub fn scaled(&self, factor: f32) -> Self {
+        Self {
+            radius: self.radius * factor,
+            radial_variance: self.radial_variance * factor,
+            height: self.height * factor,
+            horizontal_variance: self.horizontal_variance * factor,
+            ..self.clone()
+        }
+    }
+
+    /// Interpolates every parameter towards `other`.
+    /// Integer parameters are rounded and the seed and head switch over at the halfway point.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let lerp_u32 = |a: u32, b: u32| (a as f32).lerp(b as f32, t).round() as u32;
+        Self {
+            radius: self.radius.lerp(other.radius, t),
+            radial_variance: self.radial_variance.lerp(other.radial_variance, t),
+            height: self.height.lerp(other.height, t),
+            resolution: lerp_u32(self.resolution, other.resolution),
+            segments: lerp_u32(self.segments, other.segments),
+            horizontal_variance: self.horizontal_variance.lerp(other.horizontal_variance, t),
+            seed: if t < 0.5 { self.seed } else { other.seed },
+            head: if t < 0.5 { &self.head } else { &other.head }.clone(),
+        }
+    }
+
+    pub fn generate_rings(&self) -> Vec<StaffRing> {
+        let mut rand = ChaCha8Rng::seed_from_u64(self.seed);
+        generate_staff_rings(
+            self.radius,
+            self.radial_variance,
+            self.height,
+            self.segments,
+            self.horizontal_variance,
+            &mut rand,
+        )
+    }
+
+    /// The staff with its [`HeadStyle`] merged on. [`HeadStyle::Custom`] heads need
+    /// [`Self::generate_mesh_with_head`].
+    pub fn generate_mesh(&self) -> Mesh {
+        self.generate_mesh_in(MeshBuffers::default())
+    }
+
+    /// [`Self::generate_mesh`] built in `buffers`, see [`BufferPool`](crate::pool::BufferPool).
+    pub fn generate_mesh_in(&self, buffers: MeshBuffers) -> Mesh {
+        let head = self.generate_head(&HeadRegistry::default());
+        self.generate_mesh_with_head_in(head.as_ref(), buffers)
+    }
+
+    /// [`Self::generate_mesh`], unless [`Self::validate`] finds a parameter it can't build.
+    pub fn try_generate_mesh(&self) -> Result<Mesh, GenError> {
+        self.try_generate_mesh_in(MeshBuffers::default())
+    }
+
+    /// [`Self::generate_mesh_in`], unless [`Self::validate`] finds a parameter it can't build.
+    pub fn try_generate_mesh_in(&self, buffers: MeshBuffers) -> Result<Mesh, GenError> {
+        self.validate()?;
+        Ok(self.generate_mesh_in(buffers))
+    }
+
+    /// Checks the shaft, which heads are built on, and then the head's own parameters.
+    pub fn validate(&self) -> Result<(), GenError> {
+        check_length("radius", self.radius)?;
+        check_finite("radial_variance", self.radial_variance)?;
+        check_length("height", self.height)?;
+        check_resolution(self.resolution)?;
+        check_count("segments", self.segments, 1)?;
+        check_finite("horizontal_variance", self.horizontal_variance)?;
+        self.head.validate()
+    }
+
+    /// The staff's head built on its top ring, looking up [`HeadStyle::Custom`] heads in `heads`.
+    pub fn generate_head(&self, heads: &HeadRegistry) -> Option<Head> {
+        let _span = info_span!("decorations", seed = self.seed).entered();
+        let rings = self.generate_rings();
+        let frame = HeadFrame {
+            ring: rings[rings.len() - 1],
+            resolution: self.resolution,
+        };
+        self.head.generate(&frame, heads)
+    }
+
+    /// [`Self::generate_head`], unless [`Self::validate`] finds a parameter it can't build.
+    pub fn try_generate_head(&self, heads: &HeadRegistry) -> Result<Option<Head>, GenError> {
+        self.validate()?;
+        Ok(self.generate_head(heads))
+    }
+
+    /// The staff with `head` merged on, leaving out the head's gem.
+    pub fn generate_mesh_with_head(&self, head: Option<&Head>) -> Mesh {
+        self.generate_mesh_with_head_in(head, MeshBuffers::default())
+    }
+
+    /// [`Self::generate_mesh_with_head`] built in `buffers`.
+    pub fn generate_mesh_with_head_in(&self, head: Option<&Head>, buffers: MeshBuffers) -> Mesh {
+        let _span = info_span!("staff", seed = self.seed).entered();
+        let mut rand = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut mesh = generate_staff_mesh_in(
+            self.radius,
+            self.radial_variance,
+            self.height,
+            self.resolution,
+            self.segments,
+            self.horizontal_variance,
+            &mut rand,
+            buffers,
+        );
+        let Some(head) = head else {
+            return mesh;
+        };
+        let _span = info_span!("decorations").entered();
+        if head.open {
+            // The top cap's vertices follow the rings' in a generated staff
+            let ring_vertices = (self.segments + 1) * (self.resolution + 1);
+            let top_cap = ring_vertices..ring_vertices + self.resolution;
+            if let Some(Indices::U32(indices)) = mesh.indices_mut() {
+                let mut kept = 0;
+                for triangle in 0..indices.len() / 3 {
+                    let corners = triangle * 3..triangle * 3 + 3;
+                    if !indices[corners.clone()].iter().all(|i| top_cap.contains(i)) {
+                        indices.copy_within(corners, kept);
+                        kept += 3;
+                    }
+                }
+                indices.truncate(kept);
+            }
+        }
+        if let Some(head_mesh) = &head.mesh
+            && let Err(error) = mesh.merge(head_mesh)
+        {
+            warn!("Leaving the staff's head off: {error}");
+        }
+        mesh
+    }
+
+    /// [`Self::generate_mesh_with_head`], unless [`Self::validate`] finds a parameter it can't
+    /// build.
+    pub fn try_generate_mesh_with_head(&self, head: Option<&Head>) -> Result<Mesh, GenError> {
+        self.try_generate_mesh_with_head_in(head, MeshBuffers::default())
+    }
+
+    /// [`Self::generate_mesh_with_head_in`], unless [`Self::validate`] finds a parameter it
+    /// can't build.
+    pub fn try_generate_mesh_with_head_in(
+        &self,
+        head: Option<&Head>,
+        buffers: MeshBuffers,
+    ) -> Result<Mesh, GenError> {
+        self.validate()?;
+        Ok(self.generate_mesh_with_head_in(head, buffers))
+    }
+
+    /// Sockets following the staff's wander: [`sockets::TOP`] and [`sockets::BOTTOM`] at the
+    /// center of the end rings, the [`sockets::CENTER`] and [`sockets::GRIP`] on the axis between
+    /// rings, and one [`sockets::ring_socket`] per ring. The head can move them, and
+    /// [`HeadStyle::Custom`] heads need [`Self::sockets_with_head`].
+    pub fn sockets(&self) -> Sockets {
+        self.sockets_with_head(self.generate_head(&HeadRegistry::default()).as_ref())
+    }
+
+    /// The staff's sockets with those of `head` replacing them.
+    pub fn sockets_with_head(&self, head: Option<&Head>) -> Sockets {
+        let rings = self.generate_rings();
+        let center = |y: f32| Transform::from_translation(ring_axis(&rings, y));
+        let mut sockets = Sockets::default();
+        for (i, ring) in rings.iter().enumerate() {
+            sockets.insert(
+                sockets::ring_socket(i),
+                Transform::from_xyz(ring.offset.x, ring.y, ring.offset.y),
+            );
+        }
+        let (bottom, top) = (rings[0], rings[rings.len() - 1]);
+        sockets.insert(
+            sockets::TOP,
+            Transform::from_xyz(top.offset.x, top.y, top.offset.y),
+        );
+        sockets.insert(sockets::CENTER, center(0.));
+        sockets.insert(sockets::GRIP, center(self.height * 0.1));
+        sockets.insert(
+            sockets::BOTTOM,
+            Transform::from_xyz(bottom.offset.x, bottom.y, bottom.offset.y),
+        );
+        for (name, transform) in head.iter().flat_map(|head| head.sockets.iter()) {
+            sockets.insert(name, transform);
+        }
+        sockets
+    }
+}
+
+/// Point on the staff's wandering axis at height `y`, interpolated between the rings around it.
+/// Heights beyond the end rings follow the end ring's center.
+pub fn ring_axis(rings: &[StaffRing], y: f32) -> Vec3 {
+    let upper = rings
+        .partition_point(|ring| ring.y < y)
+        .clamp(1, rings.len() - 1);
+    let (below, above) = (rings[upper - 1], rings[upper]);
+    let t = ((y - below.y) / (above.y - below.y)).clamp(0., 1.);
+    let offset = below.offset.lerp(above.offset, t);
+    vec3(offset.x, y, offset.y)
+}
+
+/// Radius of the staff at height `y`, interpolated like [`ring_axis`].
+pub fn ring_radius(rings: &[StaffRing], y: f32) -> f32 {
+    let upper = rings
+        .partition_point(|ring| ring.y < y)
+        .clamp(1, rings.len() - 1);
+    let (below, above) = (rings[upper - 1], rings[upper]);
+    let t = ((y - below.y) / (above.y - below.y)).clamp(0., 1.);
+    below.radius.lerp(above.radius, t)
+}
+
+/// Radius, horizontal offset and height of one ring along the staff.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StaffRing {
+    pub radius: f32,
+    pub offset: Vec2,
+    pub y: f32,
+}
+
+pub fn generate_staff_mesh(
+    radius: f32,
+    radial_variance: f32,
+    height: f32,
+    resolution: u32,
+    segments: u32,
+    horizontal_variance: f32,
+    rand: &mut ChaCha8Rng,
+) -> Mesh {
+    generate_staff_mesh_in(
+        radius,
+        radial_variance,
+        height,
+        resolution,
+        segments,
+        horizontal_variance,
+        rand,
+        MeshBuffers::default(),
+    )
+}
+
+/// [`generate_staff_mesh`] built in `buffers`.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_staff_mesh_in(
+    radius: f32,
+    radial_variance: f32,
+    height: f32,
+    resolution: u32,
+    segments: u32,
+    horizontal_variance: f32,
+    rand: &mut ChaCha8Rng,
+    mut buffers: MeshBuffers,
+) -> Mesh {
+    let rings = generate_staff_rings(
+        radius,
+        radial_variance,
+        height,
+        segments,
+        horizontal_variance,
+        rand,
+    );
+    buffers.positions.clear();
+    buffers.normals.clear();
+    push_staff_ring_vertices(
+        &rings,
+        resolution,
+        &mut buffers.positions,
+        &mut buffers.normals,
+    );
+    build_staff_mesh_in(&rings, resolution, buffers)
+}
+
+/// Picks the random radius and offset of every ring, bottom to top.
+pub fn generate_staff_rings(
+    radius: f32,
+    radial_variance: f32,
+    height: f32,
+    segments: u32,
+    horizontal_variance: f32,
+    rand: &mut ChaCha8Rng,
+) -> Vec<StaffRing> {
+    let _span = info_span!("rings").entered();
+    let half_height = height / 2.;
+    let num_rings = segments + 1;
+    let step_y = 2.0 * half_height / segments as f32;
+
+    // Bottom and Top variance X and Z must be known for cap placement
+
+    // Bottom variance
+    // Bottom radius should be a little smaller than top
+    let bvr = vary_radius(rand, radius / 2., radial_variance);
+    let bvx = rand.random::<f32>();
+    let bvz = rand.random::<f32>();
+    // Top variance
+    let tvr = vary_radius(rand, radius, radial_variance);
+    let tvx = rand.random::<f32>();
+    let tvz = rand.random::<f32>();
+    debug!(
+        "bvx: {:?}, bvz: {:?}, tvx: {:?}, tvy: {:?}",
+        bvx, bvz, tvx, tvz
+    );
+
+    let mut rings = Vec::with_capacity(num_rings as usize);
+    for ring in 0..num_rings {
+        // Radius with variance
+
+        let (vr, vx, vz) = if ring == 0 {
+            // Bottom variance radial, X, Z
+            (bvr, bvx, bvz)
+        } else if ring == num_rings - 1 {
+            // Top variance radial, X, Z
+            (tvr, tvx, tvz)
+        } else {
+            // New random variances X and Z
+            (
+                vary_radius(rand, radius, radial_variance),
+                rand.random::<f32>(),
+                rand.random::<f32>(),
+            )
+        };
+
+        rings.push(StaffRing {
+            radius: vr,
+            offset: vec2(horizontal_variance * vx, horizontal_variance * vz),
+            y: -half_height + ring as f32 * step_y,
+        });
+    }
+    rings
+}
+
+/// A radius up to `variance` below `max`. Without variance it's always `max`, giving a straight
+/// sweep like a log or a pole.
+fn vary_radius(rand: &mut ChaCha8Rng, max: f32, variance: f32) -> f32 {
+    if variance > 0. {
+        rand.random_range((max - variance)..max)
+    } else {
+        max
+    }
+}
+
+/// Positions and normals of every ring vertex, including the duplicated seam vertex.
+pub fn staff_ring_vertices(rings: &[StaffRing], resolution: u32) -> (Vec<[f32; 3]>, Vec<[f32; 3]>) {
+    let (mut positions, mut normals) = (Vec::new(), Vec::new());
+    push_staff_ring_vertices(rings, resolution, &mut positions, &mut normals);
+    (positions, normals)
+}
+
+/// [`staff_ring_vertices`] added to the end of `positions` and `normals`.
+fn push_staff_ring_vertices(
+    rings: &[StaffRing],
+    resolution: u32,
+    positions: &mut Vec<[f32; 3]>,
+    normals: &mut Vec<[f32; 3]>,
+) {
+    let _span = info_span!("skin").entered();
+    let circle = unit_circle(resolution);
+    let num_ring_vertices = rings.len() * (resolution as usize + 1);
+    positions.reserve(num_ring_vertices);
+    normals.reserve(num_ring_vertices);
+
+    for ring in rings {
+        for &(sin, cos) in circle.iter() {
+            positions.push([
+                ring.radius * cos + ring.offset.x,
+                ring.y,
+                ring.radius * sin + ring.offset.y,
+            ]);
+            normals.push([cos, 0., sin]);
+        }
+    }
+}
+
+/// Adds UVs, the barrel skin and both caps to already placed ring vertices.
+pub fn build_staff_mesh(
+    rings: &[StaffRing],
+    resolution: u32,
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+) -> Mesh {
+    let buffers = MeshBuffers {
+        positions,
+        normals,
+        ..default()
+    };
+    build_staff_mesh_in(rings, resolution, buffers)
+}
+
+/// [`build_staff_mesh`] with the ring vertices in `buffers`, building the rest in its other
+/// vectors.
+pub fn build_staff_mesh_in(rings: &[StaffRing], resolution: u32, buffers: MeshBuffers) -> Mesh {
+    let MeshBuffers {
+        mut positions,
+        mut normals,
+        mut uvs,
+        mut indices,
+    } = buffers;
+    uvs.clear();
+    indices.clear();
+    let num_rings = rings.len() as u32;
+    let segments = num_rings - 1;
+    let num_vertices = resolution * 2 + num_rings * (resolution + 1);
+    let num_faces = resolution * (num_rings - 2);
+    let num_indices = (2 * num_faces + 2 * (resolution - 1) * 2) * 3;
+
+    positions.reserve(num_vertices as usize - positions.len());
+    normals.reserve(num_vertices as usize - normals.len());
+    uvs.reserve(num_vertices as usize);
+    indices.reserve(num_indices as usize);
+
+    let circle = unit_circle(resolution);
+    let skin = info_span!("skin").entered();
+
+    // rings
+
+    for ring in 0..num_rings {
+        for segment in 0..=resolution {
+            uvs.push([
+                segment as f32 / resolution as f32,
+                ring as f32 / segments as f32,
+            ]);
+        }
+    }
+
+    // barrel skin
+
+    for i in 0..segments {
+        let ring = i * (resolution + 1);
+        let next_ring = (i + 1) * (resolution + 1);
+
+        for j in 0..resolution {
+            indices.extend_from_slice(&[
+                ring + j,
+                next_ring + j,
+                ring + j + 1,
+                next_ring + j,
+                next_ring + j + 1,
+                ring + j + 1,
+            ]);
+        }
+    }
+
+    drop(skin);
+
+    // caps
+    let _span = info_span!("caps").entered();
+    let mut build_cap = |top: bool| {
+        let offset = positions.len() as u32;
+        let (ring, normal_y, winding) = if top {
+            (rings[rings.len() - 1], 1., (1, 0))
+        } else {
+            (rings[0], -1., (0, 1))
+        };
+
+        for i in 0..resolution {
+            let (sin, cos) = circle[i as usize];
+
+            positions.push([
+                cos * ring.radius + ring.offset.x,
+                ring.y,
+                sin * ring.radius + ring.offset.y,
+            ]);
+            normals.push([0.0, normal_y, 0.0]);
+            uvs.push([0.5 * (cos + 1.0), 1.0 - 0.5 * (sin + 1.0)]);
+        }
+
+        for i in 1..(resolution - 1) {
+            indices.extend_from_slice(&[offset, offset + i + winding.0, offset + i + winding.1]);
+        }
+    };
+
+    build_cap(true);
+    build_cap(false);
+
+    // Assume anchor is at midpoint. No need for vertex position offsets
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_indices(Indices::U32(indices))
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::MAX_COUNT;
+    use crate::mesh_util::{compare, positions};
+
+    #[test]
+    fn staff_is_deterministic_for_seed() {
+        let config = StaffConfig::default();
+        let diff = compare(&config.generate_mesh(), &config.generate_mesh());
+        assert!(diff.is_identical(), "{diff:?}");
+    }
+
+    #[test]
+    fn invalid_staffs_are_reported() {
+        let valid = StaffConfig::default();
+        assert!(valid.try_generate_mesh().is_ok());
+        let flat = StaffConfig {
+            height: 0.,
+            ..valid.clone()
+        };
+        assert!(matches!(
+            flat.try_generate_mesh(),
+            Err(GenError::NotPositive {
+                parameter: "height",
+                ..
+            })
+        ));
+        let flat_sided = StaffConfig {
+            resolution: 2,
+            ..valid.clone()
+        };
+        assert_eq!(
+            flat_sided.validate(),
+            Err(GenError::TooFew {
+                parameter: "resolution",
+                value: 2,
+                min: 3
+            })
+        );
+        let wobbly = StaffConfig {
+            horizontal_variance: f32::NAN,
+            ..valid
+        };
+        assert!(matches!(wobbly.validate(), Err(GenError::NotFinite { .. })));
+    }
+
+    #[test]
+    fn oversized_staffs_are_reported() {
+        let huge = StaffConfig {
+            resolution: 3_000_000_000,
+            ..default()
+        };
+        assert_eq!(
+            huge.try_generate_mesh().err(),
+            Some(GenError::TooMany {
+                parameter: "resolution",
+                value: 3_000_000_000,
+                max: MAX_COUNT
+            })
+        );
+        let long = StaffConfig {
+            segments: MAX_COUNT + 1,
+            ..default()
+        };
+        assert!(matches!(
+            long.validate(),
+            Err(GenError::TooMany {
+                parameter: "segments",
+                ..
+            })
+        ));
+        let at_limit = StaffConfig {
+            segments: MAX_COUNT,
+            ..default()
+        };
+        assert!(at_limit.validate().is_ok());
+    }
+
+    #[test]
+    fn sockets_follow_the_rings() {
+        let config = StaffConfig::default();
+        let rings = config.generate_rings();
+        let sockets = config.sockets();
+        let top = rings.last().unwrap();
+        assert_eq!(
+            sockets.get(sockets::TOP).unwrap().translation,
+            vec3(top.offset.x, top.y, top.offset.y)
+        );
+        for (i, ring) in rings.iter().enumerate() {
+            let socket = sockets.get(&sockets::ring_socket(i)).unwrap().translation;
+            assert_eq!(socket.y, ring.y);
+        }
+        let bottom = sockets.get(sockets::BOTTOM).unwrap().translation;
+        let grip = sockets.get(sockets::GRIP).unwrap().translation;
+        assert!(bottom.y < grip.y && grip.y < top.y, "{grip}");
+        // The grip sits on the axis between the rings around it
+        let above = rings.partition_point(|ring| ring.y < grip.y);
+        let (a, b) = (rings[above - 1].offset, rings[above].offset);
+        let (min, max) = (a.min(b) - 1e-6, a.max(b) + 1e-6);
+        assert!(
+            grip.xz().cmpge(min).all() && grip.xz().cmple(max).all(),
+            "{grip}"
+        );
+    }
+
+    #[test]
+    fn staff_snapshot() {
+        let mesh = StaffConfig::default().generate_mesh();
+        let positions = positions(&mesh);
+        assert_eq!(positions.len(), 47);
+        assert_eq!(mesh.indices().map(|indices| indices.len()), Some(168));
+
+        // One vertex from each of the bottom, middle and top rings, then the two cap starts
+        let pinned = [
+            (0, [0.015358189, -1.0, 0.06948102]),
+            (7, [0.09956767, -0.5, 0.09853879]),
+            (14, [0.10982433, 0.0, 0.05757631]),
+            (34, [0.06977085, 1.0, 0.0404923]),
+            (35, [0.06977085, 1.0, 0.040492292]),
+            (41, [0.015358189, -1.0, 0.06948102]),
+        ];
+        for (i, expected) in pinned {
+            assert!(
+                Vec3::from(positions[i]).abs_diff_eq(Vec3::from(expected), 1e-6),
+                "vertex {i}: {:?} != {expected:?}",
+                positions[i]
+            );
+        }
+    }
+}
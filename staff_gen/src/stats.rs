@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::mesh_util::{center_of_mass, volume};
+use crate::staff::StaffConfig;
+
+/// Density of seasoned hardwood in kg/m³.
+pub const WOOD_DENSITY: f32 = 700.;
+
+/// Game stats derived from a staff's parameters and generated mesh.
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct StaffStats {
+    /// Kilograms, from the mesh volume and [`WOOD_DENSITY`]
+    pub weight: f32,
+    /// Meters from end to end
+    pub reach: f32,
+    /// 0 to 1, higher for straighter and more even staffs
+    pub quality: f32,
+    /// Height of the center of mass as a fraction of the staff, 0 at the bottom
+    pub balance: f32,
+}
+
+impl StaffStats {
+    /// `mesh` must have been generated from `config`. Lengths are converted to meters with
+    /// `meters_per_unit`.
+    pub fn new(config: &StaffConfig, mesh: &Mesh, meters_per_unit: f32) -> Self {
+        // Winding only affects the sign
+        let volume = volume(mesh).abs() * meters_per_unit.powi(3);
+        let balance = center_of_mass(mesh)
+            .map_or(0.5, |center| center.y / config.height + 0.5)
+            .clamp(0., 1.);
+
+        let evenness = 1. - (config.radial_variance / config.radius).clamp(0., 1.);
+        // A horizontal wander of a tenth of the height already makes a very crooked staff
+        let straightness = 1. - (config.horizontal_variance / config.height * 10.).clamp(0., 1.);
+
+        Self {
+            weight: volume * WOOD_DENSITY,
+            reach: config.height * meters_per_unit,
+            quality: evenness * straightness,
+            balance,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_staff_stats() {
+        let config = StaffConfig::default();
+        let stats = StaffStats::new(&config, &config.generate_mesh(), 1.);
+        assert_eq!(stats.reach, 2.);
+        // Roughly a 4cm thick, 2m long wooden pole
+        assert!((1. ..10.).contains(&stats.weight), "{stats:?}");
+        assert!((0. ..=1.).contains(&stats.quality), "{stats:?}");
+        // The bottom ring is thinner, so the staff is a little top heavy
+        assert!((0.5..0.8).contains(&stats.balance), "{stats:?}");
+    }
+
+    #[test]
+    fn stats_scale_with_units() {
+        let config = StaffConfig::default();
+        let mesh = config.generate_mesh();
+        let meters = StaffStats::new(&config, &mesh, 1.);
+        let centimeters = StaffStats::new(&config, &mesh, 0.01);
+        assert!((centimeters.weight - meters.weight * 1e-6).abs() < 1e-6);
+        assert_eq!(centimeters.quality, meters.quality);
+    }
+}
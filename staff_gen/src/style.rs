@@ -0,0 +1,163 @@
+//! A low-poly look shared by every generator: fewer vertices, flat shading and a little jitter.
+
+use bevy::mesh::{Indices, VertexAttributeValues};
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::crystal::CrystalConfig;
+use crate::staff::StaffConfig;
+
+/// Distance under which vertices count as the same position and jitter together
+const JITTER_WELD_EPSILON: f32 = 1e-5;
+
+#[derive(Resource, Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Resource, Default, Serialize, Deserialize)]
+pub struct StyleConfig {
+    pub low_poly: bool,
+    /// Most vertices around a ring or cross-section while [`Self::low_poly`] is on
+    pub max_resolution: u32,
+    /// Furthest a vertex moves in each axis while [`Self::low_poly`] is on
+    pub jitter: f32,
+    pub seed: u64,
+}
+
+impl Default for StyleConfig {
+    fn default() -> Self {
+        Self {
+            low_poly: false,
+            max_resolution: 5,
+            jitter: 0.004,
+            seed: 0,
+        }
+    }
+}
+
+impl StyleConfig {
+    /// `resolution` capped to [`Self::max_resolution`] while [`Self::low_poly`] is on.
+    pub fn resolution(&self, resolution: u32) -> u32 {
+        if self.low_poly {
+            resolution.min(self.max_resolution.max(3))
+        } else {
+            resolution
+        }
+    }
+
+    pub fn staff(&self, config: &StaffConfig) -> StaffConfig {
+        StaffConfig {
+            resolution: self.resolution(config.resolution),
+            ..config.clone()
+        }
+    }
+
+    pub fn crystal(&self, config: &CrystalConfig) -> CrystalConfig {
+        CrystalConfig {
+            resolution: self.resolution(config.resolution),
+            ..config.clone()
+        }
+    }
+
+    /// Jitters and flat shades `mesh` while [`Self::low_poly`] is on. Vertices sharing a position
+    /// move together, so closed meshes stay closed.
+    pub fn apply(&self, mut mesh: Mesh) -> Mesh {
+        if !self.low_poly {
+            return mesh;
+        }
+        if let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+        {
+            for position in positions.iter_mut() {
+                *position = (Vec3::from(*position) + self.jitter_at(*position)).to_array();
+            }
+        }
+        mesh.duplicate_vertices();
+        mesh.compute_flat_normals();
+        // Indexed like every other generated mesh, one vertex per corner
+        let vertices = mesh.count_vertices() as u32;
+        mesh.insert_indices(Indices::U32((0..vertices).collect()));
+        mesh
+    }
+
+    /// Seeded straight from the style's seed and the welded position, so the jitter is the same
+    /// on every platform and Rust release, unlike anything hashed with std's hashers.
+    fn jitter_at(&self, position: [f32; 3]) -> Vec3 {
+        let mut key = [0; 32];
+        key[..8].copy_from_slice(&self.seed.to_le_bytes());
+        let cells = position.map(|x| (x / JITTER_WELD_EPSILON).round() as i64);
+        for (bytes, cell) in key[8..].chunks_exact_mut(8).zip(cells) {
+            bytes.copy_from_slice(&cell.to_le_bytes());
+        }
+        let mut rand = ChaCha8Rng::from_seed(key);
+        Vec3::from_array(std::array::from_fn(|_| rand.random_range(-1f32..=1.))) * self.jitter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_util::positions;
+    use crate::repair::check_watertight;
+
+    #[test]
+    fn jitter_is_pinned_to_seed_and_position() {
+        let style = StyleConfig {
+            seed: 7,
+            jitter: 1.,
+            ..default()
+        };
+        let jitter = style.jitter_at([0.25, 1., -0.5]);
+        // Changes here change every low-poly mesh and their determinism hashes
+        assert!(
+            jitter.abs_diff_eq(vec3(-0.20230389, -0.06333351, -0.14975023), 1e-6),
+            "{jitter}"
+        );
+        assert_eq!(
+            jitter,
+            style.jitter_at([0.25, 1. + JITTER_WELD_EPSILON * 0.1, -0.5])
+        );
+        assert_ne!(jitter, style.jitter_at([0.25, 1.001, -0.5]));
+        assert_ne!(
+            jitter,
+            StyleConfig {
+                seed: 8,
+                ..style.clone()
+            }
+            .jitter_at([0.25, 1., -0.5])
+        );
+    }
+
+    #[test]
+    fn low_poly_staff_stays_closed() {
+        let style = StyleConfig {
+            low_poly: true,
+            ..default()
+        };
+        let config = StaffConfig {
+            resolution: 12,
+            ..default()
+        };
+        let plain = config.generate_mesh();
+        let mesh = style.apply(style.staff(&config).generate_mesh());
+        let report = check_watertight(&mesh);
+        assert!(report.is_watertight(), "{report:?}");
+        // Capped to fewer sides, with a vertex per triangle corner
+        assert!(mesh.count_vertices() / 3 < plain.indices().unwrap().len() / 3);
+
+        let still = StyleConfig {
+            jitter: 0.,
+            ..style.clone()
+        }
+        .apply(style.staff(&config).generate_mesh());
+        assert_ne!(positions(&mesh), positions(&still));
+    }
+
+    #[test]
+    fn style_is_off_by_default() {
+        let style = StyleConfig::default();
+        let config = StaffConfig::default();
+        assert_eq!(style.staff(&config), config);
+        let mesh = config.generate_mesh();
+        assert_eq!(positions(&style.apply(mesh.clone())), positions(&mesh));
+    }
+}
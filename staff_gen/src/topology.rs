@@ -0,0 +1,194 @@
+//! Topology statistics of generated meshes, to check that a mesh is ready for a game engine:
+//! closed, manifold and in as many pieces as intended.
+//!
+//! Like [`repair`](crate::repair), vertices at the same position count as one, since generators
+//! duplicate them along UV and normal seams.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::mesh_util::positions;
+use crate::repair::weld_ids;
+
+/// How a mesh's triangles connect, from [`TopologyReport::new`].
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct TopologyReport {
+    /// Distinct vertex positions used by triangles
+    pub vertices: usize,
+    pub edges: usize,
+    pub triangles: usize,
+    /// Edges of a single triangle, around holes and open ends
+    pub boundary_edges: usize,
+    /// Edges shared by more than two triangles
+    pub non_manifold_edges: usize,
+    /// Vertices at the same position as another, which seams need but welding removes
+    pub duplicate_vertices: usize,
+    /// Pieces that share no edge or vertex with each other
+    pub components: usize,
+    /// Vertices minus edges plus triangles, 2 for every closed piece without a hole through it
+    pub euler_characteristic: i64,
+}
+
+impl TopologyReport {
+    /// Triangles collapsed to a line or a point are left out, as they join nothing.
+    pub fn new(mesh: &Mesh) -> Self {
+        let positions = positions(mesh);
+        let welded = weld_ids(positions);
+        let indices: Vec<usize> = match mesh.indices() {
+            Some(indices) => indices.iter().collect(),
+            None => (0..positions.len()).collect(),
+        };
+        let triangles: Vec<[u32; 3]> = indices
+            .chunks_exact(3)
+            .map(|triangle| [0, 1, 2].map(|corner| welded[triangle[corner]]))
+            .filter(|[a, b, c]| a != b && b != c && c != a)
+            .collect();
+
+        let mut edges: HashMap<(u32, u32), usize> = HashMap::new();
+        let mut components = Components::default();
+        for triangle in &triangles {
+            for corner in 0..3 {
+                let (a, b) = (triangle[corner], triangle[(corner + 1) % 3]);
+                *edges.entry((a.min(b), a.max(b))).or_default() += 1;
+                components.join(a, b);
+            }
+        }
+        let vertices = components.roots.len();
+        let distinct = welded
+            .iter()
+            .enumerate()
+            .filter(|&(i, &id)| i == id as usize);
+
+        Self {
+            vertices,
+            edges: edges.len(),
+            triangles: triangles.len(),
+            boundary_edges: edges.values().filter(|&&count| count == 1).count(),
+            non_manifold_edges: edges.values().filter(|&&count| count > 2).count(),
+            duplicate_vertices: positions.len() - distinct.count(),
+            components: components.count(),
+            euler_characteristic: vertices as i64 - edges.len() as i64 + triangles.len() as i64,
+        }
+    }
+
+    /// Every edge joins exactly two triangles, so the mesh has no holes and encloses a volume.
+    pub fn is_closed_manifold(&self) -> bool {
+        self.boundary_edges == 0 && self.non_manifold_edges == 0
+    }
+}
+
+/// Union-find over welded vertex ids.
+#[derive(Default)]
+struct Components {
+    roots: HashMap<u32, u32>,
+}
+
+impl Components {
+    fn root(&mut self, vertex: u32) -> u32 {
+        let mut root = *self.roots.entry(vertex).or_insert(vertex);
+        while let Some(&parent) = self.roots.get(&root)
+            && parent != root
+        {
+            root = parent;
+        }
+        // Point everything on the way straight at the root, so later lookups are quick
+        let mut current = vertex;
+        while current != root {
+            current = self.roots.insert(current, root).unwrap_or(root);
+        }
+        root
+    }
+
+    fn join(&mut self, a: u32, b: u32) {
+        let (a, b) = (self.root(a), self.root(b));
+        self.roots.insert(a, b);
+    }
+
+    fn count(&mut self) -> usize {
+        let vertices: Vec<u32> = self.roots.keys().copied().collect();
+        let mut roots: Vec<u32> = vertices.into_iter().map(|v| self.root(v)).collect();
+        roots.sort_unstable();
+        roots.dedup();
+        roots.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::RenderAssetUsages;
+    use bevy::mesh::{Indices, PrimitiveTopology};
+
+    use super::*;
+    use crate::cube::{CubeNormals, generate_cube_mesh};
+    use crate::cylinder::{CylinderNormals, generate_cylinder_mesh};
+    use crate::staff::StaffConfig;
+
+    #[test]
+    fn closed_meshes_are_spheres() {
+        let cube = TopologyReport::new(&generate_cube_mesh(&mut CubeNormals::default()));
+        assert_eq!(
+            cube,
+            TopologyReport {
+                vertices: 8,
+                edges: 18,
+                triangles: 12,
+                boundary_edges: 0,
+                non_manifold_edges: 0,
+                // Each corner is shared by three faces with their own normals
+                duplicate_vertices: 16,
+                components: 1,
+                euler_characteristic: 2,
+            }
+        );
+        let staff = TopologyReport::new(&StaffConfig::default().generate_mesh());
+        assert!(staff.is_closed_manifold(), "{staff:?}");
+        assert_eq!((staff.components, staff.euler_characteristic), (1, 2));
+    }
+
+    #[test]
+    fn open_and_separate_pieces_are_reported() {
+        let resolution = 8;
+        let mut tube =
+            generate_cylinder_mesh(0.5, 1., resolution, 2, &mut CylinderNormals::default());
+        // The caps are the last triangles, resolution - 2 per end
+        let mut indices: Vec<u32> = tube.indices().unwrap().iter().map(|i| i as u32).collect();
+        indices.truncate(indices.len() - 2 * (resolution as usize - 2) * 3);
+        tube.insert_indices(Indices::U32(indices));
+        let report = TopologyReport::new(&tube);
+        assert_eq!(report.boundary_edges, 2 * resolution as usize);
+        assert_eq!(report.euler_characteristic, 0);
+        assert!(!report.is_closed_manifold());
+
+        let mut cubes = generate_cube_mesh(&mut CubeNormals::default());
+        cubes
+            .merge(&generate_cube_mesh(&mut CubeNormals::default()).translated_by(Vec3::X * 2.))
+            .unwrap();
+        let report = TopologyReport::new(&cubes);
+        assert_eq!((report.components, report.euler_characteristic), (2, 4));
+    }
+
+    #[test]
+    fn fins_are_non_manifold() {
+        // Three triangles around the edge from the origin to +Z
+        let positions = vec![
+            [0., 0., 0.],
+            [0., 0., 1.],
+            [1., 0., 0.],
+            [0., 1., 0.],
+            [-1., 0., 0.],
+        ];
+        let mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_indices(Indices::U32(vec![0, 1, 2, 0, 1, 3, 0, 1, 4]));
+        let report = TopologyReport::new(&mesh);
+        assert_eq!(report.non_manifold_edges, 1);
+        assert_eq!(report.boundary_edges, 6);
+        assert_eq!(report.components, 1);
+    }
+}
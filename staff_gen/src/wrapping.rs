@@ -0,0 +1,226 @@
+//! A thin tube wound around a staff, for vine or leather cord wrapping.
+
+use std::f32::consts::TAU;
+use std::ops::Range;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{
+    GenError, MAX_COUNT, check_at_most, check_count, check_finite, check_length, check_resolution,
+};
+use crate::mesh_util::unit_circle;
+use crate::staff::{StaffConfig, StaffRing, ring_axis, ring_radius};
+
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[reflect(Component, Default, Serialize, Deserialize)]
+pub struct WrappingConfig {
+    /// Times the tube goes around the staff. Negative turns wind the other way.
+    pub turns: f32,
+    pub tube_radius: f32,
+    /// Part of the staff's height that is wrapped, from 0 at the bottom to 1 at the top
+    pub coverage: Range<f32>,
+    /// Sides of the tube
+    pub resolution: u32,
+    pub steps_per_turn: u32,
+}
+
+impl Default for WrappingConfig {
+    fn default() -> Self {
+        Self {
+            turns: 6.,
+            tube_radius: 0.008,
+            coverage: 0.45..0.65,
+            resolution: 6,
+            steps_per_turn: 16,
+        }
+    }
+}
+
+impl WrappingConfig {
+    /// Wrapping for a staff generated from `staff`, in the staff mesh's space.
+    pub fn generate_mesh(&self, staff: &StaffConfig) -> Mesh {
+        let _span = info_span!("decorations", seed = staff.seed).entered();
+        generate_wrapping_mesh(
+            &staff.generate_rings(),
+            self.turns,
+            self.tube_radius,
+            self.coverage.clone(),
+            self.resolution,
+            self.steps_per_turn,
+        )
+    }
+
+    /// [`Self::generate_mesh`], unless [`Self::validate`] finds a parameter of the wrapping or
+    /// the staff it can't build.
+    pub fn try_generate_mesh(&self, staff: &StaffConfig) -> Result<Mesh, GenError> {
+        staff.validate()?;
+        self.validate()?;
+        Ok(self.generate_mesh(staff))
+    }
+
+    pub fn validate(&self) -> Result<(), GenError> {
+        check_finite("turns", self.turns)?;
+        check_length("tube_radius", self.tube_radius)?;
+        check_finite("coverage", self.coverage.start)?;
+        check_finite("coverage", self.coverage.end)?;
+        check_resolution(self.resolution)?;
+        check_count("steps_per_turn", self.steps_per_turn, 1)?;
+        // The casts saturate, so any number of turns past the limit is reported
+        let steps = (self.turns.abs() * self.steps_per_turn as f32).ceil() as u32;
+        check_at_most("steps", steps, MAX_COUNT)
+    }
+}
+
+/// Sweeps a tube along a helix resting on the surface described by `rings`, following its
+/// wandering axis and changing radius. Both ends are capped.
+pub fn generate_wrapping_mesh(
+    rings: &[StaffRing],
+    turns: f32,
+    tube_radius: f32,
+    coverage: Range<f32>,
+    resolution: u32,
+    steps_per_turn: u32,
+) -> Mesh {
+    let (bottom, top) = (rings[0].y, rings[rings.len() - 1].y);
+    let start = bottom.lerp(top, coverage.start.clamp(0., 1.));
+    let end = bottom.lerp(top, coverage.end.clamp(0., 1.));
+    let steps = ((turns.abs() * steps_per_turn as f32).ceil() as u32).max(1);
+
+    let path: Vec<(Vec3, Vec3)> = (0..=steps)
+        .map(|step| {
+            let t = step as f32 / steps as f32;
+            let y = start.lerp(end, t);
+            let (sin, cos) = (TAU * turns * t).sin_cos();
+            let outward = vec3(cos, 0., sin);
+            let center = ring_axis(rings, y) + outward * (ring_radius(rings, y) + tube_radius);
+            (center, outward)
+        })
+        .collect();
+
+    let num_vertices =
+        (steps as usize + 1) * (resolution as usize + 1) + 2 * (resolution as usize + 1);
+    let mut positions = Vec::with_capacity(num_vertices);
+    let mut normals = Vec::with_capacity(num_vertices);
+    let mut uvs = Vec::with_capacity(num_vertices);
+    let mut indices = Vec::with_capacity((steps * resolution * 6 + 2 * resolution * 3) as usize);
+
+    let circle = unit_circle(resolution);
+    let mut frames = Vec::with_capacity(path.len());
+    let mut length = 0.;
+
+    // tube
+
+    for (i, &(center, outward)) in path.iter().enumerate() {
+        let previous = path[i.saturating_sub(1)].0;
+        let next = path[(i + 1).min(path.len() - 1)].0;
+        let tangent = (next - previous).normalize_or(Vec3::Y);
+        let normal = outward
+            .reject_from_normalized(tangent)
+            .normalize_or(outward);
+        let binormal = normal.cross(tangent);
+        if i > 0 {
+            length += center.distance(path[i - 1].0);
+        }
+        for (segment, &(sin, cos)) in circle.iter().enumerate() {
+            let offset = normal * cos + binormal * sin;
+            positions.push((center + offset * tube_radius).to_array());
+            normals.push(offset.to_array());
+            // Square texels, whatever the tube radius
+            uvs.push([
+                segment as f32 / resolution as f32,
+                length / (TAU * tube_radius),
+            ]);
+        }
+        frames.push((center, tangent, normal, binormal));
+    }
+
+    for i in 0..steps {
+        let ring = i * (resolution + 1);
+        let next_ring = (i + 1) * (resolution + 1);
+        for j in 0..resolution {
+            indices.extend_from_slice(&[
+                ring + j,
+                next_ring + j,
+                ring + j + 1,
+                next_ring + j,
+                next_ring + j + 1,
+                ring + j + 1,
+            ]);
+        }
+    }
+
+    // caps
+
+    for (frame, facing) in [(frames[0], -1.), (frames[frames.len() - 1], 1.)] {
+        let (center, tangent, normal, binormal) = frame;
+        let offset = positions.len() as u32;
+        positions.push(center.to_array());
+        normals.push((tangent * facing).to_array());
+        uvs.push([0.5, 0.5]);
+        for &(sin, cos) in &circle[..resolution as usize] {
+            positions.push((center + (normal * cos + binormal * sin) * tube_radius).to_array());
+            normals.push((tangent * facing).to_array());
+            uvs.push([0.5 * (cos + 1.), 0.5 * (sin + 1.)]);
+        }
+        for i in 0..resolution {
+            let (a, b) = (offset + 1 + i, offset + 1 + (i + 1) % resolution);
+            if facing > 0. {
+                indices.extend_from_slice(&[offset, b, a]);
+            } else {
+                indices.extend_from_slice(&[offset, a, b]);
+            }
+        }
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_indices(Indices::U32(indices))
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_util::{assert_closed_and_outward, positions};
+
+    #[test]
+    fn wrapping_rests_on_the_staff_within_its_coverage() {
+        let staff = StaffConfig::default();
+        let config = WrappingConfig::default();
+        let mesh = config.generate_mesh(&staff);
+        let rings = staff.generate_rings();
+        let (bottom, top) = (rings[0].y, rings[rings.len() - 1].y);
+        let (start, end) = (
+            bottom.lerp(top, config.coverage.start),
+            bottom.lerp(top, config.coverage.end),
+        );
+        for &position in positions(&mesh) {
+            let position = Vec3::from(position);
+            let y = position.y;
+            assert!(
+                y >= start - config.tube_radius * 2. && y <= end + config.tube_radius * 2.,
+                "{position}"
+            );
+            // No vertex sinks into the staff
+            let from_axis = (position - ring_axis(&rings, y)).xz().length();
+            assert!(
+                from_axis >= ring_radius(&rings, y) - 1e-4,
+                "{position} is inside the staff"
+            );
+        }
+    }
+
+    #[test]
+    fn wrapping_is_closed_and_faces_outwards() {
+        assert_closed_and_outward(
+            &WrappingConfig::default().generate_mesh(&StaffConfig::default()),
+        );
+    }
+}